@@ -0,0 +1,44 @@
+// examples/custom_sheet.rs
+// Appends an application's own sheet to an export workbook through
+// `TrailingSheets::extra_sheets`. Run with `cargo run --example custom_sheet`;
+// it writes custom_sheet_example.xlsx to the current directory.
+
+use anyhow::Result;
+use export_opinion::cluster_labels::LabelMap;
+use export_opinion::dashboard::{DashboardSection, ProgressOptions};
+use export_opinion::excel_writer::{self, DataSheetOptions, TrailingSheets};
+use export_opinion::models::OrganizationExportRow;
+use std::path::Path;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let organizations: Vec<OrganizationExportRow> = Vec::new();
+    let record_count = organizations.len();
+
+    let trailing_sheets = TrailingSheets {
+        method_breakdown: Vec::new(),
+        method_performance: Vec::new(),
+        orphaned_edges: Vec::new(),
+        warnings: Vec::new(),
+        legend: true,
+        // Named with a name no standard sheet uses; a clash fails the write
+        extra_sheets: Some(Box::new(move |workbook| {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name("Contract Summary")?;
+            sheet.write_string(0, 0, "contract")?;
+            sheet.write_string(0, 1, "organizations delivered")?;
+            sheet.write_string(1, 0, "Example County 2025")?;
+            sheet.write_number(1, 1, record_count as f64)?;
+            Ok(())
+        })),
+    };
+    let labels = LabelMap::default();
+    let sheet_options = DataSheetOptions { cluster_labels: &labels, footer: true, taxonomy_sheet: false };
+
+    let path = Path::new("custom_sheet_example.xlsx");
+    excel_writer::write_excel_file(
+        path, Some(organizations), None, DashboardSection::Skipped, ProgressOptions::default(), trailing_sheets, sheet_options,
+    ).await?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
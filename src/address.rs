@@ -0,0 +1,43 @@
+// src/address.rs
+
+/// The components of a service address as stored in the source `address` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressParts {
+    pub address_1: Option<String>,
+    pub address_2: Option<String>,
+    pub city: Option<String>,
+    pub state_province: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+/// Trims a component, treating NULL and blank values alike as missing.
+fn present(part: &Option<String>) -> Option<&str> {
+    part.as_deref().map(str::trim).filter(|p| !p.is_empty())
+}
+
+impl AddressParts {
+    /// The address on one line, "address_1, address_2, city, state postal_code, country",
+    /// with missing components skipped rather than blanking the whole address.
+    /// `None` when every component is missing.
+    pub fn full_address(&self) -> Option<String> {
+        let state_postal: Vec<&str> = [&self.state_province, &self.postal_code].into_iter().filter_map(present).collect();
+        let state_postal = state_postal.join(" ");
+        let parts: Vec<&str> = [present(&self.address_1), present(&self.address_2), present(&self.city)]
+            .into_iter()
+            .flatten()
+            .chain((!state_postal.is_empty()).then_some(state_postal.as_str()))
+            .chain(present(&self.country))
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// How many of the expected components (address_1, city, state_province,
+    /// postal_code, country) are missing. address_2 is optional and never counted.
+    pub fn missing_parts(&self) -> usize {
+        [&self.address_1, &self.city, &self.state_province, &self.postal_code, &self.country]
+            .into_iter()
+            .filter(|part| present(part).is_none())
+            .count()
+    }
+}
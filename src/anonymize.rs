@@ -0,0 +1,147 @@
+// src/anonymize.rs
+// Replaces names and addresses in the fetched rows with pseudonyms for `--anonymize`,
+// so cluster structure can be shown to someone who may not see the records themselves.
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::models::{OrganizationExportRow, ServiceExportRow};
+
+/// Environment variable holding the secret key pseudonyms are derived from.
+pub const ANONYMIZE_KEY_VAR: &str = "EXPORT_ANONYMIZE_KEY";
+
+/// Hex digits of the keyed hash in a pseudonym, e.g. "Org-4F2A91C07B3E". The length is
+/// fixed, so a value's pseudonym depends only on the key and the value; at 48 bits a
+/// collision within one export is negligible, and is reported as an error.
+const PSEUDONYM_DIGITS: usize = 12;
+
+/// Identifies an anonymization key in Export Info without revealing it: the first
+/// four bytes of the key's SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyId(u32);
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}", self.0)
+    }
+}
+
+/// The secret key behind the pseudonyms. The same key maps the same value to the same
+/// pseudonym in every export, so exports stay comparable with each other.
+pub struct AnonymizationKey {
+    secret: Vec<u8>,
+}
+
+impl AnonymizationKey {
+    pub fn new(secret: &[u8]) -> Result<AnonymizationKey> {
+        if secret.is_empty() {
+            return Err(anyhow::anyhow!("The anonymization key must not be empty"));
+        }
+        Ok(AnonymizationKey { secret: secret.to_vec() })
+    }
+
+    /// Reads the key from `EXPORT_ANONYMIZE_KEY`.
+    pub fn from_env() -> Result<AnonymizationKey> {
+        let secret = std::env::var(ANONYMIZE_KEY_VAR)
+            .map_err(|_| anyhow::anyhow!("--anonymize needs a secret key in {}", ANONYMIZE_KEY_VAR))?;
+        AnonymizationKey::new(secret.as_bytes())
+    }
+
+    pub fn key_id(&self) -> KeyId {
+        let digest = Sha256::digest(&self.secret);
+        KeyId(u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]))
+    }
+
+    /// HMAC-SHA256 of `kind` and `value` in upper-case hex. The kind is part of the
+    /// input, so a service and an organization with the same name hash differently.
+    fn digest(&self, kind: &str, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(kind.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().iter().map(|byte| format!("{:02X}", byte)).collect()
+    }
+}
+
+/// Hands out pseudonyms for one export, remembering them so a value maps to the same
+/// pseudonym on every sheet and no two values share one.
+pub struct Anonymizer<'k> {
+    key: &'k AnonymizationKey,
+    assigned: HashMap<(&'static str, String), String>,
+    taken: HashMap<String, String>,
+}
+
+impl<'k> Anonymizer<'k> {
+    pub fn new(key: &'k AnonymizationKey) -> Anonymizer<'k> {
+        Anonymizer { key, assigned: HashMap::new(), taken: HashMap::new() }
+    }
+
+    /// The pseudonym of `value`, e.g. "Org-4F2A91C07B3E" for kind "Org". Surrounding
+    /// whitespace is ignored. Fails if another value of this export has the same one.
+    pub fn pseudonym(&mut self, kind: &'static str, value: &str) -> Result<String> {
+        let value = value.trim();
+        if let Some(pseudonym) = self.assigned.get(&(kind, value.to_string())) {
+            return Ok(pseudonym.clone());
+        }
+        let digest = self.key.digest(kind, value);
+        let pseudonym = format!("{}-{}", kind, &digest[..PSEUDONYM_DIGITS]);
+        if let Some(other) = self.taken.get(&pseudonym) {
+            return Err(anyhow::anyhow!(
+                "Two values share the pseudonym {} ('{}' and '{}'); use a different {}",
+                pseudonym, other, value, ANONYMIZE_KEY_VAR
+            ));
+        }
+        self.taken.insert(pseudonym.clone(), value.to_string());
+        self.assigned.insert((kind, value.to_string()), pseudonym.clone());
+        Ok(pseudonym)
+    }
+
+    fn replace(&mut self, kind: &'static str, value: &mut Option<String>) -> Result<()> {
+        if let Some(original) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            *value = Some(self.pseudonym(kind, original)?);
+        }
+        Ok(())
+    }
+
+    /// Replaces each name of a comma-separated list, such as `alternate_names`.
+    fn replace_list(&mut self, kind: &'static str, value: &mut Option<String>) -> Result<()> {
+        if let Some(list) = value.take() {
+            let names = list.split(',')
+                .filter(|name| !name.trim().is_empty())
+                .map(|name| self.pseudonym(kind, name))
+                .collect::<Result<Vec<String>>>()?;
+            *value = (!names.is_empty()).then(|| names.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Replaces organization names with "Org-…" pseudonyms and drops `contributor_id`.
+    pub fn anonymize_organizations(&mut self, rows: &mut [OrganizationExportRow]) -> Result<()> {
+        for row in rows {
+            self.replace("Org", &mut row.name)?;
+            self.replace_list("Org", &mut row.alternate_names)?;
+            self.replace("Org", &mut row.cluster_representative_name)?;
+            row.contributor_id = None;
+        }
+        Ok(())
+    }
+
+    /// Replaces service, organization and location names and addresses with pseudonyms,
+    /// and drops `contributor_id` and the coordinates, which would locate the address.
+    /// Organization names use the same pseudonyms as on the Organizations sheet.
+    pub fn anonymize_services(&mut self, rows: &mut [ServiceExportRow]) -> Result<()> {
+        for row in rows {
+            self.replace("Org", &mut row.organization_name)?;
+            self.replace("Svc", &mut row.service_name)?;
+            self.replace("Loc", &mut row.location_name)?;
+            self.replace("Addr", &mut row.full_address)?;
+            row.contributor_id = None;
+            row.latitude = None;
+            row.longitude = None;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+// src/canonical.rs
+
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::models::{OrganizationExportRow, ServiceExportRow};
+
+/// How the canonical ("survivor") record of each duplicate cluster is chosen.
+/// Every strategy falls back to the lowest record id, so ties resolve deterministically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CanonicalStrategy {
+    /// Most non-empty fields, then source priority, then lowest id
+    #[default]
+    Completeness,
+    /// First contributor in the source priority list, then completeness, then lowest id
+    SourcePriority,
+    /// Lowest record id
+    LowestId,
+}
+
+/// A strategy together with the preferred source_system order it may use.
+#[derive(Debug, Clone, Default)]
+pub struct CanonicalSelection {
+    pub strategy: CanonicalStrategy,
+    /// Preferred source systems, most preferred first; compared case-insensitively
+    pub source_priority: Vec<String>,
+}
+
+impl CanonicalSelection {
+    /// Position of a source system in the priority list; unlisted or missing sources rank last.
+    fn source_rank(&self, source_system: Option<&str>) -> usize {
+        source_system
+            .and_then(|source| {
+                let source = source.trim();
+                self.source_priority.iter().position(|p| p.trim().eq_ignore_ascii_case(source))
+            })
+            .unwrap_or(self.source_priority.len())
+    }
+
+    /// Orders two candidates so that the preferred canonical record comes first.
+    fn compare<T: CanonicalCandidate>(&self, a: &T, b: &T) -> Ordering {
+        let by_completeness = || b.completeness().cmp(&a.completeness());
+        let by_source = || self.source_rank(a.source_system()).cmp(&self.source_rank(b.source_system()));
+        let primary = match self.strategy {
+            CanonicalStrategy::Completeness => by_completeness().then_with(by_source),
+            CanonicalStrategy::SourcePriority => by_source().then_with(by_completeness),
+            CanonicalStrategy::LowestId => Ordering::Equal,
+        };
+        primary.then_with(|| a.record_id().cmp(b.record_id()))
+    }
+}
+
+/// An export row that can be chosen as the canonical record of its cluster.
+pub trait CanonicalCandidate {
+    fn record_id(&self) -> &str;
+    fn cluster(&self) -> Option<&str>;
+    fn source_system(&self) -> Option<&str>;
+    /// Number of descriptive fields that are present and non-blank.
+    fn completeness(&self) -> usize;
+    fn set_canonical(&mut self, canonical: bool);
+}
+
+/// Counts the fields that are present and not just whitespace.
+fn count_present(fields: &[&Option<String>]) -> usize {
+    fields.iter().filter(|f| f.as_deref().is_some_and(|v| !v.trim().is_empty())).count()
+}
+
+impl CanonicalCandidate for OrganizationExportRow {
+    fn record_id(&self) -> &str {
+        &self.entity_id
+    }
+    fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+    fn source_system(&self) -> Option<&str> {
+        self.contributor.as_deref()
+    }
+    fn completeness(&self) -> usize {
+        count_present(&[&self.contributor, &self.contributor_id, &self.name])
+    }
+    fn set_canonical(&mut self, canonical: bool) {
+        self.canonical_record = canonical;
+    }
+}
+
+impl CanonicalCandidate for ServiceExportRow {
+    fn record_id(&self) -> &str {
+        &self.service_id
+    }
+    fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+    fn source_system(&self) -> Option<&str> {
+        self.contributor.as_deref()
+    }
+    fn completeness(&self) -> usize {
+        count_present(&[
+            &self.contributor,
+            &self.contributor_id,
+            &self.organization_name,
+            &self.service_name,
+            &self.location_name,
+            &self.full_address,
+            &self.taxonomy_terms,
+        ])
+    }
+    fn set_canonical(&mut self, canonical: bool) {
+        self.canonical_record = canonical;
+    }
+}
+
+/// Marks exactly one row of every cluster with more than one exported member as
+/// canonical, and every other row as not canonical. The result does not depend on
+/// the order of `rows`.
+pub fn mark_canonical_records<T: CanonicalCandidate>(rows: &mut [T], selection: &CanonicalSelection) {
+    let mut best_by_cluster: HashMap<String, (usize, usize)> = HashMap::new(); // cluster -> (best index, member count)
+    for index in 0..rows.len() {
+        rows[index].set_canonical(false);
+        let Some(cluster) = rows[index].cluster() else { continue };
+        match best_by_cluster.get_mut(cluster) {
+            Some((best, count)) => {
+                *count += 1;
+                if selection.compare(&rows[index], &rows[*best]) == Ordering::Less {
+                    *best = index;
+                }
+            }
+            None => {
+                best_by_cluster.insert(cluster.to_string(), (index, 1));
+            }
+        }
+    }
+
+    for (best, count) in best_by_cluster.into_values() {
+        if count > 1 {
+            rows[best].set_canonical(true);
+        }
+    }
+}
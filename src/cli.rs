@@ -0,0 +1,425 @@
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::anonymize::AnonymizationKey;
+use crate::canonical::CanonicalStrategy;
+use crate::cluster_labels::ClusterLabelStyle;
+use crate::dashboard::CompletionWeights;
+use crate::dataset_split::ForeignRows;
+use crate::excel_writer::parse_output_path;
+use crate::graph_export::{GraphFormat, GraphSelection};
+use crate::models::{ExportSelection, RecordScope, SpotCheck};
+use crate::ndjson_export::ExportFormat;
+use crate::reclustering::{
+    ClusterNaming, ClusterTemplate, SkippedEdgeLimit, DEFAULT_CLUSTER_DESCRIPTION_TEMPLATE, DEFAULT_CLUSTER_NAME_TEMPLATE,
+};
+use crate::shared_reviewers::ConflictPolicy;
+use crate::sheet_selection::{SheetKind, SheetSelection};
+use crate::table_names::{validate_table_prefix, validate_timestamp_suffix};
+use crate::team_utils::TeamInfo;
+
+/// Command-line options for the interactive export.
+#[derive(Debug, Parser)]
+#[command(name = "export", about = "Export human-reviewed edge visualization data to Excel")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Skip the confirmation prompt (required when stdin is not a terminal)
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Write the workbook to this .xlsx path instead of the generated file name
+    #[arg(short = 'o', long, value_name = "PATH", value_parser = parse_output_path)]
+    pub output: Option<PathBuf>,
+
+    /// Keep this JSON file updated with the run's stage, progress and pool usage for external monitoring
+    #[arg(long, global = true, value_name = "PATH")]
+    pub status_file: Option<PathBuf>,
+
+    /// Replace output files that already exist instead of failing
+    #[arg(long, global = true, overrides_with = "no_overwrite")]
+    pub overwrite: bool,
+
+    /// Fail when an output file already exists (the default)
+    #[arg(long, global = true, overrides_with = "overwrite")]
+    pub no_overwrite: bool,
+
+    /// Export even when the opinion has no reviewed edges (all PENDING_REVIEW), without asking
+    #[arg(long)]
+    pub allow_unreviewed: bool,
+
+    /// Export a team with no whitelisted datasets, which includes records from every dataset
+    #[arg(long, global = true)]
+    pub allow_all_datasets: bool,
+
+    /// Include records whose source_system is NULL (excluded by the dataset filter by default)
+    #[arg(long, global = true)]
+    pub include_null_source_system: bool,
+
+    /// Leave these whitelisted datasets out of this export (comma-separated)
+    #[arg(long, global = true, value_name = "DATASETS", value_delimiter = ',')]
+    pub exclude_datasets: Vec<String>,
+
+    /// Match source_system against the whitelist exactly instead of case-insensitively and trimmed
+    #[arg(long, global = true)]
+    pub exact_dataset_match: bool,
+
+    /// Opinion to export by name instead of prompting; repeat to compare several opinions
+    #[arg(long = "opinion", value_name = "NAME")]
+    pub opinion: Vec<String>,
+
+    /// Select several opinions interactively and write them into one comparison workbook
+    #[arg(long)]
+    pub compare: bool,
+
+    /// Reuse the export tables of an earlier run with this timestamp suffix instead of creating and re-clustering new ones
+    #[arg(long, value_name = "SUFFIX", value_parser = validate_timestamp_suffix)]
+    pub from_timestamp: Option<String>,
+
+    /// Read the opinion tables named {PREFIX}_{opinion}_... and name the export after PREFIX, instead of the selected user's user_opinion_prefix; the user then only supplies the team, datasets and opinions
+    #[arg(long, value_name = "PREFIX", value_parser = validate_table_prefix)]
+    pub prefix_override: Option<String>,
+
+    /// Name the export tables and file with this suffix instead of the current time (letters, digits, underscores)
+    #[arg(long, value_name = "SUFFIX", conflicts_with = "from_timestamp", value_parser = validate_timestamp_suffix)]
+    pub timestamp_suffix: Option<String>,
+
+    /// Replace an earlier export with the same --timestamp-suffix without asking
+    #[arg(long, requires = "timestamp_suffix")]
+    pub replace: bool,
+
+    /// Never write to the database; only exports tables that already exist (requires --from-timestamp)
+    #[arg(long, requires = "from_timestamp")]
+    pub read_only: bool,
+
+    /// Export only entities (organizations); skips service tables, re-clustering, and the Services sheet
+    #[arg(long, conflicts_with_all = ["services_only", "compare"])]
+    pub entities_only: bool,
+
+    /// Export only services; skips entity tables, re-clustering, and the Organizations sheet
+    #[arg(long, conflicts_with = "compare")]
+    pub services_only: bool,
+
+    /// How to choose the canonical record of each duplicate cluster
+    #[arg(long, value_enum, default_value_t = CanonicalStrategy::Completeness)]
+    pub canonical_strategy: CanonicalStrategy,
+
+    /// Preferred source systems for choosing canonical records, most preferred first (comma-separated)
+    #[arg(long, value_name = "DATASETS", value_delimiter = ',')]
+    pub source_priority: Vec<String>,
+
+    /// Also write the re-clustered graph for visualization: `all` or `clusters=<id,id,...>`
+    #[arg(long, value_name = "SELECTION", conflicts_with = "compare")]
+    pub graph_export: Option<GraphSelection>,
+
+    /// File format for --graph-export
+    #[arg(long, value_enum, default_value_t = GraphFormat::Graphml, requires = "graph_export")]
+    pub graph_format: GraphFormat,
+
+    /// Export only this cluster for a spot check (repeatable); skips the Progress Overview
+    #[arg(long = "cluster-id", value_name = "ID", conflicts_with = "compare")]
+    pub cluster_ids: Vec<String>,
+
+    /// Export only the cluster containing this entity or service id (repeatable); skips the Progress Overview
+    #[arg(long = "entity-id", value_name = "ID", conflicts_with = "compare")]
+    pub entity_ids: Vec<String>,
+
+    /// Export only clusters with an edge reviewed on or after this date (YYYY-MM-DD), for a delta workbook
+    #[arg(long, value_name = "DATE", value_parser = parse_since_date)]
+    pub since: Option<NaiveDate>,
+
+    /// Development only: process just the first N edges and records of each type; the output is marked SAMPLE
+    #[arg(long, value_name = "N", value_parser = parse_sample_size)]
+    pub sample: Option<usize>,
+
+    /// Show at most N terms in the Services sheet's taxonomy_terms, followed by "(+k more)"; taxonomy_count keeps the full count
+    #[arg(long, value_name = "N", value_parser = parse_max_taxonomy_terms)]
+    pub max_taxonomy_terms: Option<usize>,
+
+    /// Load the re-clustered export tables with COPY instead of batched INSERTs; tables of 100,000 rows or more use COPY regardless
+    #[arg(long, visible_alias = "copy-inserts", conflicts_with = "read_only")]
+    pub fast_insert: bool,
+
+    /// Also copy the finished export tables to this database (a postgres:// URL; falls back to EXPORT_TARGET_URL / EXPORT_TARGET_HOST)
+    #[arg(long, value_name = "URL", conflicts_with = "from_timestamp")]
+    pub target_db: Option<String>,
+
+    /// Apply reviewer decisions from this CSV or xlsx file (edge_id or id_1/id_2, and decision) over the opinion's confirmed_status while re-clustering; the opinion tables are not changed
+    #[arg(long, value_name = "FILE", conflicts_with = "from_timestamp")]
+    pub overrides: Option<PathBuf>,
+
+    /// For an opinion shared with other users, merge the edge decisions of its owner and other_users into the exported ones before re-clustering; a decision wins over PENDING_REVIEW, and --conflict-policy settles a CONFIRMED_MATCH against a CONFIRMED_NON_MATCH
+    #[arg(long, conflicts_with = "from_timestamp")]
+    pub include_shared_reviewers: bool,
+
+    /// How --include-shared-reviewers resolves an edge one reviewer matched and another did not
+    #[arg(long, value_enum, default_value_t = ConflictPolicy::MostRestrictive, requires = "include_shared_reviewers")]
+    pub conflict_policy: ConflictPolicy,
+
+    /// Fail the export on a re-clustering inconsistency, such as a kept edge whose endpoints landed in different clusters, or on fetched rows that do not match the records in the datasets, instead of warning
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Fail the export when more edges of one record type than this have an empty endpoint id: a count such as 500 or a percentage such as 5%
+    #[arg(long, global = true, value_name = "N|P%", value_parser = SkippedEdgeLimit::parse)]
+    pub max_skipped_edges: Option<SkippedEdgeLimit>,
+
+    /// Replace names and addresses with pseudonyms keyed by EXPORT_ANONYMIZE_KEY and drop contributor ids and coordinates, for sharing outside the data-sharing agreement
+    #[arg(long, global = true)]
+    pub anonymize: bool,
+
+    /// Name of each re-clustered cluster; placeholders {seq}, {short_id}, {size}, {type}, {TYPE}, {opinion}
+    #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_CLUSTER_NAME_TEMPLATE)]
+    pub cluster_name_template: ClusterTemplate,
+
+    /// Description of each re-clustered cluster, with the same placeholders as --cluster-name-template
+    #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_CLUSTER_DESCRIPTION_TEMPLATE)]
+    pub cluster_description_template: ClusterTemplate,
+
+    /// Grant this role read access to the export schema and its tables (falls back to EXPORT_GRANT_ROLE)
+    #[arg(long, value_name = "ROLE", conflicts_with = "read_only")]
+    pub grant_role: Option<String>,
+
+    /// Leave out the bold totals row below the Organizations and Services data, for tools that read the sheets to the end
+    #[arg(long)]
+    pub no_footer: bool,
+
+    /// Add a "Service Taxonomies" sheet with one row per taxonomy term of each service, for filtering by term
+    #[arg(long)]
+    pub include_taxonomy_sheet: bool,
+
+    /// Read each saved workbook back and fail the run if its Organizations or Services sheet differs from the rows written
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Also write one workbook per dataset, with that dataset's rows and the matched rows of other datasets reduced per --foreign-rows, and a JSON manifest of the files
+    #[arg(long, conflicts_with = "compare")]
+    pub workbook_per_dataset: bool,
+
+    /// What a dataset's workbook shows of other datasets' rows in its clusters
+    #[arg(long, value_enum, default_value_t = ForeignRows::Minimal, requires = "workbook_per_dataset")]
+    pub foreign_rows: ForeignRows,
+
+    /// Write the organization and service rows to new tables in the export schema ({prefix}_{opinion}_org_export_{ts} and _svc_export_{ts}) instead of a workbook
+    #[arg(long, conflicts_with_all = ["compare", "from_timestamp", "output", "workbook_per_dataset"])]
+    pub export_to_table: bool,
+
+    /// Write the export as a workbook, or as one ndjson file per record type ({prefix}_{opinion}_organizations_{ts}.ndjson) with one row per line
+    #[arg(long, value_enum, default_value_t = ExportFormat::Xlsx, conflicts_with_all = ["compare", "export_to_table", "workbook_per_dataset"])]
+    pub format: ExportFormat,
+
+    /// Write only these sheets (comma-separated); the queries behind the others are skipped. Default: all
+    #[arg(long, value_enum, value_name = "SHEETS", value_delimiter = ',')]
+    pub sheets: Vec<SheetKind>,
+
+    /// Omit the Progress Overview sheet and skip the dashboard queries behind it
+    #[arg(long)]
+    pub no_progress_sheet: bool,
+
+    /// Weights for the weighted overall completion on the Progress Overview, e.g. entity=1,service=2
+    #[arg(long, value_name = "WEIGHTS", default_value_t = CompletionWeights::default())]
+    pub completion_weights: CompletionWeights,
+
+    /// Reviewed items per hour, used to estimate the remaining review time on the Progress Overview
+    #[arg(long, value_name = "ITEMS_PER_HOUR", value_parser = parse_review_rate)]
+    pub review_rate: Option<f64>,
+
+    /// How cluster ids are shown in the sheets; short styles keep the full id in a hidden cluster_id column
+    #[arg(long, value_enum, default_value_t = ClusterLabelStyle::Full)]
+    pub cluster_labels: ClusterLabelStyle,
+
+    /// Flag clusters whose weakest edge confidence is below this value (no flagging by default)
+    #[arg(long, value_name = "CONFIDENCE", value_parser = parse_confidence_threshold)]
+    pub low_confidence_threshold: Option<f64>,
+
+    /// Record this run's review stats in the export schema's snapshot table for the Progress Overview trend
+    #[arg(long, conflicts_with = "read_only")]
+    pub record_snapshot: bool,
+
+    /// Fail the export instead of omitting the Progress Overview sheet when its queries fail
+    #[arg(long, alias = "require-dashboard", conflicts_with = "no_progress_sheet")]
+    pub require_progress: bool,
+}
+
+impl Cli {
+    /// The clusters and records selected by `--cluster-id` / `--entity-id`.
+    pub fn spot_check(&self) -> SpotCheck {
+        SpotCheck {
+            cluster_ids: self.cluster_ids.clone(),
+            record_ids: self.entity_ids.clone(),
+        }
+    }
+
+    /// The spot check, `--since` date, `--sample` cap and `--max-taxonomy-terms` applied to every fetch.
+    pub fn export_selection(&self) -> ExportSelection {
+        ExportSelection {
+            spot_check: self.spot_check(),
+            since: self.since,
+            sample: self.sample,
+            max_taxonomy_terms: self.max_taxonomy_terms,
+        }
+    }
+
+    /// The key for `--anonymize`, read from `EXPORT_ANONYMIZE_KEY`; `None` without the flag.
+    /// The cluster graph carries names too, so it cannot be combined with `--graph-export`.
+    pub fn anonymization_key(&self) -> anyhow::Result<Option<AnonymizationKey>> {
+        if self.anonymize && self.graph_export.is_some() {
+            return Err(anyhow::anyhow!("--anonymize cannot be combined with --graph-export; the graph is not anonymized"));
+        }
+        self.anonymize.then(AnonymizationKey::from_env).transpose()
+    }
+
+    /// The sheets selected by `--sheets`.
+    pub fn sheet_selection(&self) -> SheetSelection {
+        SheetSelection::new(self.sheets.clone())
+    }
+
+    /// The cluster name and description templates.
+    pub fn cluster_naming(&self) -> ClusterNaming {
+        ClusterNaming {
+            name: self.cluster_name_template.clone(),
+            description: self.cluster_description_template.clone(),
+        }
+    }
+
+    /// The record types selected by `--entities-only` / `--services-only`, narrowed to
+    /// the data sheets in `--sheets`. `None` when the two leave no record type.
+    pub fn selected_scope(&self) -> Option<RecordScope> {
+        self.sheet_selection().narrow(self.flag_scope())
+    }
+
+    /// The record types to export. Falls back to the flags' scope when `--sheets` leaves
+    /// none, which `main` rejects up front through `selected_scope`.
+    pub fn record_scope(&self) -> RecordScope {
+        self.selected_scope().unwrap_or_else(|| self.flag_scope())
+    }
+
+    fn flag_scope(&self) -> RecordScope {
+        if self.entities_only {
+            RecordScope::EntitiesOnly
+        } else if self.services_only {
+            RecordScope::ServicesOnly
+        } else {
+            RecordScope::Both
+        }
+    }
+}
+
+/// Parses a strictly positive review rate.
+fn parse_review_rate(s: &str) -> Result<f64, String> {
+    let rate: f64 = s.trim().parse().map_err(|_| format!("invalid review rate '{}'", s))?;
+    if !rate.is_finite() || rate <= 0.0 {
+        return Err("review rate must be greater than zero".to_string());
+    }
+    Ok(rate)
+}
+
+/// Parses `--sample`, which must be at least one.
+fn parse_sample_size(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(0) => Err("sample size must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(_) => Err(format!("invalid sample size '{}'", s)),
+    }
+}
+
+/// Parses `--max-taxonomy-terms`, which must be at least one.
+fn parse_max_taxonomy_terms(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(0) => Err("the taxonomy term limit must be at least 1".to_string()),
+        Ok(max) => Ok(max),
+        Err(_) => Err(format!("invalid taxonomy term limit '{}'", s)),
+    }
+}
+
+/// Parses `--since`, a calendar date such as 2025-03-01.
+fn parse_since_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").map_err(|_| format!("invalid date '{}'; expected YYYY-MM-DD", s))
+}
+
+/// Parses `--low-confidence-threshold`, which must be a finite number.
+fn parse_confidence_threshold(s: &str) -> Result<f64, String> {
+    let threshold: f64 = s.trim().parse().map_err(|_| format!("invalid confidence threshold '{}'", s))?;
+    if !threshold.is_finite() {
+        return Err("confidence threshold must be a finite number".to_string());
+    }
+    Ok(threshold)
+}
+
+/// The error for an interactive export started without a terminal. The team and user
+/// can only be chosen at a prompt, so it names the flags and subcommands that run
+/// unattended and lists the teams, whose ids the subcommands take.
+pub fn no_terminal_message(teams: &[TeamInfo]) -> String {
+    let mut message = String::from(
+        "Cannot prompt for a team and user because stdin is not a terminal. Run the export from a terminal; \
+         --opinion NAME and --yes skip the opinion and confirmation prompts. Unattended runs can use \
+         `export consensus --team-id ID --opinion NAME` or \
+         `export diff --team-id ID --user-prefix PREFIX --opinion NAME OLD_SUFFIX NEW_SUFFIX`.",
+    );
+    if teams.is_empty() {
+        message.push_str("\nNo teams were found.");
+    } else {
+        message.push_str("\nAvailable teams (--team-id):");
+        for team in teams {
+            message.push_str(&format!("\n  {}  {} ({})", team.id, team.display_name, team.name));
+        }
+    }
+    message
+}
+
+/// Subcommands that replace the default interactive export.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Compare two existing exports of the same opinion and write a change report workbook
+    Diff(DiffArgs),
+    /// Re-cluster one opinion of every reviewer in a team and write a consensus workbook
+    Consensus(ConsensusArgs),
+    /// Check the database connection, schemas and permissions an export needs, with a hint for each failure
+    Doctor(DoctorArgs),
+}
+
+/// Arguments for the `diff` subcommand.
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// ID of the team whose whitelisted datasets filter both exports
+    #[arg(long)]
+    pub team_id: String,
+
+    /// Opinion prefix of the user whose exports are compared
+    #[arg(long, value_parser = validate_table_prefix)]
+    pub user_prefix: String,
+
+    /// Name of the exported opinion
+    #[arg(long)]
+    pub opinion: String,
+
+    /// Timestamp suffix of the older export
+    #[arg(value_parser = validate_timestamp_suffix)]
+    pub old_suffix: String,
+
+    /// Timestamp suffix of the newer export
+    #[arg(value_parser = validate_timestamp_suffix)]
+    pub new_suffix: String,
+}
+
+/// Arguments for the `consensus` subcommand.
+#[derive(Debug, Args)]
+pub struct ConsensusArgs {
+    /// ID of the team whose reviewers are compared
+    #[arg(long)]
+    pub team_id: String,
+
+    /// Name of the opinion to use from each reviewer; reviewers without it are skipped
+    #[arg(long)]
+    pub opinion: String,
+}
+
+/// Arguments for the `doctor` subcommand.
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Skip the check that creates and drops a table in the export schema
+    #[arg(long)]
+    pub read_only: bool,
+}
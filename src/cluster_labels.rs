@@ -0,0 +1,69 @@
+// src/cluster_labels.rs
+
+use clap::ValueEnum;
+use std::collections::{BTreeSet, HashMap};
+
+/// How cluster ids are shown in the spreadsheet's `cluster` columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ClusterLabelStyle {
+    /// The full cluster UUID
+    #[default]
+    Full,
+    /// "C-000001", "C-000002", ... assigned in sorted cluster id order
+    Sequential,
+    /// The first 8 characters of the cluster id
+    Short,
+}
+
+/// Cluster id to display label for one export. Built once from every cluster id in
+/// the workbook and shared by all sheet writers, so a cluster gets the same label on
+/// every sheet.
+#[derive(Debug, Clone, Default)]
+pub struct LabelMap {
+    style: ClusterLabelStyle,
+    labels: HashMap<String, String>,
+}
+
+impl LabelMap {
+    /// Assigns labels to the distinct `cluster_ids`. The result does not depend on
+    /// their order or repetition.
+    pub fn build<'a>(style: ClusterLabelStyle, cluster_ids: impl IntoIterator<Item = &'a str>) -> Self {
+        let ids: BTreeSet<&str> = cluster_ids.into_iter().collect();
+        let labels = match style {
+            ClusterLabelStyle::Full => HashMap::new(),
+            ClusterLabelStyle::Sequential => ids.iter()
+                .enumerate()
+                .map(|(index, id)| (id.to_string(), format!("C-{:06}", index + 1)))
+                .collect(),
+            ClusterLabelStyle::Short => {
+                // Ids whose prefixes collide keep their full id so labels stay unique
+                let mut prefix_counts: HashMap<&str, usize> = HashMap::new();
+                for id in &ids {
+                    *prefix_counts.entry(short_prefix(id)).or_default() += 1;
+                }
+                ids.iter()
+                    .map(|id| {
+                        let prefix = short_prefix(id);
+                        let label = if prefix_counts[prefix] > 1 { id } else { prefix };
+                        (id.to_string(), label.to_string())
+                    })
+                    .collect()
+            }
+        };
+        LabelMap { style, labels }
+    }
+
+    /// True when labels are the full cluster ids, so no separate id column is needed.
+    pub fn is_full(&self) -> bool {
+        self.style == ClusterLabelStyle::Full
+    }
+
+    /// The label for `cluster_id`; unknown ids are shown in full.
+    pub fn label<'a>(&'a self, cluster_id: &'a str) -> &'a str {
+        self.labels.get(cluster_id).map(String::as_str).unwrap_or(cluster_id)
+    }
+}
+
+fn short_prefix(id: &str) -> &str {
+    id.char_indices().nth(8).map_or(id, |(end, _)| &id[..end])
+}
@@ -0,0 +1,152 @@
+// src/cluster_lineage.rs
+// Keeps the pipeline's cluster ids where the reviewer's decisions left a cluster as it
+// was, so downstream links to those ids keep working. Re-clustering gives every cluster
+// a new id; a new cluster with exactly the members of an original one takes that
+// cluster's id and name back, and a split or merged cluster records the clusters it
+// was derived from.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tokio_postgres::GenericClient;
+
+use crate::table_names::{self, TEAM_SCHEMA};
+
+/// The pipeline's cluster assignments, read from the opinion's group tables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OriginalClusters {
+    /// Record id to the id of its original cluster
+    pub cluster_of: HashMap<String, String>,
+    /// Original cluster id to its name, where the cluster table has one
+    pub names: HashMap<String, String>,
+}
+
+impl OriginalClusters {
+    /// Records `id_1` and `id_2` as members of `cluster_id`. A record listed under two
+    /// clusters keeps the first.
+    pub fn insert(&mut self, cluster_id: &str, id_1: &str, id_2: &str) {
+        for id in [id_1, id_2] {
+            self.cluster_of.entry(id.to_string()).or_insert_with(|| cluster_id.to_string());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cluster_of.is_empty()
+    }
+}
+
+/// How the new clusters relate to the original ones, see `match_original_clusters`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterLineage {
+    /// New cluster id to the original cluster id it takes over
+    pub reused: HashMap<String, String>,
+    /// New cluster id to the original clusters its members came from, sorted; only for
+    /// clusters that did not take over an id and had members in an original cluster
+    pub derived_from: HashMap<String, Vec<String>>,
+}
+
+/// Compares each new cluster's members with the original clusters. A new cluster whose
+/// members are exactly the members of one original cluster reuses that cluster's id;
+/// any other cluster keeps its new id and lists the original clusters its members came
+/// from. Original clusters are compared only over `clusters`' records, so records
+/// outside the export (other datasets, or beyond a sample) do not count as changes.
+/// Each original id is reused at most once; new clusters are visited in id order so
+/// the same input always gives the same lineage.
+pub fn match_original_clusters(clusters: &HashMap<String, HashSet<String>>, original: &OriginalClusters) -> ClusterLineage {
+    let exported: HashSet<&str> = clusters.values().flatten().map(String::as_str).collect();
+    let mut original_members: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for (record_id, cluster_id) in &original.cluster_of {
+        if exported.contains(record_id.as_str()) {
+            original_members.entry(cluster_id.as_str()).or_default().insert(record_id.as_str());
+        }
+    }
+
+    let mut new_ids: Vec<&String> = clusters.keys().collect();
+    new_ids.sort();
+    let mut lineage = ClusterLineage::default();
+    let mut used: HashSet<&str> = HashSet::new();
+    for new_id in new_ids {
+        let members = &clusters[new_id];
+        let sources: BTreeSet<&str> = members.iter()
+            .filter_map(|id| original.cluster_of.get(id))
+            .map(String::as_str)
+            .collect();
+        let Some(&first) = sources.iter().next() else { continue };
+        let unchanged = members.iter().all(|id| original.cluster_of.get(id).map(String::as_str) == Some(first))
+            && members.len() == original_members[first].len();
+        if unchanged && used.insert(first) {
+            lineage.reused.insert(new_id.clone(), first.to_string());
+        } else {
+            lineage.derived_from.insert(new_id.clone(), sources.into_iter().map(str::to_string).collect());
+        }
+    }
+    lineage
+}
+
+/// Gives the clusters that take over an original id that id, in both the clusters and
+/// the record-to-cluster map. An original id already held by another cluster is left
+/// alone rather than overwriting that cluster.
+pub fn apply_reused_ids(
+    lineage: &ClusterLineage,
+    clusters: &mut HashMap<String, HashSet<String>>,
+    membership: &mut HashMap<String, String>,
+) {
+    for (new_id, original_id) in &lineage.reused {
+        if clusters.contains_key(original_id) {
+            continue;
+        }
+        if let Some(members) = clusters.remove(new_id) {
+            for member in &members {
+                membership.insert(member.clone(), original_id.clone());
+            }
+            clusters.insert(original_id.clone(), members);
+        }
+    }
+}
+
+/// The sentence appended to a derived cluster's description.
+pub fn derived_from_note(original_ids: &[String]) -> String {
+    format!(" Derived from {}.", original_ids.join(", "))
+}
+
+/// Reads the pipeline's cluster assignments for `entity_or_service` from the opinion's
+/// `{prefix}_{opinion}_{type}_group` table, with names from its `_group_cluster` table.
+/// Returns no assignments when the group table does not exist.
+pub async fn load_original_clusters<C: GenericClient>(
+    client: &C,
+    user_prefix: &str,
+    opinion_name: &str,
+    entity_or_service: &str,
+) -> Result<OriginalClusters> {
+    let exists = |table: String| async move {
+        let table = table_names::qualified(TEAM_SCHEMA, &table);
+        let exists: bool = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&table]).await
+            .context(format!("Failed to check whether {} exists", table))?
+            .get(0);
+        Ok::<_, anyhow::Error>(exists.then_some(table))
+    };
+    let mut original = OriginalClusters::default();
+    let Some(group_table) = exists(table_names::source_table(user_prefix, opinion_name, &format!("{}_group", entity_or_service))).await? else {
+        return Ok(original);
+    };
+
+    let query = format!(
+        "SELECT group_cluster_id, {0}_id_1, {0}_id_2 FROM {1} WHERE group_cluster_id IS NOT NULL",
+        entity_or_service, group_table
+    );
+    let rows = client.query(&query, &[]).await
+        .context(format!("Failed to read the original {} cluster assignments", entity_or_service))?;
+    for row in rows {
+        let cluster_id: String = row.get(0);
+        let (id_1, id_2): (Option<String>, Option<String>) = (row.get(1), row.get(2));
+        if let (Some(id_1), Some(id_2)) = (id_1, id_2) {
+            original.insert(&cluster_id, &id_1, &id_2);
+        }
+    }
+
+    if let Some(cluster_table) = exists(table_names::source_table(user_prefix, opinion_name, &format!("{}_group_cluster", entity_or_service))).await? {
+        let rows = client.query(&format!("SELECT id, name FROM {} WHERE name IS NOT NULL", cluster_table), &[]).await
+            .context(format!("Failed to read the original {} cluster names", entity_or_service))?;
+        original.names = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+    }
+    Ok(original)
+}
@@ -0,0 +1,81 @@
+// comparison.rs
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{OpinionComparisonRow, OpinionExport, OrganizationExportRow};
+
+/// Maps each record to the sorted set of records it shares a cluster with
+/// (including itself), given `(record_id, cluster_id)` pairs. Cluster ids are
+/// freshly minted on every re-clustering run, so cluster membership is the only
+/// thing comparable across opinions or across exports.
+pub fn cluster_memberships<'a, I>(assignments: I) -> HashMap<&'a str, BTreeSet<&'a str>>
+where
+    I: IntoIterator<Item = (&'a str, Option<&'a str>)>,
+{
+    let assignments: Vec<(&str, Option<&str>)> = assignments.into_iter().collect();
+
+    let mut members_by_cluster: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+    for (record_id, cluster) in &assignments {
+        if let Some(cluster) = cluster {
+            members_by_cluster.entry(cluster).or_default().insert(record_id);
+        }
+    }
+
+    assignments.iter()
+        .map(|(record_id, cluster)| {
+            let members = cluster
+                .and_then(|cluster| members_by_cluster.get(cluster).cloned())
+                .unwrap_or_else(|| BTreeSet::from([*record_id]));
+            (*record_id, members)
+        })
+        .collect()
+}
+
+/// Builds one comparison row per entity showing its cluster under each opinion.
+///
+/// Entities agree when every opinion that contains them places them in a cluster
+/// with exactly the same members. An opinion that has no row for an entity leaves
+/// an empty cell and does not count as a disagreement. Rows keep the order of the
+/// first opinion, followed by entities that only appear in later opinions.
+pub fn compare_opinion_clusters(exports: &[OpinionExport]) -> Vec<OpinionComparisonRow> {
+    let memberships: Vec<HashMap<&str, BTreeSet<&str>>> = exports.iter()
+        .map(|export| cluster_memberships(
+            export.org_data.iter().map(|r| (r.entity_id.as_str(), r.cluster.as_deref()))
+        ))
+        .collect();
+    let rows_by_entity: Vec<HashMap<&str, &OrganizationExportRow>> = exports.iter()
+        .map(|export| export.org_data.iter().map(|r| (r.entity_id.as_str(), r)).collect())
+        .collect();
+
+    let mut entity_order: Vec<&OrganizationExportRow> = Vec::new();
+    let mut seen: BTreeSet<&str> = BTreeSet::new();
+    for export in exports {
+        for row in &export.org_data {
+            if seen.insert(row.entity_id.as_str()) {
+                entity_order.push(row);
+            }
+        }
+    }
+
+    entity_order.into_iter()
+        .map(|first_row| {
+            let entity_id = first_row.entity_id.as_str();
+            let clusters: Vec<Option<String>> = rows_by_entity.iter()
+                .map(|rows| rows.get(entity_id).and_then(|r| r.cluster.clone()))
+                .collect();
+
+            let mut present = memberships.iter().filter_map(|m| m.get(entity_id));
+            let clusters_agree = match present.next() {
+                Some(first) => present.all(|members| members == first),
+                None => true,
+            };
+
+            OpinionComparisonRow {
+                entity_id: entity_id.to_string(),
+                name: first_row.name.clone(),
+                contributor: first_row.contributor.clone(),
+                clusters,
+                clusters_agree,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,133 @@
+// src/confirmed_status.rs
+
+use std::fmt;
+
+/// A reviewer's decision on an edge, as stored in `confirmed_status`.
+///
+/// Legacy rows use the synonyms "MATCH" and "NON_MATCH", and some are NULL. All
+/// readers (re-clustering, the dashboard counts, the fetch queries) interpret the
+/// column through this type so their totals agree. NULL and unknown values count as
+/// PENDING_REVIEW: the edge is kept for clustering but is not treated as reviewed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfirmedStatus {
+    PendingReview,
+    ConfirmedMatch,
+    ConfirmedNonMatch,
+}
+
+impl ConfirmedStatus {
+    pub const ALL: [ConfirmedStatus; 3] = [
+        ConfirmedStatus::PendingReview,
+        ConfirmedStatus::ConfirmedMatch,
+        ConfirmedStatus::ConfirmedNonMatch,
+    ];
+
+    /// The canonical value, as written to the export tables.
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            ConfirmedStatus::PendingReview => "PENDING_REVIEW",
+            ConfirmedStatus::ConfirmedMatch => "CONFIRMED_MATCH",
+            ConfirmedStatus::ConfirmedNonMatch => "CONFIRMED_NON_MATCH",
+        }
+    }
+
+    /// Every stored spelling of this status, canonical first.
+    pub fn db_values(self) -> &'static [&'static str] {
+        match self {
+            ConfirmedStatus::PendingReview => &["PENDING_REVIEW", "PENDING"],
+            ConfirmedStatus::ConfirmedMatch => &["CONFIRMED_MATCH", "MATCH"],
+            ConfirmedStatus::ConfirmedNonMatch => &["CONFIRMED_NON_MATCH", "NON_MATCH"],
+        }
+    }
+
+    /// Maps a stored value, trimmed and compared case-insensitively, to its status.
+    /// Returns `None` for a value that is not a known spelling.
+    pub fn from_db(value: &str) -> Option<ConfirmedStatus> {
+        let value = value.trim();
+        Self::ALL.into_iter()
+            .find(|status| status.db_values().iter().any(|known| known.eq_ignore_ascii_case(value)))
+    }
+
+    /// Whether a reviewer has decided the edge either way.
+    pub fn is_reviewed(self) -> bool {
+        self != ConfirmedStatus::PendingReview
+    }
+
+    /// Whether the edge connects its records when re-clustering. Only a confirmed
+    /// non-match breaks the connection.
+    pub fn keeps_connection(self) -> bool {
+        self != ConfirmedStatus::ConfirmedNonMatch
+    }
+
+    /// SQL expression that maps `column` to the canonical status strings the same
+    /// way `from_db` does, with NULL and unknown values as 'PENDING_REVIEW'.
+    pub fn sql_case(column: &str) -> String {
+        let branches: Vec<String> = [ConfirmedStatus::ConfirmedMatch, ConfirmedStatus::ConfirmedNonMatch].into_iter()
+            .map(|status| {
+                let values: Vec<String> = status.db_values().iter().map(|v| format!("'{}'", v)).collect();
+                format!("WHEN upper(btrim({})) IN ({}) THEN '{}'", column, values.join(", "), status.as_db_str())
+            })
+            .collect();
+        format!("(CASE {} ELSE '{}' END)", branches.join(" "), ConfirmedStatus::PendingReview.as_db_str())
+    }
+}
+
+impl fmt::Display for ConfirmedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_db_str())
+    }
+}
+
+/// A stored `confirmed_status` (NULL or an unknown string) that no status maps to,
+/// with how many edges carry it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmappedStatus {
+    pub value: Option<String>,
+    pub count: i64,
+}
+
+impl fmt::Display for UnmappedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "'{}'", value),
+            None => f.write_str("NULL"),
+        }
+    }
+}
+
+/// Edge counts per status, built from stored values and their counts. Unmapped
+/// values count as pending review and are also listed, so the totals always add
+/// up to the number of edges.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusTally {
+    pub pending_review: i64,
+    pub confirmed_match: i64,
+    pub confirmed_non_match: i64,
+    pub unmapped: Vec<UnmappedStatus>,
+}
+
+impl StatusTally {
+    /// Adds `count` edges stored with `value`, returning the status they count as.
+    pub fn add(&mut self, value: Option<&str>, count: i64) -> ConfirmedStatus {
+        let status = match value.and_then(ConfirmedStatus::from_db) {
+            Some(status) => status,
+            None => {
+                match self.unmapped.iter_mut().find(|u| u.value.as_deref() == value) {
+                    Some(unmapped) => unmapped.count += count,
+                    None => self.unmapped.push(UnmappedStatus { value: value.map(str::to_string), count }),
+                }
+                ConfirmedStatus::PendingReview
+            }
+        };
+        match status {
+            ConfirmedStatus::PendingReview => self.pending_review += count,
+            ConfirmedStatus::ConfirmedMatch => self.confirmed_match += count,
+            ConfirmedStatus::ConfirmedNonMatch => self.confirmed_non_match += count,
+        }
+        status
+    }
+
+    pub fn total(&self) -> i64 {
+        self.pending_review + self.confirmed_match + self.confirmed_non_match
+    }
+}
@@ -0,0 +1,156 @@
+// src/consensus.rs
+
+use std::collections::HashMap;
+
+use crate::models::{ConsensusRow, OrganizationExportRow};
+
+/// Where one reviewer placed an entity: an index into that reviewer's clusters and
+/// into the shared status table.
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    cluster: u32,
+    status: u8,
+}
+
+/// One reviewer's clustering, reduced to entity indexes so that only a few bytes
+/// per entity are kept for each reviewer.
+#[derive(Debug)]
+struct ReviewerClustering {
+    placements: HashMap<u32, Placement>,
+    /// Sorted member indexes of each cluster
+    clusters: Vec<Vec<u32>>,
+    /// Display label of each cluster; `None` for singletons
+    labels: Vec<Option<String>>,
+}
+
+/// Builds the team consensus sheet one reviewer at a time. Each reviewer's rows are
+/// reduced as soon as they are added, so the full export rows of all reviewers are
+/// never held at once; memory still grows with entities × reviewers.
+#[derive(Debug, Default)]
+pub struct ConsensusBuilder {
+    reviewers: Vec<String>,
+    clusterings: Vec<ReviewerClustering>,
+    entity_index: HashMap<String, u32>,
+    /// `(entity_id, name, contributor)` in first-seen order
+    entities: Vec<(String, Option<String>, Option<String>)>,
+    statuses: Vec<String>,
+}
+
+impl ConsensusBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reviewer_count(&self) -> usize {
+        self.reviewers.len()
+    }
+
+    /// Adds one reviewer's organization rows. Multi-member clusters are labelled
+    /// "C-000001", "C-000002", ... in the order they first appear in `rows`; labels
+    /// are per reviewer, since cluster ids are not comparable across opinions.
+    pub fn add_reviewer(&mut self, reviewer: &str, rows: Vec<OrganizationExportRow>) {
+        let mut cluster_index: HashMap<String, u32> = HashMap::new();
+        let mut clusters: Vec<Vec<u32>> = Vec::new();
+        let mut placements: HashMap<u32, Placement> = HashMap::with_capacity(rows.len());
+
+        for row in rows {
+            let entity = self.intern_entity(row.entity_id, row.name, row.contributor);
+            let status = self.intern_status(row.cluster_confirmed_status);
+            let cluster = match row.cluster {
+                Some(cluster_id) => *cluster_index.entry(cluster_id).or_insert_with(|| {
+                    clusters.push(Vec::new());
+                    (clusters.len() - 1) as u32
+                }),
+                // Unclustered rows form their own singleton
+                None => {
+                    clusters.push(Vec::new());
+                    (clusters.len() - 1) as u32
+                }
+            };
+            clusters[cluster as usize].push(entity);
+            placements.insert(entity, Placement { cluster, status });
+        }
+
+        let mut next_label = 0;
+        let labels = clusters.iter_mut()
+            .map(|members| {
+                members.sort_unstable();
+                members.dedup();
+                (members.len() > 1).then(|| {
+                    next_label += 1;
+                    format!("C-{:06}", next_label)
+                })
+            })
+            .collect();
+
+        self.reviewers.push(reviewer.to_string());
+        self.clusterings.push(ReviewerClustering { placements, clusters, labels });
+    }
+
+    /// Returns the reviewer names, in the order they were added, and one row per entity
+    /// in first-seen order.
+    ///
+    /// Reviewers agree on an entity when every reviewer with data for it places it
+    /// with exactly the same partners. A reviewer without data for an entity leaves an
+    /// empty cell and does not count as a disagreement.
+    pub fn build(self) -> (Vec<String>, Vec<ConsensusRow>) {
+        let rows = self.entities.iter()
+            .enumerate()
+            .map(|(index, (entity_id, name, contributor))| {
+                let index = index as u32;
+                let placed: Vec<Option<(&ReviewerClustering, Placement)>> = self.clusterings.iter()
+                    .map(|clustering| clustering.placements.get(&index).map(|p| (clustering, *p)))
+                    .collect();
+
+                let placements = placed.iter()
+                    .map(|placement| placement.map(|(clustering, p)| {
+                        let status = &self.statuses[p.status as usize];
+                        match &clustering.labels[p.cluster as usize] {
+                            Some(label) => format!("{} ({})", label, status),
+                            None => status.clone(),
+                        }
+                    }))
+                    .collect();
+
+                let mut members = placed.iter()
+                    .flatten()
+                    .map(|(clustering, p)| &clustering.clusters[p.cluster as usize]);
+                let reviewers_with_data = members.clone().count();
+                let agreement = match members.next() {
+                    Some(first) => members.all(|other| other == first),
+                    None => true,
+                };
+
+                ConsensusRow {
+                    entity_id: entity_id.clone(),
+                    name: name.clone(),
+                    contributor: contributor.clone(),
+                    placements,
+                    reviewers_with_data,
+                    agreement,
+                }
+            })
+            .collect();
+        (self.reviewers, rows)
+    }
+
+    fn intern_entity(&mut self, entity_id: String, name: Option<String>, contributor: Option<String>) -> u32 {
+        if let Some(index) = self.entity_index.get(&entity_id) {
+            return *index;
+        }
+        let index = self.entities.len() as u32;
+        self.entity_index.insert(entity_id.clone(), index);
+        self.entities.push((entity_id, name, contributor));
+        index
+    }
+
+    fn intern_status(&mut self, status: String) -> u8 {
+        match self.statuses.iter().position(|s| *s == status) {
+            Some(index) => index as u8,
+            None => {
+                self.statuses.push(status);
+                (self.statuses.len() - 1) as u8
+            }
+        }
+    }
+}
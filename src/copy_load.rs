@@ -0,0 +1,110 @@
+// src/copy_load.rs
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use futures::SinkExt;
+use log::debug;
+use std::fmt;
+use std::io::Cursor;
+use std::pin::pin;
+use tokio_postgres::Transaction;
+
+use crate::sql_guard::validate_export_write;
+use crate::table_names::EXPORT_SCHEMA;
+
+/// Row count from which a re-clustered table is loaded with COPY even without
+/// `--fast-insert`. Below it the UNNEST insert is fast enough and stays the default.
+/// Measured with `benchmark_copy_against_unnest` in tests/copy_load.rs on a local
+/// PostgreSQL 7-column group table: the two are even at 10,000 rows, and COPY is
+/// about 7% faster at 100,000 rows and 11% at 1,000,000 (6.6 s against 7.4 s).
+pub const COPY_ROW_THRESHOLD: usize = 100_000;
+
+/// How a batch of re-clustered rows is written to its export table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertMethod {
+    Unnest,
+    Copy,
+}
+
+impl InsertMethod {
+    /// COPY when `--fast-insert` asks for it or the batch has at least
+    /// `COPY_ROW_THRESHOLD` rows, UNNEST otherwise.
+    pub fn for_batch(fast_insert: bool, rows: usize) -> InsertMethod {
+        if fast_insert || rows >= COPY_ROW_THRESHOLD {
+            InsertMethod::Copy
+        } else {
+            InsertMethod::Unnest
+        }
+    }
+}
+
+impl fmt::Display for InsertMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InsertMethod::Unnest => "UNNEST",
+            InsertMethod::Copy => "COPY",
+        })
+    }
+}
+
+/// One row for `COPY ... FROM STDIN` in text format: each value already rendered as
+/// PostgreSQL text input, `None` for NULL.
+pub type CopyRow = Vec<Option<String>>;
+
+/// Escapes a value for the COPY text format, where backslash, tab, newline and
+/// carriage return would otherwise be read as delimiters or escapes.
+pub fn escape_copy_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders one row as a tab-separated COPY text line, with NULL as `\N`.
+pub fn copy_text_line(row: &[Option<String>]) -> String {
+    let fields: Vec<String> = row.iter()
+        .map(|value| value.as_deref().map(escape_copy_text).unwrap_or_else(|| "\\N".to_string()))
+        .collect();
+    format!("{}\n", fields.join("\t"))
+}
+
+/// A timestamp as COPY text input, keeping microseconds.
+pub fn copy_timestamp(value: NaiveDateTime) -> String {
+    value.format("%Y-%m-%d %H:%M:%S%.6f").to_string()
+}
+
+/// A boolean as COPY text input.
+pub fn copy_bool(value: bool) -> String {
+    if value { "t" } else { "f" }.to_string()
+}
+
+/// Loads `rows` into `table` in the export schema with `COPY ... FROM STDIN`, which
+/// is faster than a batched INSERT for large row counts. The statement goes through
+/// the same export-schema check as every other write.
+pub async fn copy_into_export_table(
+    tx: &Transaction<'_>,
+    table: &str,
+    columns: &[&str],
+    rows: &[CopyRow],
+) -> Result<u64> {
+    let statement = format!(r#"COPY "{}"."{}" ({}) FROM STDIN"#, EXPORT_SCHEMA, table, columns.join(", "));
+    validate_export_write(&statement)?;
+    debug!("Executing export COPY: {}", statement);
+
+    let data: String = rows.iter().map(|row| copy_text_line(row)).collect();
+    let sink = tx.copy_in(&statement).await
+        .context(format!("Failed to start COPY into {}", table))?;
+    let mut sink = pin!(sink);
+    sink.send(Cursor::new(data.into_bytes())).await
+        .context(format!("Failed to send COPY data for {}", table))?;
+    let copied = sink.as_mut().finish().await
+        .context(format!("Failed to finish COPY into {}", table))?;
+    Ok(copied)
+}
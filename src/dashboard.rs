@@ -1,14 +1,20 @@
 use anyhow::{Context, Result};
-use log::info;
+use log::{info, warn};
 use tokio_postgres::Client;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
+use crate::anonymize::KeyId;
+use crate::confirmed_status::{StatusTally, UnmappedStatus};
 use crate::db_connect::PgPool;
-use crate::team_utils::{TeamInfo, UserInfo, OpinionInfo, create_dataset_filter_clause};
+use crate::models::DatasetPairCount;
+use crate::snapshots::SnapshotPoint;
+use crate::sql_guard;
+use crate::table_names::{self, TEAM_SCHEMA};
+use crate::team_utils::{TeamInfo, UserInfo, OpinionInfo, DatasetFilterOptions, create_dataset_filter_clause};
 
-const TEAM_SCHEMA: &str = "wa211_to_wric";
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReviewStats {
     pub pending_review: i64,
     pub confirmed_match: i64,
@@ -18,17 +24,31 @@ pub struct ReviewStats {
     pub review_percentage: f64,
 }
 
+/// Review progress of one opinion, as seen by the user exporting it. A user with
+/// several opinions has one of these per opinion; `group_by_user` collects them.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct UserDashboard {
+pub struct OpinionDashboard {
     pub username: String,
     pub user_prefix: String,
     pub opinion_name: String,
+    /// Owner of the opinion when it is not the exporting user (a shared opinion)
+    #[serde(default)]
+    pub opinion_owner: Option<String>,
+    /// Usernames the opinion is shared with
+    #[serde(default)]
+    pub shared_with: Vec<String>,
     pub entity_stats: ReviewStats,
     pub service_stats: ReviewStats,
+    /// Earlier recorded snapshots of this user's opinion, oldest first (empty if none)
+    #[serde(default)]
+    pub trend: Vec<SnapshotPoint>,
+    /// Edge decisions per pair of datasets from re-clustering (empty when the tables were reused)
+    #[serde(default)]
+    pub dataset_pairs: Vec<DatasetPairCount>,
 }
 
 impl ReviewStats {
-    fn new(pending: i64, confirmed_match: i64, confirmed_non_match: i64) -> Self {
+    pub fn new(pending: i64, confirmed_match: i64, confirmed_non_match: i64) -> Self {
         let total = pending + confirmed_match + confirmed_non_match;
         let reviewed_count = confirmed_match + confirmed_non_match;
         let review_percentage = if total > 0 {
@@ -47,61 +67,282 @@ impl ReviewStats {
         }
     }
 
-    fn is_complete(&self) -> bool {
+    pub fn is_complete(&self) -> bool {
         self.pending_review == 0 && self.total > 0
     }
+
+    /// Completion as a fraction of 1, for percent-formatted cells.
+    pub fn completion_fraction(&self) -> Option<f64> {
+        completion_fraction(self.reviewed_count, self.total)
+    }
+
+    /// Completion as text, e.g. "42.5%", or "n/a" when there is nothing to review.
+    pub fn display_percentage(&self) -> String {
+        match self.completion_fraction() {
+            Some(fraction) => format!("{:.1}%", fraction * 100.0),
+            None => "n/a".to_string(),
+        }
+    }
+
+    /// The counts of `stats` added up, with the totals and percentage recomputed, so a
+    /// sum over opinions matches a sum over users of their subtotals.
+    pub fn sum<'a>(stats: impl IntoIterator<Item = &'a ReviewStats>) -> ReviewStats {
+        let (pending, confirmed_match, confirmed_non_match) = stats.into_iter().fold((0, 0, 0), |(p, m, n), s| {
+            (p + s.pending_review, m + s.confirmed_match, n + s.confirmed_non_match)
+        });
+        ReviewStats::new(pending, confirmed_match, confirmed_non_match)
+    }
+}
+
+/// The opinions of one user on the Progress Overview, in the order they were fetched.
+#[derive(Debug)]
+pub struct UserProgress<'a> {
+    pub username: &'a str,
+    pub user_prefix: &'a str,
+    pub opinions: Vec<&'a OpinionDashboard>,
+}
+
+impl UserProgress<'_> {
+    /// Entity counts summed over the user's opinions.
+    pub fn entity_totals(&self) -> ReviewStats {
+        ReviewStats::sum(self.opinions.iter().map(|o| &o.entity_stats))
+    }
+
+    /// Service counts summed over the user's opinions.
+    pub fn service_totals(&self) -> ReviewStats {
+        ReviewStats::sum(self.opinions.iter().map(|o| &o.service_stats))
+    }
+}
+
+/// Groups opinion dashboards under their users, keyed by username and prefix. Users
+/// appear in the order of their first opinion.
+pub fn group_by_user(data: &[OpinionDashboard]) -> Vec<UserProgress<'_>> {
+    let mut users: Vec<UserProgress> = Vec::new();
+    for opinion in data {
+        match users.iter_mut().find(|u| u.username == opinion.username && u.user_prefix == opinion.user_prefix) {
+            Some(user) => user.opinions.push(opinion),
+            None => users.push(UserProgress {
+                username: &opinion.username,
+                user_prefix: &opinion.user_prefix,
+                opinions: vec![opinion],
+            }),
+        }
+    }
+    users
+}
+
+/// Entity and service counts over every opinion of every user.
+pub fn overall_totals(data: &[OpinionDashboard]) -> (ReviewStats, ReviewStats) {
+    (
+        ReviewStats::sum(data.iter().map(|o| &o.entity_stats)),
+        ReviewStats::sum(data.iter().map(|o| &o.service_stats)),
+    )
+}
+
+/// `reviewed` out of `total` as a fraction of 1, or `None` when `total` is zero:
+/// nothing assigned is shown as "n/a" so it is not mistaken for no progress.
+pub fn completion_fraction(reviewed: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| reviewed as f64 / total as f64)
+}
+
+/// Edge counts per review decision summed over the given record types, labelled for
+/// the Progress Overview status chart. `None` when there are no edges at all.
+pub fn status_split(stats: &[&ReviewStats]) -> Option<[(&'static str, i64); 3]> {
+    let sum = |count: fn(&ReviewStats) -> i64| stats.iter().map(|s| count(s)).sum::<i64>();
+    let split = [
+        ("Pending Review", sum(|s| s.pending_review)),
+        ("Confirmed Match", sum(|s| s.confirmed_match)),
+        ("Confirmed Non-Match", sum(|s| s.confirmed_non_match)),
+    ];
+    split.iter().any(|&(_, count)| count > 0).then_some(split)
+}
+
+/// Relative weights of entity and service records in the weighted overall completion.
+/// The default of 1 for both gives the raw record-count percentage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompletionWeights {
+    pub entity: f64,
+    pub service: f64,
+}
+
+impl Default for CompletionWeights {
+    fn default() -> Self {
+        CompletionWeights { entity: 1.0, service: 1.0 }
+    }
+}
+
+impl FromStr for CompletionWeights {
+    type Err = String;
+
+    /// Parses `entity=1,service=2`. Either key may be omitted and keeps its default of 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = CompletionWeights::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", part))?;
+            let value: f64 = value.trim().parse()
+                .map_err(|_| format!("invalid weight '{}' for {}", value.trim(), key.trim()))?;
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("weight for {} must be a non-negative number", key.trim()));
+            }
+            match key.trim() {
+                "entity" => weights.entity = value,
+                "service" => weights.service = value,
+                other => return Err(format!("unknown record type '{}' (expected entity or service)", other)),
+            }
+        }
+        if weights.entity == 0.0 && weights.service == 0.0 {
+            return Err("at least one weight must be greater than zero".to_string());
+        }
+        Ok(weights)
+    }
+}
+
+impl fmt::Display for CompletionWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entity={}, service={}", self.entity, self.service)
+    }
+}
+
+/// Overall completion percentage with each record weighted by its type.
+/// With the default weights this is reviewed records over all records.
+pub fn weighted_completion_percentage(entity: &ReviewStats, service: &ReviewStats, weights: CompletionWeights) -> f64 {
+    let reviewed = entity.reviewed_count as f64 * weights.entity + service.reviewed_count as f64 * weights.service;
+    let total = entity.total as f64 * weights.entity + service.total as f64 * weights.service;
+    if total > 0.0 {
+        (reviewed / total) * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// What the workbook's Progress Overview is built from.
+#[derive(Debug)]
+pub enum DashboardSection {
+    /// Statistics were fetched and are shown in full.
+    Included(Vec<OpinionDashboard>),
+    /// The sheet was turned off with `--no-progress-sheet`.
+    Skipped,
+    /// Fetching the statistics failed; a placeholder sheet shows this error summary.
+    Failed(String),
+}
+
+/// Settings for the computed figures on the Progress Overview.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProgressOptions {
+    pub completion_weights: CompletionWeights,
+    /// Reviewed items per hour, used to estimate the time to completion
+    pub review_rate: Option<f64>,
+    /// Set by `--sample`; the export summary marks the workbook as a SAMPLE
+    pub sample: Option<usize>,
+    /// Set by `--anonymize`: the id of the key the pseudonyms were made with
+    pub anonymized: Option<KeyId>,
+}
+
+/// Formats the estimated time to review `remaining` items at `rate_per_hour`,
+/// e.g. "~3.5 days at 200/hr". Returns "done" when nothing remains.
+pub fn format_review_eta(remaining: i64, rate_per_hour: f64) -> String {
+    if remaining <= 0 {
+        return "done".to_string();
+    }
+    let hours = remaining as f64 / rate_per_hour;
+    let duration = if hours < 1.0 {
+        format!("{:.0} min", (hours * 60.0).ceil())
+    } else if hours < 48.0 {
+        format!("{:.1} hours", hours)
+    } else {
+        format!("{:.1} days", hours / 24.0)
+    };
+    format!("~{} at {}/hr", duration, rate_per_hour)
 }
 
 /// Fetches dashboard data for the selected user and opinion - used for Excel export progress overview
 /// Now filters by team's whitelisted datasets and uses opinion-based table naming
+///
+/// `counts` from an earlier `get_review_counts` call (the pre-export check) are
+/// reused as-is; when `None` they are queried here. `user_prefix` names the opinion
+/// tables: the user's own prefix, or `--prefix-override`.
 pub async fn get_dashboard_data(
     pool: &PgPool, 
     user_info: &UserInfo,
+    user_prefix: &str,
     opinion_info: &OpinionInfo,
-    team_info: &TeamInfo
-) -> Result<Vec<UserDashboard>> {
+    team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+    counts: Option<OpinionReviewCounts>,
+) -> Result<Vec<OpinionDashboard>> {
     info!("Fetching dashboard data for user '{}' with opinion '{}' and dataset filtering...", 
           user_info.username, opinion_info.name);
 
     let mut user_dashboards = Vec::new();
-    let client = pool.get().await.context("Failed to get DB client for dashboard")?;
-    
-    let user_prefix = user_info.user_opinion_prefix.as_deref()
-        .ok_or_else(|| anyhow::anyhow!("User has no opinion prefix set"))?;
-    
-    // Get entity review stats with dataset filtering and opinion-based table naming
-    let entity_stats = get_review_stats(&client, user_prefix, &opinion_info.name, "entity", &team_info.whitelisted_datasets).await
-        .with_context(|| format!("Failed to get entity stats for user {} with opinion {}", user_info.username, opinion_info.name))?;
+    let client = sql_guard::read_only_client(pool, "dashboard").await?;
     
-    // Get service review stats with dataset filtering and opinion-based table naming
-    let service_stats = get_review_stats(&client, user_prefix, &opinion_info.name, "service", &team_info.whitelisted_datasets).await
-        .with_context(|| format!("Failed to get service stats for user {} with opinion {}", user_info.username, opinion_info.name))?;
+    let OpinionReviewCounts { entity: entity_stats, service: service_stats, .. } = match counts {
+        Some(counts) => counts,
+        None => get_review_counts(&client, user_prefix, &opinion_info.name, team_info, filter_options).await
+            .with_context(|| format!("Failed to get review stats for user {} with opinion {}", user_info.username, opinion_info.name))?,
+    };
 
-    user_dashboards.push(UserDashboard {
+    user_dashboards.push(OpinionDashboard {
         username: user_info.username.clone(),
         user_prefix: user_prefix.to_string(),
         opinion_name: opinion_info.name.clone(),
+        opinion_owner: (opinion_info.user_id != user_info.id).then(|| opinion_info.owner_username.clone()),
+        shared_with: opinion_info.shared_with_usernames.clone(),
         entity_stats,
         service_stats,
+        trend: Vec::new(),
+        dataset_pairs: Vec::new(),
     });
 
-    info!("Collected stats for user: {} with opinion: {} (filtered by whitelisted datasets)", 
-          user_info.username, opinion_info.name);
+    if let Some(dashboard) = user_dashboards.first() {
+        info!("Collected stats for user: {} with opinion: {} (filtered by whitelisted datasets): entities {} reviewed, services {} reviewed",
+              user_info.username, opinion_info.name,
+              dashboard.entity_stats.display_percentage(), dashboard.service_stats.display_percentage());
+    }
 
     Ok(user_dashboards)
 }
 
 /// Fetches review statistics for a specific user, opinion, and record type (entity or service)
 /// Now includes opinion name in table naming and filtering by whitelisted datasets
+/// Entity and service review counts of one opinion, as shown on the Progress Overview.
+#[derive(Debug, Clone)]
+pub struct OpinionReviewCounts {
+    pub entity: ReviewStats,
+    pub service: ReviewStats,
+    /// NULL or unknown statuses counted as pending review, by record type
+    pub unmapped: Vec<(&'static str, UnmappedStatus)>,
+}
+
+/// Fetches the confirmed_status counts of an opinion's entity and service edges,
+/// filtered by the team's whitelisted datasets.
+pub async fn get_review_counts(
+    client: &Client,
+    user_prefix: &str,
+    opinion_name: &str,
+    team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+) -> Result<OpinionReviewCounts> {
+    let (entity, entity_unmapped) = get_review_stats(client, user_prefix, opinion_name, "entity", &team_info.whitelisted_datasets, filter_options).await
+        .context("Failed to get entity review stats")?;
+    let (service, service_unmapped) = get_review_stats(client, user_prefix, opinion_name, "service", &team_info.whitelisted_datasets, filter_options).await
+        .context("Failed to get service review stats")?;
+    let unmapped = entity_unmapped.into_iter().map(|u| ("entity", u))
+        .chain(service_unmapped.into_iter().map(|u| ("service", u)))
+        .collect();
+    Ok(OpinionReviewCounts { entity, service, unmapped })
+}
+
 async fn get_review_stats(
     client: &Client,
     user_prefix: &str,
     opinion_name: &str,
     record_type: &str, // "entity" or "service"
     whitelisted_datasets: &[String],
-) -> Result<ReviewStats> {
-    // Updated table naming to include opinion: {user_prefix}_{opinion_name}_{table_suffix}
-    let table_name = format!("{}_{}_{}_edge_visualization", user_prefix, opinion_name, record_type);
+    filter_options: DatasetFilterOptions,
+) -> Result<(ReviewStats, Vec<UnmappedStatus>)> {
+    let table_name = table_names::edge_viz_source(user_prefix, opinion_name, record_type);
     
     // Determine which ID columns and source table to use for filtering
     let (id_column_1, id_column_2, source_table, source_column) = match record_type {
@@ -112,7 +353,7 @@ async fn get_review_stats(
 
     // Create dataset filter clause
     let (dataset_filter, filter_params) = create_dataset_filter_clause(
-        "src", source_column, whitelisted_datasets, 1
+        "src", source_column, whitelisted_datasets, 1, filter_options
     );
 
     let query = format!(
@@ -121,12 +362,11 @@ async fn get_review_stats(
             ev.confirmed_status,
             COUNT(*) as count
         FROM "{}"."{}" ev
-        INNER JOIN public.{} src ON (src.id = ev.{} OR src.id = ev.{})
-        WHERE ev.confirmed_status IS NOT NULL 
-        AND {}
+        INNER JOIN {} src ON (src.id = ev.{} OR src.id = ev.{})
+        WHERE {}
         GROUP BY ev.confirmed_status
         "#,
-        TEAM_SCHEMA, table_name, source_table, id_column_1, id_column_2, dataset_filter
+        TEAM_SCHEMA, table_name, table_names::hsds_table(source_table), id_column_1, id_column_2, dataset_filter
     );
 
     // Convert filter_params to Vec<&(dyn ToSql + Sync)>
@@ -136,23 +376,21 @@ async fn get_review_stats(
         .collect();
 
     let rows = client.query(&query, &params).await
-        .context(format!("Failed to query {} edge visualization stats with dataset filtering and opinion '{}'", record_type, opinion_name))?;
-
-    let mut pending_review = 0i64;
-    let mut confirmed_match = 0i64;
-    let mut confirmed_non_match = 0i64;
+        .context(format!("Failed to query {} edge visualization stats from {} joined with {} (opinion '{}')",
+                         record_type, table_names::qualified(TEAM_SCHEMA, &table_name), table_names::hsds_table(source_table), opinion_name))?;
 
+    // NULL, legacy and unknown statuses are counted the way re-clustering treats them,
+    // so the totals add up to the edge count
+    let mut tally = StatusTally::default();
     for row in rows {
-        let status: String = row.get("confirmed_status");
+        let status: Option<String> = row.get("confirmed_status");
         let count: i64 = row.get("count");
-        
-        match status.as_str() {
-            "PENDING_REVIEW" => pending_review = count,
-            "CONFIRMED_MATCH" => confirmed_match = count,
-            "CONFIRMED_NON_MATCH" => confirmed_non_match = count,
-            _ => {}, // Ignore other statuses
-        }
+        tally.add(status.as_deref(), count);
+    }
+    for unmapped in &tally.unmapped {
+        warn!("{} {} edges of opinion '{}' have confirmed_status {}; counting them as pending review.",
+              unmapped.count, record_type, opinion_name, unmapped);
     }
 
-    Ok(ReviewStats::new(pending_review, confirmed_match, confirmed_non_match))
+    Ok((ReviewStats::new(tally.pending_review, tally.confirmed_match, tally.confirmed_non_match), tally.unmapped))
 }
\ No newline at end of file
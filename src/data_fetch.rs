@@ -1,11 +1,302 @@
 use anyhow::{Context, Result};
-use log::{info, debug};
+use chrono::NaiveDate;
+use log::{info, debug, warn};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use tokio_postgres::Client;
+use crate::address::AddressParts;
+use crate::confirmed_status::ConfirmedStatus;
 use crate::db_connect::PgPool;
-use crate::models::{OrganizationExportRow, ServiceExportRow};
-use crate::team_utils::{TeamInfo, create_dataset_filter_clause};
+use crate::export_schema::{self, ReviewColumns};
+use crate::run_warnings::{RunWarnings, WarningCategory};
+use crate::sql_guard;
+use crate::models::{ExportSelection, MethodSummary, OrganizationExportRow, OrganizationServices, RecordScope, ServiceExportRow, SpotCheck, TaxonomyExportRow};
+use crate::table_names::{self, EXPORT_SCHEMA, TEAM_SCHEMA};
+use crate::team_utils::{TeamInfo, DatasetFilterOptions, create_dataset_filter_clause, count_null_source_system};
 
-const EXPORT_SCHEMA: &str = "wa211_to_wric_exports";
+/// Counts the rows in the user's opinion-specific edge visualization table for
+/// the given record type ("entity" or "service"). Used for the pre-export
+/// confirmation summary, before any export tables are created.
+pub async fn count_edge_records(
+    client: &Client,
+    user_prefix: &str,
+    opinion_name: &str,
+    record_type: &str,
+) -> Result<i64> {
+    let table_name = table_names::edge_viz_source(user_prefix, opinion_name, record_type);
+    let query = format!(r#"SELECT COUNT(*) FROM "{}"."{}""#, TEAM_SCHEMA, table_name);
+    let row = client.query_one(&query, &[]).await
+        .context(format!("Failed to count rows in {}.{}", TEAM_SCHEMA, table_name))?;
+    Ok(row.get(0))
+}
+
+/// Logs and records a warning with the number of records that have a NULL
+/// `source_system` and are therefore dropped by the dataset filter, so the omission
+/// is never silent. Failures of the count itself are logged and otherwise ignored.
+/// Called once per record type before the rows are fetched.
+pub async fn warn_on_excluded_null_source_system(
+    client: &Client,
+    record_type: &str,
+    team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+    warnings: &RunWarnings,
+) {
+    // An empty whitelist matches everything, and include_nulls keeps them explicitly
+    if filter_options.include_nulls || team_info.whitelisted_datasets.is_empty() {
+        return;
+    }
+
+    match count_null_source_system(client, record_type).await {
+        Ok(0) => {}
+        Ok(count) => {
+            warn!(
+                "{} {} records have NULL source_system and are excluded from this export (use --include-null-source-system to include them).",
+                count, record_type
+            );
+            warnings.record(WarningCategory::NullSourceSystem, format!(
+                "{} {} records with NULL source_system were excluded", count, record_type
+            ));
+        }
+        Err(e) => warn!("Could not count {} records with NULL source_system: {:#}", record_type, e),
+    }
+}
+
+/// The SQL expressions for when and by whom an edge of the export edge table `ev`
+/// was reviewed. Columns the table lacks become NULLs, so older exports still fetch,
+/// except with `--since`, which needs a review time; see `check_since_columns`.
+async fn edge_review_expressions(client: &Client, edge_viz_table: &str, since: Option<NaiveDate>) -> Result<(String, String)> {
+    let columns = ReviewColumns::detect(client, EXPORT_SCHEMA, edge_viz_table).await?;
+    check_since_columns(columns, since, edge_viz_table)?;
+    if columns.reviewed_by.is_none() {
+        debug!("{} has no reviewed_by or reviewer_id column; reviewed_by will be empty.", edge_viz_table);
+    }
+    Ok((columns.review_time_expression("ev"), columns.reviewer_expression("ev")))
+}
+
+/// Where alternate names (DBA, aka) of an entity can come from. Deployments differ:
+/// some carry an `entity.alternate_names` text array, HSDS data has
+/// `organization.alternate_name`, and some have neither. Both live in the source schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AliasSources {
+    pub entity_alternate_names: bool,
+    pub organization_alternate_name: bool,
+}
+
+impl AliasSources {
+    /// Looks the columns up in `information_schema`.
+    pub async fn detect(client: &Client) -> Result<Self> {
+        let entity_columns = export_schema::table_columns(client, table_names::source_schema(), "entity").await?;
+        let organization_columns = export_schema::table_columns(client, table_names::source_schema(), "organization").await?;
+        Ok(AliasSources {
+            entity_alternate_names: export_schema::first_present_column(&entity_columns, &["alternate_names"]).is_some(),
+            organization_alternate_name: export_schema::first_present_column(&organization_columns, &["alternate_name"]).is_some(),
+        })
+    }
+
+    /// SQL for the alternate names of entity `alias` from every available source,
+    /// trimmed, distinct, sorted and joined with ", ". Names that only repeat the
+    /// entity's own name are left out. NULL when there are none or no source exists.
+    pub fn expression(self, alias: &str) -> String {
+        let mut sources = Vec::new();
+        if self.entity_alternate_names {
+            sources.push(format!("SELECT btrim(n) AS alternate_name FROM unnest({}.alternate_names) n", alias));
+        }
+        if self.organization_alternate_name {
+            sources.push(format!(
+                "SELECT btrim(ao.alternate_name) AS alternate_name FROM {} ao WHERE ao.id = {}.organization_id",
+                table_names::hsds_table("organization"), alias
+            ));
+        }
+        if sources.is_empty() {
+            return "NULL::text".to_string();
+        }
+        format!(
+            "(SELECT string_agg(DISTINCT a.alternate_name, ', ' ORDER BY a.alternate_name) FROM ({1}) a \
+             WHERE a.alternate_name <> '' AND lower(a.alternate_name) <> lower(COALESCE(btrim({0}.name), '')))",
+            alias, sources.join(" UNION ALL ")
+        )
+    }
+}
+
+/// Whether a cluster whose weakest edge has `min_edge_confidence` should be flagged
+/// for review. Never true without a threshold or for records without cluster edges.
+pub fn is_low_confidence(min_edge_confidence: Option<f64>, threshold: Option<f64>) -> bool {
+    matches!((min_edge_confidence, threshold), (Some(min), Some(threshold)) if min < threshold)
+}
+
+/// SQL aggregate over a cluster's edges that is true when a confirmed match sits
+/// alongside a pending edge or a non-match that did not split the cluster. `status`
+/// must already map to the canonical strings, as `ConfirmedStatus::sql_case` does.
+pub fn mixed_status_expression(status: &str) -> String {
+    format!("(COUNT(DISTINCT {0}) > 1 AND bool_or({0} = 'CONFIRMED_MATCH'))", status)
+}
+
+/// Summarizes the `contributing_methods` of every edge in the export edge tables of
+/// the record types in `scope`, for the "Method Breakdown" sheet.
+pub async fn fetch_method_breakdown(
+    pool: &PgPool,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    scope: RecordScope,
+) -> Result<Vec<MethodSummary>> {
+    let client = sql_guard::read_only_client(pool, "method breakdown fetch").await?;
+
+    let mut summaries = Vec::new();
+    for record_type in ["entity", "service"].into_iter().filter(|t| scope.includes(t)) {
+        let edge_viz_table = table_names::edge_viz_export(user_prefix, opinion_name, record_type, timestamp_suffix);
+        let query = format!(
+            r#"SELECT details -> 'contributing_methods' AS methods FROM "{}"."{}" WHERE details IS NOT NULL"#,
+            EXPORT_SCHEMA, edge_viz_table
+        );
+        let rows = client.query(&query, &[]).await
+            .context(format!("Failed to fetch contributing methods from {}.{}", EXPORT_SCHEMA, edge_viz_table))?;
+        let methods: Vec<serde_json::Value> = rows.iter()
+            .filter_map(|row| row.get::<_, Option<serde_json::Value>>("methods"))
+            .collect();
+        summaries.extend(summarize_contributing_methods(record_type, &methods));
+    }
+    sort_method_summaries(&mut summaries);
+
+    info!("Summarized {} contributing methods for opinion '{}'.", summaries.len(), opinion_name);
+    Ok(summaries)
+}
+
+/// Counts, per method type, the edges whose `contributing_methods` list it and the
+/// average confidence it reported. Each element of `methods` is one edge's list of
+/// `[method_type, confidence]` pairs, as stored in the edge `details`; lists that
+/// don't parse are skipped. A method listed twice on one edge counts once, with
+/// its highest confidence.
+pub fn summarize_contributing_methods(record_type: &str, methods: &[serde_json::Value]) -> Vec<MethodSummary> {
+    let mut totals: HashMap<String, (usize, f64)> = HashMap::new();
+    for edge_methods in methods {
+        let Ok(pairs) = serde_json::from_value::<Vec<(String, f64)>>(edge_methods.clone()) else {
+            continue;
+        };
+        let mut per_edge: HashMap<String, f64> = HashMap::new();
+        for (method_type, confidence) in pairs {
+            let best = per_edge.entry(method_type).or_insert(confidence);
+            *best = best.max(confidence);
+        }
+        for (method_type, confidence) in per_edge {
+            let entry = totals.entry(method_type).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += confidence;
+        }
+    }
+
+    let mut summaries: Vec<MethodSummary> = totals.into_iter()
+        .map(|(method_type, (edge_count, confidence_sum))| MethodSummary {
+            record_type: record_type.to_string(),
+            method_type,
+            edge_count,
+            average_confidence: confidence_sum / edge_count as f64,
+        })
+        .collect();
+    sort_method_summaries(&mut summaries);
+    summaries
+}
+
+/// Orders method summaries by edge count (highest first), then record type and method name.
+pub fn sort_method_summaries(summaries: &mut [MethodSummary]) {
+    summaries.sort_by(|a, b| {
+        b.edge_count.cmp(&a.edge_count)
+            .then_with(|| a.record_type.cmp(&b.record_type))
+            .then_with(|| a.method_type.cmp(&b.method_type))
+    });
+}
+
+/// Creates the WHERE clause restricting an export to a spot check's clusters.
+///
+/// A row matches when its cluster (`cluster_column`) is one of the requested cluster
+/// ids, when its record (`table_alias.id`) is one of the requested record ids, or when
+/// it shares a cluster with a requested record in `group_table`, so a single record id
+/// pulls in its whole cluster. Parameters are numbered from `param_start_index`,
+/// cluster ids first.
+pub fn create_spot_check_clause(
+    table_alias: &str,
+    cluster_column: &str,
+    group_table: &str,
+    record_type: &str, // "entity" or "service"
+    spot_check: &SpotCheck,
+    param_start_index: usize,
+) -> (String, Vec<String>) {
+    if spot_check.is_empty() {
+        return ("1=1".to_string(), vec![]);
+    }
+
+    let placeholders = |start: usize, count: usize| -> String {
+        (start..start + count).map(|i| format!("${}", i)).collect::<Vec<_>>().join(", ")
+    };
+
+    let mut conditions = Vec::new();
+    if !spot_check.cluster_ids.is_empty() {
+        conditions.push(format!(
+            "{} = ANY(ARRAY[{}])",
+            cluster_column, placeholders(param_start_index, spot_check.cluster_ids.len())
+        ));
+    }
+    if !spot_check.record_ids.is_empty() {
+        let records = placeholders(param_start_index + spot_check.cluster_ids.len(), spot_check.record_ids.len());
+        conditions.push(format!("{}.id = ANY(ARRAY[{}])", table_alias, records));
+        conditions.push(format!(
+            "{0} IN (SELECT g.group_cluster_id FROM {1} g WHERE g.{2}_id_1 = ANY(ARRAY[{3}]) OR g.{2}_id_2 = ANY(ARRAY[{3}]))",
+            cluster_column, group_table, record_type, records
+        ));
+    }
+
+    let params = spot_check.cluster_ids.iter().chain(&spot_check.record_ids).cloned().collect();
+    (format!("({})", conditions.join(" OR ")), params)
+}
+
+/// Fails a `--since` export whose edge table has neither `reviewed_at` nor
+/// `updated_at`: every cluster would then have no review time and be filtered out,
+/// leaving an empty workbook. `created_at` is no substitute, since re-clustering sets
+/// it to the time of the export.
+pub fn check_since_columns(columns: ReviewColumns, since: Option<NaiveDate>, edge_viz_table: &str) -> Result<()> {
+    if since.is_some() && !columns.reviewed_at && !columns.updated_at {
+        return Err(anyhow::anyhow!(
+            "--since needs a reviewed_at or updated_at column, but {} has neither",
+            table_names::qualified(EXPORT_SCHEMA, edge_viz_table)
+        ));
+    }
+    Ok(())
+}
+
+/// Creates the WHERE clause restricting a `--since` export to clusters with an edge
+/// reviewed on or after `since`, i.e. whose `last_reviewed_column` is that late.
+/// Unclustered records and clusters without a qualifying review never match.
+/// Returns "1=1" without a date.
+pub fn create_since_clause(last_reviewed_column: &str, since: Option<NaiveDate>) -> String {
+    match since {
+        Some(date) => format!("{} >= DATE '{}'", last_reviewed_column, date.format("%Y-%m-%d")),
+        None => "1=1".to_string(),
+    }
+}
+
+/// Creates the `ORDER BY ... LIMIT` suffix for a `--sample` run, taking the first
+/// `sample` rows by `order_column` so repeated runs see the same rows.
+/// Returns an empty string without a sample.
+pub fn create_sample_limit_clause(order_column: &str, sample: Option<usize>) -> String {
+    match sample {
+        Some(limit) => format!("ORDER BY {} LIMIT {}", order_column, limit),
+        None => String::new(),
+    }
+}
+
+/// Creates the WHERE clause restricting a `--sample` export to the records kept by
+/// re-clustering's capped isolated-node pass: the first `sample` ids of `source_table`
+/// that match `dataset_filter`, which must be written against the alias `sampled`.
+/// It is a subquery rather than an outer LIMIT so rows multiplied by joins are not cut short.
+pub fn create_sample_clause(table_alias: &str, source_table: &str, dataset_filter: &str, sample: Option<usize>) -> String {
+    if sample.is_none() {
+        return "1=1".to_string();
+    }
+    format!(
+        "{}.id IN (SELECT sampled.id FROM {} sampled WHERE {} {})",
+        table_alias, source_table, dataset_filter, create_sample_limit_clause("sampled.id", sample)
+    )
+}
 
 /// Fetches data for the organization-level export.
 /// Now filters by team's whitelisted datasets and uses opinion-based table naming
@@ -15,20 +306,37 @@ pub async fn fetch_organization_export_data(
     opinion_name: &str,
     timestamp_suffix: &str,
     team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+    selection: &ExportSelection,
 ) -> Result<Vec<OrganizationExportRow>> {
     info!("Fetching organization export data for user '{}' with opinion '{}' filtered by whitelisted datasets...", 
           user_prefix, opinion_name);
-    let client = pool.get().await.context("Failed to get DB client for organization data fetch")?;
+    let client = sql_guard::read_only_client(pool, "organization data fetch").await?;
 
-    // Updated table naming to include opinion: {user_prefix}_{opinion_name}_{table_suffix}_export_{timestamp}
-    let cluster_table = format!("{}_{}_entity_group_cluster_export_{}", user_prefix, opinion_name, timestamp_suffix);
-    let edge_viz_table = format!("{}_{}_entity_edge_visualization_export_{}", user_prefix, opinion_name, timestamp_suffix);
-    let group_table = format!("{}_{}_entity_group_export_{}", user_prefix, opinion_name, timestamp_suffix);
+    let cluster_table = table_names::cluster_export(user_prefix, opinion_name, "entity", timestamp_suffix);
+    let edge_viz_table = table_names::edge_viz_export(user_prefix, opinion_name, "entity", timestamp_suffix);
+    let group_table = table_names::group_export(user_prefix, opinion_name, "entity", timestamp_suffix);
+    let entity_table = table_names::hsds_table("entity");
+
+    let (review_time, reviewer) = edge_review_expressions(&client, &edge_viz_table, selection.since).await?;
+    let alias_sources = AliasSources::detect(&client).await?;
+    if alias_sources == AliasSources::default() {
+        debug!("Neither {0}.entity.alternate_names nor {0}.organization.alternate_name exists; alternate_names will be empty.", table_names::source_schema());
+    }
 
     // Create dataset filter clause for entities
-    let (dataset_filter, filter_params) = create_dataset_filter_clause(
-        "e", "source_system", &team_info.whitelisted_datasets, 1
+    let (dataset_filter, mut filter_params) = create_dataset_filter_clause(
+        "e", "source_system", &team_info.whitelisted_datasets, 1, filter_options
     );
+    let (spot_check_filter, spot_check_params) = create_spot_check_clause(
+        "e", "cs.cluster_id", &format!(r#""{}"."{}""#, EXPORT_SCHEMA, group_table), "entity", &selection.spot_check, filter_params.len() + 1
+    );
+    filter_params.extend(spot_check_params);
+    // Reuses the dataset filter's parameters, so it adds none of its own
+    let (sampled_dataset_filter, _) = create_dataset_filter_clause(
+        "sampled", "source_system", &team_info.whitelisted_datasets, 1, filter_options
+    );
+    let sample_filter = create_sample_clause("e", &entity_table, &sampled_dataset_filter, selection.sample);
 
     // Query that properly handles user opinion-based clusters with dataset filtering
     let query = format!(
@@ -40,7 +348,7 @@ pub async fn fetch_organization_export_data(
                 eg.group_cluster_id AS cluster_id,
                 egc.entity_count AS cluster_entity_count
             FROM
-                public.entity e
+                {entity_table} e
             LEFT JOIN
                 "{0}"."{3}" eg ON (eg.entity_id_1 = e.id OR eg.entity_id_2 = e.id)
             LEFT JOIN
@@ -57,8 +365,8 @@ pub async fn fetch_organization_export_data(
                     WHEN ec.cluster_id IS NULL THEN 'NO_MATCH'
                     WHEN COUNT(ev.id) = 0 THEN 
                         CASE WHEN ec.cluster_entity_count > 1 THEN 'CONFIRMED' ELSE 'NO_MATCH' END
-                    WHEN COUNT(CASE WHEN ev.confirmed_status = 'PENDING_REVIEW' THEN 1 END) > 0 THEN 'PENDING_REVIEW'
-                    WHEN COUNT(CASE WHEN ev.confirmed_status = 'CONFIRMED_MATCH' THEN 1 END) > 0 THEN 'CONFIRMED'
+                    WHEN COUNT(CASE WHEN {8} = 'PENDING_REVIEW' THEN 1 END) > 0 THEN 'PENDING_REVIEW'
+                    WHEN COUNT(CASE WHEN {8} = 'CONFIRMED_MATCH' THEN 1 END) > 0 THEN 'CONFIRMED'
                     ELSE 'NO_MATCH'
                 END AS cluster_confirmed_status
             FROM 
@@ -68,26 +376,68 @@ pub async fn fetch_organization_export_data(
                     AND ev.cluster_id = ec.cluster_id
             GROUP BY 
                 ec.entity_id, ec.cluster_id, ec.cluster_entity_count
+        ),
+        VisibleClusterSizes AS (
+            -- Count only the cluster members that pass the dataset filter, i.e. the rows
+            -- actually present in this export, so has_duplicates matches the sheet
+            SELECT DISTINCT
+                ec.cluster_id,
+                COUNT(*) OVER (PARTITION BY ec.cluster_id) AS visible_member_count
+            FROM
+                EntityClusters ec
+            WHERE ec.cluster_id IS NOT NULL
+        ),
+        ClusterReviews AS (
+            -- Most recent review of any edge in the cluster (pending edges don't count),
+            -- the weakest edge holding the cluster together, the share of its edges reviewed,
+            -- and whether confirmed matches sit alongside other decisions
+            SELECT
+                ev.cluster_id,
+                MAX({5}) FILTER (WHERE {8} <> 'PENDING_REVIEW') AS last_reviewed,
+                string_agg(DISTINCT {9}, ', ' ORDER BY {9}) FILTER (WHERE {8} <> 'PENDING_REVIEW') AS reviewed_by,
+                MIN(COALESCE((ev.details ->> 'total_confidence')::float8, ev.edge_weight)) AS min_edge_confidence,
+                COUNT(*) FILTER (WHERE {8} <> 'PENDING_REVIEW')::float8 / COUNT(*) AS reviewed_edge_fraction,
+                {10} AS mixed_status
+            FROM
+                "{0}"."{2}" ev
+            GROUP BY
+                ev.cluster_id
         )
         SELECT
             e.source_system AS contributor,
             e.source_id AS contributor_id,
             e.id AS entity_id,
             e.name AS name,
+            {11} AS alternate_names,
             COALESCE(cs.cluster_confirmed_status, 'NO_MATCH') AS cluster_confirmed_status,
             cs.cluster_id AS cluster,
-            COALESCE((cs.cluster_entity_count > 1), false) AS has_duplicates
+            COALESCE((vcs.visible_member_count > 1), false) AS has_duplicates,
+            -- Representative name: the longest trimmed name among the exported members,
+            -- ties broken alphabetically and then by entity id so the pick is deterministic
+            CASE WHEN cs.cluster_id IS NULL THEN NULL ELSE
+                first_value(NULLIF(btrim(e.name), '')) OVER (
+                    PARTITION BY cs.cluster_id
+                    ORDER BY length(NULLIF(btrim(e.name), '')) DESC NULLS LAST, btrim(e.name), e.id
+                )
+            END AS cluster_representative_name,
+            cr.last_reviewed,
+            cr.reviewed_by,
+            cr.min_edge_confidence,
+            cr.reviewed_edge_fraction,
+            COALESCE(cr.mixed_status, false) AS mixed_status
         FROM
-            public.entity e
+            {entity_table} e
         LEFT JOIN
             ClusterStatuses cs ON e.id = cs.entity_id
-        WHERE {4}
-        ORDER BY
-            CASE WHEN cs.cluster_id IS NULL THEN 1 ELSE 0 END, -- NULL clusters last
-            cs.cluster_id, 
-            e.name
+        LEFT JOIN
+            VisibleClusterSizes vcs ON vcs.cluster_id = cs.cluster_id
+        LEFT JOIN
+            ClusterReviews cr ON cr.cluster_id = cs.cluster_id
+        WHERE {4} AND {6} AND {7} AND {12}
         "#,
-        EXPORT_SCHEMA, cluster_table, edge_viz_table, group_table, dataset_filter
+        EXPORT_SCHEMA, cluster_table, edge_viz_table, group_table, dataset_filter, review_time, spot_check_filter, sample_filter,
+        ConfirmedStatus::sql_case("ev.confirmed_status"), reviewer, mixed_status_expression(&ConfirmedStatus::sql_case("ev.confirmed_status")),
+        alias_sources.expression("e"), create_since_clause("cr.last_reviewed", selection.since)
     );
 
     debug!("Fetching organization data with query: {}", query);
@@ -108,16 +458,161 @@ pub async fn fetch_organization_export_data(
             contributor_id: row.try_get("contributor_id").unwrap_or(None),
             entity_id: row.try_get("entity_id").unwrap(),
             name: row.try_get("name").unwrap_or(None),
+            alternate_names: row.try_get("alternate_names").unwrap_or(None),
             cluster_confirmed_status: row.try_get("cluster_confirmed_status").unwrap(),
             cluster: row.try_get("cluster").unwrap_or(None),
             has_duplicates: row.try_get("has_duplicates").unwrap(),
+            cluster_representative_name: row.try_get("cluster_representative_name").unwrap_or(None),
+            canonical_record: false,
+            last_reviewed: row.try_get("last_reviewed").unwrap_or(None),
+            reviewed_by: row.try_get("reviewed_by").unwrap_or(None),
+            min_edge_confidence: row.try_get("min_edge_confidence").unwrap_or(None),
+            low_confidence: false,
+            reviewed_edge_fraction: row.try_get("reviewed_edge_fraction").unwrap_or(None),
+            mixed_status: row.try_get("mixed_status").unwrap_or(false),
+            services: None,
         });
     }
+    sort_organization_rows(&mut data);
     
     info!("Fetched {} organization records for export (filtered by whitelisted datasets, opinion: {}).", data.len(), opinion_name);
     Ok(data)
 }
 
+/// Counts, per entity, its organization's services and how many of them have
+/// duplicates, with the most urgent service cluster status. Services are filtered by
+/// the team's whitelisted datasets and clustered the same way as in
+/// `fetch_service_export_data`. Keyed by entity id; entities whose organization has no
+/// exported services are absent.
+///
+/// Entities and services are linked through their organization: `entity.organization_id`
+/// and `service.organization_id` both reference `organization`, the same link the
+/// service query uses for `organization_name`.
+pub async fn fetch_organization_service_summaries(
+    pool: &PgPool,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+) -> Result<HashMap<String, OrganizationServices>> {
+    let client = sql_guard::read_only_client(pool, "organization service summary").await?;
+
+    let cluster_table = table_names::cluster_export(user_prefix, opinion_name, "service", timestamp_suffix);
+    let edge_viz_table = table_names::edge_viz_export(user_prefix, opinion_name, "service", timestamp_suffix);
+    let group_table = table_names::group_export(user_prefix, opinion_name, "service", timestamp_suffix);
+    let (entity_table, service_table) = (table_names::hsds_table("entity"), table_names::hsds_table("service"));
+
+    let (dataset_filter, filter_params) = create_dataset_filter_clause(
+        "s", "source_system", &team_info.whitelisted_datasets, 1, filter_options
+    );
+
+    let query = format!(
+        r#"
+        WITH ServiceClusters AS (
+            SELECT DISTINCT
+                s.id AS service_id,
+                s.organization_id,
+                sg.group_cluster_id AS cluster_id,
+                sgc.service_count AS cluster_service_count
+            FROM
+                {service_table} s
+            LEFT JOIN
+                "{0}"."{3}" sg ON (sg.service_id_1 = s.id OR sg.service_id_2 = s.id)
+            LEFT JOIN
+                "{0}"."{1}" sgc ON sgc.id = sg.group_cluster_id
+            WHERE s.organization_id IS NOT NULL AND {4}
+        ),
+        ClusterStatuses AS (
+            -- Same status rules as the Services sheet
+            SELECT
+                sc.service_id,
+                sc.organization_id,
+                sc.cluster_id,
+                CASE
+                    WHEN sc.cluster_id IS NULL THEN 'NO_MATCH'
+                    WHEN COUNT(sv.id) = 0 THEN
+                        CASE WHEN sc.cluster_service_count > 1 THEN 'CONFIRMED' ELSE 'NO_MATCH' END
+                    WHEN COUNT(CASE WHEN {5} = 'PENDING_REVIEW' THEN 1 END) > 0 THEN 'PENDING_REVIEW'
+                    WHEN COUNT(CASE WHEN {5} = 'CONFIRMED_MATCH' THEN 1 END) > 0 THEN 'CONFIRMED'
+                    ELSE 'NO_MATCH'
+                END AS cluster_confirmed_status
+            FROM
+                ServiceClusters sc
+            LEFT JOIN
+                "{0}"."{2}" sv ON (sv.service_id_1 = sc.service_id OR sv.service_id_2 = sc.service_id)
+                    AND sv.service_group_cluster_id = sc.cluster_id
+            GROUP BY
+                sc.service_id, sc.organization_id, sc.cluster_id, sc.cluster_service_count
+        ),
+        VisibleClusterSizes AS (
+            SELECT cluster_id, COUNT(*) AS visible_member_count
+            FROM ServiceClusters
+            WHERE cluster_id IS NOT NULL
+            GROUP BY cluster_id
+        )
+        SELECT
+            e.id AS entity_id,
+            COUNT(cs.service_id) AS service_count,
+            COUNT(*) FILTER (WHERE vcs.visible_member_count > 1) AS services_with_duplicates,
+            array_agg(DISTINCT cs.cluster_confirmed_status) AS statuses
+        FROM
+            {entity_table} e
+        JOIN
+            ClusterStatuses cs ON cs.organization_id = e.organization_id
+        LEFT JOIN
+            VisibleClusterSizes vcs ON vcs.cluster_id = cs.cluster_id
+        GROUP BY
+            e.id
+        "#,
+        EXPORT_SCHEMA, cluster_table, edge_viz_table, group_table, dataset_filter,
+        ConfirmedStatus::sql_case("sv.confirmed_status")
+    );
+
+    debug!("Fetching organization service summaries with query: {}", query);
+
+    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = filter_params
+        .iter()
+        .map(|s| s as &(dyn tokio_postgres::types::ToSql + Sync))
+        .collect();
+
+    let rows = client.query(&query, &params).await
+        .context("Failed to fetch the service summary of each organization")?;
+
+    let mut summaries = HashMap::new();
+    for row in rows {
+        let service_count: i64 = row.get("service_count");
+        let services_with_duplicates: i64 = row.get("services_with_duplicates");
+        let statuses: Vec<String> = row.get("statuses");
+        summaries.insert(row.get("entity_id"), OrganizationServices {
+            service_count: service_count as usize,
+            services_with_duplicates: services_with_duplicates as usize,
+            worst_status: worst_cluster_status(statuses.iter().map(String::as_str)).map(str::to_string),
+        });
+    }
+    info!("Fetched service summaries for {} entities (opinion: {}).", summaries.len(), opinion_name);
+    Ok(summaries)
+}
+
+/// The most urgent of the given `cluster_confirmed_status` values: PENDING_REVIEW,
+/// then CONFIRMED, then NO_MATCH. `None` when there are no values.
+pub fn worst_cluster_status<'a>(statuses: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let urgency = |status: &str| match status {
+        "PENDING_REVIEW" => 2,
+        "CONFIRMED" => 1,
+        _ => 0,
+    };
+    statuses.into_iter().max_by_key(|status| urgency(status))
+}
+
+/// Copies each entity's service summary onto its row. Entities without an entry get
+/// zero counts, since their organization has no exported services.
+pub fn apply_service_summaries(rows: &mut [OrganizationExportRow], summaries: &HashMap<String, OrganizationServices>) {
+    for row in rows {
+        row.services = Some(summaries.get(&row.entity_id).cloned().unwrap_or_default());
+    }
+}
+
 /// Fetches data for the service-level export.
 /// Now filters by team's whitelisted datasets and uses opinion-based table naming
 pub async fn fetch_service_export_data(
@@ -126,23 +621,37 @@ pub async fn fetch_service_export_data(
     opinion_name: &str,
     timestamp_suffix: &str,
     team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+    selection: &ExportSelection,
 ) -> Result<Vec<ServiceExportRow>> {
     info!("Fetching service export data for user '{}' with opinion '{}' filtered by whitelisted datasets...", 
           user_prefix, opinion_name);
-    let client = pool.get().await.context("Failed to get DB client for service data fetch")?;
+    let client = sql_guard::read_only_client(pool, "service data fetch").await?;
 
-    // Updated table naming to include opinion: {user_prefix}_{opinion_name}_{table_suffix}_export_{timestamp}
-    let cluster_table = format!("{}_{}_service_group_cluster_export_{}", user_prefix, opinion_name, timestamp_suffix);
-    let edge_viz_table = format!("{}_{}_service_edge_visualization_export_{}", user_prefix, opinion_name, timestamp_suffix);
-    let group_table = format!("{}_{}_service_group_export_{}", user_prefix, opinion_name, timestamp_suffix);
+    let cluster_table = table_names::cluster_export(user_prefix, opinion_name, "service", timestamp_suffix);
+    let edge_viz_table = table_names::edge_viz_export(user_prefix, opinion_name, "service", timestamp_suffix);
+    let group_table = table_names::group_export(user_prefix, opinion_name, "service", timestamp_suffix);
+    let [service_table, organization_table, service_at_location_table, location_table, address_table, service_taxonomy_table, taxonomy_term_table] =
+        ["service", "organization", "service_at_location", "location", "address", "service_taxonomy", "taxonomy_term"].map(table_names::hsds_table);
 
     // The service edge visualization table uses 'service_group_cluster_id'
     let service_cluster_id_column_name = "service_group_cluster_id";
 
+    let (review_time, reviewer) = edge_review_expressions(&client, &edge_viz_table, selection.since).await?;
+
     // Create dataset filter clause for services
-    let (dataset_filter, filter_params) = create_dataset_filter_clause(
-        "s", "source_system", &team_info.whitelisted_datasets, 1
+    let (dataset_filter, mut filter_params) = create_dataset_filter_clause(
+        "s", "source_system", &team_info.whitelisted_datasets, 1, filter_options
     );
+    let (spot_check_filter, spot_check_params) = create_spot_check_clause(
+        "s", "cs.cluster_id", &format!(r#""{}"."{}""#, EXPORT_SCHEMA, group_table), "service", &selection.spot_check, filter_params.len() + 1
+    );
+    filter_params.extend(spot_check_params);
+    // Reuses the dataset filter's parameters, so it adds none of its own
+    let (sampled_dataset_filter, _) = create_dataset_filter_clause(
+        "sampled", "source_system", &team_info.whitelisted_datasets, 1, filter_options
+    );
+    let sample_filter = create_sample_clause("s", &service_table, &sampled_dataset_filter, selection.sample);
 
     // Query that properly handles user opinion-based service clusters with taxonomy data and dataset filtering
     let query = format!(
@@ -154,7 +663,7 @@ pub async fn fetch_service_export_data(
                 sg.group_cluster_id AS cluster_id,
                 sgc.service_count AS cluster_service_count
             FROM
-                public.service s
+                {service_table} s
             LEFT JOIN
                 "{0}"."{3}" sg ON (sg.service_id_1 = s.id OR sg.service_id_2 = s.id)
             LEFT JOIN
@@ -171,8 +680,8 @@ pub async fn fetch_service_export_data(
                     WHEN sc.cluster_id IS NULL THEN 'NO_MATCH'
                     WHEN COUNT(sv.id) = 0 THEN 
                         CASE WHEN sc.cluster_service_count > 1 THEN 'CONFIRMED' ELSE 'NO_MATCH' END
-                    WHEN COUNT(CASE WHEN sv.confirmed_status = 'PENDING_REVIEW' THEN 1 END) > 0 THEN 'PENDING_REVIEW'
-                    WHEN COUNT(CASE WHEN sv.confirmed_status = 'CONFIRMED_MATCH' THEN 1 END) > 0 THEN 'CONFIRMED'
+                    WHEN COUNT(CASE WHEN {9} = 'PENDING_REVIEW' THEN 1 END) > 0 THEN 'PENDING_REVIEW'
+                    WHEN COUNT(CASE WHEN {9} = 'CONFIRMED_MATCH' THEN 1 END) > 0 THEN 'CONFIRMED'
                     ELSE 'NO_MATCH'
                 END AS cluster_confirmed_status
             FROM 
@@ -182,6 +691,32 @@ pub async fn fetch_service_export_data(
                     AND sv.{4} = sc.cluster_id
             GROUP BY 
                 sc.service_id, sc.cluster_id, sc.cluster_service_count
+        ),
+        VisibleClusterSizes AS (
+            -- Count only the cluster members that pass the dataset filter, i.e. the rows
+            -- actually present in this export, so has_duplicates matches the sheet
+            SELECT DISTINCT
+                sc.cluster_id,
+                COUNT(*) OVER (PARTITION BY sc.cluster_id) AS visible_member_count
+            FROM
+                ServiceClusters sc
+            WHERE sc.cluster_id IS NOT NULL
+        ),
+        ClusterReviews AS (
+            -- Most recent review of any edge in the cluster (pending edges don't count),
+            -- the weakest edge holding the cluster together, the share of its edges reviewed,
+            -- and whether confirmed matches sit alongside other decisions
+            SELECT
+                ev.{4} AS cluster_id,
+                MAX({6}) FILTER (WHERE {10} <> 'PENDING_REVIEW') AS last_reviewed,
+                string_agg(DISTINCT {11}, ', ' ORDER BY {11}) FILTER (WHERE {10} <> 'PENDING_REVIEW') AS reviewed_by,
+                MIN(COALESCE((ev.details ->> 'total_confidence')::float8, ev.edge_weight)) AS min_edge_confidence,
+                COUNT(*) FILTER (WHERE {10} <> 'PENDING_REVIEW')::float8 / COUNT(*) AS reviewed_edge_fraction,
+                {12} AS mixed_status
+            FROM
+                "{0}"."{2}" ev
+            GROUP BY
+                ev.{4}
         )
         SELECT
             s.contributor_id AS contributor,
@@ -191,51 +726,72 @@ pub async fn fetch_service_export_data(
             s.name AS service_name,
             (
                 SELECT l.name
-                FROM public.service_at_location sal
-                JOIN public.location l ON sal.location_id = l.id
+                FROM {service_at_location_table} sal
+                JOIN {location_table} l ON sal.location_id = l.id
                 WHERE sal.service_id = s.id
                 ORDER BY sal.id
                 LIMIT 1
             ) AS location_name,
-            (
-                SELECT 
-                    a.address_1 || 
-                    COALESCE(', ' || a.address_2, '') || 
-                    ', ' || a.city || 
-                    ', ' || a.state_province || 
-                    ' ' || a.postal_code || 
-                    ', ' || a.country
-                FROM public.address a
-                JOIN public.service_at_location sal ON a.location_id = sal.location_id
-                WHERE sal.service_id = s.id
-                ORDER BY sal.id, a.id
-                LIMIT 1
-            ) AS full_address,
+            sa.has_address,
+            sa.address_1,
+            sa.address_2,
+            sa.city,
+            sa.state_province,
+            sa.postal_code,
+            sa.country,
+            sa.latitude,
+            sa.longitude,
             COALESCE(cs.cluster_confirmed_status, 'NO_MATCH') AS cluster_confirmed_status,
             t.id AS taxonomy_id,
             t.term AS taxonomy_term,
             t.description AS taxonomy_description,
             t.taxonomy AS taxonomy_category,
             cs.cluster_id AS cluster,
-            COALESCE((cs.cluster_service_count > 1), false) AS has_duplicates
+            COALESCE((vcs.visible_member_count > 1), false) AS has_duplicates,
+            cr.last_reviewed,
+            cr.reviewed_by,
+            cr.min_edge_confidence,
+            cr.reviewed_edge_fraction,
+            COALESCE(cr.mixed_status, false) AS mixed_status
         FROM
-            public.service s
+            {service_table} s
         LEFT JOIN 
-            public.organization o ON s.organization_id = o.id
+            {organization_table} o ON s.organization_id = o.id
         LEFT JOIN
             ClusterStatuses cs ON s.id = cs.service_id
+        LEFT JOIN
+            VisibleClusterSizes vcs ON vcs.cluster_id = cs.cluster_id
+        LEFT JOIN
+            ClusterReviews cr ON cr.cluster_id = cs.cluster_id
+        LEFT JOIN LATERAL (
+            -- The address and its coordinates come from the same location, so they agree.
+            -- Components are assembled in AddressParts, which skips the missing ones.
+            SELECT
+                true AS has_address,
+                a.address_1,
+                a.address_2,
+                a.city,
+                a.state_province,
+                a.postal_code,
+                a.country,
+                l.latitude::float8 AS latitude,
+                l.longitude::float8 AS longitude
+            FROM {address_table} a
+            JOIN {service_at_location_table} sal ON a.location_id = sal.location_id
+            LEFT JOIN {location_table} l ON l.id = sal.location_id
+            WHERE sal.service_id = s.id
+            ORDER BY sal.id, a.id
+            LIMIT 1
+        ) sa ON true
         LEFT JOIN 
-            public.service_taxonomy st ON s.id = st.service_id
+            {service_taxonomy_table} st ON s.id = st.service_id
         LEFT JOIN 
-            public.taxonomy_term t ON st.taxonomy_term_id = t.id
-        WHERE {5}
-        ORDER BY
-            CASE WHEN cs.cluster_id IS NULL THEN 1 ELSE 0 END, -- NULL clusters last
-            cs.cluster_id, 
-            s.name,
-            t.term
+            {taxonomy_term_table} t ON st.taxonomy_term_id = t.id
+        WHERE {5} AND {7} AND {8} AND {13}
         "#,
-        EXPORT_SCHEMA, cluster_table, edge_viz_table, group_table, service_cluster_id_column_name, dataset_filter
+        EXPORT_SCHEMA, cluster_table, edge_viz_table, group_table, service_cluster_id_column_name, dataset_filter, review_time,
+        spot_check_filter, sample_filter, ConfirmedStatus::sql_case("sv.confirmed_status"), ConfirmedStatus::sql_case("ev.confirmed_status"),
+        reviewer, mixed_status_expression(&ConfirmedStatus::sql_case("ev.confirmed_status")), create_since_clause("cr.last_reviewed", selection.since)
     );
 
     debug!("Fetching service data with query: {}", query);
@@ -249,68 +805,134 @@ pub async fn fetch_service_export_data(
     let rows = client.query(&query, &params).await
         .context("Failed to fetch service export data with dataset filtering and opinion-based tables")?;
 
-    // Group rows by service_id to handle multiple taxonomy terms per service
-    let mut service_map: HashMap<String, Vec<tokio_postgres::Row>> = HashMap::new();
+    // One row per (service, taxonomy term); the taxonomy terms are merged in assembly
+    let parts: Vec<(ServiceExportRow, Option<TaxonomyExportRow>)> = rows.iter()
+        .map(|row| {
+            // No address row at all leaves both address columns blank
+            let has_address: Option<bool> = row.try_get("has_address").unwrap_or(None);
+            let address = has_address.map(|_| AddressParts {
+                address_1: row.try_get("address_1").unwrap_or(None),
+                address_2: row.try_get("address_2").unwrap_or(None),
+                city: row.try_get("city").unwrap_or(None),
+                state_province: row.try_get("state_province").unwrap_or(None),
+                postal_code: row.try_get("postal_code").unwrap_or(None),
+                country: row.try_get("country").unwrap_or(None),
+            });
+            let service = ServiceExportRow {
+                contributor: row.try_get("contributor").unwrap_or(None),
+                contributor_id: row.try_get("contributor_id").unwrap_or(None),
+                service_id: row.try_get("service_id").unwrap(),
+                organization_name: row.try_get("organization_name").unwrap_or(None),
+                service_name: row.try_get("service_name").unwrap_or(None),
+                location_name: row.try_get("location_name").unwrap_or(None),
+                full_address: address.as_ref().and_then(AddressParts::full_address),
+                address_missing_parts: address.as_ref().map(AddressParts::missing_parts),
+                latitude: row.try_get("latitude").unwrap_or(None),
+                longitude: row.try_get("longitude").unwrap_or(None),
+                cluster_confirmed_status: row.try_get("cluster_confirmed_status").unwrap(),
+                taxonomy_terms: None,
+                taxonomy_count: 0,
+                taxonomies: Vec::new(),
+                cluster: row.try_get("cluster").unwrap_or(None),
+                has_duplicates: row.try_get("has_duplicates").unwrap(),
+                canonical_record: false,
+                last_reviewed: row.try_get("last_reviewed").unwrap_or(None),
+                reviewed_by: row.try_get("reviewed_by").unwrap_or(None),
+                min_edge_confidence: row.try_get("min_edge_confidence").unwrap_or(None),
+                low_confidence: false,
+                reviewed_edge_fraction: row.try_get("reviewed_edge_fraction").unwrap_or(None),
+                mixed_status: row.try_get("mixed_status").unwrap_or(false),
+            };
+            let taxonomy_id: Option<String> = row.try_get("taxonomy_id").unwrap_or(None);
+            let taxonomy_term: Option<String> = row.try_get("taxonomy_term").unwrap_or(None);
+            let taxonomy = taxonomy_id.zip(taxonomy_term).map(|(taxonomy_id, taxonomy_term)| TaxonomyExportRow {
+                service_id: service.service_id.clone(),
+                taxonomy_term,
+                taxonomy_category: row.try_get("taxonomy_category").unwrap_or(None),
+                taxonomy_id,
+            });
+            (service, taxonomy)
+        })
+        .collect();
 
-    for row in rows {
-        let service_id: String = row.try_get("service_id").unwrap();
-        service_map.entry(service_id).or_insert_with(Vec::new).push(row);
+    let data = assemble_service_rows(parts, selection.max_taxonomy_terms);
+    
+    info!("Fetched {} service records for export (filtered by whitelisted datasets, opinion: {}).", data.len(), opinion_name);
+    Ok(data)
+}
+
+/// Groups per-taxonomy-term service rows into one row per service, joining the
+/// sorted, distinct terms with ", " (see `join_taxonomy_terms`) and keeping the terms,
+/// sorted by term and id, in `taxonomies`. Returns them in `sort_service_rows` order.
+/// The result does not depend on the order of `parts`.
+pub fn assemble_service_rows(parts: Vec<(ServiceExportRow, Option<TaxonomyExportRow>)>, max_terms: Option<usize>) -> Vec<ServiceExportRow> {
+    // Group rows by service_id to handle multiple taxonomy terms per service
+    let mut service_map: HashMap<String, ServiceExportRow> = HashMap::new();
+    for (service, taxonomy) in parts {
+        let entry = service_map.entry(service.service_id.clone())
+            .or_insert(service);
+        entry.taxonomies.extend(taxonomy);
     }
 
-    debug!("Grouped {} services with taxonomy data (filtered by whitelisted datasets, opinion: {})", service_map.len(), opinion_name);
+    debug!("Grouped {} services with taxonomy data", service_map.len());
 
-    let mut data = Vec::new();
-    for (_service_id, service_rows) in service_map {
-        let first_row = &service_rows[0];
-        
-        // Collect taxonomy terms from all rows for this service
-        let taxonomy_terms: Vec<String> = service_rows
-            .iter()
-            .filter_map(|row| {
-                let taxonomy_term: Option<String> = row.try_get("taxonomy_term").unwrap_or(None);
-                taxonomy_term
-            })
-            .collect();
-        
-        // Sort taxonomy terms for consistent output
-        let mut sorted_taxonomy_terms = taxonomy_terms;
-        sorted_taxonomy_terms.sort();
-        
-        // Join taxonomy terms with comma separation
-        let taxonomy_terms_string = if sorted_taxonomy_terms.is_empty() {
-            None
-        } else {
-            Some(sorted_taxonomy_terms.join(", "))
-        };
-        
-        data.push(ServiceExportRow {
-            contributor: first_row.try_get("contributor").unwrap_or(None),
-            contributor_id: first_row.try_get("contributor_id").unwrap_or(None),
-            service_id: first_row.try_get("service_id").unwrap(),
-            organization_name: first_row.try_get("organization_name").unwrap_or(None),
-            service_name: first_row.try_get("service_name").unwrap_or(None),
-            location_name: first_row.try_get("location_name").unwrap_or(None),
-            full_address: first_row.try_get("full_address").unwrap_or(None),
-            cluster_confirmed_status: first_row.try_get("cluster_confirmed_status").unwrap(),
-            taxonomy_terms: taxonomy_terms_string,
-            cluster: first_row.try_get("cluster").unwrap_or(None),
-            has_duplicates: first_row.try_get("has_duplicates").unwrap(),
-        });
+    let mut data: Vec<ServiceExportRow> = service_map.into_values()
+        .map(|mut service| {
+            // Sort taxonomy terms for consistent output
+            service.taxonomies.sort_by(|a, b| (&a.taxonomy_term, &a.taxonomy_id).cmp(&(&b.taxonomy_term, &b.taxonomy_id)));
+            service.taxonomies.dedup_by(|a, b| a.taxonomy_term == b.taxonomy_term && a.taxonomy_id == b.taxonomy_id);
+            let mut taxonomy_terms: Vec<&str> = service.taxonomies.iter().map(|t| t.taxonomy_term.as_str()).collect();
+            taxonomy_terms.dedup();
+            service.taxonomy_count = taxonomy_terms.len();
+            service.taxonomy_terms = join_taxonomy_terms(&taxonomy_terms, max_terms);
+            service
+        })
+        .collect();
+    sort_service_rows(&mut data);
+    data
+}
+
+/// Joins sorted, distinct taxonomy terms with ", ". With `max_terms`, only that many
+/// are listed, followed by "(+k more)" for the rest. `None` when there are no terms.
+pub fn join_taxonomy_terms(terms: &[&str], max_terms: Option<usize>) -> Option<String> {
+    if terms.is_empty() {
+        return None;
     }
-    
-    // Sort the final data for consistent output
-    data.sort_by(|a, b| {
-        // Sort by cluster (None last), then by service name
-        match (&a.cluster, &b.cluster) {
-            (None, None) => a.service_name.cmp(&b.service_name),
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (Some(cluster_a), Some(cluster_b)) => {
-                cluster_a.cmp(cluster_b).then_with(|| a.service_name.cmp(&b.service_name))
-            }
-        }
+    let shown = max_terms.unwrap_or(terms.len()).min(terms.len());
+    let joined = terms[..shown].join(", ");
+    Some(match terms.len() - shown {
+        0 => joined,
+        hidden => format!("{} (+{} more)", joined, hidden),
+    })
+}
+
+/// Sorts organization rows by cluster, then name, then entity id, with missing
+/// clusters and names last. The id makes this a total order, so two exports of
+/// the same data list rows identically.
+pub fn sort_organization_rows(rows: &mut [OrganizationExportRow]) {
+    rows.sort_by(|a, b| {
+        cmp_none_last(&a.cluster, &b.cluster)
+            .then_with(|| cmp_none_last(&a.name, &b.name))
+            .then_with(|| a.entity_id.cmp(&b.entity_id))
     });
-    
-    info!("Fetched {} service records for export (filtered by whitelisted datasets, opinion: {}).", data.len(), opinion_name);
-    Ok(data)
-}
\ No newline at end of file
+}
+
+/// Sorts service rows by cluster, then service name, then service id, with missing
+/// clusters and names last; the same total order as `sort_organization_rows`.
+pub fn sort_service_rows(rows: &mut [ServiceExportRow]) {
+    rows.sort_by(|a, b| {
+        cmp_none_last(&a.cluster, &b.cluster)
+            .then_with(|| cmp_none_last(&a.service_name, &b.service_name))
+            .then_with(|| a.service_id.cmp(&b.service_id))
+    });
+}
+
+/// Compares optional values with `None` after every `Some`, as PostgreSQL orders NULLs.
+fn cmp_none_last<T: Ord>(a: &Option<T>, b: &Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
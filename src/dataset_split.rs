@@ -0,0 +1,188 @@
+// src/dataset_split.rs
+// Splits an export into one workbook per dataset for `--workbook-per-dataset`. Each
+// contributor receives its own rows in full and, for the rows of other datasets that
+// share a cluster with them, only the fields `--foreign-rows` allows.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+
+use crate::models::{DatasetPairCount, OrganizationExportRow, ServiceExportRow};
+
+/// How much of another dataset's row a dataset's workbook shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForeignRows {
+    /// The id and name, with the dataset and cluster columns
+    #[default]
+    Minimal,
+    /// Only the id, with the dataset and cluster columns
+    IdOnly,
+    /// Leave rows of other datasets out
+    Omit,
+}
+
+/// The rows one dataset's workbook holds. A record type outside the export is `None`,
+/// as for the combined workbook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetPartition {
+    pub dataset: String,
+    pub org_data: Option<Vec<OrganizationExportRow>>,
+    pub svc_data: Option<Vec<ServiceExportRow>>,
+}
+
+impl DatasetPartition {
+    /// The rows that came from this dataset, across both sheets.
+    pub fn own_rows(&self) -> usize {
+        let orgs = self.org_data.iter().flatten().filter(|r| organization_dataset(r) == Some(&self.dataset)).count();
+        let svcs = self.svc_data.iter().flatten().filter(|r| service_dataset(r) == Some(&self.dataset)).count();
+        orgs + svcs
+    }
+
+    /// The redacted rows of other datasets, across both sheets.
+    pub fn foreign_rows(&self) -> usize {
+        self.org_data.iter().flatten().count() + self.svc_data.iter().flatten().count() - self.own_rows()
+    }
+}
+
+/// The dataset (source system) of an organization row.
+pub fn organization_dataset(row: &OrganizationExportRow) -> Option<&String> {
+    row.contributor.as_ref()
+}
+
+/// The dataset (source system) of a service row. The service query returns the source
+/// system as `contributor_id`, so that is the column read here.
+pub fn service_dataset(row: &ServiceExportRow) -> Option<&String> {
+    row.contributor_id.as_ref()
+}
+
+/// Splits the fetched rows by dataset, one partition per dataset in name order. Each
+/// partition has the dataset's own rows unchanged and, unless `foreign` is `Omit`, the
+/// rows of other datasets in the same clusters, redacted by `redact_organization` and
+/// `redact_service`. Rows keep their order. Rows without a dataset get no partition.
+pub fn partition_by_dataset(
+    org_data: Option<&[OrganizationExportRow]>,
+    svc_data: Option<&[ServiceExportRow]>,
+    foreign: ForeignRows,
+) -> Vec<DatasetPartition> {
+    let datasets: BTreeSet<&String> = org_data.into_iter().flatten().filter_map(organization_dataset)
+        .chain(svc_data.into_iter().flatten().filter_map(service_dataset))
+        .collect();
+
+    datasets.into_iter()
+        .map(|dataset| DatasetPartition {
+            dataset: dataset.clone(),
+            org_data: org_data.map(|rows| {
+                split_rows(rows, dataset, foreign, organization_dataset, |r| r.cluster.as_ref(), redact_organization)
+            }),
+            svc_data: svc_data.map(|rows| {
+                split_rows(rows, dataset, foreign, service_dataset, |r| r.cluster.as_ref(), redact_service)
+            }),
+        })
+        .collect()
+}
+
+/// The rows of `dataset`, plus the redacted rows of other datasets in their clusters.
+fn split_rows<R: Clone>(
+    rows: &[R],
+    dataset: &str,
+    foreign: ForeignRows,
+    dataset_of: fn(&R) -> Option<&String>,
+    cluster_of: fn(&R) -> Option<&String>,
+    redact: fn(&mut R, ForeignRows),
+) -> Vec<R> {
+    let is_own = |row: &R| dataset_of(row).map(String::as_str) == Some(dataset);
+    let clusters: HashSet<&String> = rows.iter().filter(|r| is_own(r)).filter_map(cluster_of).collect();
+    rows.iter()
+        .filter_map(|row| {
+            if is_own(row) {
+                Some(row.clone())
+            } else if foreign != ForeignRows::Omit && cluster_of(row).is_some_and(|c| clusters.contains(c)) {
+                let mut row = row.clone();
+                redact(&mut row, foreign);
+                Some(row)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Keeps the id, dataset, and cluster columns of another dataset's organization, and
+/// the name with `Minimal`. The cluster-wide review columns are kept, since the
+/// dataset's own rows in the cluster show the same values.
+pub fn redact_organization(row: &mut OrganizationExportRow, foreign: ForeignRows) {
+    if foreign != ForeignRows::Minimal {
+        row.name = None;
+    }
+    row.contributor_id = None;
+    row.alternate_names = None;
+    row.cluster_representative_name = None;
+    row.services = None;
+}
+
+/// Keeps the id, dataset, and cluster columns of another dataset's service, and the
+/// service name with `Minimal`; organization, location, address, and taxonomy are cleared.
+pub fn redact_service(row: &mut ServiceExportRow, foreign: ForeignRows) {
+    if foreign != ForeignRows::Minimal {
+        row.service_name = None;
+    }
+    row.contributor = None;
+    row.organization_name = None;
+    row.location_name = None;
+    row.full_address = None;
+    row.address_missing_parts = None;
+    row.latitude = None;
+    row.longitude = None;
+    row.taxonomy_terms = None;
+    row.taxonomy_count = 0;
+    row.taxonomies = Vec::new();
+}
+
+/// Turns a dataset name into a file name part: anything but ASCII letters, digits,
+/// '-' and '_' becomes '_'.
+pub fn file_name_part(dataset: &str) -> String {
+    let part: String = dataset.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if part.is_empty() { "dataset".to_string() } else { part }
+}
+
+/// The `file_name_part` of each dataset, in order, made unique: a dataset whose part
+/// another dataset already has, ignoring case, gets "_2", "_3", ... appended, so "a b"
+/// and "a_b" never write to the same file.
+pub fn unique_file_name_parts<'a>(datasets: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    datasets.into_iter()
+        .map(|dataset| {
+            let base = file_name_part(dataset);
+            let part = std::iter::once(base.clone())
+                .chain((2..).map(|n| format!("{}_{}", base, n)))
+                .find(|part| !taken.contains(&part.to_lowercase()))
+                .expect("an unused suffix exists");
+            taken.insert(part.to_lowercase());
+            part
+        })
+        .collect()
+}
+
+/// One file written by the run, as listed in the manifest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestFile {
+    /// `None` for the combined workbook
+    pub dataset: Option<String>,
+    pub path: String,
+    pub own_rows: usize,
+    pub foreign_rows: usize,
+}
+
+/// The JSON manifest written next to the workbooks of a `--workbook-per-dataset` run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Manifest {
+    pub opinion: String,
+    pub timestamp_suffix: String,
+    pub foreign_rows: ForeignRows,
+    pub files: Vec<ManifestFile>,
+    /// Edge decisions per pair of datasets, as on the Progress Overview
+    pub dataset_pairs: Vec<DatasetPairCount>,
+}
@@ -1,19 +1,39 @@
 // src/utils/db_connect.rs
 
 use anyhow::{Context, Result};
-use bb8::Pool;
+use bb8::{Pool, PooledConnection};
 use bb8_postgres::PostgresConnectionManager;
 use log::info;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
-use tokio_postgres::{Config, NoTls};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_postgres::{Client, Config, NoTls};
+
+/// Maximum number of connections in each pool built by `build_pool`.
+pub const POOL_MAX_SIZE: u32 = 90;
+
+/// Number of clients checked out through `get_client` at once when
+/// `PG_MAX_CONCURRENCY` is unset or invalid.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// Shared by every pool, so a replica pool does not double the bound.
+static DB_PERMITS: OnceLock<Arc<Semaphore>> = OnceLock::new();
 
 /// Builds the PostgreSQL connection configuration from environment variables.
 /// This function sets up host, port, database name, user, password,
 /// application name, and connection timeout.
 fn build_pg_config() -> Config {
-    let mut config = Config::new();
     let host = std::env::var("POSTGRES_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port_str = std::env::var("POSTGRES_PORT").unwrap_or_else(|_| "5432".to_string());
+    build_pg_config_for(&host, &port_str)
+}
+
+/// Builds a connection configuration for the given host and port, taking the
+/// database name, user, and password from the same environment variables as the primary.
+fn build_pg_config_for(host: &str, port_str: &str) -> Config {
+    let mut config = Config::new();
     let port = port_str.parse::<u16>().unwrap_or(5432);
     let dbname = std::env::var("POSTGRES_DB").unwrap_or_else(|_| "dataplatform".to_string());
     let user = std::env::var("POSTGRES_USER").unwrap_or_else(|_| "postgres".to_string());
@@ -24,7 +44,7 @@ fn build_pg_config() -> Config {
         host, port, dbname, user
     );
     config
-        .host(&host)
+        .host(host)
         .port(port)
         .dbname(&dbname)
         .user(&user)
@@ -34,11 +54,162 @@ fn build_pg_config() -> Config {
     config
 }
 
+/// Builds the read-only connection configuration, if one is configured.
+///
+/// `DATABASE_URL_RO` (a full connection string) takes precedence. Otherwise
+/// `POSTGRES_READ_HOST` (and optionally `POSTGRES_READ_PORT`) selects a replica
+/// host that shares the primary's database name and credentials.
+fn build_read_pg_config() -> Result<Option<Config>> {
+    if let Ok(url) = std::env::var("DATABASE_URL_RO") {
+        let mut config = Config::from_str(&url).context("Failed to parse DATABASE_URL_RO")?;
+        config.application_name("deduplication_pipeline");
+        config.connect_timeout(Duration::from_secs(10));
+        info!("Read-only DB Config: from DATABASE_URL_RO, Hosts={:?}", config.get_hosts());
+        return Ok(Some(config));
+    }
+
+    if let Ok(host) = std::env::var("POSTGRES_READ_HOST") {
+        let port_str = std::env::var("POSTGRES_READ_PORT")
+            .or_else(|_| std::env::var("POSTGRES_PORT"))
+            .unwrap_or_else(|_| "5432".to_string());
+        return Ok(Some(build_pg_config_for(&host, &port_str)));
+    }
+
+    Ok(None)
+}
+
+/// Builds the configuration of the optional second export target.
+///
+/// `url` (from `--target-db`) takes precedence, then `EXPORT_TARGET_URL`. Otherwise
+/// `EXPORT_TARGET_HOST` selects the server, with `EXPORT_TARGET_PORT`, `EXPORT_TARGET_DB`,
+/// `EXPORT_TARGET_USER` and `EXPORT_TARGET_PASSWORD` falling back to the primary's settings.
+pub fn build_target_pg_config(url: Option<&str>) -> Result<Option<Config>> {
+    let url = url.map(str::to_string).or_else(|| std::env::var("EXPORT_TARGET_URL").ok());
+    if let Some(url) = url {
+        let mut config = Config::from_str(&url).context("Failed to parse the export target connection string")?;
+        config.application_name("deduplication_pipeline");
+        config.connect_timeout(Duration::from_secs(10));
+        info!("Export target DB Config: from connection string, Hosts={:?}", config.get_hosts());
+        return Ok(Some(config));
+    }
+
+    let Ok(host) = std::env::var("EXPORT_TARGET_HOST") else {
+        return Ok(None);
+    };
+    let env_or_primary = |name: &str, primary: &str| std::env::var(name).or_else(|_| std::env::var(primary)).ok();
+    let port = env_or_primary("EXPORT_TARGET_PORT", "POSTGRES_PORT")
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or(5432);
+    let dbname = env_or_primary("EXPORT_TARGET_DB", "POSTGRES_DB").unwrap_or_else(|| "dataplatform".to_string());
+    let user = env_or_primary("EXPORT_TARGET_USER", "POSTGRES_USER").unwrap_or_else(|| "postgres".to_string());
+    let password = env_or_primary("EXPORT_TARGET_PASSWORD", "POSTGRES_PASSWORD").unwrap_or_default();
+
+    info!("Export target DB Config: Host={}, Port={}, DB={}, User={}", host, port, dbname, user);
+    let mut config = Config::new();
+    config
+        .host(&host)
+        .port(port)
+        .dbname(&dbname)
+        .user(&user)
+        .password(&password);
+    config.application_name("deduplication_pipeline");
+    config.connect_timeout(Duration::from_secs(10));
+    Ok(Some(config))
+}
+
 /// Type alias for the PostgreSQL connection pool.
 /// This uses `bb8` for connection pooling with `tokio_postgres`.
 pub type PgPool = Pool<PostgresConnectionManager<NoTls>>;
 
-/// Establishes and initializes the PostgreSQL database connection pool.
+/// Parses a `PG_MAX_CONCURRENCY` value. Missing, unparsable, and zero values
+/// fall back to `DEFAULT_MAX_CONCURRENCY`.
+pub fn parse_max_concurrency(value: Option<&str>) -> usize {
+    value
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_CONCURRENCY)
+}
+
+/// The semaphore bounding concurrent DB operations, sized from `PG_MAX_CONCURRENCY` on first use.
+fn db_permits() -> Arc<Semaphore> {
+    DB_PERMITS
+        .get_or_init(|| {
+            let permits = parse_max_concurrency(std::env::var("PG_MAX_CONCURRENCY").ok().as_deref());
+            info!("Allowing up to {} concurrent DB operations (PG_MAX_CONCURRENCY).", permits);
+            Arc::new(Semaphore::new(permits))
+        })
+        .clone()
+}
+
+/// A pooled client together with the concurrency permit it was checked out under.
+/// The permit is released when the client goes back to the pool.
+pub struct LimitedClient<'a> {
+    client: PooledConnection<'a, PostgresConnectionManager<NoTls>>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for LimitedClient<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl DerefMut for LimitedClient<'_> {
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+}
+
+/// Checks out a client once fewer than `PG_MAX_CONCURRENCY` are in use, so a burst
+/// of queries queues here instead of exhausting the pool and the server's connections.
+/// `stage` names the caller in the error raised when the pool times out.
+pub async fn get_client<'a>(pool: &'a PgPool, stage: &str) -> Result<LimitedClient<'a>> {
+    let permit = db_permits().acquire_owned().await
+        .context(format!("Failed to acquire a DB permit for {}", stage))?;
+    let client = pool.get().await.context(format!("Failed to get DB client for {}", stage))?;
+    Ok(LimitedClient { client, _permit: permit })
+}
+
+/// Establishes and initializes the primary PostgreSQL database connection pool.
+/// See `build_pool` for the pool configuration.
+pub async fn connect() -> Result<PgPool> {
+    let config = build_pg_config();
+    info!("Connecting to PostgreSQL database...");
+    build_pool(config).await
+}
+
+/// Establishes a pool for an arbitrary configuration, such as the export target.
+/// See `build_pool` for the pool configuration.
+pub async fn connect_to(config: Config) -> Result<PgPool> {
+    info!("Connecting to PostgreSQL database {:?} on {:?}...", config.get_dbname(), config.get_hosts());
+    build_pool(config).await
+}
+
+/// Establishes the pool used for the read-heavy fetch and dashboard queries.
+///
+/// When `DATABASE_URL_RO` or `POSTGRES_READ_HOST` is set, a separate pool is built
+/// against that (replica) server; otherwise the primary pool is returned so both
+/// paths share it. DDL and re-clustering writes always stay on the primary.
+///
+/// The replica path assumes the export tables copied and re-clustered on the
+/// primary have already replicated by the time they are read. With significant
+/// replication lag the fetch may see missing or partially populated tables.
+pub async fn connect_read_pool(primary: &PgPool) -> Result<PgPool> {
+    match build_read_pg_config()? {
+        Some(config) => {
+            info!("Connecting to read-only PostgreSQL database for fetch queries...");
+            build_pool(config).await.context("Failed to connect to read-only database")
+        }
+        None => {
+            info!("No read-only database configured; fetch queries use the primary pool.");
+            Ok(primary.clone())
+        }
+    }
+}
+
+/// Builds a connection pool for the given configuration and verifies it.
 ///
 /// Configures the pool with:
 /// - `max_size`: Maximum number of connections in the pool (90).
@@ -47,13 +218,11 @@ pub type PgPool = Pool<PostgresConnectionManager<NoTls>>;
 /// - `connection_timeout`: How long to wait to establish a new connection (40 seconds).
 ///
 /// It also performs a test query (`SELECT 1`) to ensure the pool is working.
-pub async fn connect() -> Result<PgPool> {
-    let config = build_pg_config();
-    info!("Connecting to PostgreSQL database...");
+async fn build_pool(config: Config) -> Result<PgPool> {
     let manager = PostgresConnectionManager::new(config, NoTls);
 
     // Define pool configuration values to be logged
-    let pool_max_size = 90;
+    let pool_max_size = POOL_MAX_SIZE;
     let pool_min_idle = Some(2);
     let pool_idle_timeout = Some(Duration::from_secs(180));
     let pool_connection_timeout = Duration::from_secs(40);
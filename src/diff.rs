@@ -0,0 +1,150 @@
+// diff.rs
+use anyhow::Result;
+use log::info;
+use std::collections::HashMap;
+
+use crate::comparison::cluster_memberships;
+use crate::db_connect::PgPool;
+use crate::data_fetch;
+use crate::models::{ChangeKind, ExportSelection, ChangeRecord, OrganizationExportRow, ServiceExportRow};
+use crate::team_utils::{DatasetFilterOptions, TeamInfo};
+
+/// The fields of an export row that take part in a diff.
+#[derive(Debug, Clone)]
+pub struct DiffRecord<'a> {
+    pub id: &'a str,
+    pub name: Option<&'a str>,
+    pub contributor: Option<&'a str>,
+    pub cluster: Option<&'a str>,
+    pub status: &'a str,
+}
+
+impl<'a> From<&'a OrganizationExportRow> for DiffRecord<'a> {
+    fn from(row: &'a OrganizationExportRow) -> Self {
+        DiffRecord {
+            id: &row.entity_id,
+            name: row.name.as_deref(),
+            contributor: row.contributor.as_deref(),
+            cluster: row.cluster.as_deref(),
+            status: &row.cluster_confirmed_status,
+        }
+    }
+}
+
+impl<'a> From<&'a ServiceExportRow> for DiffRecord<'a> {
+    fn from(row: &'a ServiceExportRow) -> Self {
+        DiffRecord {
+            id: &row.service_id,
+            name: row.service_name.as_deref(),
+            contributor: row.contributor.as_deref(),
+            cluster: row.cluster.as_deref(),
+            status: &row.cluster_confirmed_status,
+        }
+    }
+}
+
+/// Counts of each change kind for one record type.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub cluster_changed: usize,
+    pub status_changed: usize,
+}
+
+impl DiffSummary {
+    pub fn from_changes(changes: &[ChangeRecord]) -> Self {
+        let mut summary = DiffSummary::default();
+        for change in changes {
+            match change.change {
+                ChangeKind::Added => summary.added += 1,
+                ChangeKind::Removed => summary.removed += 1,
+                ChangeKind::ClusterChanged => summary.cluster_changed += 1,
+                ChangeKind::StatusChanged => summary.status_changed += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Compares two exports of the same record type.
+///
+/// Records only in `new` are `Added`, records only in `old` are `Removed`. For
+/// records on both sides, a `ClusterChanged` record is emitted when the set of
+/// records sharing its cluster differs (cluster ids are regenerated on every run,
+/// so they are never compared directly), and a `StatusChanged` record when
+/// `cluster_confirmed_status` differs. A record can appear under both kinds.
+pub fn diff_records(old: &[DiffRecord], new: &[DiffRecord]) -> Vec<ChangeRecord> {
+    let old_members = cluster_memberships(old.iter().map(|r| (r.id, r.cluster)));
+    let new_members = cluster_memberships(new.iter().map(|r| (r.id, r.cluster)));
+    let old_by_id: HashMap<&str, &DiffRecord> = old.iter().map(|r| (r.id, r)).collect();
+    let new_by_id: HashMap<&str, &DiffRecord> = new.iter().map(|r| (r.id, r)).collect();
+
+    let change = |kind: ChangeKind, old: Option<&DiffRecord>, new: Option<&DiffRecord>| {
+        let current = new.or(old).expect("a change always has at least one side");
+        ChangeRecord {
+            record_id: current.id.to_string(),
+            name: current.name.map(str::to_string),
+            contributor: current.contributor.map(str::to_string),
+            change: kind,
+            old_cluster: old.and_then(|r| r.cluster).map(str::to_string),
+            new_cluster: new.and_then(|r| r.cluster).map(str::to_string),
+            old_status: old.map(|r| r.status.to_string()),
+            new_status: new.map(|r| r.status.to_string()),
+        }
+    };
+
+    let mut changes = Vec::new();
+    for new_record in new {
+        match old_by_id.get(new_record.id) {
+            None => changes.push(change(ChangeKind::Added, None, Some(new_record))),
+            Some(old_record) => {
+                if old_members.get(new_record.id) != new_members.get(new_record.id) {
+                    changes.push(change(ChangeKind::ClusterChanged, Some(old_record), Some(new_record)));
+                }
+                if old_record.status != new_record.status {
+                    changes.push(change(ChangeKind::StatusChanged, Some(old_record), Some(new_record)));
+                }
+            }
+        }
+    }
+    for old_record in old {
+        if !new_by_id.contains_key(old_record.id) {
+            changes.push(change(ChangeKind::Removed, Some(old_record), None));
+        }
+    }
+    changes
+}
+
+/// Loads the organization and service rows of two existing exports (identified by
+/// their timestamp suffixes) and returns the organization and service changes.
+pub async fn diff_exports(
+    pool: &PgPool,
+    user_prefix: &str,
+    opinion_name: &str,
+    old_suffix: &str,
+    new_suffix: &str,
+    team_info: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+) -> Result<(Vec<ChangeRecord>, Vec<ChangeRecord>)> {
+    info!("Diffing exports '{}' and '{}' for user '{}' with opinion '{}'...",
+          old_suffix, new_suffix, user_prefix, opinion_name);
+
+    let whole_team = ExportSelection::default();
+    let old_orgs = data_fetch::fetch_organization_export_data(pool, user_prefix, opinion_name, old_suffix, team_info, filter_options, &whole_team).await?;
+    let new_orgs = data_fetch::fetch_organization_export_data(pool, user_prefix, opinion_name, new_suffix, team_info, filter_options, &whole_team).await?;
+    let org_changes = diff_records(
+        &old_orgs.iter().map(DiffRecord::from).collect::<Vec<_>>(),
+        &new_orgs.iter().map(DiffRecord::from).collect::<Vec<_>>(),
+    );
+
+    let old_svcs = data_fetch::fetch_service_export_data(pool, user_prefix, opinion_name, old_suffix, team_info, filter_options, &whole_team).await?;
+    let new_svcs = data_fetch::fetch_service_export_data(pool, user_prefix, opinion_name, new_suffix, team_info, filter_options, &whole_team).await?;
+    let svc_changes = diff_records(
+        &old_svcs.iter().map(DiffRecord::from).collect::<Vec<_>>(),
+        &new_svcs.iter().map(DiffRecord::from).collect::<Vec<_>>(),
+    );
+
+    info!("Found {} organization changes and {} service changes.", org_changes.len(), svc_changes.len());
+    Ok((org_changes, svc_changes))
+}
@@ -0,0 +1,252 @@
+// src/doctor.rs
+// The checks behind `export doctor`, which verifies that a new environment can run an
+// export: the connection, the auth and source tables, the team schema, and the right to
+// create tables in the export schema. Each check is an async fn returning a
+// `CheckResult`; `run_checks` runs them in order, so a new check is one more line there.
+
+use anyhow::Result;
+use std::fmt;
+use tokio_postgres::GenericClient;
+use uuid::Uuid;
+
+use crate::db_connect::{self, PgPool};
+use crate::preflight;
+use crate::sql_guard::{self, execute_export_write};
+use crate::table_names::{self, EXPORT_SCHEMA, TEAM_SCHEMA};
+
+/// The auth tables the selection prompts read.
+pub const AUTH_TABLES: [&str; 3] = ["teams", "users", "opinions"];
+
+/// How a check ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckOutcome {
+    Pass,
+    /// A required check failed; `export doctor` exits non-zero
+    Fail,
+    /// An optional check failed, or a check was skipped
+    Warn,
+}
+
+/// The result of one check, printed as one line with a remediation hint when it did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub outcome: CheckOutcome,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> CheckResult {
+        CheckResult { name, outcome: CheckOutcome::Pass, detail: detail.into(), hint: None }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> CheckResult {
+        CheckResult { name, outcome: CheckOutcome::Fail, detail: detail.into(), hint: Some(hint.into()) }
+    }
+
+    pub fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> CheckResult {
+        CheckResult { name, outcome: CheckOutcome::Warn, detail: detail.into(), hint: Some(hint.into()) }
+    }
+
+    /// A failed required check, reported with the error's full chain.
+    fn from_error(name: &'static str, error: anyhow::Error, hint: &str) -> CheckResult {
+        CheckResult::fail(name, format!("{:#}", error), hint)
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.outcome {
+            CheckOutcome::Pass => "✅ PASS",
+            CheckOutcome::Fail => "❌ FAIL",
+            CheckOutcome::Warn => "⚠️  WARN",
+        };
+        write!(f, "{}  {}: {}", label, self.name, self.detail)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n         hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// How many required checks failed.
+pub fn failed_checks(results: &[CheckResult]) -> usize {
+    results.iter().filter(|r| r.outcome == CheckOutcome::Fail).count()
+}
+
+/// The hint for a database that cannot be reached.
+const CONNECT_HINT: &str = "Check DATABASE_URL or the POSTGRES_* variables in .env, and that the server accepts connections from this host.";
+
+/// Connects to the primary database and runs every check, in order. When the
+/// connection fails, that is the only result.
+pub async fn run_checks() -> Vec<CheckResult> {
+    let pool = match db_connect::connect().await {
+        Ok(pool) => pool,
+        Err(e) => return vec![CheckResult::from_error("connect", e, CONNECT_HINT)],
+    };
+    let connection = check_connection(&pool).await;
+    if connection.outcome != CheckOutcome::Pass {
+        return vec![connection];
+    }
+    let mut results = vec![connection];
+    let client = match sql_guard::read_only_client(&pool, "doctor").await {
+        Ok(client) => client,
+        Err(e) => {
+            results.push(CheckResult::from_error("read-only session", e, "Check that the role may SET default_transaction_read_only."));
+            return results;
+        }
+    };
+    results.push(check_auth_tables(&*client).await);
+    results.push(check_required_columns(&client).await);
+    results.push(check_team_schema(&*client).await);
+    results.push(check_source_tables(&*client).await);
+    results.push(check_users_with_prefix(&*client).await);
+    drop(client);
+    results.push(check_export_privileges(&pool).await);
+    results
+}
+
+/// The database answers a query.
+pub async fn check_connection(pool: &PgPool) -> CheckResult {
+    const NAME: &str = "connect";
+    let client = match sql_guard::read_only_client(pool, "doctor connection").await {
+        Ok(client) => client,
+        Err(e) => return CheckResult::from_error(NAME, e, CONNECT_HINT),
+    };
+    match client.query_one("SELECT current_user::text, current_database()::text, version()", &[]).await {
+        Ok(row) => {
+            let (user, database, version): (String, String, String) = (row.get(0), row.get(1), row.get(2));
+            let version = version.split(" on ").next().unwrap_or(&version).to_string();
+            CheckResult::pass(NAME, format!("connected to {} as {} ({})", database, user, version))
+        }
+        Err(e) => CheckResult::from_error(NAME, e.into(), CONNECT_HINT),
+    }
+}
+
+/// The role can SELECT from auth.teams, auth.users and auth.opinions.
+pub async fn check_auth_tables<C: GenericClient>(client: &C) -> CheckResult {
+    let mut problems = Vec::new();
+    for table in AUTH_TABLES {
+        let query = format!("SELECT 1 FROM auth.{} LIMIT 1", table);
+        if let Err(e) = client.query(&query, &[]).await {
+            problems.push(format!("auth.{}: {}", table, e.as_db_error().map(|d| d.message().to_string()).unwrap_or_else(|| e.to_string())));
+        }
+    }
+    auth_tables_result(&problems)
+}
+
+/// The result of `check_auth_tables` for the tables that could not be read.
+pub fn auth_tables_result(problems: &[String]) -> CheckResult {
+    const NAME: &str = "auth tables";
+    if problems.is_empty() {
+        return CheckResult::pass(NAME, format!("can read auth.{}", AUTH_TABLES.join(", auth.")));
+    }
+    CheckResult::fail(NAME, problems.join("; "),
+        "The auth schema is created by the review application. Point the export at its database, or GRANT USAGE ON SCHEMA auth and SELECT on these tables to this role.")
+}
+
+/// The tables and columns the export queries rely on exist, as checked at the start of every export.
+pub async fn check_required_columns(client: &tokio_postgres::Client) -> CheckResult {
+    match preflight::preflight_schema_check(client).await {
+        Ok(()) => CheckResult::pass("required columns", "every table and column the export reads exists"),
+        Err(e) => CheckResult::from_error("required columns", e,
+            "Run the export against the up-to-date review database; set SOURCE_SCHEMA if the HSDS tables are not in 'public'."),
+    }
+}
+
+/// The team schema holding the opinion tables exists and the role may use it.
+pub async fn check_team_schema<C: GenericClient>(client: &C) -> CheckResult {
+    let query = "SELECT to_regnamespace($1::text) IS NOT NULL, \
+                 CASE WHEN to_regnamespace($1::text) IS NULL THEN false ELSE has_schema_privilege($1::text, 'USAGE') END";
+    match client.query_one(query, &[&TEAM_SCHEMA]).await {
+        Ok(row) => team_schema_result(row.get(0), row.get(1)),
+        Err(e) => CheckResult::from_error("team schema", e.into(), "Check that the role can read the system catalogs."),
+    }
+}
+
+/// The result of `check_team_schema`.
+pub fn team_schema_result(exists: bool, usage: bool) -> CheckResult {
+    const NAME: &str = "team schema";
+    match (exists, usage) {
+        (true, true) => CheckResult::pass(NAME, format!("schema {} exists", TEAM_SCHEMA)),
+        (true, false) => CheckResult::fail(NAME, format!("no USAGE privilege on schema {}", TEAM_SCHEMA),
+            format!("GRANT USAGE ON SCHEMA {0} and SELECT ON ALL TABLES IN SCHEMA {0} to this role.", TEAM_SCHEMA)),
+        (false, _) => CheckResult::fail(NAME, format!("schema {} does not exist", TEAM_SCHEMA),
+            "The opinion tables are written by the matching pipeline and review application; is this the right database?"),
+    }
+}
+
+/// The HSDS source tables exist in the source schema.
+pub async fn check_source_tables<C: GenericClient>(client: &C) -> CheckResult {
+    let source_schema = table_names::source_schema();
+    let mut missing = Vec::new();
+    for (table, _) in preflight::SOURCE_TABLE_COLUMNS {
+        let name = table_names::qualified(source_schema, table);
+        match client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&name]).await {
+            Ok(row) if row.get::<_, bool>(0) => {}
+            Ok(_) => missing.push(name),
+            Err(e) => return CheckResult::from_error("source tables", e.into(), "Check that the role can read the system catalogs."),
+        }
+    }
+    source_tables_result(source_schema, &missing)
+}
+
+/// The result of `check_source_tables` for the tables that do not exist.
+pub fn source_tables_result(source_schema: &str, missing: &[String]) -> CheckResult {
+    const NAME: &str = "source tables";
+    if missing.is_empty() {
+        return CheckResult::pass(NAME, format!("all {} HSDS tables exist in schema {}", preflight::SOURCE_TABLE_COLUMNS.len(), source_schema));
+    }
+    CheckResult::fail(NAME, format!("missing {}", missing.join(", ")),
+        format!("Set SOURCE_SCHEMA to the schema holding the HSDS tables (now '{}'), or grant this role access to them.", source_schema))
+}
+
+/// At least one active user has an opinion prefix, so there is someone to export.
+/// Optional: a new environment may not have reviewers yet.
+pub async fn check_users_with_prefix<C: GenericClient>(client: &C) -> CheckResult {
+    let query = "SELECT count(*) FROM auth.users WHERE is_active AND user_opinion_prefix IS NOT NULL AND btrim(user_opinion_prefix) <> ''";
+    match client.query_one(query, &[]).await {
+        Ok(row) => users_with_prefix_result(row.get(0)),
+        Err(e) => CheckResult::warn("users with a prefix", format!("could not count users: {}", e), "See the auth tables check."),
+    }
+}
+
+/// The result of `check_users_with_prefix` for the number of active users with a prefix.
+pub fn users_with_prefix_result(count: i64) -> CheckResult {
+    const NAME: &str = "users with a prefix";
+    if count > 0 {
+        return CheckResult::pass(NAME, format!("{} active users have an opinion prefix", count));
+    }
+    CheckResult::warn(NAME, "no active user has an opinion prefix, so there is no one to export",
+        "Set auth.users.user_opinion_prefix for the reviewers, or use --prefix-override.")
+}
+
+/// The role can create and drop a table in the export schema. A throwaway table is
+/// created and dropped in a transaction that is rolled back, together with the export
+/// schema itself when it does not exist yet, so the check leaves nothing behind.
+/// Skipped with `--read-only`.
+pub async fn check_export_privileges(pool: &PgPool) -> CheckResult {
+    const NAME: &str = "export schema writes";
+    if sql_guard::writes_disabled() {
+        return CheckResult::warn(NAME, "skipped in read-only mode", "Run `export doctor` without --read-only to check it.");
+    }
+    match try_export_writes(pool).await {
+        Ok(()) => CheckResult::pass(NAME, format!("can create and drop tables in {}", EXPORT_SCHEMA)),
+        Err(e) => CheckResult::from_error(NAME, e, &format!(
+            "GRANT CREATE ON SCHEMA {0} to this role, or, when {0} does not exist yet, GRANT CREATE ON DATABASE so the first export can create it.",
+            EXPORT_SCHEMA
+        )),
+    }
+}
+
+async fn try_export_writes(pool: &PgPool) -> Result<()> {
+    let mut client = sql_guard::export_write_client(pool, "doctor export writes").await?;
+    let tx = client.transaction().await?;
+    let table = table_names::qualified(EXPORT_SCHEMA, &format!("doctor_check_{}", Uuid::new_v4().simple()));
+    execute_export_write(&tx, &format!("CREATE SCHEMA IF NOT EXISTS {}", EXPORT_SCHEMA), &[]).await?;
+    execute_export_write(&tx, &format!("CREATE TABLE {} (id integer)", table), &[]).await?;
+    execute_export_write(&tx, &format!("DROP TABLE {}", table), &[]).await?;
+    tx.rollback().await?;
+    Ok(())
+}
@@ -1,159 +1,1406 @@
-use anyhow::Result;
-use rust_xlsxwriter::{Workbook, FormatAlign, Worksheet, Format};
-use std::path::Path;
+use anyhow::{Context, Result};
+use rust_xlsxwriter::{Chart, ChartDataLabel, ChartType, ExcelDateTime, Format, Note, Workbook, Worksheet};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use log::info;
 use chrono;
 
-use crate::models::{OrganizationExportRow, ServiceExportRow};
-use crate::dashboard::{UserDashboard, ReviewStats};
+use crate::models::{
+    ChangeKind, ChangeRecord, ConsensusRow, DatasetPairCount, MethodStats, MethodSummary, OpinionComparisonRow, OpinionExport, OrganizationExportRow, OrphanedEdge,
+    RecordScope, ServiceExportRow,
+};
+use crate::cluster_labels::LabelMap;
+use crate::dashboard::{self, CompletionWeights, DashboardSection, ProgressOptions, OpinionDashboard};
+use crate::diff::DiffSummary;
+use crate::run_warnings::WarningSummary;
 
-/// Writes the extracted organization and service data to an Excel file with multiple sheets.
+/// Excel's maximum worksheet name length, in characters.
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// Characters Excel does not allow in a worksheet name.
+const ILLEGAL_SHEET_NAME_CHARS: [char; 7] = [':', '\\', '/', '?', '*', '[', ']'];
+
+/// Makes `raw` a legal worksheet name: illegal characters become "_", leading and
+/// trailing apostrophes are dropped, and the result is cut to 31 characters. A name
+/// left empty becomes "Sheet". Uniqueness is up to `SheetNameAllocator`.
+pub fn sanitize_sheet_name(raw: &str) -> String {
+    let replaced: String = raw.chars()
+        .map(|c| if ILLEGAL_SHEET_NAME_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+        .collect();
+    let name: String = replaced.trim().trim_matches('\'').chars().take(MAX_SHEET_NAME_LEN).collect();
+    let name = name.trim_end().trim_end_matches('\'');
+    if name.is_empty() {
+        "Sheet".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Hands out worksheet names that follow Excel's rules and are unique within
+/// a workbook. Excel compares sheet names case-insensitively, so collisions are
+/// detected that way and resolved by appending " (2)", " (3)", etc.
+#[derive(Debug, Default)]
+pub struct SheetNameAllocator {
+    used: HashSet<String>,
+}
+
+impl SheetNameAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a unique, legal name derived from `raw` with `sanitize_sheet_name` and reserves it.
+    pub fn allocate(&mut self, raw: &str) -> String {
+        let base = sanitize_sheet_name(raw);
+        let mut candidate = base.clone();
+        let mut counter = 2;
+        while self.used.contains(&candidate.to_lowercase()) {
+            let suffix = format!(" ({})", counter);
+            let keep = MAX_SHEET_NAME_LEN - suffix.chars().count();
+            candidate = format!("{}{}", base.chars().take(keep).collect::<String>(), suffix);
+            counter += 1;
+        }
+        self.used.insert(candidate.to_lowercase());
+        candidate
+    }
+
+    /// Reserves `name` exactly as given, failing if a sheet already has it.
+    pub fn reserve(&mut self, name: &str) -> Result<()> {
+        if !self.used.insert(name.to_lowercase()) {
+            return Err(anyhow::anyhow!("the workbook already has a sheet named '{}'", name));
+        }
+        Ok(())
+    }
+}
+
+/// Refuses an output path that already exists unless `overwrite` is set, so a previous
+/// export that has not been moved off disk yet is not silently replaced. Called before
+/// the (slow) export starts, so the run fails early rather than after the work is done.
+pub fn check_output_path(path: &Path, overwrite: bool) -> Result<()> {
+    if path.exists() && !overwrite {
+        return Err(anyhow::anyhow!(
+            "Output file {} already exists; move it away or pass --overwrite to replace it",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Parses `--output`. Workbooks are only written as xlsx, so the path must end in
+/// `.xlsx` (any case); a missing or different extension is rejected rather than
+/// silently producing a file whose name does not match its format.
+pub fn parse_output_path(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s.trim());
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("xlsx") => Ok(path),
+        Some(ext) => Err(format!("unsupported output format '.{}'; workbooks are written as .xlsx", ext)),
+        None => Err(format!("output path '{}' needs an .xlsx extension", s)),
+    }
+}
+
+/// Writes the export workbook to `file_path`; see `write_excel_to_bytes` for its sheets.
 pub async fn write_excel_file(
     file_path: &Path,
-    org_data: Vec<OrganizationExportRow>,
-    svc_data: Vec<ServiceExportRow>,
-    dashboard_data: Option<Vec<UserDashboard>>,
+    org_data: Option<Vec<OrganizationExportRow>>,
+    svc_data: Option<Vec<ServiceExportRow>>,
+    dashboard_section: DashboardSection,
+    progress_options: ProgressOptions,
+    trailing_sheets: TrailingSheets,
+    sheet_options: DataSheetOptions<'_>,
 ) -> Result<()> {
     info!("Initializing Excel workbook for file: {:?}", file_path);
+    let bytes = write_excel_to_bytes(org_data, svc_data, dashboard_section, progress_options, trailing_sheets, sheet_options)?;
+    std::fs::write(file_path, bytes).context(format!("Failed to write {}", file_path.display()))?;
+    info!("Excel file saved successfully to {:?}", file_path);
+    Ok(())
+}
+
+/// Serializes the extracted organization and service data into an xlsx workbook with
+/// multiple sheets and returns its bytes, for callers that stream or upload it.
+/// A `None` dataset was not exported: its sheet is omitted and the Progress Overview
+/// only covers the included record type. If the dashboard statistics failed, a
+/// placeholder Progress Overview explains why instead of the sheet silently missing.
+/// An "Orphaned Edges" sheet is appended when any orphaned edges were found, and a
+/// "Warnings" sheet when the run recorded any warnings. The "Legend" sheet comes last unless left out,
+/// followed only by the caller's `extra_sheets`.
+pub fn write_excel_to_bytes(
+    org_data: Option<Vec<OrganizationExportRow>>,
+    svc_data: Option<Vec<ServiceExportRow>>,
+    dashboard_section: DashboardSection,
+    progress_options: ProgressOptions,
+    trailing_sheets: TrailingSheets,
+    sheet_options: DataSheetOptions<'_>,
+) -> Result<Vec<u8>> {
+    let TrailingSheets { method_breakdown, method_performance, orphaned_edges, warnings, legend, extra_sheets } = trailing_sheets;
     let mut workbook = Workbook::new();
 
-    // Add "Progress Overview" sheet first if dashboard data is provided
-    if let Some(progress_data) = dashboard_data {
-        let progress_sheet = workbook.add_worksheet();
-        write_progress_overview_sheet(progress_sheet, progress_data)?;
+    // Add "Progress Overview" and "User Breakdown" sheets first if dashboard data is provided
+    match dashboard_section {
+        DashboardSection::Included(progress_data) => {
+            let progress_sheet = workbook.add_worksheet();
+            write_progress_overview_sheet(
+                progress_sheet, &progress_data, progress_options, org_data.is_some(), svc_data.is_some(),
+            )?;
+            let breakdown_sheet = workbook.add_worksheet();
+            write_user_breakdown_sheet(
+                breakdown_sheet, &progress_data, progress_options, org_data.is_some(), svc_data.is_some(),
+            )?;
+        }
+        DashboardSection::Failed(error_summary) => {
+            let progress_sheet = workbook.add_worksheet();
+            write_progress_unavailable_sheet(progress_sheet, &error_summary)?;
+        }
+        DashboardSection::Skipped => {}
     }
 
     // Add "Organizations" sheet
-    let org_sheet = workbook.add_worksheet();
-    write_organization_sheet(org_sheet, org_data)?;
+    if let Some(org_data) = org_data {
+        let org_sheet = workbook.add_worksheet();
+        write_table_sheet(org_sheet, "Organizations", &organization_sheet(), &org_data, sheet_options)?;
+    }
 
     // Add "Services" sheet
-    let svc_sheet = workbook.add_worksheet();
-    write_service_sheet(svc_sheet, svc_data)?;
+    if let Some(svc_data) = svc_data {
+        let svc_sheet = workbook.add_worksheet();
+        write_table_sheet(svc_sheet, "Services", &service_sheet(), &svc_data, sheet_options)?;
+        if sheet_options.taxonomy_sheet {
+            let taxonomy_sheet = workbook.add_worksheet();
+            write_taxonomy_sheet(taxonomy_sheet, &svc_data)?;
+        }
+    }
+
+    if !method_breakdown.is_empty() {
+        let method_sheet = workbook.add_worksheet();
+        write_method_breakdown_sheet(method_sheet, method_breakdown)?;
+    }
+
+    if !method_performance.is_empty() {
+        let performance_sheet = workbook.add_worksheet();
+        write_method_performance_sheet(performance_sheet, &method_performance)?;
+    }
+
+    if !orphaned_edges.is_empty() {
+        let orphan_sheet = workbook.add_worksheet();
+        write_orphaned_edges_sheet(orphan_sheet, orphaned_edges)?;
+    }
+
+    if !warnings.is_empty() {
+        let warnings_sheet = workbook.add_worksheet();
+        write_warnings_sheet(warnings_sheet, "Warnings", &warnings)?;
+    }
+
+    if legend {
+        let legend_sheet = workbook.add_worksheet();
+        write_legend_sheet(legend_sheet)?;
+    }
+
+    if let Some(extra_sheets) = extra_sheets {
+        add_extra_sheets(&mut workbook, extra_sheets)?;
+    }
+
+    info!("Serializing Excel workbook...");
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// How the Organizations and Services sheets are laid out.
+#[derive(Debug, Clone, Copy)]
+pub struct DataSheetOptions<'a> {
+    pub cluster_labels: &'a LabelMap,
+    /// Add the bold totals row below the data (`--no-footer` turns it off)
+    pub footer: bool,
+    /// Follow the Services sheet with "Service Taxonomies" (`--include-taxonomy-sheet`)
+    pub taxonomy_sheet: bool,
+}
+
+/// Sheets written after the Organizations and Services sheets; each is omitted when empty.
+pub struct TrailingSheets {
+    pub method_breakdown: Vec<MethodSummary>,
+    pub method_performance: Vec<MethodStats>,
+    pub orphaned_edges: Vec<OrphanedEdge>,
+    pub warnings: Vec<WarningSummary>,
+    /// Whether to end with the "Legend" sheet
+    pub legend: bool,
+    /// Sheets of an embedding application, added after all of the above
+    pub extra_sheets: Option<ExtraSheets>,
+}
 
-    info!("Saving Excel workbook...");
+/// Adds a caller's own sheets to the finished export workbook, typically with the
+/// public `write_*_sheet` helpers or `Workbook::add_worksheet`. It runs after the
+/// standard sheets are written and before the workbook is saved.
+pub type ExtraSheets = Box<dyn FnOnce(&mut Workbook) -> Result<()> + Send>;
+
+/// Runs `extra_sheets` and checks, through a `SheetNameAllocator` holding the standard
+/// sheet names, that none of the sheets it added reuses one.
+fn add_extra_sheets(workbook: &mut Workbook, extra_sheets: ExtraSheets) -> Result<()> {
+    let mut sheet_names = SheetNameAllocator::new();
+    for sheet in workbook.worksheets() {
+        sheet_names.reserve(&sheet.name())?;
+    }
+    let standard_sheets = workbook.worksheets().len();
+
+    extra_sheets(workbook).context("Failed to write the extra sheets")?;
+    for sheet in &workbook.worksheets()[standard_sheets..] {
+        sheet_names.reserve(&sheet.name()).context("An extra sheet collides with a standard sheet")?;
+    }
+    info!("{} extra sheets added.", workbook.worksheets().len() - standard_sheets);
+    Ok(())
+}
+
+/// Writes several opinions' exports into one workbook for side-by-side comparison:
+/// an "Opinion Comparison" sheet first, then an Organizations and Services sheet per opinion,
+/// and a "Warnings" sheet when the run recorded any warnings.
+pub async fn write_comparison_excel_file(
+    file_path: &Path,
+    opinion_exports: Vec<OpinionExport>,
+    comparison_rows: Vec<OpinionComparisonRow>,
+    warnings: &[WarningSummary],
+    footer: bool,
+) -> Result<()> {
+    info!("Initializing comparison workbook for file: {:?}", file_path);
+    let mut workbook = Workbook::new();
+    let mut sheet_names = SheetNameAllocator::new();
+
+    let opinion_names: Vec<&str> = opinion_exports.iter().map(|e| e.opinion_name.as_str()).collect();
+    let comparison_sheet = workbook.add_worksheet();
+    write_comparison_sheet(comparison_sheet, &sheet_names.allocate("Opinion Comparison"), &opinion_names, comparison_rows)?;
+
+    let labels = LabelMap::default();
+    let sheet_options = DataSheetOptions { cluster_labels: &labels, footer, taxonomy_sheet: false };
+    for export in opinion_exports {
+        let org_sheet = workbook.add_worksheet();
+        write_table_sheet(org_sheet, &sheet_names.allocate(&format!("Organizations – {}", export.opinion_name)), &organization_sheet(), &export.org_data, sheet_options)?;
+
+        let svc_sheet = workbook.add_worksheet();
+        write_table_sheet(svc_sheet, &sheet_names.allocate(&format!("Services – {}", export.opinion_name)), &service_sheet(), &export.svc_data, sheet_options)?;
+    }
+
+    if !warnings.is_empty() {
+        let warnings_sheet = workbook.add_worksheet();
+        write_warnings_sheet(warnings_sheet, &sheet_names.allocate("Warnings"), warnings)?;
+    }
+
+    info!("Saving comparison workbook...");
     workbook.save(file_path)?;
-    info!("Excel file saved successfully to {:?}", file_path);
+    info!("Comparison file saved successfully to {:?}", file_path);
     Ok(())
 }
 
-/// Helper function to write data to the "Organizations" sheet.
-fn write_organization_sheet(sheet: &mut Worksheet, data: Vec<OrganizationExportRow>) -> Result<()> {
-    sheet.set_name("Organizations")?;
+/// Writes the team consensus: a "Consensus" sheet with one column per reviewer, and a
+/// "Warnings" sheet when the run recorded any warnings.
+pub async fn write_consensus_excel_file(
+    file_path: &Path,
+    reviewers: &[String],
+    rows: Vec<ConsensusRow>,
+    warnings: &[WarningSummary],
+) -> Result<()> {
+    info!("Initializing consensus workbook for file: {:?}", file_path);
+    let mut workbook = Workbook::new();
+    let mut sheet_names = SheetNameAllocator::new();
 
-    // Define headers
-    let headers = vec![
-        "contributor",
-        "contributor_id",
-        "entity_id",
+    let consensus_sheet = workbook.add_worksheet();
+    write_consensus_sheet(consensus_sheet, &sheet_names.allocate("Consensus"), reviewers, rows)?;
+
+    if !warnings.is_empty() {
+        let warnings_sheet = workbook.add_worksheet();
+        write_warnings_sheet(warnings_sheet, &sheet_names.allocate("Warnings"), warnings)?;
+    }
+
+    info!("Saving consensus workbook...");
+    workbook.save(file_path)?;
+    info!("Consensus file saved successfully to {:?}", file_path);
+    Ok(())
+}
+
+/// Writes a change report between two exports: an "Overview" sheet with counts,
+/// followed by one sheet per change kind for organizations and for services.
+pub async fn write_diff_excel_file(
+    file_path: &Path,
+    old_label: &str,
+    new_label: &str,
+    org_changes: Vec<ChangeRecord>,
+    svc_changes: Vec<ChangeRecord>,
+) -> Result<()> {
+    info!("Initializing change report workbook for file: {:?}", file_path);
+    let mut workbook = Workbook::new();
+
+    let overview_sheet = workbook.add_worksheet();
+    write_diff_overview_sheet(
+        overview_sheet,
+        old_label,
+        new_label,
+        DiffSummary::from_changes(&org_changes),
+        DiffSummary::from_changes(&svc_changes),
+    )?;
+
+    let change_sheets = [
+        (ChangeKind::Added, "Added"),
+        (ChangeKind::Removed, "Removed"),
+        (ChangeKind::ClusterChanged, "Cluster Changed"),
+        (ChangeKind::StatusChanged, "Status Changed"),
+    ];
+    for (record_label, id_header, changes) in [
+        ("Orgs", "entity_id", &org_changes),
+        ("Services", "service_id", &svc_changes),
+    ] {
+        for (kind, kind_label) in change_sheets {
+            let sheet = workbook.add_worksheet();
+            let rows: Vec<&ChangeRecord> = changes.iter().filter(|c| c.change == kind).collect();
+            write_change_sheet(sheet, &format!("{} {}", record_label, kind_label), id_header, &rows)?;
+        }
+    }
+
+    info!("Saving change report workbook...");
+    workbook.save(file_path)?;
+    info!("Change report saved successfully to {:?}", file_path);
+    Ok(())
+}
+
+/// Helper function to write the change counts to the "Overview" sheet.
+fn write_diff_overview_sheet(
+    sheet: &mut Worksheet,
+    old_label: &str,
+    new_label: &str,
+    org_summary: DiffSummary,
+    svc_summary: DiffSummary,
+) -> Result<()> {
+    sheet.set_name("Overview")?;
+    sheet.set_column_width(0, 20)?;
+
+    sheet.write_string(0, 0, "CHANGE REPORT")?;
+    sheet.write_string(2, 0, "Old Export")?;
+    sheet.write_string(2, 1, old_label)?;
+    sheet.write_string(3, 0, "New Export")?;
+    sheet.write_string(3, 1, new_label)?;
+
+    let headers = ["Record Type", "Added", "Removed", "Cluster Changed", "Status Changed"];
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(5, col_num as u16, *header)?;
+    }
+
+    for (row, (label, summary)) in [("Organizations", org_summary), ("Services", svc_summary)].into_iter().enumerate() {
+        let current_row = 6 + row as u32;
+        sheet.write_string(current_row, 0, label)?;
+        sheet.write_number(current_row, 1, summary.added as f64)?;
+        sheet.write_number(current_row, 2, summary.removed as f64)?;
+        sheet.write_number(current_row, 3, summary.cluster_changed as f64)?;
+        sheet.write_number(current_row, 4, summary.status_changed as f64)?;
+    }
+
+    sheet.write_string(9, 0, "Generated")?;
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    sheet.write_string(9, 1, &timestamp)?;
+
+    info!("'Overview' sheet written.");
+    Ok(())
+}
+
+/// Helper function to write one kind of change to its own sheet.
+fn write_change_sheet(sheet: &mut Worksheet, sheet_name: &str, id_header: &str, data: &[&ChangeRecord]) -> Result<()> {
+    sheet.set_name(sheet_name)?;
+
+    let headers = [
+        id_header,
         "name",
-        "cluster_confirmed_status",
-        "cluster",
-        "has_duplicates",
+        "contributor",
+        "old_cluster",
+        "new_cluster",
+        "old_status",
+        "new_status",
     ];
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+    }
+
+    for (row_num, row_data) in data.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        sheet.write_string(current_row, 0, &row_data.record_id)?;
+        sheet.write_string(current_row, 1, row_data.name.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 2, row_data.contributor.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 3, row_data.old_cluster.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 4, row_data.new_cluster.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 5, row_data.old_status.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 6, row_data.new_status.as_deref().unwrap_or(""))?;
+    }
+    info!("'{}' sheet written with {} rows.", sheet_name, data.len());
+    Ok(())
+}
+
+/// Helper function to write the per-entity cluster comparison across opinions.
+fn write_comparison_sheet(
+    sheet: &mut Worksheet,
+    sheet_name: &str,
+    opinion_names: &[&str],
+    data: Vec<OpinionComparisonRow>,
+) -> Result<()> {
+    sheet.set_name(sheet_name)?;
+
+    // Fixed leading columns, one cluster column per opinion, then the agreement flag
+    let mut headers: Vec<String> = vec!["entity_id".to_string(), "name".to_string(), "contributor".to_string()];
+    headers.extend(opinion_names.iter().map(|name| format!("cluster ({})", name)));
+    headers.push("clusters_agree".to_string());
+
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, header)?;
+    }
+
+    let agree_col = (headers.len() - 1) as u16;
+    for (row_num, row_data) in data.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        sheet.write_string(current_row, 0, &row_data.entity_id)?;
+        sheet.write_string(current_row, 1, row_data.name.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 2, row_data.contributor.as_deref().unwrap_or(""))?;
+        for (i, cluster) in row_data.clusters.iter().enumerate() {
+            sheet.write_string(current_row, (3 + i) as u16, cluster.as_deref().unwrap_or(""))?;
+        }
+        sheet.write_boolean(current_row, agree_col, row_data.clusters_agree)?;
+    }
+    info!("'{}' sheet written with {} rows.", sheet_name, data.len());
+    Ok(())
+}
+
+/// Helper function to write the consensus rows: the entity, one placement column per
+/// reviewer (left empty when the reviewer has no data for it), and the agreement flag.
+fn write_consensus_sheet(sheet: &mut Worksheet, sheet_name: &str, reviewers: &[String], data: Vec<ConsensusRow>) -> Result<()> {
+    sheet.set_name(sheet_name)?;
+
+    let mut headers: Vec<String> = vec!["entity_id".to_string(), "name".to_string(), "contributor".to_string()];
+    headers.extend(reviewers.iter().map(|reviewer| format!("cluster ({})", reviewer)));
+    headers.push("reviewers_with_data".to_string());
+    headers.push("reviewers_agree".to_string());
 
-    // Write headers
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, header)?;
+    }
+
+    let count_col = (headers.len() - 2) as u16;
+    for (row_num, row_data) in data.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        sheet.write_string(current_row, 0, &row_data.entity_id)?;
+        sheet.write_string(current_row, 1, row_data.name.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 2, row_data.contributor.as_deref().unwrap_or(""))?;
+        for (i, placement) in row_data.placements.iter().enumerate() {
+            if let Some(placement) = placement {
+                sheet.write_string(current_row, (3 + i) as u16, placement)?;
+            }
+        }
+        sheet.write_number(current_row, count_col, row_data.reviewers_with_data as f64)?;
+        sheet.write_boolean(current_row, count_col + 1, row_data.agreement)?;
+    }
+    info!("'{}' sheet written with {} rows.", sheet_name, data.len());
+    Ok(())
+}
+
+/// A cell of an Organizations or Services row, by how it is written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue<'a> {
+    /// Written as a string; a missing value is an empty string
+    Text(&'a str),
+    /// Written as TRUE or FALSE
+    Flag(bool),
+    /// Written as a number in the column's `num_format`; `None` leaves the cell blank
+    Number(Option<f64>),
+    /// Written as an Excel date; `None` leaves the cell blank
+    ReviewTime(Option<chrono::NaiveDateTime>),
+    /// Written as a number in the column's `num_format`, or as the given text when `None`
+    NumberOrText(Option<f64>, &'a str),
+    /// Left blank
+    Blank,
+}
+
+/// One column of a table sheet: the header, how to read its cell from a row, and
+/// how to format it.
+pub struct TableColumn<R> {
+    pub header: &'static str,
+    pub cell: for<'r> fn(&'r R, &'r LabelMap) -> CellValue<'r>,
+    /// Excel number format for `CellValue::Number` cells; `None` writes them plain
+    pub num_format: Option<&'static str>,
+    /// Column width in characters; `None` keeps Excel's default
+    pub width: Option<f64>,
+}
+
+/// A row of a table sheet: what the hidden `cluster_id` column and the footer need.
+pub trait TableRow {
+    fn cluster(&self) -> Option<&str>;
+    fn has_duplicates(&self) -> bool;
+}
+
+impl TableRow for OrganizationExportRow {
+    fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.has_duplicates
+    }
+}
+
+impl TableRow for ServiceExportRow {
+    fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.has_duplicates
+    }
+}
+
+/// The layout of the Organizations or Services sheet. Headers, cells and footer
+/// positions all come from the one column list, so they cannot drift apart.
+pub struct TableSheet<R> {
+    pub columns: Vec<TableColumn<R>>,
+    /// Header of the record id column, under which the footer shows the record count
+    pub id_header: &'static str,
+}
+
+impl<R: TableRow> TableSheet<R> {
+    /// The headers, in column order.
+    pub fn headers(&self) -> Vec<&'static str> {
+        self.columns.iter().map(|column| column.header).collect()
+    }
+
+    /// The cells of one data row, in column order.
+    pub fn cells<'r>(&self, row: &'r R, labels: &'r LabelMap) -> Vec<CellValue<'r>> {
+        self.columns.iter().map(|column| (column.cell)(row, labels)).collect()
+    }
+
+    fn column_index(&self, header: &str) -> Result<u16> {
+        self.columns.iter()
+            .position(|column| column.header == header)
+            .map(|index| index as u16)
+            .ok_or_else(|| anyhow::anyhow!("Table sheet has no '{}' column", header))
+    }
+}
+
+/// Reads an optional text field as a string cell.
+fn text(value: &Option<String>) -> CellValue<'_> {
+    CellValue::Text(value.as_deref().unwrap_or(""))
+}
+
+/// Reads a cluster id as its display label.
+fn cluster_label<'r>(cluster: &'r Option<String>, labels: &'r LabelMap) -> CellValue<'r> {
+    CellValue::Text(cluster.as_deref().map(|c| labels.label(c)).unwrap_or(""))
+}
+
+/// Number format of the `min_edge_confidence` columns.
+const CONFIDENCE_FORMAT: &str = "0.000";
+/// Coordinates keep six decimals (about 0.1 m)
+const COORDINATE_FORMAT: &str = "0.000000";
+/// Number format of the `reviewed_edge_fraction` columns.
+const FRACTION_FORMAT: &str = "0.0%";
+
+/// A cluster's review coverage, "n/a" when it has no edges to review.
+fn review_coverage(fraction: Option<f64>) -> CellValue<'static> {
+    CellValue::NumberOrText(fraction, "n/a")
+}
+
+/// Columns of the "Service Taxonomies" sheet, in order.
+pub const TAXONOMY_HEADERS: [&str; 4] = ["service_id", "taxonomy_term", "taxonomy_category", "taxonomy_id"];
+
+/// Columns of the "Organizations" sheet, in order.
+pub fn organization_sheet() -> TableSheet<OrganizationExportRow> {
+    type Row = OrganizationExportRow;
+    let column = |header, cell: for<'r> fn(&'r Row, &'r LabelMap) -> CellValue<'r>| TableColumn { header, cell, num_format: None, width: None };
+    TableSheet {
+        columns: vec![
+            column("contributor", |r, _| text(&r.contributor)),
+            column("contributor_id", |r, _| text(&r.contributor_id)),
+            column("entity_id", |r, _| CellValue::Text(&r.entity_id)),
+            column("name", |r, _| text(&r.name)),
+            column("alternate_names", |r, _| text(&r.alternate_names)),
+            column("cluster_confirmed_status", |r, _| CellValue::Text(&r.cluster_confirmed_status)),
+            column("cluster", |r, labels| cluster_label(&r.cluster, labels)),
+            column("has_duplicates", |r, _| CellValue::Flag(r.has_duplicates)),
+            column("cluster_representative_name", |r, _| text(&r.cluster_representative_name)),
+            column("canonical_record", |r, _| CellValue::Flag(r.canonical_record)),
+            column("last_reviewed", |r, _| CellValue::ReviewTime(r.last_reviewed)),
+            column("reviewed_by", |r, _| text(&r.reviewed_by)),
+            TableColumn { num_format: Some(CONFIDENCE_FORMAT), ..column("min_edge_confidence", |r, _| CellValue::Number(r.min_edge_confidence)) },
+            column("low_confidence", |r, _| CellValue::Flag(r.low_confidence)),
+            TableColumn { num_format: Some(FRACTION_FORMAT), ..column("reviewed_edge_fraction", |r, _| review_coverage(r.reviewed_edge_fraction)) },
+            column("mixed_status", |r, _| CellValue::Flag(r.mixed_status)),
+            // The service columns are left blank when services are not part of this export
+            column("service_count", |r, _| CellValue::Number(r.services.as_ref().map(|s| s.service_count as f64))),
+            column("services_with_duplicates", |r, _| CellValue::Number(r.services.as_ref().map(|s| s.services_with_duplicates as f64))),
+            column("worst_service_status", |r, _| match &r.services {
+                Some(services) => text(&services.worst_status),
+                None => CellValue::Blank,
+            }),
+        ],
+        id_header: "entity_id",
+    }
+}
+
+/// Columns of the "Services" sheet, in order.
+pub fn service_sheet() -> TableSheet<ServiceExportRow> {
+    type Row = ServiceExportRow;
+    let column = |header, cell: for<'r> fn(&'r Row, &'r LabelMap) -> CellValue<'r>| TableColumn { header, cell, num_format: None, width: None };
+    TableSheet {
+        columns: vec![
+            column("contributor", |r, _| text(&r.contributor)),
+            column("contributor_id", |r, _| text(&r.contributor_id)),
+            column("service_id", |r, _| CellValue::Text(&r.service_id)),
+            column("organization_name", |r, _| text(&r.organization_name)),
+            column("service_name", |r, _| text(&r.service_name)),
+            column("location_name", |r, _| text(&r.location_name)),
+            column("full_address", |r, _| text(&r.full_address)),
+            column("address_missing_parts", |r, _| CellValue::Number(r.address_missing_parts.map(|m| m as f64))),
+            TableColumn { num_format: Some(COORDINATE_FORMAT), ..column("latitude", |r, _| CellValue::Number(r.latitude)) },
+            TableColumn { num_format: Some(COORDINATE_FORMAT), ..column("longitude", |r, _| CellValue::Number(r.longitude)) },
+            column("cluster_confirmed_status", |r, _| CellValue::Text(&r.cluster_confirmed_status)),
+            column("taxonomy_terms", |r, _| text(&r.taxonomy_terms)),
+            column("taxonomy_count", |r, _| CellValue::Number(Some(r.taxonomy_count as f64))),
+            column("cluster", |r, labels| cluster_label(&r.cluster, labels)),
+            column("has_duplicates", |r, _| CellValue::Flag(r.has_duplicates)),
+            column("canonical_record", |r, _| CellValue::Flag(r.canonical_record)),
+            column("last_reviewed", |r, _| CellValue::ReviewTime(r.last_reviewed)),
+            column("reviewed_by", |r, _| text(&r.reviewed_by)),
+            TableColumn { num_format: Some(CONFIDENCE_FORMAT), ..column("min_edge_confidence", |r, _| CellValue::Number(r.min_edge_confidence)) },
+            column("low_confidence", |r, _| CellValue::Flag(r.low_confidence)),
+            TableColumn { num_format: Some(FRACTION_FORMAT), ..column("reviewed_edge_fraction", |r, _| review_coverage(r.reviewed_edge_fraction)) },
+            column("mixed_status", |r, _| CellValue::Flag(r.mixed_status)),
+        ],
+        id_header: "service_id",
+    }
+}
+
+/// Writes a data sheet laid out by `table`: the annotated headers, one row per
+/// record, the hidden `cluster_id` column for short labels, and the footer.
+/// `organization_sheet` and `service_sheet` give the standard layouts.
+pub fn write_table_sheet<R: TableRow>(
+    sheet: &mut Worksheet,
+    sheet_name: &str,
+    table: &TableSheet<R>,
+    data: &[R],
+    options: DataSheetOptions<'_>,
+) -> Result<()> {
+    sheet.set_name(sheet_name)?;
+    let labels = options.cluster_labels;
+
+    let headers = table.headers();
+    write_annotated_headers(sheet, &headers)?;
+    for (col_num, column) in table.columns.iter().enumerate() {
+        if let Some(width) = column.width {
+            sheet.set_column_width(col_num as u16, width)?;
+        }
+    }
+    let formats: Vec<Option<Format>> = table.columns.iter()
+        .map(|column| column.num_format.map(|num_format| Format::new().set_num_format(num_format)))
+        .collect();
+
+    for (row_num, row_data) in data.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        for (col_num, cell) in table.cells(row_data, labels).into_iter().enumerate() {
+            let col = col_num as u16;
+            match cell {
+                CellValue::Text(value) => {
+                    sheet.write_string(current_row, col, value)?;
+                }
+                CellValue::Flag(value) => {
+                    sheet.write_boolean(current_row, col, value)?;
+                }
+                CellValue::Number(Some(value)) | CellValue::NumberOrText(Some(value), _) => match &formats[col_num] {
+                    Some(format) => {
+                        sheet.write_number_with_format(current_row, col, value, format)?;
+                    }
+                    None => {
+                        sheet.write_number(current_row, col, value)?;
+                    }
+                },
+                CellValue::NumberOrText(None, fallback) => {
+                    sheet.write_string(current_row, col, fallback)?;
+                }
+                CellValue::Number(None) | CellValue::Blank => {}
+                CellValue::ReviewTime(reviewed) => write_review_time(sheet, current_row, col, reviewed)?,
+            }
+        }
+    }
+    write_cluster_id_column(sheet, headers.len() as u16, labels, data.iter().map(TableRow::cluster))?;
+    if options.footer {
+        let id_col = table.column_index(table.id_header)?;
+        let duplicates_col = table.column_index("has_duplicates")?;
+        write_footer(sheet, id_col, duplicates_col, FooterCounts::count(data.iter().map(TableRow::has_duplicates)))?;
+    }
+    info!("'{}' sheet written with {} rows.", sheet_name, data.len());
+    Ok(())
+}
+
+/// Totals shown in the footer row of the Organizations and Services sheets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FooterCounts {
+    pub records: usize,
+    pub with_duplicates: usize,
+}
+
+impl FooterCounts {
+    /// Counts the rows and their TRUE `has_duplicates` values.
+    pub fn count(has_duplicates: impl Iterator<Item = bool>) -> FooterCounts {
+        has_duplicates.fold(FooterCounts::default(), |counts, flag| FooterCounts {
+            records: counts.records + 1,
+            with_duplicates: counts.with_duplicates + flag as usize,
+        })
+    }
+
+    /// The sheet row of the footer: after the header, the data rows and one blank row,
+    /// so the footer stays out of a filter range that ends at the last data row.
+    pub fn row(&self) -> u32 {
+        self.records as u32 + 2
+    }
+}
+
+/// Writes the bold footer row: "Total" in the first column, the record count under
+/// the id column and the number of duplicated records under `has_duplicates`.
+fn write_footer(sheet: &mut Worksheet, id_col: u16, duplicates_col: u16, counts: FooterCounts) -> Result<()> {
+    let bold = Format::new().set_bold();
+    let row = counts.row();
+    sheet.write_string_with_format(row, 0, "Total", &bold)?;
+    sheet.write_number_with_format(row, id_col, counts.records as f64, &bold)?;
+    sheet.write_number_with_format(row, duplicates_col, counts.with_duplicates as f64, &bold)?;
+    Ok(())
+}
+
+/// When the `cluster` column shows short labels, adds a hidden `cluster_id` column at
+/// `col` with the full cluster ids, so the sheet can still be joined on them.
+fn write_cluster_id_column<'a>(
+    sheet: &mut Worksheet,
+    col: u16,
+    labels: &LabelMap,
+    clusters: impl Iterator<Item = Option<&'a str>>,
+) -> Result<()> {
+    if labels.is_full() {
+        return Ok(());
+    }
+    sheet.write_string(0, col, "cluster_id")?;
+    for (row_num, cluster) in clusters.enumerate() {
+        sheet.write_string((row_num + 1) as u32, col, cluster.unwrap_or(""))?;
+    }
+    sheet.set_column_hidden(col)?;
+    Ok(())
+}
+
+/// Explanation shown as a note on the header of a computed column of the
+/// Organizations and Services sheets, or `None` for a column copied from the source.
+/// Both sheets take their notes from here so they describe a column the same way.
+pub fn header_note(header: &str) -> Option<&'static str> {
+    match header {
+        "cluster_confirmed_status" => Some(
+            "PENDING_REVIEW = at least one match in the cluster still needs review. \
+             CONFIRMED = at least one confirmed match in the cluster and none pending. \
+             NO_MATCH = not matched to any other record."
+        ),
+        "cluster" => Some("The duplicate cluster this record belongs to. Records with the same value are duplicates of each other."),
+        "has_duplicates" => Some("TRUE when at least one other member of the same cluster is in this file."),
+        "alternate_names" => Some(
+            "Other names the organization goes by (DBA, aka), comma-separated. Duplicates often differ in their primary \
+             name but share one of these. Empty when none are recorded."
+        ),
+        "cluster_representative_name" => Some("The longest name among the cluster's members, to recognize the cluster at a glance."),
+        "canonical_record" => Some("TRUE for the one record of each duplicate cluster chosen to keep when merging."),
+        "taxonomy_terms" => Some(
+            "The taxonomy terms of the service, comma-separated. With --max-taxonomy-terms only the first terms are \
+             listed, followed by (+k more)."
+        ),
+        "taxonomy_count" => Some("How many distinct taxonomy terms the service has, including any left out of taxonomy_terms."),
+        "address_missing_parts" => Some(
+            "How many of street, city, state, postal code and country are missing from the address (0 = complete). Blank when the service has no address."
+        ),
+        "last_reviewed" => Some("When any match in the cluster was last reviewed. Empty if none has been reviewed yet."),
+        "reviewed_by" => Some("Everyone who reviewed a match in the cluster. Empty when the opinion does not record reviewers."),
+        "min_edge_confidence" => Some("The weakest match confidence in the cluster (0 to 1). Lower values deserve a second look."),
+        "low_confidence" => Some("TRUE when min_edge_confidence is below the threshold chosen for this export."),
+        "reviewed_edge_fraction" => Some(
+            "Share of the cluster's match edges that have a review decision; the rest are still pending. \
+             n/a for a record without any edges to review."
+        ),
+        "mixed_status" => Some(
+            "TRUE when the cluster has confirmed matches alongside pending edges or non-matches that did not split it. \
+             Worth a closer look even when cluster_confirmed_status looks settled."
+        ),
+        "service_count" => Some("Services of this organization in this export. Blank when services were not exported."),
+        "services_with_duplicates" => Some("How many of those services have duplicates on the Services sheet."),
+        "worst_service_status" => Some(
+            "The most urgent cluster_confirmed_status among the organization's services: \
+             PENDING_REVIEW, then CONFIRMED, then NO_MATCH. Empty when it has no services."
+        ),
+        _ => None,
+    }
+}
+
+/// Writes a header row, attaching each column's `header_note`. Notes are stored
+/// apart from cell values, so they do not change a sheet's data or filter range.
+fn write_annotated_headers(sheet: &mut Worksheet, headers: &[&str]) -> Result<()> {
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+        if let Some(text) = header_note(header) {
+            sheet.insert_note(0, col_num as u16, &Note::new(text).set_width(300).set_height(90))?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a review timestamp as an Excel date, leaving the cell empty when there is none.
+fn write_review_time(sheet: &mut Worksheet, row: u32, col: u16, reviewed: Option<chrono::NaiveDateTime>) -> Result<()> {
+    if let Some(reviewed) = reviewed {
+        let date_format = Format::new().set_num_format("yyyy-mm-dd hh:mm");
+        let excel_time = ExcelDateTime::from_timestamp(reviewed.and_utc().timestamp())?;
+        sheet.write_datetime_with_format(row, col, &excel_time, &date_format)?;
+    }
+    Ok(())
+}
+
+/// Helper function to write the "Method Breakdown" sheet: one row per record type and
+/// matching method, already sorted by edge count.
+pub fn write_method_breakdown_sheet(sheet: &mut Worksheet, data: Vec<MethodSummary>) -> Result<()> {
+    sheet.set_name("Method Breakdown")?;
+
+    let headers = ["record_type", "method_type", "edge_count", "average_confidence"];
     for (col_num, header) in headers.iter().enumerate() {
         sheet.write_string(0, col_num as u16, *header)?;
     }
 
-    // Write data rows
+    let confidence_format = Format::new().set_num_format("0.000");
     for (row_num, row_data) in data.iter().enumerate() {
         let current_row = (row_num + 1) as u32; // +1 for header row
-        sheet.write_string(current_row, 0, row_data.contributor.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 1, row_data.contributor_id.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 2, &row_data.entity_id)?;
-        sheet.write_string(current_row, 3, row_data.name.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 4, &row_data.cluster_confirmed_status)?;
-        sheet.write_string(current_row, 5, row_data.cluster.as_deref().unwrap_or(""))?;
-        sheet.write_boolean(current_row, 6, row_data.has_duplicates)?;
-    }
-    info!("'Organizations' sheet written with {} rows.", data.len());
+        sheet.write_string(current_row, 0, &row_data.record_type)?;
+        sheet.write_string(current_row, 1, &row_data.method_type)?;
+        sheet.write_number(current_row, 2, row_data.edge_count as f64)?;
+        sheet.write_number_with_format(current_row, 3, row_data.average_confidence, &confidence_format)?;
+    }
+    info!("'Method Breakdown' sheet written with {} rows.", data.len());
     Ok(())
 }
 
-/// Helper function to write data to the "Services" sheet.
-fn write_service_sheet(sheet: &mut Worksheet, data: Vec<ServiceExportRow>) -> Result<()> {
-    sheet.set_name("Services")?;
+/// Helper function to write the "Method Performance" sheet: how the edges each matching
+/// method contributed to were decided, one row per record type and method.
+pub fn write_method_performance_sheet(sheet: &mut Worksheet, data: &[MethodStats]) -> Result<()> {
+    sheet.set_name("Method Performance")?;
 
-    // Define headers
-    let headers = vec![
-        "contributor",
-        "contributor_id",
-        "service_id",
-        "organization_name",
-        "service_name",
-        "location_name",
-        "full_address",
-        "cluster_confirmed_status",
-        "taxonomy_terms",
-        "cluster",
-        "has_duplicates",
+    let headers = [
+        "record_type",
+        "method_type",
+        "edge_count",
+        "confirmed_match",
+        "confirmed_non_match",
+        "pending_review",
+        "mean_edge_weight",
     ];
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+    }
 
-    // Write headers
+    let weight_format = Format::new().set_num_format("0.000");
+    for (row_num, row_data) in data.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        sheet.write_string(current_row, 0, &row_data.record_type)?;
+        sheet.write_string(current_row, 1, &row_data.method_type)?;
+        sheet.write_number(current_row, 2, row_data.edge_count as f64)?;
+        sheet.write_number(current_row, 3, row_data.confirmed_match as f64)?;
+        sheet.write_number(current_row, 4, row_data.confirmed_non_match as f64)?;
+        sheet.write_number(current_row, 5, row_data.pending_review as f64)?;
+        sheet.write_number_with_format(current_row, 6, row_data.mean_edge_weight, &weight_format)?;
+    }
+    info!("'Method Performance' sheet written with {} rows.", data.len());
+    Ok(())
+}
+
+/// Helper function to write edges referencing deleted records to the "Orphaned Edges" sheet.
+pub fn write_orphaned_edges_sheet(sheet: &mut Worksheet, data: Vec<OrphanedEdge>) -> Result<()> {
+    sheet.set_name("Orphaned Edges")?;
+
+    let headers = [
+        "record_type",
+        "edge_id",
+        "id_1",
+        "id_2",
+        "missing_ids",
+        "confirmed_status",
+    ];
     for (col_num, header) in headers.iter().enumerate() {
         sheet.write_string(0, col_num as u16, *header)?;
     }
 
-    // Write data rows
     for (row_num, row_data) in data.iter().enumerate() {
         let current_row = (row_num + 1) as u32; // +1 for header row
-        sheet.write_string(current_row, 0, row_data.contributor.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 1, row_data.contributor_id.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 2, &row_data.service_id)?;
-        sheet.write_string(current_row, 3, row_data.organization_name.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 4, row_data.service_name.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 5, row_data.location_name.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 6, row_data.full_address.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 7, &row_data.cluster_confirmed_status)?;
-        sheet.write_string(current_row, 8, row_data.taxonomy_terms.as_deref().unwrap_or(""))?;
-        sheet.write_string(current_row, 9, row_data.cluster.as_deref().unwrap_or(""))?;
-        sheet.write_boolean(current_row, 10, row_data.has_duplicates)?;
-    }
-    info!("'Services' sheet written with {} rows.", data.len());
+        sheet.write_string(current_row, 0, &row_data.record_type)?;
+        sheet.write_string(current_row, 1, &row_data.edge_id)?;
+        sheet.write_string(current_row, 2, &row_data.id_1)?;
+        sheet.write_string(current_row, 3, &row_data.id_2)?;
+        sheet.write_string(current_row, 4, &row_data.missing_ids)?;
+        sheet.write_string(current_row, 5, row_data.confirmed_status.as_deref().unwrap_or(""))?;
+    }
+    info!("'Orphaned Edges' sheet written with {} rows.", data.len());
+    Ok(())
+}
+
+/// Helper function to write the "Service Taxonomies" sheet: one row per taxonomy term
+/// of each exported service, in Services sheet order, so terms can be filtered on.
+pub fn write_taxonomy_sheet(sheet: &mut Worksheet, data: &[ServiceExportRow]) -> Result<()> {
+    sheet.set_name("Service Taxonomies")?;
+    sheet.set_column_width(0, 38)?;
+    sheet.set_column_width(1, 40)?;
+    sheet.set_column_width(2, 24)?;
+    sheet.set_column_width(3, 38)?;
+
+    let headers = TAXONOMY_HEADERS;
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+    }
+
+    let mut current_row = 1;
+    for taxonomy in data.iter().flat_map(|service| &service.taxonomies) {
+        sheet.write_string(current_row, 0, &taxonomy.service_id)?;
+        sheet.write_string(current_row, 1, &taxonomy.taxonomy_term)?;
+        sheet.write_string(current_row, 2, taxonomy.taxonomy_category.as_deref().unwrap_or(""))?;
+        sheet.write_string(current_row, 3, &taxonomy.taxonomy_id)?;
+        current_row += 1;
+    }
+
+    // Filter over the header and every data row; keep the header visible when scrolling
+    sheet.autofilter(0, 0, current_row.saturating_sub(1).max(1), (headers.len() - 1) as u16)?;
+    sheet.set_freeze_panes(1, 0)?;
+
+    info!("'Service Taxonomies' sheet written with {} rows.", current_row - 1);
+    Ok(())
+}
+
+/// Helper function to write the run's warnings, one row per category with its count
+/// and first few examples.
+pub fn write_warnings_sheet(sheet: &mut Worksheet, sheet_name: &str, data: &[WarningSummary]) -> Result<()> {
+    sheet.set_name(sheet_name)?;
+    sheet.set_column_width(0, 24)?;
+    sheet.set_column_width(2, 100)?;
+
+    let headers = ["category", "count", "examples"];
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+    }
+
+    for (row_num, summary) in data.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        sheet.write_string(current_row, 0, summary.category.to_string())?;
+        sheet.write_number(current_row, 1, summary.count as f64)?;
+        sheet.write_string(current_row, 2, summary.examples.join("; "))?;
+    }
+    info!("'{}' sheet written with {} rows.", sheet_name, data.len());
+    Ok(())
+}
+
+/// One row of the "Legend" sheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegendEntry {
+    pub sheet: &'static str,
+    pub column: &'static str,
+    pub description: &'static str,
+    /// Possible values of a status or flag column; empty for free-form columns
+    pub values: &'static str,
+}
+
+const STATUS_VALUES: &str = "PENDING_REVIEW, CONFIRMED, NO_MATCH";
+const FLAG_VALUES: &str = "TRUE, FALSE";
+
+/// The columns of every sheet in the export workbook. An empty description is taken
+/// from the column's `header_note`, so the legend and the header notes never disagree.
+const LEGEND: &[(&str, &str, &str, &str)] = &[
+    ("Progress Overview", "(rows)", "Review totals per record type for the exported opinion: pending, confirmed match, confirmed non-match, completion and estimated time remaining, with a pie chart of the review decisions.", ""),
+    ("User Breakdown", "User / User Prefix / Opinion Name", "The reviewer and the opinion the counts belong to.", ""),
+    ("User Breakdown", "Record Type", "Which records the row counts.", "Entity, Service"),
+    ("User Breakdown", "Pending Review / Confirmed Match / Confirmed Non-Match", "Number of edges with each review decision.", ""),
+    ("User Breakdown", "Total Records / Reviewed Count", "All edges, and the edges with a decision.", ""),
+    ("User Breakdown", "Completion %", "Reviewed Count as a share of Total Records; n/a when there are no records.", ""),
+    ("User Breakdown", "Est. Time Remaining", "Time to review the pending edges at the given review rate (only with --review-rate).", ""),
+    ("Organizations", "contributor", "Dataset (source system) the organization came from.", ""),
+    ("Organizations", "contributor_id", "The organization's id in that dataset.", ""),
+    ("Organizations", "entity_id", "The organization's id in this system.", ""),
+    ("Organizations", "name", "The organization's name.", ""),
+    ("Organizations", "alternate_names", "", ""),
+    ("Organizations", "cluster_confirmed_status", "", STATUS_VALUES),
+    ("Organizations", "cluster", "", ""),
+    ("Organizations", "has_duplicates", "", FLAG_VALUES),
+    ("Organizations", "cluster_representative_name", "", ""),
+    ("Organizations", "canonical_record", "", FLAG_VALUES),
+    ("Organizations", "last_reviewed", "", ""),
+    ("Organizations", "reviewed_by", "", ""),
+    ("Organizations", "min_edge_confidence", "", ""),
+    ("Organizations", "low_confidence", "", FLAG_VALUES),
+    ("Organizations", "reviewed_edge_fraction", "", ""),
+    ("Organizations", "mixed_status", "", FLAG_VALUES),
+    ("Organizations", "service_count", "", ""),
+    ("Organizations", "services_with_duplicates", "", ""),
+    ("Organizations", "worst_service_status", "", STATUS_VALUES),
+    ("Services", "contributor", "Contributor id recorded on the service.", ""),
+    ("Services", "contributor_id", "Dataset (source system) the service came from.", ""),
+    ("Services", "service_id", "The service's id in this system.", ""),
+    ("Services", "organization_name", "Name of the organization offering the service.", ""),
+    ("Services", "service_name", "The service's name.", ""),
+    ("Services", "location_name", "Name of the service's location.", ""),
+    ("Services", "full_address", "Address of that location. Missing components are skipped.", ""),
+    ("Services", "address_missing_parts", "", ""),
+    ("Services", "latitude / longitude", "Coordinates of the same location; blank when not recorded.", ""),
+    ("Services", "cluster_confirmed_status", "", STATUS_VALUES),
+    ("Services", "taxonomy_terms", "", ""),
+    ("Services", "taxonomy_count", "", ""),
+    ("Services", "cluster", "", ""),
+    ("Services", "has_duplicates", "", FLAG_VALUES),
+    ("Services", "canonical_record", "", FLAG_VALUES),
+    ("Services", "last_reviewed", "", ""),
+    ("Services", "reviewed_by", "", ""),
+    ("Services", "min_edge_confidence", "", ""),
+    ("Services", "low_confidence", "", FLAG_VALUES),
+    ("Services", "reviewed_edge_fraction", "", ""),
+    ("Services", "mixed_status", "", FLAG_VALUES),
+    ("Organizations / Services", "cluster_id", "Full cluster id, hidden; only present when the cluster column shows short labels.", ""),
+    ("Organizations / Services", "Total (footer)", "Bold row after a blank row below the data: the record count under the id column and the number of TRUE has_duplicates. Left out with --no-footer.", ""),
+    ("Service Taxonomies", "service_id", "The service, as on the Services sheet. Only written with --include-taxonomy-sheet.", ""),
+    ("Service Taxonomies", "taxonomy_term", "One taxonomy term of the service; a service with several terms has several rows.", ""),
+    ("Service Taxonomies", "taxonomy_category", "The taxonomy the term belongs to.", ""),
+    ("Service Taxonomies", "taxonomy_id", "The term's id in the source taxonomy_term table.", ""),
+    ("Method Breakdown", "record_type", "Which records the row counts.", "entity, service"),
+    ("Method Breakdown", "method_type", "A matching method that proposed edges.", ""),
+    ("Method Breakdown", "edge_count", "Exported edges the method contributed to.", ""),
+    ("Method Breakdown", "average_confidence", "The method's mean confidence on those edges.", ""),
+    ("Method Performance", "record_type", "Which records the row counts.", "entity, service"),
+    ("Method Performance", "method_type", "A matching method that proposed edges.", ""),
+    ("Method Performance", "edge_count", "The opinion's edges the method contributed to, non-matches included.", ""),
+    ("Method Performance", "confirmed_match", "Of those, edges confirmed as matches.", ""),
+    ("Method Performance", "confirmed_non_match", "Of those, edges confirmed as non-matches.", ""),
+    ("Method Performance", "pending_review", "Of those, edges not yet reviewed.", ""),
+    ("Method Performance", "mean_edge_weight", "The mean calculated weight of those edges.", ""),
+    ("Orphaned Edges", "record_type", "Which records the edge connects.", "entity, service"),
+    ("Orphaned Edges", "edge_id", "The edge's id in the opinion.", ""),
+    ("Orphaned Edges", "id_1 / id_2", "The two records the edge connects.", ""),
+    ("Orphaned Edges", "missing_ids", "Which of them no longer exist.", ""),
+    ("Orphaned Edges", "confirmed_status", "The edge's review decision as stored.", "PENDING_REVIEW, CONFIRMED_MATCH, CONFIRMED_NON_MATCH"),
+    ("Warnings", "category", "The kind of problem the run worked around.", ""),
+    ("Warnings", "count", "How often it occurred.", ""),
+    ("Warnings", "examples", "The first few occurrences.", ""),
+];
+
+/// The rows of the "Legend" sheet, with descriptions filled in from the header notes.
+pub fn legend_entries() -> Vec<LegendEntry> {
+    LEGEND.iter()
+        .map(|&(sheet, column, description, values)| LegendEntry {
+            sheet,
+            column,
+            description: if description.is_empty() { header_note(column).unwrap_or_default() } else { description },
+            values,
+        })
+        .collect()
+}
+
+/// Helper function to write the "Legend" sheet: every column of the workbook with a
+/// one-line description and the possible values of status and flag columns.
+pub fn write_legend_sheet(sheet: &mut Worksheet) -> Result<()> {
+    sheet.set_name("Legend")?;
+    sheet.set_column_width(0, 24)?;
+    sheet.set_column_width(1, 32)?;
+    sheet.set_column_width(2, 100)?;
+    sheet.set_column_width(3, 40)?;
+
+    let headers = ["sheet", "column", "description", "values"];
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+    }
+
+    let entries = legend_entries();
+    for (row_num, entry) in entries.iter().enumerate() {
+        let current_row = (row_num + 1) as u32; // +1 for header row
+        sheet.write_string(current_row, 0, entry.sheet)?;
+        sheet.write_string(current_row, 1, entry.column)?;
+        sheet.write_string(current_row, 2, entry.description)?;
+        sheet.write_string(current_row, 3, entry.values)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    info!("'Legend' sheet written with {} rows.", entries.len());
     Ok(())
 }
 
+/// Helper function to write a placeholder "Progress Overview" sheet when the review
+/// statistics could not be fetched, so recipients can see why they are missing.
+fn write_progress_unavailable_sheet(sheet: &mut Worksheet, error_summary: &str) -> Result<()> {
+    sheet.set_name("Progress Overview")?;
+    sheet.set_column_width(0, 20)?;
+    sheet.set_column_width(1, 100)?;
+
+    sheet.write_string(0, 0, "EXPORT SUMMARY")?;
+    sheet.write_string(2, 0, "Status")?;
+    sheet.write_string(2, 1, "Review statistics were unavailable for this export. The data sheets are complete.")?;
+    sheet.write_string(3, 0, "Error")?;
+    sheet.write_string(3, 1, error_summary)?;
+    sheet.write_string(5, 0, "Generated")?;
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    sheet.write_string(5, 1, &timestamp)?;
+
+    info!("Placeholder 'Progress Overview' sheet written because dashboard data was unavailable.");
+    Ok(())
+}
+
+/// Writes a completion fraction with `format` (a percent format), or "n/a" when
+/// there was nothing to review.
+fn write_completion(sheet: &mut Worksheet, row: u32, col: u16, fraction: Option<f64>, format: &Format) -> Result<()> {
+    match fraction {
+        Some(fraction) => sheet.write_number_with_format(row, col, fraction, format)?,
+        None => sheet.write_string(row, col, "n/a")?,
+    };
+    Ok(())
+}
+
+/// Helper function to write one row per user and record type to the "User Breakdown"
+/// sheet. It has a single header row and no blank rows, so it can be sorted and filtered.
+pub fn write_user_breakdown_sheet(
+    sheet: &mut Worksheet,
+    data: &[OpinionDashboard],
+    options: ProgressOptions,
+    include_entities: bool,
+    include_services: bool,
+) -> Result<()> {
+    sheet.set_name("User Breakdown")?;
+
+    let mut headers = vec![
+        "User", "User Prefix", "Opinion Name", "Record Type", "Pending Review", "Confirmed Match",
+        "Confirmed Non-Match", "Total Records", "Reviewed Count", "Completion %"
+    ];
+    if options.review_rate.is_some() {
+        headers.push("Est. Time Remaining");
+    }
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col_num as u16, *header)?;
+        sheet.set_column_width(col_num as u16, 18)?;
+    }
+
+    let percentage_format = Format::new().set_num_format("0.0%");
+    let mut current_row = 1u32;
+    for user in data {
+        for (record_type, stats, included) in [
+            ("Entity", &user.entity_stats, include_entities),
+            ("Service", &user.service_stats, include_services),
+        ] {
+            if !included {
+                continue;
+            }
+            sheet.write_string(current_row, 0, &user.username)?;
+            sheet.write_string(current_row, 1, &user.user_prefix)?;
+            sheet.write_string(current_row, 2, &user.opinion_name)?;
+            sheet.write_string(current_row, 3, record_type)?;
+            sheet.write_number(current_row, 4, stats.pending_review as f64)?;
+            sheet.write_number(current_row, 5, stats.confirmed_match as f64)?;
+            sheet.write_number(current_row, 6, stats.confirmed_non_match as f64)?;
+            sheet.write_number(current_row, 7, stats.total as f64)?;
+            sheet.write_number(current_row, 8, stats.reviewed_count as f64)?;
+            write_completion(sheet, current_row, 9, stats.completion_fraction(), &percentage_format)?;
+            if let Some(rate) = options.review_rate {
+                sheet.write_string(current_row, 10, dashboard::format_review_eta(stats.pending_review, rate))?;
+            }
+            current_row += 1;
+        }
+    }
+
+    // Filter over the header and every data row; keep the header visible when scrolling
+    let last_row = current_row.saturating_sub(1).max(1);
+    sheet.autofilter(0, 0, last_row, (headers.len() - 1) as u16)?;
+    sheet.set_freeze_panes(1, 0)?;
+
+    info!("'User Breakdown' sheet written with {} rows.", current_row - 1);
+    Ok(())
+}
+
+/// Label/value rows describing the export at the top of the Progress Overview.
+/// A shared opinion adds its owner, and an opinion shared with others lists them,
+/// so the file records whose review decisions it contains. A `--sample` export is
+/// flagged on the first row so it is not mistaken for a complete one, and an
+/// `--anonymize` export names the id of its key, never the key itself.
+pub fn export_info_rows(user: &OpinionDashboard, record_types: RecordScope, options: ProgressOptions) -> Vec<(&'static str, String)> {
+    let mut rows = run_flag_rows(options);
+    rows.extend([
+        ("User", user.username.clone()),
+        ("User Prefix", user.user_prefix.clone()),
+        ("Opinion Name", user.opinion_name.clone()),
+    ]);
+    if let Some(owner) = &user.opinion_owner {
+        rows.push(("Opinion Owner", owner.clone()));
+        rows.push(("Exported By", user.username.clone()));
+    }
+    if !user.shared_with.is_empty() {
+        rows.push(("Shared With", user.shared_with.join(", ")));
+    }
+    rows.push(("Record Types", record_types.label().to_string()));
+    rows
+}
+
+/// Label/value rows at the top of a Progress Overview that covers several opinions:
+/// the same sample and anonymization flags as `export_info_rows`, then how many users
+/// and opinions the figures add up.
+pub fn group_info_rows(data: &[OpinionDashboard], record_types: RecordScope, options: ProgressOptions) -> Vec<(&'static str, String)> {
+    let mut rows = run_flag_rows(options);
+    rows.extend([
+        ("Users", dashboard::group_by_user(data).len().to_string()),
+        ("Opinions", data.len().to_string()),
+        ("Record Types", record_types.label().to_string()),
+    ]);
+    rows
+}
+
+/// The `--sample` and `--anonymize` rows that open the export summary.
+fn run_flag_rows(options: ProgressOptions) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    if let Some(limit) = options.sample {
+        rows.push(("SAMPLE", format!("Only the first {} records and edges per type; not for delivery", limit)));
+    }
+    if let Some(key_id) = options.anonymized {
+        rows.push(("Anonymized", format!("Names and addresses are pseudonyms made with key {}", key_id)));
+    }
+    rows
+}
+
+/// Writes the "BY OPINION" table of the Progress Overview from `start_row`: one row
+/// per user and opinion, a bold subtotal after each user with several opinions, and a
+/// bold total over all users. Returns the row after the table.
+fn write_opinion_breakdown(
+    sheet: &mut Worksheet,
+    start_row: u32,
+    data: &[OpinionDashboard],
+    include_entities: bool,
+    include_services: bool,
+    percentage_format: &Format,
+) -> Result<u32> {
+    let mut current_row = start_row;
+    sheet.write_string(current_row, 0, "BY OPINION")?;
+    current_row += 1;
+
+    let record_types: Vec<&str> = [(include_entities, "Entity"), (include_services, "Service")]
+        .into_iter()
+        .filter_map(|(included, label)| included.then_some(label))
+        .collect();
+    let mut headers = vec!["User".to_string(), "Opinion".to_string()];
+    for record_type in &record_types {
+        headers.push(format!("{} Pending", record_type));
+        headers.push(format!("{} Reviewed", record_type));
+    }
+    headers.extend(["Total Records".to_string(), "Completion %".to_string()]);
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(current_row, col_num as u16, header)?;
+    }
+    current_row += 1;
+
+    let bold = Format::new().set_bold();
+    let bold_percentage = percentage_format.clone().set_bold();
+    let mut write_row = |row: u32, user: &str, opinion: &str, entity: &dashboard::ReviewStats, service: &dashboard::ReviewStats, total: bool| -> Result<()> {
+        let (format, completion_format) = if total { (&bold, &bold_percentage) } else { (&Format::new(), percentage_format) };
+        sheet.write_string_with_format(row, 0, user, format)?;
+        sheet.write_string_with_format(row, 1, opinion, format)?;
+        let exported: Vec<&dashboard::ReviewStats> = [(include_entities, entity), (include_services, service)]
+            .into_iter()
+            .filter_map(|(included, stats)| included.then_some(stats))
+            .collect();
+        let mut col = 2u16;
+        for stats in &exported {
+            sheet.write_number_with_format(row, col, stats.pending_review as f64, format)?;
+            sheet.write_number_with_format(row, col + 1, stats.reviewed_count as f64, format)?;
+            col += 2;
+        }
+        let combined = dashboard::ReviewStats::sum(exported.iter().copied());
+        sheet.write_number_with_format(row, col, combined.total as f64, format)?;
+        write_completion(sheet, row, col + 1, combined.completion_fraction(), completion_format)?;
+        Ok(())
+    };
+
+    for user in dashboard::group_by_user(data) {
+        for opinion in &user.opinions {
+            write_row(current_row, user.username, &opinion.opinion_name, &opinion.entity_stats, &opinion.service_stats, false)?;
+            current_row += 1;
+        }
+        if user.opinions.len() > 1 {
+            write_row(current_row, user.username, "Subtotal", &user.entity_totals(), &user.service_totals(), true)?;
+            current_row += 1;
+        }
+    }
+    let (entity_totals, service_totals) = dashboard::overall_totals(data);
+    write_row(current_row, "All Users", "Total", &entity_totals, &service_totals, true)?;
+    Ok(current_row + 1)
+}
+
+/// Writes the "DATASET PAIRS" table of the Progress Overview from `start_row`: one row
+/// per record type and pair of datasets with the opinion's edges between them by
+/// status. Returns the row after the table.
+fn write_dataset_pairs(sheet: &mut Worksheet, start_row: u32, pairs: &[DatasetPairCount]) -> Result<u32> {
+    let mut current_row = start_row;
+    sheet.write_string(current_row, 0, "DATASET PAIRS")?;
+    current_row += 1;
+    let headers = ["Record Type", "Dataset 1", "Dataset 2", "Confirmed Match", "Confirmed Non-Match", "Pending Review", "Total Edges"];
+    for (col_num, header) in headers.iter().enumerate() {
+        sheet.write_string(current_row, col_num as u16, *header)?;
+    }
+    current_row += 1;
+    for pair in pairs {
+        sheet.write_string(current_row, 0, &pair.record_type)?;
+        sheet.write_string(current_row, 1, &pair.dataset_1)?;
+        sheet.write_string(current_row, 2, &pair.dataset_2)?;
+        sheet.write_number(current_row, 3, pair.confirmed_match as f64)?;
+        sheet.write_number(current_row, 4, pair.confirmed_non_match as f64)?;
+        sheet.write_number(current_row, 5, pair.pending_review as f64)?;
+        sheet.write_number(current_row, 6, pair.edge_count() as f64)?;
+        current_row += 1;
+    }
+    Ok(current_row)
+}
+
 /// Helper function to write dashboard data to the "Progress Overview" sheet.
-/// Updated to handle single user with opinion information.
-fn write_progress_overview_sheet(sheet: &mut Worksheet, data: Vec<UserDashboard>) -> Result<()> {
+/// The totals add up every opinion in `data`; with more than one, a "BY OPINION" table
+/// breaks them down per user and opinion, and the export summary and review trend,
+/// which describe a single opinion, give way to user and opinion counts. A single
+/// opinion also gets its edge decisions per pair of datasets when it was re-clustered.
+/// Both the raw record-count completion and the completion weighted by the configured
+/// weights are shown, along with the remaining review count and, when a review rate is
+/// set, the estimated time to completion.
+/// A record type that was not exported is left out of the totals and the breakdown.
+pub fn write_progress_overview_sheet(
+    sheet: &mut Worksheet,
+    data: &[OpinionDashboard],
+    options: ProgressOptions,
+    include_entities: bool,
+    include_services: bool,
+) -> Result<()> {
     sheet.set_name("Progress Overview")?;
 
     // Set column widths for better readability
-    sheet.set_column_width(0, 20)?; // User/Metric column
-    sheet.set_column_width(1, 15)?; // User Prefix column  
-    sheet.set_column_width(2, 15)?; // Opinion Name column
-    sheet.set_column_width(3, 15)?; // Record Type column
-    sheet.set_column_width(4, 15)?; // Pending Review column
-    sheet.set_column_width(5, 15)?; // Confirmed Match column
-    sheet.set_column_width(6, 18)?; // Confirmed Non-Match column
-    sheet.set_column_width(7, 15)?; // Total Records column
-    sheet.set_column_width(8, 15)?; // Reviewed Count column
-    sheet.set_column_width(9, 15)?; // Completion % column
+    sheet.set_column_width(0, 22)?; // Metric column
+    sheet.set_column_width(1, 15)?; // Entity Records column
+    sheet.set_column_width(2, 15)?; // Service Records column
+    sheet.set_column_width(3, 15)?; // Total Records column
 
     let mut current_row = 0u32;
 
-    // Since we're now processing a single user with a single opinion, 
-    // the dashboard data should contain only one user
-    let user = data.first().ok_or_else(|| anyhow::anyhow!("No dashboard data provided"))?;
+    if data.is_empty() {
+        return Err(anyhow::anyhow!("No dashboard data provided"));
+    }
+    let (entity_totals, service_totals) = dashboard::overall_totals(data);
 
-    let total_entity_pending = user.entity_stats.pending_review;
-    let total_entity_reviewed = user.entity_stats.reviewed_count;
-    let total_service_pending = user.service_stats.pending_review;
-    let total_service_reviewed = user.service_stats.reviewed_count;
+    let (total_entity_pending, total_entity_reviewed) = if include_entities {
+        (entity_totals.pending_review, entity_totals.reviewed_count)
+    } else {
+        (0, 0)
+    };
+    let (total_service_pending, total_service_reviewed) = if include_services {
+        (service_totals.pending_review, service_totals.reviewed_count)
+    } else {
+        (0, 0)
+    };
 
     let total_pending = total_entity_pending + total_service_pending;
     let total_reviewed = total_entity_reviewed + total_service_reviewed;
     let total_all = total_pending + total_reviewed;
-    let overall_percentage = if total_all > 0 {
-        (total_reviewed as f64 / total_all as f64) * 100.0
-    } else {
-        0.0
+    let overall_completion = dashboard::completion_fraction(total_reviewed, total_all);
+    // An excluded record type carries no weight
+    let weights = options.completion_weights;
+    let effective_weights = CompletionWeights {
+        entity: if include_entities { weights.entity } else { 0.0 },
+        service: if include_services { weights.service } else { 0.0 },
     };
+    // Without any records there is no weighted completion either
+    let weighted_completion = overall_completion.map(|_| {
+        dashboard::weighted_completion_percentage(&entity_totals, &service_totals, effective_weights) / 100.0
+    });
 
-    // Create format for percentages
-    let percentage_format = Format::new().set_num_format("0.0");
+    // Percentages are stored as fractions and shown with Excel's percent format
+    let percentage_format = Format::new().set_num_format("0.0%");
 
     // Write export summary section
     sheet.write_string(current_row, 0, "EXPORT SUMMARY")?;
@@ -162,22 +1409,26 @@ fn write_progress_overview_sheet(sheet: &mut Worksheet, data: Vec<UserDashboard>
     current_row += 1;
 
     // Export details
-    sheet.write_string(current_row, 0, "User")?;
-    sheet.write_string(current_row, 1, &user.username)?;
-    current_row += 1;
-
-    sheet.write_string(current_row, 0, "User Prefix")?;
-    sheet.write_string(current_row, 1, &user.user_prefix)?;
-    current_row += 1;
-
-    sheet.write_string(current_row, 0, "Opinion Name")?;
-    sheet.write_string(current_row, 1, &user.opinion_name)?;
-    current_row += 1;
+    let record_types = match (include_entities, include_services) {
+        (true, false) => RecordScope::EntitiesOnly,
+        (false, true) => RecordScope::ServicesOnly,
+        _ => RecordScope::Both,
+    };
+    let info_rows = match data {
+        [opinion] => export_info_rows(opinion, record_types, options),
+        _ => group_info_rows(data, record_types, options),
+    };
+    for (label, value) in info_rows {
+        sheet.write_string(current_row, 0, label)?;
+        sheet.write_string(current_row, 1, &value)?;
+        current_row += 1;
+    }
 
     current_row += 1; // Add spacing
 
-    // Overall stats headers
-    let summary_headers = vec![
+    // Overall stats headers; the status chart is anchored next to them
+    let summary_row = current_row;
+    let summary_headers = [
         "Metric", "Entity Records", "Service Records", "Total Records"
     ];
     for (col_num, header) in summary_headers.iter().enumerate() {
@@ -186,7 +1437,7 @@ fn write_progress_overview_sheet(sheet: &mut Worksheet, data: Vec<UserDashboard>
     current_row += 1;
 
     // Overall stats data
-    let summary_rows = vec![
+    let summary_rows = [
         ("Pending Review", total_entity_pending, total_service_pending, total_pending),
         ("Reviewed (Confirmed)", total_entity_reviewed, total_service_reviewed, total_reviewed),
         ("Total Records", total_entity_pending + total_entity_reviewed, total_service_pending + total_service_reviewed, total_all),
@@ -194,8 +1445,16 @@ fn write_progress_overview_sheet(sheet: &mut Worksheet, data: Vec<UserDashboard>
 
     for (metric, entity_count, service_count, total_count) in summary_rows {
         sheet.write_string(current_row, 0, metric)?;
-        sheet.write_number(current_row, 1, entity_count as f64)?;
-        sheet.write_number(current_row, 2, service_count as f64)?;
+        if include_entities {
+            sheet.write_number(current_row, 1, entity_count as f64)?;
+        } else {
+            sheet.write_string(current_row, 1, "not exported")?;
+        }
+        if include_services {
+            sheet.write_number(current_row, 2, service_count as f64)?;
+        } else {
+            sheet.write_string(current_row, 2, "not exported")?;
+        }
         sheet.write_number(current_row, 3, total_count as f64)?;
         current_row += 1;
     }
@@ -204,56 +1463,105 @@ fn write_progress_overview_sheet(sheet: &mut Worksheet, data: Vec<UserDashboard>
     sheet.write_string(current_row, 0, "Overall Completion %")?;
     sheet.write_string(current_row, 1, "")?;
     sheet.write_string(current_row, 2, "")?;
-    sheet.write_number_with_format(current_row, 3, overall_percentage, &percentage_format)?;
-    current_row += 2; // Extra spacing
+    write_completion(sheet, current_row, 3, overall_completion, &percentage_format)?;
+    current_row += 1;
 
-    // Write detailed breakdown section
-    sheet.write_string(current_row, 0, "DETAILED BREAKDOWN")?;
+    // Weighted completion percentage, with the weights used
+    sheet.write_string(current_row, 0, "Weighted Completion %")?;
+    sheet.write_string(current_row, 1, weights.to_string())?;
+    sheet.write_string(current_row, 2, "")?;
+    write_completion(sheet, current_row, 3, weighted_completion, &percentage_format)?;
     current_row += 1;
-    sheet.write_string(current_row, 0, "")?; // Empty row for spacing
+
+    // Remaining work, with an estimate when a review rate was given
+    sheet.write_string(current_row, 0, "Remaining to Review")?;
+    sheet.write_string(current_row, 1, "")?;
+    sheet.write_string(current_row, 2, "")?;
+    sheet.write_number(current_row, 3, total_pending as f64)?;
     current_row += 1;
+    if let Some(rate) = options.review_rate {
+        sheet.write_string(current_row, 0, "Est. Time Remaining")?;
+        sheet.write_string(current_row, 1, "")?;
+        sheet.write_string(current_row, 2, "")?;
+        sheet.write_string(current_row, 3, dashboard::format_review_eta(total_pending, rate))?;
+        current_row += 1;
+    }
+    current_row += 1; // Extra spacing
 
-    // Detailed breakdown headers
-    let detail_headers = vec![
-        "User", "User Prefix", "Opinion Name", "Record Type", "Pending Review", "Confirmed Match", 
-        "Confirmed Non-Match", "Total Records", "Reviewed Count", "Completion %"
-    ];
-    for (col_num, header) in detail_headers.iter().enumerate() {
-        sheet.write_string(current_row, col_num as u16, *header)?;
+    if data.len() > 1 {
+        current_row = write_opinion_breakdown(sheet, current_row, data, include_entities, include_services, &percentage_format)?;
+        current_row += 1;
     }
-    current_row += 1;
 
-    // Entity row
-    sheet.write_string(current_row, 0, &user.username)?;
-    sheet.write_string(current_row, 1, &user.user_prefix)?;
-    sheet.write_string(current_row, 2, &user.opinion_name)?;
-    sheet.write_string(current_row, 3, "Entity")?;
-    sheet.write_number(current_row, 4, user.entity_stats.pending_review as f64)?;
-    sheet.write_number(current_row, 5, user.entity_stats.confirmed_match as f64)?;
-    sheet.write_number(current_row, 6, user.entity_stats.confirmed_non_match as f64)?;
-    sheet.write_number(current_row, 7, user.entity_stats.total as f64)?;
-    sheet.write_number(current_row, 8, user.entity_stats.reviewed_count as f64)?;
-    sheet.write_number_with_format(current_row, 9, user.entity_stats.review_percentage, &percentage_format)?;
-    current_row += 1;
+    // Review decisions across the exported record types, charted as a pie
+    let exported_stats: Vec<&dashboard::ReviewStats> = [(include_entities, &entity_totals), (include_services, &service_totals)]
+        .into_iter()
+        .filter_map(|(included, stats)| included.then_some(stats))
+        .collect();
+    if let Some(split) = dashboard::status_split(&exported_stats) {
+        sheet.write_string(current_row, 0, "REVIEW STATUS")?;
+        current_row += 1;
+        let first_row = current_row;
+        for (label, count) in split {
+            sheet.write_string(current_row, 0, label)?;
+            sheet.write_number(current_row, 3, count as f64)?;
+            current_row += 1;
+        }
+        let last_row = current_row - 1;
+
+        let mut chart = Chart::new(ChartType::Pie);
+        chart.title().set_name("Review Status");
+        chart.add_series()
+            .set_categories(("Progress Overview", first_row, 0, last_row, 0))
+            .set_values(("Progress Overview", first_row, 3, last_row, 3))
+            .set_data_label(ChartDataLabel::new().show_percentage());
+        sheet.insert_chart(summary_row, 5, &chart)?;
+        current_row += 1;
+    }
 
-    // Service row
-    sheet.write_string(current_row, 0, &user.username)?;
-    sheet.write_string(current_row, 1, &user.user_prefix)?;
-    sheet.write_string(current_row, 2, &user.opinion_name)?;
-    sheet.write_string(current_row, 3, "Service")?;
-    sheet.write_number(current_row, 4, user.service_stats.pending_review as f64)?;
-    sheet.write_number(current_row, 5, user.service_stats.confirmed_match as f64)?;
-    sheet.write_number(current_row, 6, user.service_stats.confirmed_non_match as f64)?;
-    sheet.write_number(current_row, 7, user.service_stats.total as f64)?;
-    sheet.write_number(current_row, 8, user.service_stats.reviewed_count as f64)?;
-    sheet.write_number_with_format(current_row, 9, user.service_stats.review_percentage, &percentage_format)?;
-    current_row += 2;
+    // Review trend from earlier recorded snapshots of a single opinion
+    let trend = match data {
+        [opinion] => opinion.trend.as_slice(),
+        _ => &[],
+    };
+    if !trend.is_empty() {
+        sheet.write_string(current_row, 0, "REVIEW TREND")?;
+        current_row += 1;
+        let trend_headers = ["Recorded", "Entity %", "Service %", "Overall %"];
+        for (col_num, header) in trend_headers.iter().enumerate() {
+            sheet.write_string(current_row, col_num as u16, *header)?;
+        }
+        current_row += 1;
+        for point in trend {
+            let recorded = point.recorded_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string();
+            sheet.write_string(current_row, 0, &recorded)?;
+            // Snapshots record percentages out of 100
+            sheet.write_number_with_format(current_row, 1, point.entity_percentage / 100.0, &percentage_format)?;
+            sheet.write_number_with_format(current_row, 2, point.service_percentage / 100.0, &percentage_format)?;
+            sheet.write_number_with_format(current_row, 3, point.overall_percentage / 100.0, &percentage_format)?;
+            current_row += 1;
+        }
+        current_row += 1;
+    }
+
+    // Edge decisions between (and within) datasets from re-clustering a single opinion
+    let dataset_pairs = match data {
+        [opinion] => opinion.dataset_pairs.as_slice(),
+        _ => &[],
+    };
+    if !dataset_pairs.is_empty() {
+        current_row = write_dataset_pairs(sheet, current_row, dataset_pairs)?;
+        current_row += 1;
+    }
 
     // Add timestamp
     sheet.write_string(current_row, 0, "Generated")?;
     let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     sheet.write_string(current_row, 1, &timestamp)?;
 
-    info!("'Progress Overview' sheet written for user: {} with opinion: {}", user.username, user.opinion_name);
+    match data {
+        [opinion] => info!("'Progress Overview' sheet written for user: {} with opinion: {}", opinion.username, opinion.opinion_name),
+        _ => info!("'Progress Overview' sheet written for {} opinions.", data.len()),
+    }
     Ok(())
 }
\ No newline at end of file
@@ -1,58 +1,146 @@
 use anyhow::{Context, Result};
-use tokio_postgres::Client;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use tokio_postgres::{Client, GenericClient};
 use log::info;
 
-const TEAM_SCHEMA: &str = "wa211_to_wric";
-const EXPORT_SCHEMA: &str = "wa211_to_wric_exports";
+use crate::models::RecordScope;
+use crate::sql_guard::execute_export_write;
+use crate::table_names::{self, EXPORT_RUNS_TABLE, EXPORT_SCHEMA, EXPORTED_TABLES, TEAM_SCHEMA};
 
 /// Creates the dedicated export schema if it does not already exist.
 pub async fn create_export_schema(client: &Client) -> Result<()> {
     info!("Ensuring export schema '{}' exists...", EXPORT_SCHEMA);
     let query = format!("CREATE SCHEMA IF NOT EXISTS {};", EXPORT_SCHEMA);
-    client.execute(&query, &[]).await
+    execute_export_write(client, &query, &[]).await
         .context(format!("Failed to create schema {}", EXPORT_SCHEMA))?;
     info!("Schema '{}' ensured.", EXPORT_SCHEMA);
     Ok(())
 }
 
+/// Grants `role` read access to the export schema: usage on the schema, SELECT on the
+/// tables already in it, and SELECT on tables this connection's role creates there later.
+pub async fn grant_export_access(client: &Client, role: &str) -> Result<()> {
+    info!("Granting role '{}' read access to schema '{}'...", role, EXPORT_SCHEMA);
+    let role = format!("\"{}\"", role.replace('"', "\"\""));
+    for query in [
+        format!("GRANT USAGE ON SCHEMA {} TO {};", EXPORT_SCHEMA, role),
+        format!("GRANT SELECT ON ALL TABLES IN SCHEMA {} TO {};", EXPORT_SCHEMA, role),
+        format!("ALTER DEFAULT PRIVILEGES IN SCHEMA {} GRANT SELECT ON TABLES TO {};", EXPORT_SCHEMA, role),
+    ] {
+        execute_export_write(client, &query, &[]).await
+            .context(format!("Failed to grant {} access to schema {}", role, EXPORT_SCHEMA))?;
+    }
+    info!("Read access to schema '{}' granted.", EXPORT_SCHEMA);
+    Ok(())
+}
+
+/// How an export table gets its structure and initial rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableSource {
+    /// Created LIKE the opinion table in the team schema and filled with its rows
+    Copy,
+    /// Created empty from `fallback_table_columns` because the opinion table does not exist
+    Synthesized,
+}
+
+/// Column definitions for the export tables that newer pipeline runs no longer create
+/// in the team schema, where everything is derived from the edges. They hold the
+/// columns re-clustering writes and the fetch reads; re-clustering fills them anyway.
+/// `None` for the edge tables, which every opinion must have.
+pub fn fallback_table_columns(table: &str) -> Option<&'static str> {
+    match table {
+        "entity_group" => Some(
+            "id TEXT PRIMARY KEY, entity_id_1 TEXT NOT NULL, entity_id_2 TEXT NOT NULL, group_cluster_id TEXT, \
+             method_type TEXT, created_at TIMESTAMP, updated_at TIMESTAMP, confirmed_status TEXT"
+        ),
+        "service_group" => Some(
+            "id TEXT PRIMARY KEY, service_id_1 TEXT NOT NULL, service_id_2 TEXT NOT NULL, group_cluster_id TEXT, \
+             method_type TEXT, created_at TIMESTAMP, updated_at TIMESTAMP, confirmed_status TEXT"
+        ),
+        "entity_group_cluster" => Some(
+            "id TEXT PRIMARY KEY, name TEXT, description TEXT, created_at TIMESTAMP, updated_at TIMESTAMP, \
+             entity_count INTEGER, group_count INTEGER, average_coherence_score DOUBLE PRECISION, was_reviewed BOOLEAN"
+        ),
+        "service_group_cluster" => Some(
+            "id TEXT PRIMARY KEY, name TEXT, description TEXT, created_at TIMESTAMP, updated_at TIMESTAMP, \
+             service_count INTEGER, service_group_count INTEGER, average_coherence_score DOUBLE PRECISION, was_reviewed BOOLEAN"
+        ),
+        _ => None,
+    }
+}
+
+/// Decides for each table in `scope` whether it is copied or synthesized, given which
+/// opinion tables `source_exists` in the team schema. Fails when a table without a
+/// fallback, i.e. an edge table, is missing.
+pub fn plan_table_sources(scope: RecordScope, source_exists: impl Fn(&str) -> bool) -> Result<Vec<(&'static str, TableSource)>> {
+    scoped_tables(scope)
+        .map(|table| {
+            if source_exists(table) {
+                Ok((table, TableSource::Copy))
+            } else if fallback_table_columns(table).is_some() {
+                Ok((table, TableSource::Synthesized))
+            } else {
+                Err(anyhow::anyhow!("The opinion has no {} table in schema {}", table, TEAM_SCHEMA))
+            }
+        })
+        .collect()
+}
+
 /// Creates and populates the timestamped export tables for a given user and opinion.
 /// These tables are based on the user's opinion-specific tables in the team schema.
+/// Group and cluster tables missing there are created empty from `fallback_table_columns`.
 /// Also removes check constraints that would prevent our reclustering logic from working.
-pub async fn create_timestamped_tables(
-    client: &Client,
+/// Only the tables for the record types in `scope` are created.
+pub async fn create_timestamped_tables<C: GenericClient>(
+    client: &C,
     user_prefix: &str,
     opinion_name: &str,
     timestamp_suffix: &str,
+    scope: RecordScope,
 ) -> Result<()> {
     info!("Creating timestamped tables for user '{}' with opinion '{}' and suffix '{}'...", 
           user_prefix, opinion_name, timestamp_suffix);
 
-    let tables_to_copy = vec![
-        "entity_group",
-        "entity_group_cluster", 
-        "entity_edge_visualization",
-        "service_group",
-        "service_group_cluster",
-        "service_edge_visualization",
-    ];
-
-    for table_name in tables_to_copy {
-        // Updated table naming to include opinion: {user_prefix}_{opinion_name}_{table_suffix}
-        let source_table_full = format!(r#""{}"."{}_{}_{}" "#, TEAM_SCHEMA, user_prefix, opinion_name, table_name);
-        let target_table_name = format!("{}_{}_{}_export_{}", user_prefix, opinion_name, table_name, timestamp_suffix);
-        let target_table_full = format!(r#""{}"."{}""#, EXPORT_SCHEMA, target_table_name);
+    let mut existing_sources = HashSet::new();
+    for table_name in scoped_tables(scope) {
+        let source_table_full = table_names::qualified(TEAM_SCHEMA, &table_names::source_table(user_prefix, opinion_name, table_name));
+        let exists: bool = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&source_table_full]).await
+            .context(format!("Failed to check whether {} exists", source_table_full))?
+            .get(0);
+        if exists {
+            existing_sources.insert(table_name);
+        }
+    }
+    let plan = plan_table_sources(scope, |table| existing_sources.contains(table))
+        .context(format!("Cannot export opinion '{}' of user '{}'", opinion_name, user_prefix))?;
+
+    for (table_name, source) in plan {
+        let source_table_full = table_names::qualified(TEAM_SCHEMA, &table_names::source_table(user_prefix, opinion_name, table_name));
+        let target_table_name = table_names::export_table(user_prefix, opinion_name, table_name, timestamp_suffix);
+        let target_table_full = table_names::qualified(EXPORT_SCHEMA, &target_table_name);
 
         // Drop existing table in export schema to ensure a clean slate for this timestamp
         let drop_query = format!("DROP TABLE IF EXISTS {} CASCADE;", target_table_full);
-        client.execute(&drop_query, &[]).await
+        execute_export_write(client, &drop_query, &[]).await
             .context(format!("Failed to drop table {}", target_table_full))?;
 
+        if source == TableSource::Synthesized {
+            // plan_table_sources only synthesizes tables that have a fallback
+            let columns = fallback_table_columns(table_name).unwrap_or_default();
+            let create_query = format!("CREATE TABLE {} ({});", target_table_full, columns);
+            execute_export_write(client, &create_query, &[]).await
+                .context(format!("Failed to create table {} from its fallback definition", target_table_full))?;
+            info!("{} does not exist; created an empty {} from the built-in definition.", source_table_full, target_table_full);
+            continue;
+        }
+
         // Create table structure (LIKE ... INCLUDING ALL)
         let create_query = format!(
             "CREATE TABLE {} (LIKE {} INCLUDING ALL);",
             target_table_full, source_table_full
         );
-        client.execute(&create_query, &[]).await
+        execute_export_write(client, &create_query, &[]).await
             .context(format!("Failed to create table structure for {}", target_table_full))?;
 
         // Drop problematic check constraints that prevent our reclustering logic
@@ -73,25 +161,20 @@ pub async fn create_timestamped_tables(
                 target_table_full
             );
             
+            // This runs inside the export transaction, where a failed statement aborts
+            // everything after it, so errors are returned rather than skipped
             let constraint_rows = client.query(&find_constraints_query, &[]).await
-                .unwrap_or_else(|_| vec![]); // If query fails, just continue
+                .context(format!("Failed to list check constraints on {}", target_table_full))?;
             
             for constraint_row in constraint_rows {
                 let constraint_name: String = constraint_row.get("conname");
                 let drop_constraint_query = format!(
-                    "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};",
-                    target_table_full, constraint_name
+                    r#"ALTER TABLE {} DROP CONSTRAINT IF EXISTS "{}";"#,
+                    target_table_full, constraint_name.replace('"', "\"\"")
                 );
-                
-                match client.execute(&drop_constraint_query, &[]).await {
-                    Ok(_) => {
-                        info!("Dropped constraint '{}' from {}", constraint_name, target_table_full);
-                    }
-                    Err(e) => {
-                        info!("Could not drop constraint '{}' from {}: {}", 
-                              constraint_name, target_table_full, e);
-                    }
-                }
+                execute_export_write(client, &drop_constraint_query, &[]).await
+                    .context(format!("Failed to drop constraint '{}' from {}", constraint_name, target_table_full))?;
+                info!("Dropped constraint '{}' from {}", constraint_name, target_table_full);
             }
         }
 
@@ -100,7 +183,7 @@ pub async fn create_timestamped_tables(
             "INSERT INTO {} SELECT * FROM {};",
             target_table_full, source_table_full
         );
-        client.execute(&copy_query, &[]).await
+        execute_export_write(client, &copy_query, &[]).await
             .context(format!("Failed to copy data to {}", target_table_full))?;
 
         let count_query = format!("SELECT COUNT(*) FROM {};", target_table_full);
@@ -111,4 +194,253 @@ pub async fn create_timestamped_tables(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Returns the qualified names of the timestamped export tables for a given user,
+/// opinion, and suffix that do not exist. Used when reusing an earlier run's tables.
+/// Only the tables for the record types in `scope` are checked.
+pub async fn missing_export_tables(
+    client: &Client,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    scope: RecordScope,
+) -> Result<Vec<String>> {
+    let tables = scoped_tables(scope)
+        .map(|table| table_names::export_table(user_prefix, opinion_name, table, timestamp_suffix))
+        .collect();
+    tables_where(client, tables, false).await
+}
+
+/// Returns the qualified names of the timestamped export tables for a given user,
+/// opinion, and suffix that already exist. Used before a run with a fixed suffix
+/// replaces an earlier export. Only the tables for the record types in `scope` are
+/// checked, and with `row_tables` also the `--export-to-table` row tables.
+pub async fn existing_export_tables(
+    client: &Client,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    scope: RecordScope,
+    row_tables: bool,
+) -> Result<Vec<String>> {
+    let mut tables: Vec<String> = scoped_tables(scope)
+        .map(|table| table_names::export_table(user_prefix, opinion_name, table, timestamp_suffix))
+        .collect();
+    if row_tables {
+        tables.extend(scoped_row_tables(scope).map(|rows| table_names::rows_export(user_prefix, opinion_name, rows, timestamp_suffix)));
+    }
+    tables_where(client, tables, true).await
+}
+
+/// The qualified names of the export schema `tables` whose existence equals `exists`.
+async fn tables_where(client: &Client, names: Vec<String>, exists: bool) -> Result<Vec<String>> {
+    let mut tables = Vec::new();
+    for target_table_name in names {
+        let target_table_full = table_names::qualified(EXPORT_SCHEMA, &target_table_name);
+        let row = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&target_table_full]).await
+            .context(format!("Failed to check whether {} exists", target_table_full))?;
+        let table_exists: bool = row.get(0);
+        if table_exists == exists {
+            tables.push(target_table_full);
+        }
+    }
+    Ok(tables)
+}
+
+
+/// Returns the column names of `schema.table`, or none when the table does not exist.
+/// Used for columns that not every deployment carries, or names differently.
+pub async fn table_columns<C: GenericClient>(client: &C, schema: &str, table: &str) -> Result<Vec<String>> {
+    let rows = client.query(
+        "SELECT column_name::text FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2",
+        &[&schema, &table],
+    ).await
+        .context(format!("Failed to read the columns of {}", table_names::qualified(schema, table)))?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// The SQL type of `schema.table.column` as Postgres spells it in a cast, e.g.
+/// `integer` or `character varying(64)`.
+pub async fn column_type<C: GenericClient>(client: &C, schema: &str, table: &str, column: &str) -> Result<String> {
+    let row = client.query_one(
+        "SELECT format_type(a.atttypid, a.atttypmod) FROM pg_attribute a \
+         JOIN pg_class c ON c.oid = a.attrelid JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = $1 AND c.relname = $2 AND a.attname = $3 AND NOT a.attisdropped",
+        &[&schema, &table, &column],
+    ).await
+        .context(format!("Failed to read the type of {}.{}", table_names::qualified(schema, table), column))?;
+    Ok(row.get(0))
+}
+
+/// The first of `candidates` that `columns` contains, for a column whose name differs
+/// between deployments. `None` when the table has none of them.
+pub fn first_present_column<'a>(columns: &[String], candidates: &[&'a str]) -> Option<&'a str> {
+    candidates.iter().copied().find(|candidate| columns.iter().any(|column| column == candidate))
+}
+
+/// Looks up which of `candidates` `schema.table` has; see `first_present_column`.
+pub async fn find_column<'a, C: GenericClient>(client: &C, schema: &str, table: &str, candidates: &[&'a str]) -> Result<Option<&'a str>> {
+    let columns = table_columns(client, schema, table).await?;
+    Ok(first_present_column(&columns, candidates))
+}
+
+/// The names an edge table may use for who reviewed the edge. Opinion tables record a
+/// reviewer name in `reviewed_by`; some tenants keep a `reviewer_id` instead.
+pub const REVIEWER_COLUMN_NAMES: [&str; 2] = ["reviewed_by", "reviewer_id"];
+
+/// Which optional review metadata columns an edge table has. Older opinion tables
+/// carry only some of them, or none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReviewColumns {
+    pub updated_at: bool,
+    pub reviewed_at: bool,
+    /// The reviewer column, one of `REVIEWER_COLUMN_NAMES`, when the table has one.
+    pub reviewed_by: Option<&'static str>,
+}
+
+impl ReviewColumns {
+    /// Looks the columns up in `information_schema`.
+    pub async fn detect<C: GenericClient>(client: &C, schema: &str, table: &str) -> Result<Self> {
+        Ok(ReviewColumns::from_columns(&table_columns(client, schema, table).await?))
+    }
+
+    /// Which of the review columns are among a table's `columns`.
+    pub fn from_columns(columns: &[String]) -> Self {
+        let has = |name: &str| first_present_column(columns, &[name]).is_some();
+        ReviewColumns {
+            updated_at: has("updated_at"),
+            reviewed_at: has("reviewed_at"),
+            reviewed_by: first_present_column(columns, &REVIEWER_COLUMN_NAMES),
+        }
+    }
+
+    /// SQL for when an edge of table `alias` was reviewed: `reviewed_at` when recorded,
+    /// otherwise `updated_at`, or a NULL timestamp when the table has neither.
+    pub fn review_time_expression(self, alias: &str) -> String {
+        match (self.reviewed_at, self.updated_at) {
+            (true, true) => format!("COALESCE({0}.reviewed_at::timestamp, {0}.updated_at::timestamp)", alias),
+            (true, false) => format!("{}.reviewed_at::timestamp", alias),
+            (false, true) => format!("{}.updated_at::timestamp", alias),
+            (false, false) => "NULL::timestamp".to_string(),
+        }
+    }
+
+    /// SQL for who reviewed an edge of table `alias` as text, or a NULL text when the
+    /// table has no reviewer column.
+    pub fn reviewer_expression(self, alias: &str) -> String {
+        match self.reviewed_by {
+            Some(column) => format!("{}.{}::text", alias, column),
+            None => "NULL::text".to_string(),
+        }
+    }
+}
+
+/// An export whose tables were all created, re-clustered, and committed.
+#[derive(Debug, Clone)]
+pub struct CompletedExport {
+    pub user_prefix: String,
+    pub opinion_name: String,
+    pub timestamp_suffix: String,
+    pub record_types: String,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Creates the completion marker table in the export schema if it does not already exist.
+pub async fn ensure_export_runs_table<C: GenericClient>(client: &C) -> Result<()> {
+    let table = table_names::qualified(EXPORT_SCHEMA, EXPORT_RUNS_TABLE);
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            user_prefix TEXT NOT NULL,
+            opinion_name TEXT NOT NULL,
+            timestamp_suffix TEXT NOT NULL,
+            record_types TEXT NOT NULL,
+            completed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            PRIMARY KEY (user_prefix, opinion_name, timestamp_suffix)
+        )
+        "#,
+        table
+    );
+    execute_export_write(client, &query, &[]).await
+        .context(format!("Failed to create export marker table {}", table))?;
+    Ok(())
+}
+
+/// Records that the export tables for this suffix are complete. Written in the same
+/// transaction as the tables, so the marker exists exactly when they were committed.
+pub async fn mark_export_complete<C: GenericClient>(
+    client: &C,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    scope: RecordScope,
+) -> Result<()> {
+    ensure_export_runs_table(client).await?;
+
+    let table = table_names::qualified(EXPORT_SCHEMA, EXPORT_RUNS_TABLE);
+    let query = format!(
+        r#"
+        INSERT INTO {} (user_prefix, opinion_name, timestamp_suffix, record_types)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_prefix, opinion_name, timestamp_suffix)
+        DO UPDATE SET record_types = EXCLUDED.record_types, completed_at = now()
+        "#,
+        table
+    );
+    let record_types = ["entity", "service"].into_iter()
+        .filter(|record_type| scope.includes(record_type))
+        .collect::<Vec<_>>()
+        .join(",");
+    execute_export_write(client, &query, &[&user_prefix, &opinion_name, &timestamp_suffix, &record_types]).await
+        .context(format!("Failed to record completed export in {}", table))?;
+    Ok(())
+}
+
+/// Returns the completed exports for a user's opinion, newest first. Exports that
+/// were aborted, or that predate the marker table, are not listed.
+pub async fn completed_exports(client: &Client, user_prefix: &str, opinion_name: &str) -> Result<Vec<CompletedExport>> {
+    let table = table_names::qualified(EXPORT_SCHEMA, EXPORT_RUNS_TABLE);
+    let exists: bool = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&table]).await
+        .context(format!("Failed to check whether {} exists", table))?
+        .get(0);
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let query = format!(
+        r#"
+        SELECT user_prefix, opinion_name, timestamp_suffix, record_types, completed_at
+        FROM {}
+        WHERE user_prefix = $1 AND opinion_name = $2
+        ORDER BY completed_at DESC
+        "#,
+        table
+    );
+    let rows = client.query(&query, &[&user_prefix, &opinion_name]).await
+        .context(format!("Failed to read completed exports from {}", table))?;
+    Ok(rows.iter()
+        .map(|row| CompletedExport {
+            user_prefix: row.get("user_prefix"),
+            opinion_name: row.get("opinion_name"),
+            timestamp_suffix: row.get("timestamp_suffix"),
+            record_types: row.get("record_types"),
+            completed_at: row.get("completed_at"),
+        })
+        .collect())
+}
+
+/// The `--export-to-table` row tables ("org", "svc") of the record types in `scope`.
+pub fn scoped_row_tables(scope: RecordScope) -> impl Iterator<Item = &'static str> {
+    [("entity", "org"), ("service", "svc")].into_iter()
+        .filter(move |(record_type, _)| scope.includes(record_type))
+        .map(|(_, rows)| rows)
+}
+
+/// The exported tables belonging to the record types in `scope`.
+pub fn scoped_tables(scope: RecordScope) -> impl Iterator<Item = &'static str> {
+    EXPORTED_TABLES.into_iter().filter(move |table| {
+        let record_type = if table.starts_with("entity") { "entity" } else { "service" };
+        scope.includes(record_type)
+    })
+}
@@ -0,0 +1,287 @@
+// src/graph_export.rs
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::info;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::db_connect::PgPool;
+use crate::export_schema::ReviewColumns;
+use crate::sql_guard;
+use crate::table_names::{self, qualified, EXPORT_SCHEMA};
+
+/// File format for the re-clustered graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum GraphFormat {
+    /// GraphML, readable by Gephi, yEd, and Cytoscape
+    #[default]
+    Graphml,
+    /// Graphviz DOT
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            GraphFormat::Graphml => "graphml",
+            GraphFormat::Dot => "dot",
+        }
+    }
+}
+
+/// Which clusters of the re-clustered graph to export, parsed from
+/// `all` or `clusters=<id,id,...>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphSelection {
+    All,
+    Clusters(Vec<String>),
+}
+
+impl FromStr for GraphSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(GraphSelection::All);
+        }
+        let ids = s.strip_prefix("clusters=")
+            .ok_or_else(|| format!("expected 'all' or 'clusters=<id,id,...>', got '{}'", s))?;
+        let ids: Vec<String> = ids.split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+        if ids.is_empty() {
+            return Err("'clusters=' needs at least one cluster id".to_string());
+        }
+        Ok(GraphSelection::Clusters(ids))
+    }
+}
+
+impl fmt::Display for GraphSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphSelection::All => write!(f, "all"),
+            GraphSelection::Clusters(ids) => write!(f, "clusters={}", ids.join(",")),
+        }
+    }
+}
+
+/// A record in a multi-member cluster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    pub record_id: String,
+    pub name: Option<String>,
+    pub cluster_id: String,
+    pub dataset: Option<String>,
+}
+
+/// A valid (CONFIRMED_MATCH or PENDING_REVIEW) edge kept by re-clustering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub weight: f64,
+    pub confirmed_status: Option<String>,
+    pub reviewed_by: Option<String>, // None for unreviewed edges or when the table records no reviewer
+}
+
+/// The valid-edge graph of one record type, as stored in the export tables.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterGraph {
+    pub record_type: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Reads the re-clustered graph for `entity_or_service` back from the timestamped
+/// export tables, so it works both for a fresh run and with `--from-timestamp`.
+/// Singleton clusters have no edges and are left out.
+pub async fn fetch_cluster_graph(
+    pool: &PgPool,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    entity_or_service: &str, // "entity" or "service"
+    selection: &GraphSelection,
+) -> Result<ClusterGraph> {
+    let client = sql_guard::read_only_client(pool, "graph export").await?;
+
+    let group_table = table_names::group_export(user_prefix, opinion_name, entity_or_service, timestamp_suffix);
+    let edge_table = table_names::edge_viz_export(user_prefix, opinion_name, entity_or_service, timestamp_suffix);
+    let cluster_id_column_name = if entity_or_service == "entity" {
+        "cluster_id"
+    } else {
+        "service_group_cluster_id"
+    };
+    let cluster_filter: Option<Vec<String>> = match selection {
+        GraphSelection::All => None,
+        GraphSelection::Clusters(ids) => Some(ids.clone()),
+    };
+
+    let node_query = format!(
+        r#"
+        WITH members AS (
+            SELECT {0}_id_1 AS record_id, group_cluster_id AS cluster_id FROM {1} WHERE {0}_id_1 <> {0}_id_2
+            UNION
+            SELECT {0}_id_2 AS record_id, group_cluster_id AS cluster_id FROM {1} WHERE {0}_id_1 <> {0}_id_2
+        )
+        SELECT m.record_id, m.cluster_id, p.name, p.source_system AS dataset
+        FROM members m
+        LEFT JOIN {2} p ON p.id = m.record_id
+        WHERE $1::text[] IS NULL OR m.cluster_id = ANY($1)
+        ORDER BY m.cluster_id, m.record_id
+        "#,
+        entity_or_service, qualified(EXPORT_SCHEMA, &group_table), table_names::hsds_table(entity_or_service)
+    );
+    let nodes = client.query(&node_query, &[&cluster_filter]).await
+        .context(format!("Failed to fetch {} graph nodes from {}", entity_or_service, group_table))?
+        .iter()
+        .map(|row| GraphNode {
+            record_id: row.get("record_id"),
+            name: row.get("name"),
+            cluster_id: row.get("cluster_id"),
+            dataset: row.get("dataset"),
+        })
+        .collect();
+
+    let review_columns = ReviewColumns::detect(&*client, EXPORT_SCHEMA, &edge_table).await?;
+    let edge_query = format!(
+        r#"
+        SELECT e.{0}_id_1 AS source, e.{0}_id_2 AS target, e.edge_weight, e.confirmed_status, {3} AS reviewed_by
+        FROM {1} e
+        WHERE $1::text[] IS NULL OR e.{2} = ANY($1)
+        ORDER BY e.{2}, e.{0}_id_1, e.{0}_id_2
+        "#,
+        entity_or_service, qualified(EXPORT_SCHEMA, &edge_table), cluster_id_column_name,
+        review_columns.reviewer_expression("e")
+    );
+    let edges = client.query(&edge_query, &[&cluster_filter]).await
+        .context(format!("Failed to fetch {} graph edges from {}", entity_or_service, edge_table))?
+        .iter()
+        .map(|row| GraphEdge {
+            source: row.get("source"),
+            target: row.get("target"),
+            weight: row.get("edge_weight"),
+            confirmed_status: row.get("confirmed_status"),
+            reviewed_by: row.get("reviewed_by"),
+        })
+        .collect();
+
+    Ok(ClusterGraph { record_type: entity_or_service.to_string(), nodes, edges })
+}
+
+/// Writes `graph` to `path` in the given format through a buffered writer.
+pub fn write_graph_file(path: &Path, graph: &ClusterGraph, format: GraphFormat) -> Result<()> {
+    let file = File::create(path).context(format!("Failed to create graph file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    match format {
+        GraphFormat::Graphml => write_graphml(&mut writer, graph)?,
+        GraphFormat::Dot => write_dot(&mut writer, graph)?,
+    }
+    writer.flush().context(format!("Failed to write graph file {}", path.display()))?;
+    info!("Wrote {} graph with {} nodes and {} edges to {}.",
+          graph.record_type, graph.nodes.len(), graph.edges.len(), path.display());
+    Ok(())
+}
+
+/// Writes `graph` as GraphML, one element at a time.
+pub fn write_graphml<W: Write>(writer: &mut W, graph: &ClusterGraph) -> Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    for (key, target, attr_type) in [
+        ("record_id", "node", "string"),
+        ("name", "node", "string"),
+        ("cluster_id", "node", "string"),
+        ("dataset", "node", "string"),
+        ("weight", "edge", "double"),
+        ("confirmed_status", "edge", "string"),
+        ("reviewed_by", "edge", "string"),
+    ] {
+        writeln!(writer, r#"  <key id="{0}" for="{1}" attr.name="{0}" attr.type="{2}"/>"#, key, target, attr_type)?;
+    }
+    writeln!(writer, r#"  <graph id="{}" edgedefault="undirected">"#, xml_escape(&graph.record_type))?;
+
+    for node in &graph.nodes {
+        writeln!(writer, r#"    <node id="{}">"#, xml_escape(&node.record_id))?;
+        writeln!(writer, r#"      <data key="record_id">{}</data>"#, xml_escape(&node.record_id))?;
+        if let Some(name) = &node.name {
+            writeln!(writer, r#"      <data key="name">{}</data>"#, xml_escape(name))?;
+        }
+        writeln!(writer, r#"      <data key="cluster_id">{}</data>"#, xml_escape(&node.cluster_id))?;
+        if let Some(dataset) = &node.dataset {
+            writeln!(writer, r#"      <data key="dataset">{}</data>"#, xml_escape(dataset))?;
+        }
+        writeln!(writer, "    </node>")?;
+    }
+
+    for (index, edge) in graph.edges.iter().enumerate() {
+        writeln!(writer, r#"    <edge id="e{}" source="{}" target="{}">"#,
+                 index, xml_escape(&edge.source), xml_escape(&edge.target))?;
+        writeln!(writer, r#"      <data key="weight">{}</data>"#, edge.weight)?;
+        if let Some(status) = &edge.confirmed_status {
+            writeln!(writer, r#"      <data key="confirmed_status">{}</data>"#, xml_escape(status))?;
+        }
+        if let Some(reviewer) = &edge.reviewed_by {
+            writeln!(writer, r#"      <data key="reviewed_by">{}</data>"#, xml_escape(reviewer))?;
+        }
+        writeln!(writer, "    </edge>")?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    Ok(())
+}
+
+/// Writes `graph` as an undirected Graphviz DOT graph, one statement at a time.
+pub fn write_dot<W: Write>(writer: &mut W, graph: &ClusterGraph) -> Result<()> {
+    writeln!(writer, "graph \"{}\" {{", dot_escape(&graph.record_type))?;
+    for node in &graph.nodes {
+        write!(writer, "  \"{}\" [cluster_id=\"{}\"", dot_escape(&node.record_id), dot_escape(&node.cluster_id))?;
+        if let Some(name) = &node.name {
+            write!(writer, ", label=\"{}\"", dot_escape(name))?;
+        }
+        if let Some(dataset) = &node.dataset {
+            write!(writer, ", dataset=\"{}\"", dot_escape(dataset))?;
+        }
+        writeln!(writer, "];")?;
+    }
+    for edge in &graph.edges {
+        write!(writer, "  \"{}\" -- \"{}\" [weight={}", dot_escape(&edge.source), dot_escape(&edge.target), edge.weight)?;
+        if let Some(status) = &edge.confirmed_status {
+            write!(writer, ", confirmed_status=\"{}\"", dot_escape(status))?;
+        }
+        if let Some(reviewer) = &edge.reviewed_by {
+            write!(writer, ", reviewed_by=\"{}\"", dot_escape(reviewer))?;
+        }
+        writeln!(writer, "];")?;
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/// Escapes the five XML special characters for use in text and attribute values.
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes backslashes, quotes, and newlines for a quoted DOT string.
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
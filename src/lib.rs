@@ -1,9 +1,40 @@
+pub mod address;
+pub mod anonymize;
+pub mod canonical;
+pub mod cli;
+pub mod cluster_labels;
+pub mod cluster_lineage;
+pub mod comparison;
+pub mod confirmed_status;
+pub mod consensus;
+pub mod copy_load;
 pub mod db_connect;
+pub mod diff;
+pub mod doctor;
 pub mod dashboard;
+pub mod dataset_split;
 pub mod env_loader;
 pub mod models;
+pub mod ndjson_export;
+pub mod overrides;
+pub mod pool_metrics;
+pub mod precheck;
+pub mod preflight;
 pub mod export_schema;
 pub mod reclustering;
+pub mod replication;
+pub mod retry;
+pub mod row_count_check;
+pub mod run_warnings;
+pub mod sheet_selection;
+pub mod shared_reviewers;
+pub mod snapshots;
+pub mod sql_guard;
+pub mod status_file;
 pub mod data_fetch;
 pub mod excel_writer;
+pub mod graph_export;
+pub mod table_export;
+pub mod table_names;
 pub mod team_utils;
+pub mod workbook_verify;
@@ -1,99 +1,1129 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Local;
-use log::info;
+use clap::Parser;
+use log::{info, warn};
+use std::collections::{BTreeSet, HashSet};
+use std::future::Future;
+use std::io::IsTerminal;
 use std::path::PathBuf;
-use dialoguer::{theme::ColorfulTheme, Select};
+use std::time::Duration;
+use dialoguer::{theme::ColorfulTheme, Confirm, MultiSelect, Select};
+use tokio_postgres::{Client, IsolationLevel, NoTls};
 
+use export_opinion::anonymize::{AnonymizationKey, Anonymizer};
+use export_opinion::canonical::{self, CanonicalSelection};
+use export_opinion::cluster_labels::LabelMap;
+use export_opinion::cli::{no_terminal_message, Cli, Command, ConsensusArgs, DiffArgs, DoctorArgs};
+use export_opinion::comparison;
+use export_opinion::consensus::ConsensusBuilder;
 use export_opinion::db_connect;
-use export_opinion::dashboard;
+use export_opinion::diff;
+use export_opinion::doctor;
+use export_opinion::dashboard::{self, DashboardSection};
+use export_opinion::dataset_split::{self, DatasetPartition, Manifest, ManifestFile};
 use export_opinion::env_loader;
 use export_opinion::export_schema;
-use export_opinion::reclustering;
+use export_opinion::reclustering::{self, ClusterNaming, ReclusterOptions, ReclusterOutput, SkippedEdgeLimit};
+use export_opinion::replication;
+use export_opinion::retry::{self, RetryPolicy};
+use export_opinion::row_count_check;
+use export_opinion::run_warnings::{RunWarnings, WarningCategory};
+use export_opinion::sheet_selection::SheetKind;
 use export_opinion::data_fetch;
 use export_opinion::excel_writer;
-use export_opinion::team_utils::{self, TeamInfo, UserInfo, OpinionInfo};
+use export_opinion::graph_export;
+use export_opinion::pool_metrics;
+use export_opinion::precheck::ReviewPrecheck;
+use export_opinion::preflight;
+use export_opinion::snapshots;
+use export_opinion::sql_guard;
+use export_opinion::status_file::StatusReporter;
+use export_opinion::table_export;
+use export_opinion::table_names;
+use export_opinion::ndjson_export::{self, ExportFormat};
+use export_opinion::overrides::StatusOverrides;
+use export_opinion::shared_reviewers::{self, ConflictPolicy, SharedReview};
+use export_opinion::models::{
+    DatasetPairCount, ExportSelection, MethodStats, OpinionExport, OrganizationExportRow, OrphanedEdge, RecordScope, ServiceExportRow,
+};
+use export_opinion::team_utils::{self, DatasetFilterOptions, TeamInfo, UserInfo, OpinionInfo};
+use export_opinion::workbook_verify::{self, ExpectedWorkbook};
+
+/// How many recorded snapshots the Progress Overview trend shows.
+const TREND_SNAPSHOTS: i64 = 10;
+
+/// How long each preview COUNT(*) may run before it is reported as "unknown".
+const PREVIEW_COUNT_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if cli.selected_scope().is_none() {
+        return Err(anyhow::anyhow!("--sheets must include the organizations or services sheet of a record type being exported"));
+    }
+
     // Load environment variables using your existing loader
     env_loader::load_env();
     env_logger::init(); // Initialize logger
 
     info!("Starting interactive data export process.");
+    let source_schema = table_names::init_source_schema().map_err(|e| anyhow::anyhow!(e))?;
+    info!("Reading the HSDS source tables from schema '{}'.", source_schema);
+
+    // The doctor makes its own connection, so a database that cannot be reached is one of its findings
+    if let Some(Command::Doctor(args)) = &cli.command {
+        return run_doctor(args).await;
+    }
 
     // Establish database connection pool using your existing connection logic
     let pool = db_connect::connect().await?;
     info!("Database connection pool established.");
 
+    // Report the run's progress to --status-file until it ends, however it ends
+    let status = StatusReporter::start(cli.status_file.clone(), pool.clone());
+    let result = run(&cli, &pool, &status).await;
+    status.finish(&result);
+    result
+}
+
+/// Runs `export doctor`: prints one line per check and fails when a required check failed.
+async fn run_doctor(args: &DoctorArgs) -> Result<()> {
+    if args.read_only {
+        sql_guard::disable_writes();
+    }
+    println!("🩺 Checking the export environment...");
+    let results = doctor::run_checks().await;
+    for result in &results {
+        println!("{}", result);
+    }
+    match doctor::failed_checks(&results) {
+        0 => {
+            println!("✅ The environment is ready to export.");
+            Ok(())
+        }
+        failed => Err(anyhow::anyhow!("{} required check(s) failed", failed)),
+    }
+}
+
+/// Everything after connecting to the database: a subcommand or the interactive export.
+async fn run(cli: &Cli, pool: &db_connect::PgPool, status: &StatusReporter) -> Result<()> {
+    status.stage("schema check");
+    let preflight_client = sql_guard::read_only_client(pool, "schema check").await?;
+    preflight::preflight_schema_check(&preflight_client).await?;
+    drop(preflight_client);
+
+    // Read-heavy fetch and dashboard queries may run against a replica
+    let read_pool = db_connect::connect_read_pool(pool).await?;
+
+    // Log pool usage in the background and warn when connections run short
+    let _pool_monitor = pool_metrics::spawn_pool_monitor(pool.clone(), "primary", pool_metrics::MonitorConfig::from_env());
+
+    if cli.read_only {
+        sql_guard::disable_writes();
+        info!("Read-only mode: no tables will be created, re-clustered, or modified.");
+    }
+
+    let filter_options = DatasetFilterOptions {
+        include_nulls: cli.include_null_source_system,
+        normalize: !cli.exact_dataset_match,
+    };
+
+    match &cli.command {
+        Some(Command::Diff(args)) => {
+            status.stage("diff");
+            return run_diff(&read_pool, args, filter_options, cli.allow_all_datasets, &cli.exclude_datasets, cli.overwrite).await;
+        }
+        Some(Command::Consensus(args)) => {
+            status.stage("consensus");
+            return run_consensus(pool, &read_pool, args, filter_options, cli, status).await;
+        }
+        // The doctor runs in main, before connecting
+        Some(Command::Doctor(_)) | None => {}
+    }
+
+    // A bad overrides file fails here, before any prompt or table is created
+    let overrides = cli.overrides.as_deref().map(StatusOverrides::load).transpose()?;
+    if let (Some(path), Some(overrides)) = (&cli.overrides, &overrides) {
+        info!("Loaded {} overrides from {} ({} repeated rows ignored).", overrides.len(), path.display(), overrides.duplicate_rows);
+    }
+
     // Interactive CLI workflow
-    let (selected_team, selected_user, selected_opinion) = run_interactive_selection(&pool).await?;
+    status.stage("selection");
+    let Some((mut selected_team, selected_user, selected_opinions)) = run_interactive_selection(pool, cli).await? else {
+        info!("Export cancelled during selection.");
+        return Ok(());
+    };
     
+    let opinion_names: Vec<&str> = selected_opinions.iter().map(|o| o.name.as_str()).collect();
     info!(
-        "Selected export configuration: Team='{}', User='{}', Opinion='{}'",
-        selected_team.display_name, selected_user.username, selected_opinion.name
+        "Selected export configuration: Team='{}', User='{}', Opinion(s)='{}'",
+        selected_team.display_name, selected_user.username, opinion_names.join("', '")
     );
 
+    team_utils::check_dataset_whitelist(&selected_team, cli.allow_all_datasets)?;
+    team_utils::exclude_datasets(&mut selected_team, &cli.exclude_datasets, filter_options)?;
+
+    // Interactive selection only offers users with a prefix unless it is overridden,
+    // so this only trips on a bad record
+    let user_prefix = team_utils::opinion_table_prefix(&selected_user, cli.prefix_override.as_deref())?;
+    if let Some(prefix) = &cli.prefix_override {
+        warn!("--prefix-override: reading the opinion tables {}_{{opinion}}_... instead of those of user '{}' (prefix {:?}); the user only supplies the team, datasets and opinions.",
+              prefix, selected_user.username, selected_user.user_opinion_prefix.as_deref().unwrap_or_default());
+    }
+
+    if let Some(suffix) = &cli.from_timestamp {
+        println!("\n♻️  Reusing the export tables with suffix {}; nothing will be created or re-clustered.", suffix);
+    }
+
+    // Comparison is based on organization clusters and writes both sheets per opinion
+    if selected_opinions.len() > 1 && cli.record_scope() != RecordScope::Both {
+        return Err(anyhow::anyhow!("--entities-only, --services-only and a --sheets list without both data sheets cannot be used when comparing opinions"));
+    }
+
+    // An opinion with nothing reviewed would only reproduce the pipeline clusters
+    let Some(review_counts) = check_reviewed_edges(&read_pool, &selected_team, &selected_opinions, user_prefix, filter_options, cli).await? else {
+        info!("Export cancelled: no reviewed edges.");
+        return Ok(());
+    };
+
+    // Show what is about to be exported and ask for confirmation before the heavy work begins
+    if !confirm_export(pool, &selected_team, &selected_user, &selected_opinions, user_prefix, filter_options, cli.yes).await? {
+        info!("Export cancelled by user.");
+        return Ok(());
+    }
+
+    // Reusing an earlier run's tables needs no writes, so the primary pool is only passed on otherwise
+    let write_pool = if cli.from_timestamp.is_some() { None } else { Some(pool) };
+
     // Create the export schema once before processing
-    let schema_client = pool.get().await?;
+    if let Some(pool) = write_pool {
+        let schema_client = sql_guard::export_write_client(pool, "export schema creation").await?;
+        export_schema::create_export_schema(&schema_client).await?;
+        // Default privileges cover the tables this run creates after the grant
+        let grant_role = cli.grant_role.clone()
+            .or_else(|| std::env::var("EXPORT_GRANT_ROLE").ok())
+            .map(|role| role.trim().to_string())
+            .filter(|role| !role.is_empty());
+        if let Some(role) = grant_role {
+            export_schema::grant_export_access(&schema_client, &role).await?;
+        }
+        drop(schema_client); // Release the client back to the pool
+        info!("Export schema created/ensured.");
+    }
+
+    // Generate a unique timestamp for the export tables and file, unless reusing existing tables
+    // or given a fixed suffix
+    let timestamp_suffix = cli.from_timestamp.clone()
+        .or_else(|| cli.timestamp_suffix.clone())
+        .unwrap_or_else(|| Local::now().format("%Y%m%d%H%M%S").to_string());
+
+    // A fixed suffix may name an earlier export, which this run would drop and recreate
+    if let (Some(pool), Some(_)) = (write_pool, &cli.timestamp_suffix) {
+        if !confirm_replace_existing(pool, &selected_opinions, user_prefix, &timestamp_suffix, cli).await? {
+            info!("Export cancelled; the existing export tables were kept.");
+            return Ok(());
+        }
+    }
+
+    let selection = cli.export_selection();
+    let spot_check = &selection.spot_check;
+    let warnings = RunWarnings::new();
+    for (opinion, counts) in selected_opinions.iter().zip(&review_counts) {
+        for (record_type, unmapped) in counts.iter().flat_map(|c| &c.unmapped) {
+            warnings.record(WarningCategory::UnmappedStatus, format!(
+                "{} {} edges of opinion '{}' with confirmed_status {} counted as pending review",
+                unmapped.count, record_type, opinion.name, unmapped
+            ));
+        }
+    }
+    if let Some(since) = selection.since {
+        info!("--since {}: exporting only clusters with an edge reviewed on or after that date.", since);
+    }
+    if let Some(limit) = selection.sample {
+        warn!("--sample {}: only part of the data is exported; do not deliver this file.", limit);
+        warnings.record(WarningCategory::SampleExport, format!("Only the first {} edges and records of each type were processed", limit));
+    }
+    // The second export target is optional, so failing to reach it only costs the copy
+    let anonymization_key = cli.anonymization_key()?;
+    let target_pool = match write_pool {
+        Some(_) => connect_export_target(cli, &warnings).await,
+        None => None,
+    };
+    let naming = cli.cluster_naming();
+    let canonical = CanonicalSelection {
+        strategy: cli.canonical_strategy,
+        source_priority: cli.source_priority.clone(),
+    };
+    let settings = ExportSettings {
+        team: &selected_team,
+        user_prefix,
+        filter_options,
+        scope: cli.record_scope(),
+        canonical: &canonical,
+        low_confidence_threshold: cli.low_confidence_threshold,
+        selection: &selection,
+        warnings: &warnings,
+        status,
+        fast_insert: cli.fast_insert,
+        naming: &naming,
+        target: target_pool.as_ref(),
+        strict: cli.strict,
+        max_skipped_edges: cli.max_skipped_edges,
+        overrides: overrides.as_ref(),
+        include_shared_reviewers: cli.include_shared_reviewers.then_some(cli.conflict_policy),
+        anonymize: anonymization_key.as_ref(),
+    };
+    // Samples are for development only, which the file name makes impossible to miss
+    let sample_marker = if selection.sample.is_some() { "_SAMPLE" } else { "" };
+
+    if let [selected_opinion] = selected_opinions.as_slice() {
+        let sheets = cli.sheet_selection();
+        // Spot checks and --since cover only some clusters, which the file name makes obvious
+        let partial_marker = if spot_check.is_empty() && selection.since.is_none() { "" } else { "_partial" };
+        let export_file_name = format!("{}_{}_export{}{}_{}.xlsx", user_prefix, selected_opinion.name, partial_marker, sample_marker, timestamp_suffix);
+        let export_file_path = cli.output.clone().unwrap_or_else(|| PathBuf::from(export_file_name));
+        let ndjson_file_path = |rows: &str| PathBuf::from(ndjson_export::ndjson_file_name(
+            user_prefix, &selected_opinion.name, &format!("{}{}{}", rows, partial_marker, sample_marker), &timestamp_suffix,
+        ));
+        let ndjson_files: Vec<(&str, PathBuf)> = [("entity", "organizations"), ("service", "services")].into_iter()
+            .filter(|(record_type, _)| cli.format == ExportFormat::Ndjson && settings.scope.includes(record_type))
+            .map(|(_, rows)| (rows, ndjson_file_path(rows)))
+            .collect();
+        if cli.format == ExportFormat::Ndjson {
+            if cli.output.is_some() {
+                return Err(anyhow::anyhow!("--output names a workbook; --format ndjson writes one file per record type under generated names"));
+            }
+            for (_, path) in &ndjson_files {
+                excel_writer::check_output_path(path, cli.overwrite)?;
+            }
+        } else if !cli.export_to_table {
+            excel_writer::check_output_path(&export_file_path, cli.overwrite)?;
+        }
+
+        let graph_record_types: Vec<&str> = ["entity", "service"].into_iter()
+            .filter(|t| cli.graph_export.is_some() && settings.scope.includes(t))
+            .collect();
+        let graph_file_path = |record_type: &str| PathBuf::from(format!(
+            "{}_{}_{}_graph_{}.{}",
+            user_prefix, selected_opinion.name, record_type, timestamp_suffix, cli.graph_format.extension()
+        ));
+        for record_type in &graph_record_types {
+            excel_writer::check_output_path(&graph_file_path(record_type), cli.overwrite)?;
+        }
+
+        let (org_data, svc_data, orphaned_edges, method_performance, dataset_pairs) = export_opinion_data(
+            write_pool, &read_pool, &settings, selected_opinion, &timestamp_suffix,
+        ).await?;
+        status.method_performance(&method_performance);
+
+        if let Some(selection) = &cli.graph_export {
+            for record_type in graph_record_types {
+                let graph = graph_export::fetch_cluster_graph(
+                    &read_pool, user_prefix, &selected_opinion.name, &timestamp_suffix, record_type, selection,
+                ).await?;
+                let graph_file_path = graph_file_path(record_type);
+                graph_export::write_graph_file(&graph_file_path, &graph, cli.graph_format)?;
+                println!("🕸️  {} graph written to {}", record_type, graph_file_path.display());
+            }
+        }
+
+        if cli.export_to_table {
+            status.stage("writing export tables");
+            let tables = table_export::write_export_tables(
+                pool, user_prefix, &selected_opinion.name, &timestamp_suffix, org_data.as_deref(), svc_data.as_deref(),
+            ).await?;
+            for table in tables.names() {
+                println!("🗄️  Export rows written to {}", table);
+            }
+            status.export_tables(&tables.names());
+            info!("Export for user {} with opinion {} written to tables; no workbook was written.", selected_user.username, selected_opinion.name);
+            warnings.print_summary();
+            return Ok(());
+        }
+
+        if cli.format == ExportFormat::Ndjson {
+            status.stage("writing ndjson");
+            for (rows, path) in &ndjson_files {
+                let written = match *rows {
+                    "organizations" => ndjson_export::write_ndjson_file(path, org_data.iter().flatten())?,
+                    _ => ndjson_export::write_ndjson_file(path, svc_data.iter().flatten())?,
+                };
+                println!("📄 {} {} written to {}", written, rows, path.display());
+            }
+            info!("Export for user {} with opinion {} written as ndjson; no workbook was written.", selected_user.username, selected_opinion.name);
+            warnings.print_summary();
+            return Ok(());
+        }
+
+        // Fetch dashboard data for progress overview tab with dataset filtering
+        status.stage("progress overview");
+        let mut dashboard_section = if cli.no_progress_sheet || !sheets.includes(SheetKind::Progress) {
+            info!("Skipping progress overview (--no-progress-sheet or --sheets).");
+            DashboardSection::Skipped
+        } else if !spot_check.is_empty() {
+            info!("Skipping progress overview for a spot check (--cluster-id/--entity-id).");
+            DashboardSection::Skipped
+        } else {
+            info!("Fetching dashboard data for progress overview (filtered by whitelisted datasets)...");
+            let counts = review_counts.into_iter().next().flatten();
+            match dashboard::get_dashboard_data(&read_pool, &selected_user, user_prefix, selected_opinion, &selected_team, filter_options, counts).await {
+                Ok(data) => DashboardSection::Included(data),
+                Err(e) if cli.require_progress => {
+                    return Err(e.context("Progress Overview is required (--require-progress) but its data could not be fetched"));
+                }
+                Err(e) => {
+                    warn!("Failed to fetch dashboard data; the Progress Overview sheet will only note the failure (pass --require-progress to fail instead): {:#}", e);
+                    warnings.record(WarningCategory::ProgressOverview, format!("Dashboard data could not be fetched: {:#}", e));
+                    DashboardSection::Failed(format!("{:#}", e))
+                }
+            }
+        };
+
+        if let DashboardSection::Included(data) = &mut dashboard_section {
+            if let [dashboard] = data.as_mut_slice() {
+                track_review_trend(pool, &read_pool, dashboard, cli.record_snapshot, &warnings).await;
+                dashboard.dataset_pairs = dataset_pairs.clone();
+            }
+        }
+
+        if let DashboardSection::Included(data) = &dashboard_section {
+            if let [dashboard] = data.as_slice() {
+                let remaining = [
+                    (settings.scope.includes("entity"), dashboard.entity_stats.pending_review),
+                    (settings.scope.includes("service"), dashboard.service_stats.pending_review),
+                ].iter().filter(|(included, _)| *included).map(|(_, pending)| pending).sum::<i64>();
+                match cli.review_rate {
+                    Some(rate) => println!("\n⏳ {} records still pending review ({})", remaining, dashboard::format_review_eta(remaining, rate)),
+                    None => println!("\n⏳ {} records still pending review", remaining),
+                }
+            }
+        }
+
+        let progress_options = dashboard::ProgressOptions {
+            completion_weights: cli.completion_weights,
+            review_rate: cli.review_rate,
+            sample: selection.sample,
+            anonymized: anonymization_key.as_ref().map(AnonymizationKey::key_id),
+        };
+
+        let method_breakdown = if sheets.includes(SheetKind::MethodBreakdown) {
+            data_fetch::fetch_method_breakdown(
+                &read_pool, user_prefix, &selected_opinion.name, &timestamp_suffix, settings.scope,
+            ).await?
+        } else {
+            Vec::new()
+        };
+
+        // Write data to Excel file (including progress overview)
+        status.stage("writing workbook");
+        info!("Writing data to Excel file: {:?}", export_file_path);
+        // One label map for the whole workbook, so a cluster reads the same on every sheet
+        let cluster_ids = org_data.iter().flatten().filter_map(|r| r.cluster.as_deref())
+            .chain(svc_data.iter().flatten().filter_map(|r| r.cluster.as_deref()));
+        let cluster_labels = LabelMap::build(cli.cluster_labels, cluster_ids);
+        // Omitted sheets are passed on empty, which the writer already skips
+        let trailing_sheets = excel_writer::TrailingSheets {
+            method_breakdown,
+            method_performance: if sheets.includes(SheetKind::MethodPerformance) { method_performance } else { Vec::new() },
+            orphaned_edges: if sheets.includes(SheetKind::OrphanedEdges) { orphaned_edges } else { Vec::new() },
+            warnings: if sheets.includes(SheetKind::Warnings) { warnings.summaries() } else { Vec::new() },
+            legend: sheets.includes(SheetKind::Legend),
+            extra_sheets: None,
+        };
+        let sheet_options = excel_writer::DataSheetOptions {
+            cluster_labels: &cluster_labels,
+            footer: !cli.no_footer,
+            taxonomy_sheet: cli.include_taxonomy_sheet,
+        };
+        let partitions = cli.workbook_per_dataset.then(|| {
+            dataset_split::partition_by_dataset(org_data.as_deref(), svc_data.as_deref(), cli.foreign_rows)
+        });
+        let combined_rows = org_data.iter().flatten().count() + svc_data.iter().flatten().count();
+        let expected = cli.verify
+            .then(|| ExpectedWorkbook::for_rows(org_data.as_deref(), svc_data.as_deref(), sheet_options))
+            .transpose()?;
+        excel_writer::write_excel_file(
+            &export_file_path, org_data, svc_data, dashboard_section, progress_options, trailing_sheets, sheet_options,
+        ).await?;
+        if let Some(expected) = &expected {
+            workbook_verify::verify_workbook_file(&export_file_path, expected)?;
+            info!("Read {} back: it matches the rows written.", export_file_path.display());
+        }
+        if let Some(partitions) = partitions {
+            status.stage("writing dataset workbooks");
+            let mut manifest = Manifest {
+                opinion: selected_opinion.name.clone(),
+                timestamp_suffix: timestamp_suffix.clone(),
+                foreign_rows: cli.foreign_rows,
+                files: vec![ManifestFile {
+                    dataset: None,
+                    path: export_file_path.display().to_string(),
+                    own_rows: combined_rows,
+                    foreign_rows: 0,
+                }],
+                dataset_pairs,
+            };
+            manifest.files.extend(write_dataset_workbooks(partitions, &selected_opinion.name, &timestamp_suffix, sheets.includes(SheetKind::Legend), sheet_options, cli.overwrite, cli.verify).await?);
+            let manifest_path = PathBuf::from(format!("{}_{}_manifest_{}.json", user_prefix, selected_opinion.name, timestamp_suffix));
+            excel_writer::check_output_path(&manifest_path, cli.overwrite)?;
+            let json = serde_json::to_vec_pretty(&manifest).context("Failed to serialize the workbook manifest")?;
+            std::fs::write(&manifest_path, json).context(format!("Failed to write {}", manifest_path.display()))?;
+            println!("🗂️  {} dataset workbooks listed in {}", manifest.files.len() - 1, manifest_path.display());
+        }
+        info!("Export for user {} with opinion {} completed successfully (filtered by team's whitelisted datasets).", selected_user.username, selected_opinion.name);
+    } else {
+        if cli.graph_export.is_some() {
+            warn!("--graph-export only applies to single-opinion exports; no graph is written for a comparison.");
+        }
+        if cli.format == ExportFormat::Ndjson {
+            warn!("--format ndjson only applies to single-opinion exports; a comparison is written as a workbook.");
+        }
+        if cli.export_to_table {
+            warn!("--export-to-table only applies to single-opinion exports; a comparison is written as a workbook.");
+        }
+        if cli.workbook_per_dataset {
+            warn!("--workbook-per-dataset only applies to single-opinion exports; a comparison is written as one workbook.");
+        }
+        if cli.include_taxonomy_sheet {
+            warn!("--include-taxonomy-sheet only applies to single-opinion exports; a comparison has no Service Taxonomies sheet.");
+        }
+        if cli.verify {
+            warn!("--verify only applies to single-opinion exports; the comparison workbook is not read back.");
+        }
+        let export_file_name = format!("{}_comparison_export{}_{}.xlsx", user_prefix, sample_marker, timestamp_suffix);
+        let export_file_path = cli.output.clone().unwrap_or_else(|| PathBuf::from(export_file_name));
+        excel_writer::check_output_path(&export_file_path, cli.overwrite)?;
+
+        let mut opinion_exports = Vec::new();
+        for (index, opinion) in selected_opinions.iter().enumerate() {
+            // Each opinion gets its own suffix so its timestamped tables never collide with another's
+            let opinion_suffix = format!("{}_{}", timestamp_suffix, index + 1);
+            let (org_data, svc_data, _orphaned_edges, _method_performance, _dataset_pairs) = export_opinion_data(
+                write_pool, &read_pool, &settings, opinion, &opinion_suffix,
+            ).await?;
+            opinion_exports.push(OpinionExport {
+                opinion_name: opinion.name.clone(),
+                org_data: org_data.unwrap_or_default(),
+                svc_data: svc_data.unwrap_or_default(),
+            });
+        }
+
+        let comparison_rows = comparison::compare_opinion_clusters(&opinion_exports);
+        let disagreements = comparison_rows.iter().filter(|r| !r.clusters_agree).count();
+        info!("Compared {} entities across {} opinions: {} with differing cluster membership.",
+              comparison_rows.len(), opinion_exports.len(), disagreements);
+
+        status.stage("writing workbook");
+        info!("Writing opinion comparison to Excel file: {:?}", export_file_path);
+        excel_writer::write_comparison_excel_file(
+            &export_file_path, opinion_exports, comparison_rows, &warnings.summaries(), !cli.no_footer,
+        ).await?;
+        info!("Comparison export for user {} with opinions '{}' completed successfully.", selected_user.username, opinion_names.join("', '"));
+    }
+
+    warnings.print_summary();
+
+    Ok(())
+}
+
+/// Connects to the second export target from `--target-db` or the `EXPORT_TARGET_*`
+/// variables, if one is configured. A target that cannot be reached is recorded as a
+/// warning and skipped, since the local export does not depend on it.
+async fn connect_export_target(cli: &Cli, warnings: &RunWarnings) -> Option<db_connect::PgPool> {
+    let connected = match db_connect::build_target_pg_config(cli.target_db.as_deref()) {
+        Ok(Some(config)) => db_connect::connect_to(config).await,
+        Ok(None) => return None,
+        Err(e) => Err(e),
+    };
+    match connected {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            warn!("Could not connect to the export target; the export is only written locally: {:#}", e);
+            warnings.record(WarningCategory::TargetReplication, format!("The export target could not be reached: {:#}", e));
+            None
+        }
+    }
+}
+
+/// Records the dashboard's review stats as a snapshot when `record` is set, then attaches
+/// the most recent snapshots as the dashboard's trend. Failures only log a warning.
+async fn track_review_trend(
+    pool: &db_connect::PgPool,
+    read_pool: &db_connect::PgPool,
+    dashboard: &mut dashboard::OpinionDashboard,
+    record: bool,
+    warnings: &RunWarnings,
+) {
+    if record {
+        let result = async {
+            let client = sql_guard::export_write_client(pool, "snapshot recording").await?;
+            snapshots::record_snapshot(&client, dashboard).await
+        }.await;
+        if let Err(e) = result {
+            warn!("Failed to record review snapshot: {:#}", e);
+            warnings.record(WarningCategory::ProgressOverview, format!("Review snapshot could not be recorded: {:#}", e));
+        }
+    }
+
+    let trend = async {
+        let client = sql_guard::read_only_client(read_pool, "snapshot trend").await?;
+        snapshots::recent_snapshots(&client, &dashboard.user_prefix, &dashboard.opinion_name, TREND_SNAPSHOTS).await
+    }.await;
+    match trend {
+        Ok(points) => dashboard.trend = points,
+        Err(e) => {
+            warn!("Failed to read review snapshots; the trend will be omitted: {:#}", e);
+            warnings.record(WarningCategory::ProgressOverview, format!("Review trend omitted: {:#}", e));
+        }
+    }
+}
+
+/// Compares two existing exports and writes a change report workbook.
+/// Only reads are involved, so `pool` is the read pool.
+async fn run_diff(
+    pool: &db_connect::PgPool,
+    args: &DiffArgs,
+    filter_options: DatasetFilterOptions,
+    allow_all_datasets: bool,
+    excluded_datasets: &[String],
+    overwrite: bool,
+) -> Result<()> {
+    let report_file_path = PathBuf::from(format!(
+        "{}_{}_diff_{}_{}.xlsx", args.user_prefix, args.opinion, args.old_suffix, args.new_suffix
+    ));
+    excel_writer::check_output_path(&report_file_path, overwrite)?;
+
+    let mut team = team_utils::get_team_by_id(pool, &args.team_id).await?;
+    team_utils::check_dataset_whitelist(&team, allow_all_datasets)?;
+    team_utils::exclude_datasets(&mut team, excluded_datasets, filter_options)?;
+
+    let warnings = RunWarnings::new();
+    {
+        let client = sql_guard::read_only_client(pool, "NULL source_system count").await?;
+        for record_type in ["entity", "service"] {
+            data_fetch::warn_on_excluded_null_source_system(&client, record_type, &team, filter_options, &warnings).await;
+        }
+    }
+
+    let (org_changes, svc_changes) = diff::diff_exports(
+        pool, &args.user_prefix, &args.opinion, &args.old_suffix, &args.new_suffix, &team, filter_options,
+    ).await?;
+
+    let org_summary = diff::DiffSummary::from_changes(&org_changes);
+    let svc_summary = diff::DiffSummary::from_changes(&svc_changes);
+    println!("\n📊 Changes from {} to {}:", args.old_suffix, args.new_suffix);
+    for (label, summary) in [("Organizations", org_summary), ("Services", svc_summary)] {
+        println!(
+            "   {:<14} added: {}, removed: {}, cluster changed: {}, status changed: {}",
+            label, summary.added, summary.removed, summary.cluster_changed, summary.status_changed
+        );
+    }
+
+    info!("Writing change report to Excel file: {:?}", report_file_path);
+    excel_writer::write_diff_excel_file(&report_file_path, &args.old_suffix, &args.new_suffix, org_changes, svc_changes).await?;
+    println!("✅ Change report written to {}", report_file_path.display());
+    warnings.print_summary();
+    Ok(())
+}
+
+/// Re-clusters the named opinion of every reviewer in the team into their own export
+/// tables, then writes one workbook showing per entity how each reviewer clustered it
+/// and whether they agree. Only entities are compared.
+async fn run_consensus(
+    pool: &db_connect::PgPool,
+    read_pool: &db_connect::PgPool,
+    args: &ConsensusArgs,
+    filter_options: DatasetFilterOptions,
+    cli: &Cli,
+    status: &StatusReporter,
+) -> Result<()> {
+    let mut team = team_utils::get_team_by_id(pool, &args.team_id).await?;
+    team_utils::check_dataset_whitelist(&team, cli.allow_all_datasets)?;
+    team_utils::exclude_datasets(&mut team, &cli.exclude_datasets, filter_options)?;
+
+    let reviewers = team_utils::get_users_for_team(pool, &team.id, true).await?;
+    let hidden_users = team_utils::count_users_without_prefix(pool, &team.id).await?;
+    if hidden_users > 0 {
+        info!("{} user(s) without an opinion prefix skipped (cannot be exported).", hidden_users);
+    }
+
+    let schema_client = sql_guard::export_write_client(pool, "export schema creation").await?;
     export_schema::create_export_schema(&schema_client).await?;
-    drop(schema_client); // Release the client back to the pool
-    info!("Export schema created/ensured.");
+    drop(schema_client);
 
-    // Generate a unique timestamp for the export tables and file
     let timestamp_suffix = Local::now().format("%Y%m%d%H%M%S").to_string();
-    let user_prefix = selected_user.user_opinion_prefix.as_deref()
-        .ok_or_else(|| anyhow::anyhow!("User has no opinion prefix set"))?;
-    
-    let export_file_name = format!("{}_{}_export_{}.xlsx", user_prefix, selected_opinion.name, timestamp_suffix);
-    let export_file_path = PathBuf::from(export_file_name);
+    let export_file_path = PathBuf::from(format!("{}_{}_consensus_{}.xlsx", team.name, args.opinion, timestamp_suffix));
+    excel_writer::check_output_path(&export_file_path, cli.overwrite)?;
 
-    info!("Processing export for user: {} with opinion: {} (team: {}, datasets: {:?})", 
-          selected_user.username, selected_opinion.name, selected_team.name, selected_team.whitelisted_datasets);
+    let canonical = CanonicalSelection::default();
+    let naming = cli.cluster_naming();
+    let anonymization_key = cli.anonymization_key()?;
+    let selection = ExportSelection::default();
+    let warnings = RunWarnings::new();
+    let mut builder = ConsensusBuilder::new();
 
-    // Get a client from the pool for table operations
-    let client_for_tables = pool.get().await?;
-    
-    // Create timestamped tables with opinion-specific naming
-    export_schema::create_timestamped_tables(&client_for_tables, user_prefix, &selected_opinion.name, &timestamp_suffix).await?;
-    drop(client_for_tables); // Release the client back to the pool
+    for reviewer in &reviewers {
+        let opinions = team_utils::get_opinions_for_user(pool, &reviewer.id).await?;
+        // Only the reviewer's own opinion counts; a shared one is someone else's judgement
+        let Some(opinion) = opinions.iter().find(|o| o.user_id == reviewer.id && o.name == args.opinion) else {
+            info!("Reviewer {} has no opinion named '{}'; skipping.", reviewer.username, args.opinion);
+            continue;
+        };
+        let user_prefix = reviewer.user_opinion_prefix.as_deref().unwrap_or_default();
+        let settings = ExportSettings {
+            team: &team,
+            user_prefix,
+            filter_options,
+            scope: RecordScope::EntitiesOnly,
+            canonical: &canonical,
+            low_confidence_threshold: None,
+            selection: &selection,
+            warnings: &warnings,
+            status,
+            fast_insert: cli.fast_insert,
+            naming: &naming,
+            target: None,
+            strict: cli.strict,
+            max_skipped_edges: cli.max_skipped_edges,
+            overrides: None,
+            include_shared_reviewers: None,
+            anonymize: anonymization_key.as_ref(),
+        };
+        // One reviewer's export is a single transaction that drops and recreates its
+        // tables, so after a transient failure it is simply run again from the start;
+        // only the warnings of the attempt that succeeded are kept
+        let stage = format!("Consensus export of reviewer {}", reviewer.username);
+        let (org_data, _, _, _, _) = retry::retry_recording_warnings(&stage, RetryPolicy::default(), &warnings, |attempt_warnings| {
+            let (settings, timestamp_suffix) = (&settings, &timestamp_suffix);
+            async move {
+                let settings = ExportSettings { warnings: &attempt_warnings, ..*settings };
+                export_opinion_data(Some(pool), read_pool, &settings, opinion, timestamp_suffix).await
+            }
+        }).await?;
+        builder.add_reviewer(&reviewer.username, org_data.unwrap_or_default());
+    }
+
+    if builder.reviewer_count() == 0 {
+        return Err(anyhow::anyhow!(
+            "No reviewer in team '{}' has an opinion named '{}'", team.name, args.opinion
+        ));
+    }
+
+    let (reviewer_names, rows) = builder.build();
+    let disagreements = rows.iter().filter(|r| !r.agreement).count();
+    println!("\n🤝 {} entities across {} reviewers; {} with disagreements.", rows.len(), reviewer_names.len(), disagreements);
+
+    info!("Writing consensus to Excel file: {:?}", export_file_path);
+    excel_writer::write_consensus_excel_file(&export_file_path, &reviewer_names, rows, &warnings.summaries()).await?;
+    println!("✅ Consensus written to {}", export_file_path.display());
+    warnings.print_summary();
+    Ok(())
+}
+
+/// Settings shared by every opinion exported in one run.
+#[derive(Clone, Copy)]
+struct ExportSettings<'a> {
+    team: &'a TeamInfo,
+    user_prefix: &'a str,
+    filter_options: DatasetFilterOptions,
+    scope: RecordScope,
+    canonical: &'a CanonicalSelection,
+    low_confidence_threshold: Option<f64>,
+    selection: &'a ExportSelection,
+    warnings: &'a RunWarnings,
+    status: &'a StatusReporter,
+    fast_insert: bool,
+    naming: &'a ClusterNaming,
+    strict: bool,
+    max_skipped_edges: Option<SkippedEdgeLimit>,
+    /// Set by `--overrides`: reviewer decisions applied over the stored statuses
+    overrides: Option<&'a StatusOverrides>,
+    /// Set by `--include-shared-reviewers`: merge the decisions of the opinion's other
+    /// reviewers, resolving conflicts with this `--conflict-policy`
+    include_shared_reviewers: Option<ConflictPolicy>,
+    /// The second database the committed export tables are copied to, if any
+    target: Option<&'a db_connect::PgPool>,
+    /// Set by `--anonymize`: names and addresses are replaced with pseudonyms after the fetch
+    anonymize: Option<&'a AnonymizationKey>,
+}
 
-    // Run re-clustering for entities with dataset filtering
-    info!("Running entity re-clustering for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, selected_opinion.name);
-    reclustering::run_reclustering(&pool, user_prefix, &selected_opinion.name, &timestamp_suffix, "entity", &selected_team).await?;
+/// Creates the timestamped tables for one opinion, re-clusters entities and services,
+/// and fetches the organization and service rows for the export.
+/// Table creation and re-clustering use the primary `write_pool`; the fetches use `read_pool`.
+/// When `write_pool` is `None` the existing tables for `timestamp_suffix` are fetched as-is.
+/// A record type outside `settings.scope` is skipped entirely and returned as `None`.
+/// Also returns the orphaned edges excluded during re-clustering and the per-method
+/// statistics it tallied; both are empty when the tables are reused.
+async fn export_opinion_data(
+    write_pool: Option<&db_connect::PgPool>,
+    read_pool: &db_connect::PgPool,
+    settings: &ExportSettings<'_>,
+    opinion: &OpinionInfo,
+    timestamp_suffix: &str,
+) -> Result<(Option<Vec<OrganizationExportRow>>, Option<Vec<ServiceExportRow>>, Vec<OrphanedEdge>, Vec<MethodStats>, Vec<DatasetPairCount>)> {
+    let ExportSettings {
+        team, user_prefix, filter_options, scope, canonical, low_confidence_threshold, selection, warnings, status, fast_insert, naming, target, strict,
+        max_skipped_edges, overrides, include_shared_reviewers, anonymize,
+    } = *settings;
+    let shared_review = match include_shared_reviewers {
+        Some(policy) if write_pool.is_some() => Some(find_shared_reviewers(read_pool, opinion, user_prefix, policy, warnings).await?),
+        _ => None,
+    };
+    let recluster_options = ReclusterOptions {
+        team_info: team, filter_options, warnings, sample: selection.sample, fast_insert, naming, strict, max_skipped_edges, overrides,
+        shared_review: shared_review.as_ref(),
+    };
+    let spot_check = &selection.spot_check;
+    info!("Processing export for user prefix: {} with opinion: {} (team: {}, datasets: {:?}, record types: {})", 
+          user_prefix, opinion.name, team.name, team.whitelisted_datasets, scope.label());
 
-    // Run re-clustering for services with dataset filtering
-    info!("Running service re-clustering for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, selected_opinion.name);
-    reclustering::run_reclustering(&pool, user_prefix, &selected_opinion.name, &timestamp_suffix, "service", &selected_team).await?;
+    let mut orphaned_edges = Vec::new();
+    let mut method_performance = Vec::new();
+    let mut overrides_applied = BTreeSet::new();
+    let mut dataset_pairs = Vec::new();
+    if let Some(pool) = write_pool {
+        // Table creation, both re-clusterings, and the completion marker share one
+        // REPEATABLE READ transaction: a failure anywhere leaves no tables behind, and
+        // every step reads the opinion tables as of the same snapshot
+        let mut client = sql_guard::export_write_client(pool, "export table writes").await?;
+        let tx = client.build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .start()
+            .await
+            .context("Failed to start the export transaction")?;
+
+        // Create timestamped tables with opinion-specific naming
+        export_schema::create_timestamped_tables(&tx, user_prefix, &opinion.name, timestamp_suffix, scope).await?;
+
+        // Run re-clustering for entities with dataset filtering
+        if scope.includes("entity") {
+            status.stage(&format!("re-clustering entities ({})", opinion.name));
+            info!("Running entity re-clustering for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, opinion.name);
+            let output = reclustering::run_reclustering(&tx, user_prefix, &opinion.name, timestamp_suffix, "entity", &recluster_options).await?;
+            log_recluster_summary("entity", &opinion.name, &output);
+            orphaned_edges.extend(output.orphaned_edges);
+            method_performance.extend(output.method_stats);
+            overrides_applied.extend(output.overrides_applied);
+            dataset_pairs.extend(output.dataset_pairs);
+        }
+
+        // Run re-clustering for services with dataset filtering
+        if scope.includes("service") {
+            status.stage(&format!("re-clustering services ({})", opinion.name));
+            info!("Running service re-clustering for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, opinion.name);
+            let output = reclustering::run_reclustering(&tx, user_prefix, &opinion.name, timestamp_suffix, "service", &recluster_options).await?;
+            log_recluster_summary("service", &opinion.name, &output);
+            orphaned_edges.extend(output.orphaned_edges);
+            method_performance.extend(output.method_stats);
+            overrides_applied.extend(output.overrides_applied);
+            dataset_pairs.extend(output.dataset_pairs);
+        }
+
+        if let Some(overrides) = overrides {
+            report_overrides(overrides, &overrides_applied, &opinion.name, warnings);
+        }
+
+        export_schema::mark_export_complete(&tx, user_prefix, &opinion.name, timestamp_suffix, scope).await?;
+        tx.commit().await.context("Failed to commit the export tables")?;
+        info!("Export tables with suffix '{}' for opinion '{}' committed.", timestamp_suffix, opinion.name);
+        drop(client);
+
+        if let Some(target) = target {
+            status.stage(&format!("replicating to export target ({})", opinion.name));
+            if let Err(e) = replication::replicate_export(pool, target, user_prefix, &opinion.name, timestamp_suffix, scope).await {
+                warn!("Could not copy the export of opinion '{}' to the export target; the local export is unaffected: {:#}", opinion.name, e);
+                warnings.record(WarningCategory::TargetReplication, format!("Opinion '{}' was not copied to the export target: {:#}", opinion.name, e));
+            }
+        }
+    } else {
+        let client = sql_guard::read_only_client(read_pool, "export table check").await?;
+        let missing = export_schema::missing_export_tables(&client, user_prefix, &opinion.name, timestamp_suffix, scope).await?;
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot reuse export tables with suffix '{}' for opinion '{}'; missing: {}",
+                timestamp_suffix, opinion.name, missing.join(", ")
+            ));
+        }
+        let completed = export_schema::completed_exports(&client, user_prefix, &opinion.name).await?;
+        if !completed.iter().any(|export| export.timestamp_suffix == timestamp_suffix) {
+            warn!("Export tables with suffix '{}' for opinion '{}' have no completion marker; they may be from an aborted run or predate markers.",
+                  timestamp_suffix, opinion.name);
+        }
+        info!("Reusing existing export tables with suffix '{}' for opinion '{}'; no method performance is tallied without re-clustering.",
+              timestamp_suffix, opinion.name);
+    }
+
+    {
+        let client = sql_guard::read_only_client(read_pool, "NULL source_system count").await?;
+        for record_type in ["entity", "service"].into_iter().filter(|t| scope.includes(t)) {
+            data_fetch::warn_on_excluded_null_source_system(&client, record_type, team, filter_options, warnings).await;
+        }
+    }
+
+    // A spot check, --since or --sample export covers only part of the datasets, so
+    // the fetched rows are only compared with the records of a full export
+    let check_row_counts = spot_check.is_empty() && selection.since.is_none() && selection.sample.is_none();
 
     // Fetch organization export data with dataset filtering
-    info!("Fetching organization data for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, selected_opinion.name);
-    let org_data = data_fetch::fetch_organization_export_data(&pool, user_prefix, &selected_opinion.name, &timestamp_suffix, &selected_team).await?;
-    info!("Fetched {} organization records (filtered by whitelisted datasets).", org_data.len());
+    let mut org_data = if scope.includes("entity") {
+        status.stage(&format!("fetching organizations ({})", opinion.name));
+        info!("Fetching organization data for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, opinion.name);
+        let mut org_data = data_fetch::fetch_organization_export_data(read_pool, user_prefix, &opinion.name, timestamp_suffix, team, filter_options, selection).await?;
+        if check_row_counts {
+            check_fetched_rows(read_pool, "entity", org_data.iter().map(|r| r.entity_id.as_str()), team, filter_options, strict, warnings).await?;
+        }
+        canonical::mark_canonical_records(&mut org_data, canonical);
+        for row in &mut org_data {
+            row.low_confidence = data_fetch::is_low_confidence(row.min_edge_confidence, low_confidence_threshold);
+        }
+        if scope.includes("service") {
+            let summaries = data_fetch::fetch_organization_service_summaries(read_pool, user_prefix, &opinion.name, timestamp_suffix, team, filter_options).await?;
+            data_fetch::apply_service_summaries(&mut org_data, &summaries);
+        }
+        info!("Fetched {} organization records (filtered by whitelisted datasets).", org_data.len());
+        status.add_rows(org_data.len());
+        Some(org_data)
+    } else {
+        None
+    };
 
     // Fetch service export data with dataset filtering
-    info!("Fetching service data for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, selected_opinion.name);
-    let svc_data = data_fetch::fetch_service_export_data(&pool, user_prefix, &selected_opinion.name, &timestamp_suffix, &selected_team).await?;
-    info!("Fetched {} service records (filtered by whitelisted datasets).", svc_data.len());
+    let mut svc_data = if scope.includes("service") {
+        status.stage(&format!("fetching services ({})", opinion.name));
+        info!("Fetching service data for user: {} with opinion: {} (filtered by whitelisted datasets)", user_prefix, opinion.name);
+        let mut svc_data = data_fetch::fetch_service_export_data(read_pool, user_prefix, &opinion.name, timestamp_suffix, team, filter_options, selection).await?;
+        if check_row_counts {
+            check_fetched_rows(read_pool, "service", svc_data.iter().map(|r| r.service_id.as_str()), team, filter_options, strict, warnings).await?;
+        }
+        canonical::mark_canonical_records(&mut svc_data, canonical);
+        for row in &mut svc_data {
+            row.low_confidence = data_fetch::is_low_confidence(row.min_edge_confidence, low_confidence_threshold);
+        }
+        info!("Fetched {} service records (filtered by whitelisted datasets).", svc_data.len());
+        status.add_rows(svc_data.len());
+        Some(svc_data)
+    } else {
+        None
+    };
+
+    if !spot_check.is_empty() {
+        let exported: HashSet<&str> = org_data.iter().flatten()
+            .flat_map(|r| [Some(r.entity_id.as_str()), r.cluster.as_deref()])
+            .chain(svc_data.iter().flatten().flat_map(|r| [Some(r.service_id.as_str()), r.cluster.as_deref()]))
+            .flatten()
+            .collect();
+        let missing = spot_check.missing_ids(&exported);
+        if missing.len() == spot_check.cluster_ids.len() + spot_check.record_ids.len() {
+            return Err(anyhow::anyhow!(
+                "None of the requested cluster or record ids exist in the export for opinion '{}': {}",
+                opinion.name, missing.join(", ")
+            ));
+        }
+        if !missing.is_empty() {
+            warn!("These requested ids were not found and are left out of the export: {}", missing.join(", "));
+            for id in missing {
+                warnings.record(WarningCategory::MissingSpotCheckId, format!("'{}' is not a cluster or record id in this export", id));
+            }
+        }
+    }
 
-    // Fetch dashboard data for progress overview tab with dataset filtering
-    info!("Fetching dashboard data for progress overview (filtered by whitelisted datasets)...");
-    let dashboard_data = dashboard::get_dashboard_data(&pool, &selected_user, &selected_opinion, &selected_team).await.ok(); // Use .ok() to make it optional
+    if let Some(key) = anonymize {
+        // One anonymizer for both sheets, so an organization's pseudonym matches on each
+        let mut anonymizer = Anonymizer::new(key);
+        anonymizer.anonymize_organizations(org_data.as_deref_mut().unwrap_or_default())?;
+        anonymizer.anonymize_services(svc_data.as_deref_mut().unwrap_or_default())?;
+        info!("Replaced names and addresses with pseudonyms for opinion '{}' (key {}).", opinion.name, key.key_id());
+    }
 
-    // Write data to Excel file (including progress overview)
-    info!("Writing data to Excel file: {:?}", export_file_path);
-    excel_writer::write_excel_file(&export_file_path, org_data, svc_data, dashboard_data).await?; 
-    info!("Export for user {} with opinion {} completed successfully (filtered by team's whitelisted datasets).", selected_user.username, selected_opinion.name);
+    if !orphaned_edges.is_empty() {
+        warn!("{} orphaned edges reference deleted records for opinion '{}'; see the 'Orphaned Edges' sheet.",
+              orphaned_edges.len(), opinion.name);
+    }
 
+    reclustering::sort_method_stats(&mut method_performance);
+    Ok((org_data, svc_data, orphaned_edges, method_performance, dataset_pairs))
+}
+
+/// The other reviewers of `opinion` whose decisions `--include-shared-reviewers` merges
+/// into the tables exported under `user_prefix`, resolving conflicts with `policy`.
+/// Reviewers that cannot be merged are logged and recorded as warnings.
+async fn find_shared_reviewers(
+    pool: &db_connect::PgPool,
+    opinion: &OpinionInfo,
+    user_prefix: &str,
+    policy: ConflictPolicy,
+    warnings: &RunWarnings,
+) -> Result<SharedReview> {
+    let reviewer_ids: Vec<String> = std::iter::once(&opinion.user_id).chain(&opinion.other_users).cloned().collect();
+    let users = team_utils::get_users_by_ids(pool, &reviewer_ids).await?;
+    let resolved = shared_reviewers::resolve_shared_reviewers(opinion, &users, user_prefix);
+    for reason in &resolved.skipped {
+        warn!("Not merging a reviewer of opinion '{}': {}.", opinion.name, reason);
+        warnings.record(WarningCategory::SharedReviewer, format!("Opinion '{}': {}", opinion.name, reason));
+    }
+    if resolved.reviewers.is_empty() {
+        info!("Opinion '{}' has no other reviewers to merge; exporting the decisions under '{}' only.", opinion.name, user_prefix);
+    } else {
+        let names: Vec<&str> = resolved.reviewers.iter().map(|r| r.username.as_str()).collect();
+        println!("👥 Merging the decisions of {} into opinion '{}' ({} on conflicts).", names.join(", "), opinion.name, policy.label());
+    }
+    Ok(SharedReview {
+        reviewers: resolved.reviewers,
+        exporting_reviewer: resolved.exporting.unwrap_or_else(|| user_prefix.to_string()),
+        owner: opinion.owner_username.clone(),
+        policy,
+    })
+}
+
+/// Logs how many `--overrides` entries matched an edge of the opinion, and warns
+/// about each one that matched none, such as a mistyped id.
+fn report_overrides(overrides: &StatusOverrides, applied: &BTreeSet<usize>, opinion_name: &str, warnings: &RunWarnings) {
+    println!("📝 Applied {} of {} overrides to opinion '{}'.", applied.len(), overrides.len(), opinion_name);
+    for unmatched in overrides.unmatched(applied) {
+        warn!("Override on line {} ({}) matched no edge of opinion '{}'.", unmatched.line, unmatched.key, opinion_name);
+        warnings.record(WarningCategory::UnmatchedOverride, format!(
+            "Line {}: {} matched no edge of opinion '{}'", unmatched.line, unmatched.key, opinion_name
+        ));
+    }
+}
+
+/// Compares the fetched rows of one record type with the records in the whitelisted
+/// datasets, each of which should appear exactly once. The records are counted first;
+/// their ids are only read to find the difference when the counts disagree or a row
+/// was fetched twice. A mismatch is logged and recorded as a warning, or fails the
+/// export with `--strict`.
+async fn check_fetched_rows<'a>(
+    pool: &db_connect::PgPool,
+    record_type: &str,
+    fetched_ids: impl IntoIterator<Item = &'a str>,
+    team: &TeamInfo,
+    filter_options: DatasetFilterOptions,
+    strict: bool,
+    warnings: &RunWarnings,
+) -> Result<()> {
+    let fetched_ids: Vec<&str> = fetched_ids.into_iter().collect();
+    let client = sql_guard::read_only_client(pool, "row count check").await?;
+    let expected = team_utils::count_whitelisted_records(&client, record_type, &team.whitelisted_datasets, filter_options).await?;
+    let distinct = fetched_ids.iter().collect::<HashSet<_>>().len();
+    if expected == fetched_ids.len() as i64 && distinct == fetched_ids.len() {
+        info!("Row count check passed: all {} {} records in the whitelisted datasets were fetched once.", expected, record_type);
+        return Ok(());
+    }
+    let expected_ids = team_utils::fetch_whitelisted_ids(&client, record_type, &team.whitelisted_datasets, filter_options).await?;
+    let Some(mismatch) = row_count_check::reconcile_rows(record_type, &expected_ids, fetched_ids) else {
+        info!("Row count check passed: all {} {} records in the whitelisted datasets were fetched once.", expected_ids.len(), record_type);
+        return Ok(());
+    };
+    if strict {
+        return Err(anyhow::anyhow!("Row count check failed: {}", mismatch));
+    }
+    warn!("Row count check failed: {}", mismatch);
+    warnings.record(WarningCategory::RowCountMismatch, mismatch.to_string());
     Ok(())
 }
 
-/// Runs the interactive selection process for team, user, and opinion
-async fn run_interactive_selection(pool: &db_connect::PgPool) -> Result<(TeamInfo, UserInfo, OpinionInfo)> {
+/// Writes `{dataset}_{opinion}_{timestamp}.xlsx` for each partition, with the data
+/// sheets and optionally the legend. The team-wide sheets (progress, method statistics,
+/// orphaned edges, warnings) stay in the combined workbook. Every path is checked before
+/// the first is written, and with `verify` each workbook is read back once saved.
+/// Returns the files for the manifest.
+async fn write_dataset_workbooks(
+    partitions: Vec<DatasetPartition>,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    legend: bool,
+    sheet_options: excel_writer::DataSheetOptions<'_>,
+    overwrite: bool,
+    verify: bool,
+) -> Result<Vec<ManifestFile>> {
+    let parts = dataset_split::unique_file_name_parts(partitions.iter().map(|p| p.dataset.as_str()));
+    for (partition, part) in partitions.iter().zip(&parts) {
+        if *part != dataset_split::file_name_part(&partition.dataset) {
+            warn!("Dataset '{}' has the same file name as another dataset; its workbook is named after '{}'.", partition.dataset, part);
+        }
+    }
+    let paths: Vec<PathBuf> = parts.iter()
+        .map(|part| PathBuf::from(format!("{}_{}_{}.xlsx", part, opinion_name, timestamp_suffix)))
+        .collect();
+    for path in &paths {
+        excel_writer::check_output_path(path, overwrite)?;
+    }
+
+    let mut files = Vec::new();
+    for (partition, path) in partitions.into_iter().zip(paths) {
+        let file = ManifestFile {
+            dataset: Some(partition.dataset.clone()),
+            path: path.display().to_string(),
+            own_rows: partition.own_rows(),
+            foreign_rows: partition.foreign_rows(),
+        };
+        let trailing_sheets = excel_writer::TrailingSheets {
+            method_breakdown: Vec::new(),
+            method_performance: Vec::new(),
+            orphaned_edges: Vec::new(),
+            warnings: Vec::new(),
+            legend,
+            extra_sheets: None,
+        };
+        let expected = verify
+            .then(|| ExpectedWorkbook::for_rows(partition.org_data.as_deref(), partition.svc_data.as_deref(), sheet_options))
+            .transpose()?;
+        excel_writer::write_excel_file(
+            &path, partition.org_data, partition.svc_data, DashboardSection::Skipped, dashboard::ProgressOptions::default(),
+            trailing_sheets, sheet_options,
+        ).await?;
+        if let Some(expected) = &expected {
+            workbook_verify::verify_workbook_file(&path, expected)?;
+        }
+        info!("Wrote dataset '{}' workbook with {} own and {} matched rows to {}.",
+              partition.dataset, file.own_rows, file.foreign_rows, path.display());
+        files.push(file);
+    }
+    Ok(files)
+}
+
+/// Logs how many clusters re-clustering produced and how many of them have duplicates.
+fn log_recluster_summary(record_type: &str, opinion_name: &str, output: &ReclusterOutput) {
+    let multi_member_clusters = output.cluster_count - (output.membership.len() - output.node_count);
+    info!(
+        "Re-clustered {} {}s for opinion '{}' into {} clusters ({} with duplicates) over {} valid edges.",
+        output.membership.len(), record_type, opinion_name, output.cluster_count, multi_member_clusters, output.edge_count
+    );
+    if output.reused_cluster_ids > 0 {
+        info!("{} unchanged {} clusters kept their original id.", output.reused_cluster_ids, record_type);
+    }
+    if output.shared_status_changes > 0 {
+        info!("{} {} edges took a shared reviewer's decision.", output.shared_status_changes, record_type);
+    }
+}
+
+/// The answer to a selection prompt, or `None` when the read was interrupted (Ctrl-C),
+/// so the run can stop cleanly instead of failing with an I/O error.
+fn prompt_answer<T>(answer: dialoguer::Result<T>) -> Result<Option<T>> {
+    match answer {
+        Ok(value) => Ok(Some(value)),
+        Err(dialoguer::Error::IO(e)) if e.kind() == std::io::ErrorKind::Interrupted => {
+            // The prompt hides the cursor and does not get to show it again
+            let _ = dialoguer::console::Term::stderr().show_cursor();
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Runs the interactive selection process for team, user, and opinion(s).
+/// More than one opinion is returned when `--opinion` is repeated or `--compare` is used.
+/// Returns `Ok(None)` when a prompt is interrupted. Without a terminal it fails before
+/// the first prompt, listing the teams and the options for unattended runs.
+async fn run_interactive_selection(pool: &db_connect::PgPool, cli: &Cli) -> Result<Option<(TeamInfo, UserInfo, Vec<OpinionInfo>)>> {
     let theme = ColorfulTheme::default();
     
     // Step 1: Team Selection
-    println!("\n🏢 Select a team:");
     let teams = team_utils::get_all_teams(pool).await?;
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(no_terminal_message(&teams)));
+    }
+    println!("\n🏢 Select a team:");
     
     if teams.is_empty() {
         return Err(anyhow::anyhow!("No teams found in the database"));
@@ -103,46 +1133,75 @@ async fn run_interactive_selection(pool: &db_connect::PgPool) -> Result<(TeamInf
         .map(|t| format!("{} ({})", t.display_name, t.name))
         .collect();
     
-    let team_selection = Select::with_theme(&theme)
+    let Some(team_selection) = prompt_answer(Select::with_theme(&theme)
         .with_prompt("Choose a team")
         .default(0)
         .items(&team_options)
-        .interact()?;
+        .interact())? else {
+        return Ok(None);
+    };
     
     let selected_team = teams[team_selection].clone();
     println!("✅ Selected team: {}", selected_team.display_name);
     
     // Step 2: User Selection
     println!("\n👤 Select a user:");
-    let users = team_utils::get_users_for_team(pool, &selected_team.id).await?;
+    // Fetch everyone so the number of hidden prefix-less users can be reported
+    let users = team_utils::get_users_for_team(pool, &selected_team.id, false).await?;
     
     if users.is_empty() {
         return Err(anyhow::anyhow!("No users found for team: {}", selected_team.display_name));
     }
+
+    // Users without an opinion prefix have no opinion tables and cannot be exported,
+    // unless --prefix-override names the tables instead
+    let (users, hidden_users) = if cli.prefix_override.is_some() {
+        (users, 0)
+    } else {
+        team_utils::filter_exportable_users(users)
+    };
+    if hidden_users > 0 {
+        println!("ℹ️  {} user(s) without an opinion prefix hidden (cannot be exported)", hidden_users);
+    }
+    if users.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No users with an opinion prefix found for team: {}", selected_team.display_name
+        ));
+    }
     
     let user_options: Vec<String> = users.iter()
         .map(|u| {
-            let prefix = u.user_opinion_prefix.as_deref().unwrap_or("no prefix");
+            let prefix = u.user_opinion_prefix.as_deref().unwrap_or_default();
             format!("{} ({})", u.username, prefix)
         })
         .collect();
     
-    let user_selection = Select::with_theme(&theme)
+    let Some(user_selection) = prompt_answer(Select::with_theme(&theme)
         .with_prompt("Choose a user")
         .default(0)
         .items(&user_options)
-        .interact()?;
+        .interact())? else {
+        return Ok(None);
+    };
     
     let selected_user = users[user_selection].clone();
     println!("✅ Selected user: {}", selected_user.username);
     
     // Step 3: Opinion Selection
-    println!("\n💭 Select an opinion:");
     let opinions = team_utils::get_opinions_for_user(pool, &selected_user.id).await?;
     
     if opinions.is_empty() {
         return Err(anyhow::anyhow!("No opinions found for user: {}", selected_user.username));
     }
+
+    // Opinions named on the command line skip the prompt entirely
+    if !cli.opinion.is_empty() {
+        let selected_opinions = resolve_opinions_by_name(&opinions, &cli.opinion)?;
+        for opinion in &selected_opinions {
+            println!("✅ Selected opinion: {} (owner: {})", opinion.name, opinion.owner_username);
+        }
+        return Ok(Some((selected_team, selected_user, selected_opinions)));
+    }
     
     let opinion_options: Vec<String> = opinions.iter()
         .map(|o| {
@@ -153,15 +1212,243 @@ async fn run_interactive_selection(pool: &db_connect::PgPool) -> Result<(TeamInf
             }
         })
         .collect();
+
+    let opinion_selections = if cli.compare {
+        println!("\n💭 Select the opinions to compare:");
+        let Some(selections) = prompt_answer(MultiSelect::with_theme(&theme)
+            .with_prompt("Choose opinions (space to toggle, enter to confirm)")
+            .items(&opinion_options)
+            .interact())? else {
+            return Ok(None);
+        };
+        if selections.is_empty() {
+            return Err(anyhow::anyhow!("No opinions selected for comparison"));
+        }
+        selections
+    } else {
+        println!("\n💭 Select an opinion:");
+        let Some(selection) = prompt_answer(Select::with_theme(&theme)
+            .with_prompt("Choose an opinion")
+            .default(0)
+            .items(&opinion_options)
+            .interact())? else {
+            return Ok(None);
+        };
+        vec![selection]
+    };
     
-    let opinion_selection = Select::with_theme(&theme)
-        .with_prompt("Choose an opinion")
-        .default(0)
-        .items(&opinion_options)
-        .interact()?;
-    
-    let selected_opinion = opinions[opinion_selection].clone();
-    println!("✅ Selected opinion: {} (owner: {})", selected_opinion.name, selected_opinion.owner_username);
+    let selected_opinions: Vec<OpinionInfo> = opinion_selections.into_iter()
+        .map(|i| opinions[i].clone())
+        .collect();
+    for opinion in &selected_opinions {
+        println!("✅ Selected opinion: {} (owner: {})", opinion.name, opinion.owner_username);
+    }
     
-    Ok((selected_team, selected_user, selected_opinion))
-}
\ No newline at end of file
+    Ok(Some((selected_team, selected_user, selected_opinions)))
+}
+
+/// Picks the opinions named on the command line, in the order given, ignoring duplicates.
+fn resolve_opinions_by_name(opinions: &[OpinionInfo], names: &[String]) -> Result<Vec<OpinionInfo>> {
+    let mut selected: Vec<OpinionInfo> = Vec::new();
+    for name in names {
+        if selected.iter().any(|o| &o.name == name) {
+            continue;
+        }
+        let opinion = opinions.iter().find(|o| &o.name == name).ok_or_else(|| {
+            let available: Vec<&str> = opinions.iter().map(|o| o.name.as_str()).collect();
+            anyhow::anyhow!("Opinion '{}' not found for this user. Available opinions: {}", name, available.join(", "))
+        })?;
+        selected.push(opinion.clone());
+    }
+    Ok(selected)
+}
+
+/// Checks whether export tables with the fixed `--timestamp-suffix` already exist and,
+/// if so, asks before they are replaced. `--replace` skips the question; without a
+/// terminal (or with `--yes`) it is required instead. Returns `Ok(false)` if the user declines.
+async fn confirm_replace_existing(
+    pool: &db_connect::PgPool,
+    opinions: &[OpinionInfo],
+    user_prefix: &str,
+    timestamp_suffix: &str,
+    cli: &Cli,
+) -> Result<bool> {
+    let client = sql_guard::read_only_client(pool, "existing export table check").await?;
+    let mut existing = Vec::new();
+    for (index, opinion) in opinions.iter().enumerate() {
+        // Comparisons give each opinion its own suffix, as in the export itself
+        let opinion_suffix = if opinions.len() == 1 {
+            timestamp_suffix.to_string()
+        } else {
+            format!("{}_{}", timestamp_suffix, index + 1)
+        };
+        existing.extend(export_schema::existing_export_tables(
+            &client, user_prefix, &opinion.name, &opinion_suffix, cli.record_scope(), cli.export_to_table,
+        ).await?);
+    }
+    drop(client);
+
+    if existing.is_empty() {
+        return Ok(true);
+    }
+    if cli.replace {
+        warn!("Replacing {} existing export tables with suffix '{}' (--replace).", existing.len(), timestamp_suffix);
+        return Ok(true);
+    }
+
+    println!("\n⚠️  Export tables with suffix '{}' already exist and would be replaced:", timestamp_suffix);
+    for table in &existing {
+        println!("   {}", table);
+    }
+    if cli.yes || !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Export tables with suffix '{}' already exist; pass --replace to overwrite them", timestamp_suffix
+        ));
+    }
+
+    let replace = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Replace them?")
+        .default(false)
+        .interact()?;
+    Ok(replace)
+}
+
+/// Prints a summary of the selected team/user/opinion with quick row counts and asks
+/// the user to confirm. Returns `Ok(false)` if the user declines.
+/// When stdin is not a terminal the prompt cannot be shown, so `--yes` is required.
+async fn confirm_export(
+    pool: &db_connect::PgPool,
+    team: &TeamInfo,
+    user: &UserInfo,
+    opinions: &[OpinionInfo],
+    user_prefix: &str,
+    filter_options: DatasetFilterOptions,
+    assume_yes: bool,
+) -> Result<bool> {
+    let client = sql_guard::read_only_client(pool, "export preview counts").await?;
+
+    let mut edge_counts = Vec::new();
+    for opinion in opinions {
+        let entity_edges = count_or_unknown(&client, data_fetch::count_edge_records(&client, user_prefix, &opinion.name, "entity")).await;
+        let service_edges = count_or_unknown(&client, data_fetch::count_edge_records(&client, user_prefix, &opinion.name, "service")).await;
+        edge_counts.push((opinion, entity_edges, service_edges));
+    }
+    let entities = count_or_unknown(&client, team_utils::count_whitelisted_records(&client, "entity", &team.whitelisted_datasets, filter_options)).await;
+    let services = count_or_unknown(&client, team_utils::count_whitelisted_records(&client, "service", &team.whitelisted_datasets, filter_options)).await;
+    drop(client);
+
+    println!("\n📋 Export summary:");
+    println!("   Team:                  {} ({})", team.display_name, team.name);
+    println!("   User:                  {} ({})", user.username, user_prefix);
+    println!("   Whitelisted datasets:  {}", team.whitelisted_datasets.join(", "));
+    for (opinion, entity_edges, service_edges) in &edge_counts {
+        println!("   Opinion:               {} (owner: {})", opinion.name, opinion.owner_username);
+        println!("     Entity edges:        {}", entity_edges);
+        println!("     Service edges:       {}", service_edges);
+    }
+    println!("   Whitelisted entities:  {}", entities);
+    println!("   Whitelisted services:  {}", services);
+
+    if assume_yes {
+        return Ok(true);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Cannot ask for confirmation because stdin is not a terminal; pass --yes to proceed without prompting"
+        ));
+    }
+
+    let proceed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Proceed?")
+        .default(false)
+        .interact()?;
+    Ok(proceed)
+}
+
+/// Counts each opinion's reviewed and pending edges and, when none of them has anything
+/// reviewed in the exported record types, asks before continuing. Without a terminal
+/// (or with `--yes`) `--allow-unreviewed` is required instead. Returns `Ok(None)` if the
+/// user declines, otherwise the counts per opinion (`None` where they could not be read)
+/// so the Progress Overview can reuse them.
+async fn check_reviewed_edges(
+    pool: &db_connect::PgPool,
+    team: &TeamInfo,
+    opinions: &[OpinionInfo],
+    user_prefix: &str,
+    filter_options: DatasetFilterOptions,
+    cli: &Cli,
+) -> Result<Option<Vec<Option<dashboard::OpinionReviewCounts>>>> {
+    let client = sql_guard::read_only_client(pool, "review precheck").await?;
+    let scope = cli.record_scope();
+
+    let mut all_counts = Vec::new();
+    let mut unreviewed = Vec::new();
+    for opinion in opinions {
+        let counts = match dashboard::get_review_counts(&client, user_prefix, &opinion.name, team, filter_options).await {
+            Ok(counts) => counts,
+            Err(e) => {
+                warn!("Could not check review counts for opinion '{}'; continuing without the check: {:#}", opinion.name, e);
+                all_counts.push(None);
+                continue;
+            }
+        };
+        let in_scope = [("entity", &counts.entity), ("service", &counts.service)]
+            .into_iter()
+            .filter(|(record_type, _)| scope.includes(record_type))
+            .map(|(_, stats)| stats);
+        let precheck = ReviewPrecheck::evaluate(in_scope);
+        info!("{}", precheck.describe(&opinion.name));
+        if precheck.needs_confirmation() {
+            unreviewed.push(precheck.describe(&opinion.name));
+        }
+        all_counts.push(Some(counts));
+    }
+    drop(client);
+
+    // Only stop when every selected opinion is unreviewed; a comparison against one is still useful
+    if unreviewed.len() < opinions.len() {
+        return Ok(Some(all_counts));
+    }
+
+    println!();
+    for message in &unreviewed {
+        println!("⚠️  {}", message);
+    }
+    if cli.allow_unreviewed {
+        return Ok(Some(all_counts));
+    }
+    if cli.yes || !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Nothing has been reviewed in the selected opinion(s); pass --allow-unreviewed to export anyway"
+        ));
+    }
+
+    let proceed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Export anyway?")
+        .default(false)
+        .interact()?;
+    Ok(proceed.then_some(all_counts))
+}
+
+/// Runs a preview count with a short timeout, cancelling the server-side query and
+/// returning "unknown" instead of blocking if it is slow or fails.
+async fn count_or_unknown<F>(client: &Client, count: F) -> String
+where
+    F: Future<Output = Result<i64>>,
+{
+    match tokio::time::timeout(PREVIEW_COUNT_TIMEOUT, count).await {
+        Ok(Ok(n)) => n.to_string(),
+        Ok(Err(e)) => {
+            warn!("Preview count failed: {:#}", e);
+            "unknown".to_string()
+        }
+        Err(_) => {
+            warn!("Preview count timed out after {:?}", PREVIEW_COUNT_TIMEOUT);
+            if let Err(e) = client.cancel_token().cancel_query(NoTls).await {
+                warn!("Failed to cancel timed-out preview count: {}", e);
+            }
+            "unknown".to_string()
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use serde_json::Value; // For the 'details' jsonb column
+use std::collections::HashSet;
 
 // Raw data structs (examples, adjust as needed based on actual queries)
 #[derive(Debug, Clone)]
@@ -11,6 +13,9 @@ pub struct RawEdgeVisualization {
     pub service_id_2: Option<String>, // For service edges
     pub confirmed_status: Option<String>,
     pub details: Option<Value>,
+    pub updated_at: Option<NaiveDateTime>, // None when the edge table has no updated_at column
+    pub reviewed_at: Option<NaiveDateTime>, // None when the edge table has no reviewed_at column
+    pub reviewed_by: Option<String>, // None when the edge table has no reviewed_by column
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,19 +26,90 @@ pub struct EntityEdgeDetails {
     pub calculated_edge_weight: f64,
 }
 
+/// An edge whose endpoint ids no longer exist in the public source table.
+/// These are excluded from re-clustering and reported for cleanup.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedEdge {
+    pub record_type: String, // "entity" or "service"
+    pub edge_id: String,
+    pub id_1: String,
+    pub id_2: String,
+    pub missing_ids: String, // Comma-separated ids not found in the source table
+    pub confirmed_status: Option<String>,
+}
+
+/// How often one matching method contributed to the exported edges of a record type.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MethodSummary {
+    pub record_type: String, // "entity" or "service"
+    pub method_type: String,
+    pub edge_count: usize,
+    pub average_confidence: f64,
+}
+
+/// How the edges one matching method contributed to were decided, tallied over an
+/// opinion's edges during re-clustering, non-matches included.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MethodStats {
+    pub record_type: String, // "entity" or "service"
+    pub method_type: String,
+    pub edge_count: usize,
+    pub confirmed_match: usize,
+    pub confirmed_non_match: usize,
+    pub pending_review: usize, // NULL and unknown statuses included, as re-clustering treats them
+    pub mean_edge_weight: f64,
+}
+
+/// How an opinion decided the edges between records of two datasets, tallied during
+/// re-clustering. `dataset_1` <= `dataset_2`, and both are the same for edges within
+/// one dataset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetPairCount {
+    pub record_type: String, // "entity" or "service"
+    pub dataset_1: String,
+    pub dataset_2: String,
+    pub confirmed_match: usize,
+    pub confirmed_non_match: usize,
+    pub pending_review: usize, // NULL and unknown statuses included, as re-clustering treats them
+}
+
+impl DatasetPairCount {
+    pub fn edge_count(&self) -> usize {
+        self.confirmed_match + self.confirmed_non_match + self.pending_review
+    }
+}
+
 // Final export row structs
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct OrganizationExportRow {
     pub contributor: Option<String>,
     pub contributor_id: Option<String>,
     pub entity_id: String,
     pub name: Option<String>,
+    pub alternate_names: Option<String>, // DBA / aka names, comma-separated; None without any
     pub cluster_confirmed_status: String,
     pub cluster: Option<String>,
     pub has_duplicates: bool,
+    pub cluster_representative_name: Option<String>, // Longest member name in the cluster
+    pub canonical_record: bool, // Set by canonical::mark_canonical_records after fetch
+    pub last_reviewed: Option<NaiveDateTime>, // Latest review of any edge in the cluster
+    pub reviewed_by: Option<String>, // Distinct reviewers of the cluster's edges, comma-separated
+    pub min_edge_confidence: Option<f64>, // Lowest total_confidence among the cluster's edges
+    pub low_confidence: bool, // min_edge_confidence below --low-confidence-threshold
+    pub reviewed_edge_fraction: Option<f64>, // Share of the cluster's edges with a review decision; None without edges
+    pub mixed_status: bool, // Cluster has confirmed-match edges alongside pending or non-match ones
+    pub services: Option<OrganizationServices>, // Set by data_fetch::apply_service_summaries; None when services are not exported
 }
 
-#[derive(Debug, Serialize)]
+/// How many of an organization's services have duplicates, for the Organizations sheet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OrganizationServices {
+    pub service_count: usize,
+    pub services_with_duplicates: usize,
+    pub worst_status: Option<String>, // Most urgent cluster_confirmed_status among the services
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct ServiceExportRow {
     pub contributor: Option<String>,
     pub contributor_id: Option<String>,
@@ -41,9 +117,148 @@ pub struct ServiceExportRow {
     pub organization_name: Option<String>,
     pub service_name: Option<String>,
     pub location_name: Option<String>,
-    pub full_address: Option<String>,
+    pub full_address: Option<String>, // Assembled by AddressParts::full_address
+    pub address_missing_parts: Option<usize>, // Expected address components that are missing; None without an address
+    pub latitude: Option<f64>, // From the location chosen for full_address
+    pub longitude: Option<f64>,
     pub cluster_confirmed_status: String,
-    pub taxonomy_terms: Option<String>, // Comma-separated string
+    pub taxonomy_terms: Option<String>, // Comma-separated string, cut short by --max-taxonomy-terms
+    pub taxonomy_count: usize, // Distinct taxonomy terms of the service, however many are shown
+    pub taxonomies: Vec<TaxonomyExportRow>, // The same terms one per row, for the "Service Taxonomies" sheet
     pub cluster: Option<String>,
     pub has_duplicates: bool,
-}
\ No newline at end of file
+    pub canonical_record: bool, // Set by canonical::mark_canonical_records after fetch
+    pub last_reviewed: Option<NaiveDateTime>, // Latest review of any edge in the cluster
+    pub reviewed_by: Option<String>, // Distinct reviewers of the cluster's edges, comma-separated
+    pub min_edge_confidence: Option<f64>, // Lowest total_confidence among the cluster's edges
+    pub low_confidence: bool, // min_edge_confidence below --low-confidence-threshold
+    pub reviewed_edge_fraction: Option<f64>, // Share of the cluster's edges with a review decision; None without edges
+    pub mixed_status: bool, // Cluster has confirmed-match edges alongside pending or non-match ones
+}
+
+/// One taxonomy term of a service, a row of the optional "Service Taxonomies" sheet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaxonomyExportRow {
+    pub service_id: String,
+    pub taxonomy_term: String,
+    pub taxonomy_category: Option<String>, // The taxonomy the term belongs to
+    pub taxonomy_id: String,
+}
+
+/// The export rows produced for a single opinion, used when several opinions
+/// are written into one comparison workbook.
+#[derive(Debug)]
+pub struct OpinionExport {
+    pub opinion_name: String,
+    pub org_data: Vec<OrganizationExportRow>,
+    pub svc_data: Vec<ServiceExportRow>,
+}
+
+/// One entity's cluster assignment under each compared opinion.
+#[derive(Debug, Serialize)]
+pub struct OpinionComparisonRow {
+    pub entity_id: String,
+    pub name: Option<String>,
+    pub contributor: Option<String>,
+    pub clusters: Vec<Option<String>>, // One entry per opinion, in comparison order
+    pub clusters_agree: bool,
+}
+
+/// One entity on the team consensus sheet: how each reviewer's opinion clustered it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConsensusRow {
+    pub entity_id: String,
+    pub name: Option<String>,
+    pub contributor: Option<String>,
+    pub placements: Vec<Option<String>>, // One entry per reviewer: cluster label and status, or None without data
+    pub reviewers_with_data: usize,
+    pub agreement: bool,
+}
+
+/// The kind of change detected between two exports of the same opinion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    ClusterChanged,
+    StatusChanged,
+}
+
+/// One organization or service that differs between an old and a new export.
+/// Fields for the side the record is missing from are `None`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeRecord {
+    pub record_id: String,
+    pub name: Option<String>,
+    pub contributor: Option<String>,
+    pub change: ChangeKind,
+    pub old_cluster: Option<String>,
+    pub new_cluster: Option<String>,
+    pub old_status: Option<String>,
+    pub new_status: Option<String>,
+}
+
+/// Which record types a run exports. Excluded types are not re-clustered, fetched, or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordScope {
+    #[default]
+    Both,
+    EntitiesOnly,
+    ServicesOnly,
+}
+
+impl RecordScope {
+    /// Whether the given record type ("entity" or "service") is part of this export.
+    pub fn includes(self, record_type: &str) -> bool {
+        match self {
+            RecordScope::Both => true,
+            RecordScope::EntitiesOnly => record_type == "entity",
+            RecordScope::ServicesOnly => record_type == "service",
+        }
+    }
+
+    /// Human-readable description for summaries and logs.
+    pub fn label(self) -> &'static str {
+        match self {
+            RecordScope::Both => "Entities and services",
+            RecordScope::EntitiesOnly => "Entities only",
+            RecordScope::ServicesOnly => "Services only",
+        }
+    }
+}
+
+/// Cluster and record ids a spot-check export is restricted to. A record id pulls in
+/// every member of its cluster. Empty means the whole team is exported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpotCheck {
+    pub cluster_ids: Vec<String>,
+    pub record_ids: Vec<String>,
+}
+
+impl SpotCheck {
+    pub fn is_empty(&self) -> bool {
+        self.cluster_ids.is_empty() && self.record_ids.is_empty()
+    }
+
+    /// The requested ids that are neither a cluster id nor a record id in `exported`.
+    pub fn missing_ids<'a>(&'a self, exported: &HashSet<&str>) -> Vec<&'a str> {
+        self.cluster_ids.iter()
+            .chain(&self.record_ids)
+            .map(String::as_str)
+            .filter(|id| !exported.contains(id))
+            .collect()
+    }
+}
+
+/// Which records an export fetches: a spot check's clusters, optionally capped by `--sample`
+/// and narrowed by `--since`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSelection {
+    pub spot_check: SpotCheck,
+    /// Only clusters with an edge reviewed on or after this date are exported
+    pub since: Option<NaiveDate>,
+    /// Only the first N records (by id) of each type and the first N edges are processed
+    pub sample: Option<usize>,
+    /// Only the first N taxonomy terms are joined into `taxonomy_terms`; `None` keeps all
+    pub max_taxonomy_terms: Option<usize>,
+}
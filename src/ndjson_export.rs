@@ -0,0 +1,45 @@
+// src/ndjson_export.rs
+// Writes export rows as newline-delimited JSON for `--format ndjson`: one object per
+// line, for datasets too large to open in Excel and for loading into warehouses.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The file format of a single-opinion export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ExportFormat {
+    /// One workbook with the data, progress, and method sheets
+    #[default]
+    Xlsx,
+    /// One newline-delimited JSON file per record type, without the other sheets
+    Ndjson,
+}
+
+/// Name of the ndjson file for `rows` ("organizations" or "services"):
+/// `{prefix}_{opinion}_{rows}_{ts}.ndjson`.
+pub fn ndjson_file_name(user_prefix: &str, opinion_name: &str, rows: &str, timestamp_suffix: &str) -> String {
+    format!("{}_{}_{}_{}.ndjson", user_prefix, opinion_name, rows, timestamp_suffix)
+}
+
+/// Writes each row as one line of compact JSON and returns how many were written.
+/// Rows are pulled one at a time, so only the current line is held in memory.
+pub fn write_ndjson<W: Write, R: Serialize>(mut writer: W, rows: impl IntoIterator<Item = R>) -> Result<usize> {
+    let mut written = 0;
+    for row in rows {
+        serde_json::to_writer(&mut writer, &row).context("Failed to serialize an export row")?;
+        writer.write_all(b"\n")?;
+        written += 1;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Writes `rows` to a new ndjson file at `path` through a buffered writer.
+pub fn write_ndjson_file<R: Serialize>(path: &Path, rows: impl IntoIterator<Item = R>) -> Result<usize> {
+    let file = File::create(path).context(format!("Failed to create {}", path.display()))?;
+    write_ndjson(BufWriter::new(file), rows).context(format!("Failed to write {}", path.display()))
+}
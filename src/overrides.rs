@@ -0,0 +1,201 @@
+// src/overrides.rs
+// Reviewer decisions read from an `--overrides` file (CSV or xlsx) and applied over the
+// stored `confirmed_status` while re-clustering. The opinion's edge tables are never
+// written; the overlay only changes the export.
+
+use anyhow::{Context, Result};
+use calamine::{open_workbook, Reader, Xlsx};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+use crate::confirmed_status::ConfirmedStatus;
+
+/// How many row problems the parse error lists before summarizing the rest.
+pub const MAX_REPORTED_PROBLEMS: usize = 10;
+
+/// Which edge an override applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OverrideKey {
+    /// The edge with this id
+    Edge(String),
+    /// Any edge between these two records, in either direction; stored in sorted order
+    Pair(String, String),
+}
+
+impl OverrideKey {
+    /// A pair key for two record ids, independent of their order.
+    pub fn pair(id_1: &str, id_2: &str) -> OverrideKey {
+        let (a, b) = (id_1.trim(), id_2.trim());
+        if a <= b {
+            OverrideKey::Pair(a.to_string(), b.to_string())
+        } else {
+            OverrideKey::Pair(b.to_string(), a.to_string())
+        }
+    }
+}
+
+impl fmt::Display for OverrideKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverrideKey::Edge(id) => write!(f, "edge {}", id),
+            OverrideKey::Pair(a, b) => write!(f, "pair {} / {}", a, b),
+        }
+    }
+}
+
+/// One decision from the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusOverride {
+    /// The line (CSV) or row (xlsx) it was read from, counting the header as 1
+    pub line: usize,
+    pub key: OverrideKey,
+    pub decision: ConfirmedStatus,
+}
+
+/// The decisions of an overrides file, looked up by edge id or by record pair.
+#[derive(Debug, Clone, Default)]
+pub struct StatusOverrides {
+    overrides: Vec<StatusOverride>,
+    by_key: HashMap<OverrideKey, usize>,
+    /// Rows that repeated an earlier key with the same decision
+    pub duplicate_rows: usize,
+}
+
+impl StatusOverrides {
+    /// Reads `path` as xlsx (first sheet) when it ends in `.xlsx`, otherwise as CSV.
+    pub fn load(path: &Path) -> Result<StatusOverrides> {
+        let is_xlsx = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("xlsx"));
+        let rows = if is_xlsx {
+            xlsx_rows(path)?
+        } else {
+            let file = std::fs::File::open(path).context(format!("Failed to open overrides file {}", path.display()))?;
+            csv_rows(file)?
+        };
+        Self::from_rows(rows).context(format!("Invalid overrides file {}", path.display()))
+    }
+
+    /// Parses CSV with a header row; see `from_rows` for the columns.
+    pub fn parse_csv(reader: impl Read) -> Result<StatusOverrides> {
+        Self::from_rows(csv_rows(reader)?)
+    }
+
+    /// Builds the overrides from a header row followed by data rows. The header names
+    /// `decision` and either `edge_id` or both `id_1` and `id_2`, in any order and case;
+    /// other columns are ignored. A row with an `edge_id` is keyed by it, otherwise by
+    /// its unordered `id_1`/`id_2` pair. Blank rows are skipped, and a key repeated with
+    /// the same decision counts once. Every malformed row and every key given two
+    /// different decisions is reported in one error.
+    pub fn from_rows(rows: Vec<Vec<String>>) -> Result<StatusOverrides> {
+        let mut rows = rows.into_iter().enumerate().map(|(i, row)| (i + 1, row));
+        let Some((_, header)) = rows.next() else {
+            return Err(anyhow::anyhow!("The overrides file is empty; expected a header row"));
+        };
+        let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let (edge_id, id_1, id_2) = (column("edge_id"), column("id_1"), column("id_2"));
+        let Some(decision) = column("decision") else {
+            return Err(anyhow::anyhow!("The overrides header has no 'decision' column"));
+        };
+        if edge_id.is_none() && (id_1.is_none() || id_2.is_none()) {
+            return Err(anyhow::anyhow!("The overrides header needs an 'edge_id' column or both 'id_1' and 'id_2'"));
+        }
+
+        let mut parsed = StatusOverrides::default();
+        let mut problems = Vec::new();
+        for (line, row) in rows {
+            let cell = |index: Option<usize>| index.and_then(|i| row.get(i)).map(|v| v.trim()).unwrap_or("");
+            if row.iter().all(|v| v.trim().is_empty()) {
+                continue;
+            }
+            let key = match (cell(edge_id), cell(id_1), cell(id_2)) {
+                (edge, _, _) if !edge.is_empty() => OverrideKey::Edge(edge.to_string()),
+                ("", "", "") => {
+                    problems.push(format!("line {}: no edge_id or id_1/id_2", line));
+                    continue;
+                }
+                (_, a, b) if a.is_empty() || b.is_empty() => {
+                    problems.push(format!("line {}: a record pair needs both id_1 and id_2", line));
+                    continue;
+                }
+                (_, a, b) if a == b => {
+                    problems.push(format!("line {}: id_1 and id_2 are the same record '{}'", line, a));
+                    continue;
+                }
+                (_, a, b) => OverrideKey::pair(a, b),
+            };
+            let Some(status) = ConfirmedStatus::from_db(cell(Some(decision))) else {
+                problems.push(format!(
+                    "line {}: unknown decision '{}'; expected CONFIRMED_MATCH, CONFIRMED_NON_MATCH or PENDING_REVIEW",
+                    line, cell(Some(decision))
+                ));
+                continue;
+            };
+            match parsed.by_key.get(&key).map(|&i| &parsed.overrides[i]) {
+                Some(earlier) if earlier.decision == status => parsed.duplicate_rows += 1,
+                Some(earlier) => problems.push(format!(
+                    "line {}: {} is {} here but {} on line {}", line, key, status, earlier.decision, earlier.line
+                )),
+                None => {
+                    parsed.by_key.insert(key.clone(), parsed.overrides.len());
+                    parsed.overrides.push(StatusOverride { line, key, decision: status });
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            let mut message = format!("{} problem(s) in the overrides:\n  {}", problems.len(),
+                problems.iter().take(MAX_REPORTED_PROBLEMS).cloned().collect::<Vec<_>>().join("\n  "));
+            if problems.len() > MAX_REPORTED_PROBLEMS {
+                message.push_str(&format!("\n  ... and {} more", problems.len() - MAX_REPORTED_PROBLEMS));
+            }
+            return Err(anyhow::anyhow!(message));
+        }
+        Ok(parsed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.overrides.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusOverride> {
+        self.overrides.iter()
+    }
+
+    /// The decision for an edge, and its index for `unmatched`. An override by edge id
+    /// wins over one for the edge's record pair.
+    pub fn decision_for(&self, edge_id: &str, id_1: &str, id_2: &str) -> Option<(usize, ConfirmedStatus)> {
+        self.by_key.get(&OverrideKey::Edge(edge_id.trim().to_string()))
+            .or_else(|| self.by_key.get(&OverrideKey::pair(id_1, id_2)))
+            .map(|&i| (i, self.overrides[i].decision))
+    }
+
+    /// The overrides whose index is not in `applied`, in file order.
+    pub fn unmatched<'a>(&'a self, applied: &'a BTreeSet<usize>) -> impl Iterator<Item = &'a StatusOverride> {
+        self.overrides.iter().enumerate().filter(|(i, _)| !applied.contains(i)).map(|(_, o)| o)
+    }
+}
+
+/// Every CSV record as a row of fields, the header included.
+fn csv_rows(reader: impl Read) -> Result<Vec<Vec<String>>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader)
+        .records()
+        .map(|record| Ok(record.context("Failed to read the overrides CSV")?.iter().map(str::to_string).collect()))
+        .collect()
+}
+
+/// The rows of the workbook's first sheet, with every cell as text.
+fn xlsx_rows(path: &Path) -> Result<Vec<Vec<String>>> {
+    let mut workbook: Xlsx<_> = open_workbook(path).context(format!("Failed to open overrides workbook {}", path.display()))?;
+    let range = workbook.worksheet_range_at(0)
+        .ok_or_else(|| anyhow::anyhow!("Overrides workbook {} has no sheets", path.display()))?
+        .context(format!("Failed to read the first sheet of {}", path.display()))?;
+    Ok(range.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect())
+}
@@ -0,0 +1,96 @@
+// src/pool_metrics.rs
+
+use log::{debug, warn};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use crate::db_connect::{self, PgPool, POOL_MAX_SIZE};
+
+/// How often the monitor samples the pool when `POOL_MONITOR_INTERVAL_SECS` is not set.
+const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long in-use connections must stay above the threshold before a warning is logged.
+const SATURATION_GRACE: Duration = Duration::from_secs(60);
+
+/// A snapshot of connection pool usage, serializable for a stats endpoint.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PoolStats {
+    pub total: usize,
+    pub idle: usize,
+    pub in_use: usize,
+    pub max_size: u32,
+}
+
+impl PoolStats {
+    /// Takes a snapshot of the pool's current state.
+    pub fn snapshot(pool: &PgPool) -> Self {
+        let (total, idle, in_use) = db_connect::get_pool_status(pool);
+        PoolStats { total, idle, in_use, max_size: POOL_MAX_SIZE }
+    }
+}
+
+/// Settings for the background pool monitor, read from the environment.
+///
+/// - `POOL_MONITOR_INTERVAL_SECS`: sampling interval (default 15).
+/// - `POOL_SATURATION_THRESHOLD`: in-use connection count that counts as saturated
+///   (default 80% of the pool's max size).
+#[derive(Debug, Clone, Copy)]
+pub struct MonitorConfig {
+    pub interval: Duration,
+    pub saturation_threshold: usize,
+}
+
+impl MonitorConfig {
+    pub fn from_env() -> Self {
+        let interval = std::env::var("POOL_MONITOR_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_MONITOR_INTERVAL);
+        let saturation_threshold = std::env::var("POOL_SATURATION_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(POOL_MAX_SIZE as usize * 8 / 10);
+        MonitorConfig { interval, saturation_threshold }
+    }
+}
+
+/// Spawns a task that logs pool usage at debug level every interval and warns
+/// when in-use connections stay above the threshold for longer than a minute.
+/// The task runs until the returned handle is aborted or the runtime shuts down.
+pub fn spawn_pool_monitor(pool: PgPool, label: &'static str, config: MonitorConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(config.interval);
+        let mut saturated_since: Option<Instant> = None;
+        let mut warned = false;
+
+        loop {
+            ticker.tick().await;
+            let stats = PoolStats::snapshot(&pool);
+            debug!(
+                "{} pool: total={}, idle={}, in_use={} (max {})",
+                label, stats.total, stats.idle, stats.in_use, stats.max_size
+            );
+
+            if stats.in_use <= config.saturation_threshold {
+                if warned {
+                    debug!("{} pool is no longer saturated (in_use={}).", label, stats.in_use);
+                }
+                saturated_since = None;
+                warned = false;
+                continue;
+            }
+
+            let since = *saturated_since.get_or_insert_with(Instant::now);
+            if !warned && since.elapsed() >= SATURATION_GRACE {
+                warn!(
+                    "{} pool saturated: {} of {} connections in use for over {:?} (threshold {}). Connection timeouts are likely.",
+                    label, stats.in_use, stats.max_size, since.elapsed(), config.saturation_threshold
+                );
+                warned = true;
+            }
+        }
+    })
+}
@@ -0,0 +1,49 @@
+// src/precheck.rs
+
+use crate::dashboard::ReviewStats;
+
+/// Whether an opinion has anything reviewed, checked before the (slow) export starts.
+/// An opinion with no reviewed edges exports the raw pipeline clusters unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewPrecheck {
+    /// At least one edge is CONFIRMED_MATCH or CONFIRMED_NON_MATCH
+    Reviewed { reviewed: i64, pending: i64 },
+    /// Every edge is still PENDING_REVIEW
+    AllPending { pending: i64 },
+    /// There are no edges at all
+    NoEdges,
+}
+
+impl ReviewPrecheck {
+    /// Classifies the combined counts of the record types being exported.
+    pub fn evaluate<'a>(stats: impl IntoIterator<Item = &'a ReviewStats>) -> Self {
+        let (reviewed, pending) = stats.into_iter()
+            .fold((0, 0), |(reviewed, pending), s| {
+                (reviewed + s.confirmed_match + s.confirmed_non_match, pending + s.pending_review)
+            });
+        match (reviewed, pending) {
+            (0, 0) => ReviewPrecheck::NoEdges,
+            (0, pending) => ReviewPrecheck::AllPending { pending },
+            (reviewed, pending) => ReviewPrecheck::Reviewed { reviewed, pending },
+        }
+    }
+
+    /// True when exporting would only reproduce the pipeline clusters.
+    pub fn needs_confirmation(self) -> bool {
+        !matches!(self, ReviewPrecheck::Reviewed { .. })
+    }
+
+    /// One-line description of the counts for the given opinion.
+    pub fn describe(self, opinion_name: &str) -> String {
+        match self {
+            ReviewPrecheck::Reviewed { reviewed, pending } => {
+                format!("Opinion '{}' has {} reviewed and {} pending edges.", opinion_name, reviewed, pending)
+            }
+            ReviewPrecheck::AllPending { pending } => format!(
+                "Opinion '{}' has no reviewed edges (0 confirmed match, 0 confirmed non-match, {} pending); the export will match the pipeline clusters.",
+                opinion_name, pending
+            ),
+            ReviewPrecheck::NoEdges => format!("Opinion '{}' has no edges in the whitelisted datasets.", opinion_name),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// src/preflight.rs
+
+use anyhow::{Context, Result};
+use log::info;
+use std::collections::HashSet;
+use tokio_postgres::Client;
+
+use crate::table_names;
+use crate::team_utils::DISCONNECT_COLUMN_NAMES;
+
+/// Tables the export queries read, with the columns they rely on. Checked at startup
+/// so a drifted or wrong database fails with one clear message instead of deep inside
+/// a query. The per-user opinion tables in the team schema are not listed here.
+pub const REQUIRED_COLUMNS: &[(&str, &str, &[&str])] = &[
+    ("auth", "teams", &["id", "name", "display_name", "whitelisted_datasets", "is_active"]),
+    ("auth", "users", &["id", "username", "email", "user_opinion_prefix", "team_id", "is_active"]),
+    ("auth", "opinions", &["id", "name", "user_id", "other_users"]),
+];
+
+/// The HSDS source tables the export queries read, with the columns they rely on. They
+/// live in the configured source schema (`SOURCE_SCHEMA`, "public" by default).
+pub const SOURCE_TABLE_COLUMNS: &[(&str, &[&str])] = &[
+    ("entity", &["id", "name", "source_system", "source_id", "organization_id"]),
+    ("service", &["id", "name", "source_system", "contributor_id", "organization_id"]),
+    ("organization", &["id", "name"]),
+    ("service_at_location", &["id", "service_id", "location_id"]),
+    ("location", &["id", "name", "latitude", "longitude"]),
+    ("address", &[
+        "id", "location_id", "address_1", "address_2", "city", "state_province", "postal_code", "country",
+    ]),
+    ("service_taxonomy", &["service_id", "taxonomy_term_id"]),
+    ("taxonomy_term", &["id", "term", "description", "taxonomy"]),
+];
+
+/// `REQUIRED_COLUMNS` followed by `SOURCE_TABLE_COLUMNS` in `source_schema`.
+pub fn required_columns(source_schema: &str) -> Vec<(&str, &'static str, &'static [&'static str])> {
+    REQUIRED_COLUMNS.iter()
+        .copied()
+        .chain(SOURCE_TABLE_COLUMNS.iter().map(|(table, columns)| (source_schema, *table, *columns)))
+        .collect()
+}
+
+/// Columns named differently across deployments: each table needs one of the names.
+pub const ALTERNATIVE_COLUMNS: &[(&str, &str, &[&str])] = &[
+    ("auth", "opinions", &DISCONNECT_COLUMN_NAMES),
+];
+
+/// Verifies that every table and column in `REQUIRED_COLUMNS`, and one name of each
+/// `ALTERNATIVE_COLUMNS` entry, exists, and returns a single error listing everything
+/// that is missing.
+pub async fn preflight_schema_check(client: &Client) -> Result<()> {
+    info!("Checking the database schema for the tables and columns the export needs...");
+    let source_schema = table_names::source_schema();
+    let schemas: Vec<&str> = required_columns(source_schema).iter().map(|(schema, _, _)| *schema).collect();
+    let rows = client.query(
+        "SELECT table_schema, table_name, column_name FROM information_schema.columns WHERE table_schema = ANY($1)",
+        &[&schemas],
+    ).await.context("Failed to read information_schema.columns for the schema check")?;
+
+    let present: Vec<(String, String, String)> = rows.iter()
+        .map(|row| (row.get("table_schema"), row.get("table_name"), row.get("column_name")))
+        .collect();
+    let missing = missing_schema_objects(&present, source_schema);
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The database schema is missing objects this export needs; is it pointed at the right (and up-to-date) database?\n  - {}",
+            missing.join("\n  - ")
+        ));
+    }
+    info!("Database schema check passed.");
+    Ok(())
+}
+
+/// Lists the required tables and columns absent from `present` (schema, table, column
+/// triples), looking for the source tables in `source_schema`. A missing table is
+/// reported once rather than column by column.
+pub fn missing_schema_objects(present: &[(String, String, String)], source_schema: &str) -> Vec<String> {
+    let columns: HashSet<(&str, &str, &str)> = present.iter()
+        .map(|(schema, table, column)| (schema.as_str(), table.as_str(), column.as_str()))
+        .collect();
+    let tables: HashSet<(&str, &str)> = columns.iter().map(|(schema, table, _)| (*schema, *table)).collect();
+
+    let mut missing = Vec::new();
+    for (schema, table, required) in &required_columns(source_schema) {
+        if !tables.contains(&(*schema, *table)) {
+            missing.push(format!("table {}.{}", schema, table));
+            continue;
+        }
+        missing.extend(required.iter()
+            .filter(|column| !columns.contains(&(*schema, *table, **column)))
+            .map(|column| format!("column {}.{}.{}", schema, table, column)));
+    }
+    for (schema, table, names) in ALTERNATIVE_COLUMNS {
+        if tables.contains(&(*schema, *table)) && !names.iter().any(|column| columns.contains(&(*schema, *table, *column))) {
+            missing.push(format!("column {}.{}.{}", schema, table, names.join(" or ")));
+        }
+    }
+    missing
+}
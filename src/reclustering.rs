@@ -1,53 +1,316 @@
 // reclustering.rs
 use anyhow::{Context, Result};
-use chrono::Local;
-use std::collections::{HashMap, HashSet};
+use chrono::{Local, NaiveDateTime};
+use futures::TryStreamExt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::pin::pin;
+use std::str::FromStr;
+use std::time::Instant;
 use petgraph::graph::{NodeIndex, UnGraph};
 use log::{info, debug, warn};
 use uuid::Uuid;
 use serde_json::{json, Value};
+use tokio_postgres::Transaction;
 use tokio_postgres::types::ToSql;
 
-use crate::db_connect::PgPool;
-use crate::models::{RawEdgeVisualization, EntityEdgeDetails};
-use crate::team_utils::{TeamInfo, create_dataset_filter_clause};
+use crate::cluster_lineage::{apply_reused_ids, derived_from_note, load_original_clusters, match_original_clusters};
+use crate::confirmed_status::{ConfirmedStatus, StatusTally};
+use crate::copy_load::{copy_bool, copy_into_export_table, copy_timestamp, CopyRow, InsertMethod};
+use crate::export_schema::{self, ReviewColumns};
+use crate::data_fetch::create_sample_limit_clause;
+use crate::sql_guard::execute_export_write;
+use crate::models::{DatasetPairCount, RawEdgeVisualization, EntityEdgeDetails, MethodStats, OrphanedEdge};
+use crate::overrides::StatusOverrides;
+use crate::run_warnings::{RunWarnings, WarningCategory};
+use crate::shared_reviewers::{fetch_reviewer_statuses, merge_reviewer_statuses, ReviewerDecisions, SharedReview};
+use crate::table_names::{self, EXPORT_SCHEMA, TEAM_SCHEMA};
+use crate::team_utils::{TeamInfo, DatasetFilterOptions, create_dataset_filter_clause};
 
-const TEAM_SCHEMA: &str = "wa211_to_wric";
-const EXPORT_SCHEMA: &str = "wa211_to_wric_exports";
+/// What one re-clustering run produced.
+#[derive(Debug, Clone, Default)]
+pub struct ReclusterOutput {
+    /// Clusters written, singletons included
+    pub cluster_count: usize,
+    /// Records connected by at least one valid edge
+    pub node_count: usize,
+    /// Valid (CONFIRMED_MATCH or PENDING_REVIEW) edges kept
+    pub edge_count: usize,
+    /// Record id to its new cluster id, for every record in the whitelisted datasets
+    pub membership: HashMap<String, String>,
+    pub orphaned_edges: Vec<OrphanedEdge>,
+    /// Per-method decision counts over every fetched edge, see `tally_method_stats`
+    pub method_stats: Vec<MethodStats>,
+    /// Indexes of the `--overrides` entries that matched an edge of this record type
+    pub overrides_applied: BTreeSet<usize>,
+    /// Edge decisions per pair of datasets, see `tally_dataset_pairs`
+    pub dataset_pairs: Vec<DatasetPairCount>,
+    /// Edges whose status changed when the shared reviewers' decisions were merged in
+    pub shared_status_changes: usize,
+    /// Clusters whose members are unchanged, which kept their original pipeline id
+    pub reused_cluster_ids: usize,
+}
+
+/// Default `--cluster-name-template`, e.g. "ENTITYCluster-1a2b3c4d".
+pub const DEFAULT_CLUSTER_NAME_TEMPLATE: &str = "{TYPE}Cluster-{short_id}";
+
+/// Default `--cluster-description-template`.
+pub const DEFAULT_CLUSTER_DESCRIPTION_TEMPLATE: &str =
+    "Re-clustered {type} of {size} {type}s based on user opinion '{opinion}' (whitelisted datasets only).";
+
+/// Placeholders a cluster template may use.
+const TEMPLATE_PLACEHOLDERS: [&str; 6] = ["seq", "short_id", "size", "type", "TYPE", "opinion"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+/// A cluster name or description with placeholders, checked when parsed:
+/// `{seq}` (1-based, in representative id order), `{short_id}` (first 8 characters of
+/// the cluster id), `{size}`, `{type}` / `{TYPE}` ("entity" / "ENTITY") and `{opinion}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterTemplate {
+    source: String,
+    parts: Vec<TemplatePart>,
+}
+
+/// What a cluster template is rendered with.
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterTemplateValues<'a> {
+    pub seq: usize,
+    pub cluster_id: &'a str,
+    pub size: usize,
+    pub record_type: &'a str,
+    pub opinion: &'a str,
+}
+
+impl ClusterTemplate {
+    pub fn render(&self, values: &ClusterTemplateValues<'_>) -> String {
+        self.parts.iter()
+            .map(|part| match part {
+                TemplatePart::Text(text) => text.clone(),
+                TemplatePart::Placeholder("seq") => values.seq.to_string(),
+                TemplatePart::Placeholder("short_id") => values.cluster_id.chars().take(8).collect(),
+                TemplatePart::Placeholder("size") => values.size.to_string(),
+                TemplatePart::Placeholder("type") => values.record_type.to_string(),
+                TemplatePart::Placeholder("TYPE") => values.record_type.to_uppercase(),
+                TemplatePart::Placeholder(_) => values.opinion.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for ClusterTemplate {
+    type Err = String;
+
+    /// Parses a template, rejecting unknown placeholders and unclosed braces.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(TemplatePart::Text(rest[..open].to_string()));
+            }
+            let close = rest[open..].find('}')
+                .ok_or_else(|| format!("unclosed '{{' in template '{}'", s))?;
+            let name = &rest[open + 1..open + close];
+            let placeholder = TEMPLATE_PLACEHOLDERS.iter().find(|p| **p == name)
+                .ok_or_else(|| format!(
+                    "unknown placeholder '{{{}}}' in template '{}' (expected one of {{{}}})",
+                    name, s, TEMPLATE_PLACEHOLDERS.join("}, {")
+                ))?;
+            parts.push(TemplatePart::Placeholder(placeholder));
+            rest = &rest[open + close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+        Ok(ClusterTemplate { source: s.to_string(), parts })
+    }
+}
+
+impl fmt::Display for ClusterTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// How re-clustered clusters are named and described.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterNaming {
+    pub name: ClusterTemplate,
+    pub description: ClusterTemplate,
+}
+
+impl Default for ClusterNaming {
+    fn default() -> Self {
+        ClusterNaming {
+            name: DEFAULT_CLUSTER_NAME_TEMPLATE.parse().expect("default cluster name template is valid"),
+            description: DEFAULT_CLUSTER_DESCRIPTION_TEMPLATE.parse().expect("default cluster description template is valid"),
+        }
+    }
+}
+
+/// The clusters in `{seq}` order: sorted by their representative, the smallest member
+/// id, so a rerun over the same data numbers them the same way whatever the random
+/// cluster ids are.
+pub fn clusters_in_sequence(clusters: &HashMap<String, HashSet<String>>) -> Vec<(&String, &HashSet<String>)> {
+    let mut ordered: Vec<(&String, &HashSet<String>)> = clusters.iter().collect();
+    ordered.sort_by_key(|(_, members)| members.iter().min());
+    ordered
+}
+
+/// The team's dataset filter for re-clustering, and where to record the edges it
+/// has to skip or patch up.
+#[derive(Clone, Copy)]
+pub struct ReclusterOptions<'a> {
+    pub team_info: &'a TeamInfo,
+    pub filter_options: DatasetFilterOptions,
+    pub warnings: &'a RunWarnings,
+    /// Set by `--sample`: caps both the edges read and the records given isolated clusters
+    pub sample: Option<usize>,
+    /// Set by `--fast-insert`: load the export tables with COPY instead of UNNEST inserts.
+    /// Batches of `COPY_ROW_THRESHOLD` rows or more use COPY either way
+    pub fast_insert: bool,
+    /// Name and description templates for the new clusters
+    pub naming: &'a ClusterNaming,
+    /// Set by `--strict`: fail on an edge whose endpoints landed in different clusters
+    pub strict: bool,
+    /// Set by `--max-skipped-edges`: fail when too many edges have an empty endpoint
+    pub max_skipped_edges: Option<SkippedEdgeLimit>,
+    /// Set by `--overrides`: decisions applied over the stored `confirmed_status`
+    pub overrides: Option<&'a StatusOverrides>,
+    /// Set by `--include-shared-reviewers`: the other reviewers of a shared opinion,
+    /// whose decisions are merged into the edges before re-clustering
+    pub shared_review: Option<&'a SharedReview>,
+}
+
+/// The connected components of an edge graph: each new cluster's members, and the
+/// cluster id of every node.
+#[derive(Debug, Clone, Default)]
+pub struct GraphClusters {
+    pub clusters: HashMap<String, HashSet<String>>,
+    pub membership: HashMap<String, String>,
+}
+
+/// Finds the connected components of `graph`, giving each a new cluster id.
+pub fn connected_clusters<E>(graph: &UnGraph<String, E>) -> GraphClusters {
+    let mut visited = HashSet::new();
+    let mut found = GraphClusters::default();
+
+    for node_idx in graph.node_indices() {
+        let node_id = graph[node_idx].clone();
+        if !visited.contains(&node_id) {
+            let cluster_id = Uuid::new_v4().to_string();
+            let mut stack = vec![node_idx];
+            let mut current_cluster_nodes = HashSet::new();
+
+            // DFS to find all connected nodes
+            while let Some(current_node_idx) = stack.pop() {
+                let current_node_id = graph[current_node_idx].clone();
+                if visited.insert(current_node_id.clone()) {
+                    current_cluster_nodes.insert(current_node_id.clone());
+                    found.membership.insert(current_node_id.clone(), cluster_id.clone());
+
+                    for neighbor_node_idx in graph.neighbors(current_node_idx) {
+                        let neighbor_node_id = graph[neighbor_node_idx].clone();
+                        if !visited.contains(&neighbor_node_id) {
+                            stack.push(neighbor_node_idx);
+                        }
+                    }
+                }
+            }
+            found.clusters.insert(cluster_id, current_cluster_nodes);
+        }
+    }
+    found
+}
+
+/// Where the endpoints of a kept edge ended up after re-clustering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeCluster<'a> {
+    /// Both endpoints are in this cluster, or only one endpoint has a cluster
+    Same(&'a str),
+    /// The endpoints are in different clusters, which correct re-clustering never produces
+    Split(&'a str, &'a str),
+    /// Neither endpoint has a cluster
+    Missing,
+}
+
+/// Looks up the cluster of both endpoints of an edge in `membership`.
+pub fn edge_cluster<'a>(membership: &'a HashMap<String, String>, id_1: &str, id_2: &str) -> EdgeCluster<'a> {
+    match (membership.get(id_1), membership.get(id_2)) {
+        (Some(first), Some(second)) if first != second => EdgeCluster::Split(first, second),
+        (Some(cluster), _) | (None, Some(cluster)) => EdgeCluster::Same(cluster),
+        (None, None) => EdgeCluster::Missing,
+    }
+}
 
 /// Runs the re-clustering logic for either entities or services based on user opinions.
 /// This starts with the user's reviewed edges and creates new clusters by filtering out
 /// CONFIRMED_NON_MATCH edges and keeping CONFIRMED_MATCH and PENDING_REVIEW edges.
 /// Now includes filtering by team's whitelisted datasets and opinion-based table naming.
+///
+/// Edges referencing ids that no longer exist in the public source table are excluded
+/// from clustering and returned as orphaned edges so they can be reported. The cluster
+/// membership and counts are returned too, so callers need not re-query the tables.
+///
+/// All reads and writes go through `tx`, the caller's export transaction, so nothing
+/// is visible to other sessions until the whole export commits.
 pub async fn run_reclustering(
-    pool: &PgPool,
+    tx: &Transaction<'_>,
     user_prefix: &str,
     opinion_name: &str,
     timestamp_suffix: &str,
     entity_or_service: &str, // "entity" or "service"
-    team_info: &TeamInfo,
-) -> Result<()> {
+    options: &ReclusterOptions<'_>,
+) -> Result<ReclusterOutput> {
+    let ReclusterOptions { team_info, filter_options, warnings, sample, fast_insert, naming, strict, max_skipped_edges, overrides, shared_review } = *options;
     info!("Starting re-clustering for {} for user '{}' with opinion '{}' and dataset filtering...", 
           entity_or_service, user_prefix, opinion_name);
 
-    // Updated table naming to include opinion: {user_prefix}_{opinion_name}_{table_suffix}
-    let edge_table_name = format!("{}_{}_{}_edge_visualization", user_prefix, opinion_name, entity_or_service);
-    let export_edge_table = format!("{}_{}_{}_edge_visualization_export_{}", user_prefix, opinion_name, entity_or_service, timestamp_suffix);
-    let export_group_table = format!("{}_{}_{}_group_export_{}", user_prefix, opinion_name, entity_or_service, timestamp_suffix);
-    let export_cluster_table = format!("{}_{}_{}_group_cluster_export_{}", user_prefix, opinion_name, entity_or_service, timestamp_suffix);
+    let edge_table_name = table_names::edge_viz_source(user_prefix, opinion_name, entity_or_service);
+    let export_edge_table = table_names::edge_viz_export(user_prefix, opinion_name, entity_or_service, timestamp_suffix);
+    let export_group_table = table_names::group_export(user_prefix, opinion_name, entity_or_service, timestamp_suffix);
+    let export_cluster_table = table_names::cluster_export(user_prefix, opinion_name, entity_or_service, timestamp_suffix);
 
-    let mut client = pool.get().await.context("Failed to get DB client for reclustering")?;
+    // Review metadata is carried over only where the opinion's edge table records it;
+    // the export table is created LIKE the source, so both have the same columns
+    let review_columns = ReviewColumns::detect(tx, TEAM_SCHEMA, &edge_table_name).await?;
+    if !(review_columns.reviewed_at && review_columns.reviewed_by.is_some()) {
+        info!("{} has no reviewed_at/reviewed_by columns; review provenance falls back to updated_at or stays empty.",
+              table_names::qualified(TEAM_SCHEMA, &edge_table_name));
+    }
+    // The reviewer is carried as text and cast back to the column's own type on insert,
+    // since a reviewer_id column may be an integer or uuid
+    let reviewer_type = match review_columns.reviewed_by {
+        Some(column) => Some(export_schema::column_type(tx, TEAM_SCHEMA, &edge_table_name, column).await?),
+        None => None,
+    };
+    let optional_column = |present: bool, column: &str, sql_type: &str| if present {
+        format!("{}::{}", column, sql_type)
+    } else {
+        format!("NULL::{}", sql_type)
+    };
 
     // 1. Fetch edge data from user's opinion-specific table
     let query = format!(
         r#"
-        SELECT id, {0}_id_1, {0}_id_2, confirmed_status, details, edge_weight
+        SELECT id, {0}_id_1, {0}_id_2, confirmed_status, details, edge_weight,
+               {3} AS updated_at, {4} AS reviewed_at, {5} AS reviewed_by
         FROM "{1}"."{2}"
+        {6}
         "#,
-        entity_or_service, TEAM_SCHEMA, edge_table_name
+        entity_or_service, TEAM_SCHEMA, edge_table_name,
+        optional_column(review_columns.updated_at, "updated_at", "timestamp"),
+        optional_column(review_columns.reviewed_at, "reviewed_at", "timestamp"),
+        optional_column(review_columns.reviewed_by.is_some(), review_columns.reviewed_by.unwrap_or("reviewed_by"), "text"),
+        create_sample_limit_clause("id", sample)
     );
     debug!("Fetching edges with query: {}", query);
-    let rows = client.query(&query, &[]).await
+    let rows = tx.query(&query, &[]).await
         .context(format!("Failed to fetch {} edge data for reclustering with opinion '{}'", entity_or_service, opinion_name))?;
 
     let mut all_edges: Vec<RawEdgeVisualization> = Vec::new();
@@ -60,144 +323,150 @@ pub async fn run_reclustering(
             service_id_2: if entity_or_service == "service" { row.get(format!("{}_id_2", entity_or_service).as_str()) } else { None },
             confirmed_status: row.get("confirmed_status"),
             details: row.get("details"),
+            updated_at: row.get("updated_at"),
+            reviewed_at: row.get("reviewed_at"),
+            reviewed_by: row.get("reviewed_by"),
         });
     }
     info!("Fetched {} {} edges from user opinion '{}'.", all_edges.len(), entity_or_service, opinion_name);
 
-    // 2. Filter edges based on user opinions - keep only valid connections
-    let mut graph = UnGraph::<String, EntityEdgeDetails>::new_undirected();
-    let mut node_map: HashMap<String, NodeIndex> = HashMap::new();
-    let mut valid_edges_for_viz: Vec<(String, String, f64, Value, String)> = Vec::new();
-
-    for edge in &all_edges {
-        let id1 = if entity_or_service == "entity" { 
-            edge.entity_id_1.clone().unwrap_or_default() 
-        } else { 
-            edge.service_id_1.clone().unwrap_or_default() 
-        };
-        let id2 = if entity_or_service == "entity" { 
-            edge.entity_id_2.clone().unwrap_or_default() 
-        } else { 
-            edge.service_id_2.clone().unwrap_or_default() 
-        };
+    let MethodTally { stats: method_stats, malformed_entries } =
+        tally_method_stats(&all_edges, entity_or_service, overrides);
+    if malformed_entries > 0 {
+        warn!("Skipped {} malformed contributing_methods entries in {} edges (opinion '{}').",
+              malformed_entries, entity_or_service, opinion_name);
+        warnings.record(WarningCategory::MalformedMethod, format!(
+            "{} malformed contributing_methods entries in {} edges were left out of the method statistics",
+            malformed_entries, entity_or_service
+        ));
+    }
 
-        if id1.is_empty() || id2.is_empty() {
-            warn!("Skipping edge with empty ID: {:?} - {:?}", id1, id2);
-            continue;
+    // The Method Performance sheet counts the opinion's own decisions; everything after
+    // it sees the merged ones
+    let mut shared_status_changes = 0;
+    if let Some(shared) = shared_review {
+        let mut others = Vec::new();
+        for reviewer in &shared.reviewers {
+            match fetch_reviewer_statuses(tx, reviewer, opinion_name, entity_or_service).await? {
+                Some(statuses) => {
+                    info!("Read {} {} decisions of reviewer {}.", statuses.len(), entity_or_service, reviewer.username);
+                    others.push(ReviewerDecisions { username: reviewer.username.clone(), statuses });
+                }
+                None => {
+                    let table = table_names::edge_viz_source(&reviewer.prefix, opinion_name, entity_or_service);
+                    warn!("Reviewer {} has no {} table; their {} decisions are not merged.", reviewer.username, table, entity_or_service);
+                    warnings.record(WarningCategory::SharedReviewer, format!(
+                        "{} has no {} table for opinion '{}'", reviewer.username, table, opinion_name
+                    ));
+                }
+            }
         }
-
-        let status = edge.confirmed_status.as_deref().unwrap_or("PENDING_REVIEW");
-        
-        // Valid connections: CONFIRMED_MATCH or PENDING_REVIEW
-        // Invalid connections: CONFIRMED_NON_MATCH (breaks the connection)
-        let is_valid_connection = status == "PENDING_REVIEW" || status == "CONFIRMED_MATCH";
-
-        if is_valid_connection {
-            // Add nodes to graph if they don't exist
-            let node_idx_1 = *node_map.entry(id1.clone()).or_insert_with(|| graph.add_node(id1.clone()));
-            let node_idx_2 = *node_map.entry(id2.clone()).or_insert_with(|| graph.add_node(id2.clone()));
-
-            // Extract edge weight and details from the original edge
-            let edge_weight = edge.details.as_ref()
-                .and_then(|d| d.get("calculated_edge_weight"))
-                .and_then(|w| w.as_f64())
-                .unwrap_or(1.0); // Default weight if not available
-
-            let edge_details = edge.details.clone().unwrap_or_else(|| {
-                json!({
-                    "contributing_methods": [],
-                    "total_confidence": edge_weight,
-                    "pre_rl_total_confidence": edge_weight,
-                    "calculated_edge_weight": edge_weight
-                })
-            });
-
-            // Add edge to graph
-            graph.add_edge(node_idx_1, node_idx_2, EntityEdgeDetails {
-                contributing_methods: edge_details.get("contributing_methods")
-                    .and_then(|m| serde_json::from_value(m.clone()).ok())
-                    .unwrap_or_default(),
-                total_confidence: edge_details.get("total_confidence")
-                    .and_then(|c| c.as_f64())
-                    .unwrap_or(edge_weight),
-                pre_rl_total_confidence: edge_details.get("pre_rl_total_confidence")
-                    .and_then(|c| c.as_f64())
-                    .unwrap_or(edge_weight),
-                calculated_edge_weight: edge_weight,
-            });
-
-            valid_edges_for_viz.push((
-                id1.clone(),
-                id2.clone(),
-                edge_weight,
-                edge_details,
-                status.to_string(),
-            ));
+        let merged = merge_reviewer_statuses(&mut all_edges, entity_or_service, shared, &others);
+        if merged.conflicts() > 0 {
+            info!("{} {} edges had conflicting decisions; under {} {} were resolved as CONFIRMED_MATCH and {} as CONFIRMED_NON_MATCH.",
+                  merged.conflicts(), entity_or_service, shared.policy.label(), merged.conflicts_matched, merged.conflicts_split);
         }
+        shared_status_changes = merged.changed;
     }
 
-    info!("Built graph with {} nodes and {} valid edges after applying user opinions for opinion '{}'.", 
-          graph.node_count(), graph.edge_count(), opinion_name);
-
-    // 3. Get all original entities/services to ensure everything is included, filtered by whitelisted datasets
     let all_original_ids_table = if entity_or_service == "entity" { "entity" } else { "service" };
-    let (dataset_filter, filter_params) = create_dataset_filter_clause(
-        "t", "source_system", &team_info.whitelisted_datasets, 1
-    );
-    
-    let all_original_ids_query = format!(
-        r#"SELECT id FROM public.{} t WHERE {}"#,
-        all_original_ids_table, dataset_filter
+
+    // Find which edge endpoints still exist at all (regardless of dataset), so edges
+    // pointing at deleted records can be told apart from non-whitelisted ones
+    let endpoint_ids: Vec<String> = all_edges.iter()
+        .flat_map(|edge| edge_endpoint_ids(edge, entity_or_service))
+        .filter(|id| !id.is_empty())
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+    let existing_ids_query = format!(
+        "SELECT id FROM {} WHERE id = ANY($1)",
+        table_names::hsds_table(all_original_ids_table)
     );
-    
-    // Convert filter_params to Vec<&(dyn ToSql + Sync)>
-    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = filter_params
+    let existing_ids: HashSet<String> = tx.query(&existing_ids_query, &[&endpoint_ids]).await
+        .context(format!("Failed to check which {} edge endpoints exist", entity_or_service))?
         .iter()
-        .map(|s| s as &(dyn tokio_postgres::types::ToSql + Sync))
+        .map(|row| row.get("id"))
         .collect();
-    
-    let original_rows = client.query(&all_original_ids_query, &params).await
-        .context(format!("Failed to fetch all public {} IDs filtered by whitelisted datasets", entity_or_service))?;
 
-    info!("Found {} original {}s in whitelisted datasets for opinion '{}'", original_rows.len(), entity_or_service, opinion_name);
+    // 2. Filter edges based on user opinions - keep only valid connections
+    let ClassifiedEdges { valid: valid_edges_for_viz, orphaned: orphaned_edges, empty_ids, overrides_applied } =
+        classify_edges(&all_edges, entity_or_service, &existing_ids, overrides, warnings);
+    if let Some(overrides) = overrides {
+        info!("Applied {} of {} overrides to {} edges (opinion '{}').",
+              overrides_applied.len(), overrides.len(), entity_or_service, opinion_name);
+    }
+    check_skipped_edges(max_skipped_edges, &empty_ids, all_edges.len(), entity_or_service)
+        .context(format!("Re-clustering opinion '{}'", opinion_name))?;
 
-    // 4. Identify connected components (new clusters) and handle isolated nodes
-    let mut visited = HashSet::new();
-    let mut clusters: HashMap<String, HashSet<String>> = HashMap::new();
-    let mut node_to_cluster_id: HashMap<String, String> = HashMap::new();
+    let mut graph = UnGraph::<String, EntityEdgeDetails>::new_undirected();
+    let mut node_map: HashMap<String, NodeIndex> = HashMap::new();
+    for edge in &valid_edges_for_viz {
+        // Add nodes to graph if they don't exist
+        let node_idx_1 = *node_map.entry(edge.id_1.clone()).or_insert_with(|| graph.add_node(edge.id_1.clone()));
+        let node_idx_2 = *node_map.entry(edge.id_2.clone()).or_insert_with(|| graph.add_node(edge.id_2.clone()));
 
-    // First, handle connected components in the graph
-    for node_idx in graph.node_indices() {
-        let node_id = graph[node_idx].clone();
-        if !visited.contains(&node_id) {
-            let cluster_id = Uuid::new_v4().to_string();
-            let mut stack = vec![node_idx];
-            let mut current_cluster_nodes = HashSet::new();
+        // Add edge to graph
+        graph.add_edge(node_idx_1, node_idx_2, EntityEdgeDetails {
+            contributing_methods: edge.details.get("contributing_methods")
+                .and_then(|m| serde_json::from_value(m.clone()).ok())
+                .unwrap_or_default(),
+            total_confidence: edge.details.get("total_confidence")
+                .and_then(|c| c.as_f64())
+                .unwrap_or(edge.weight),
+            pre_rl_total_confidence: edge.details.get("pre_rl_total_confidence")
+                .and_then(|c| c.as_f64())
+                .unwrap_or(edge.weight),
+            calculated_edge_weight: edge.weight,
+        });
+    }
 
-            // DFS to find all connected nodes
-            while let Some(current_node_idx) = stack.pop() {
-                let current_node_id = graph[current_node_idx].clone();
-                if visited.insert(current_node_id.clone()) {
-                    current_cluster_nodes.insert(current_node_id.clone());
-                    node_to_cluster_id.insert(current_node_id.clone(), cluster_id.clone());
-                    
-                    for neighbor_node_idx in graph.neighbors(current_node_idx) {
-                        let neighbor_node_id = graph[neighbor_node_idx].clone();
-                        if !visited.contains(&neighbor_node_id) {
-                            stack.push(neighbor_node_idx);
-                        }
-                    }
-                }
-            }
-            clusters.insert(cluster_id, current_cluster_nodes);
-        }
+    info!("Built graph with {} nodes and {} valid edges after applying user opinions for opinion '{}'.", 
+          graph.node_count(), graph.edge_count(), opinion_name);
+
+    if !orphaned_edges.is_empty() {
+        warn!("Excluded {} orphaned {} edges referencing ids missing from {} (opinion '{}').",
+              orphaned_edges.len(), entity_or_service, table_names::hsds_table(all_original_ids_table), opinion_name);
     }
 
-    // Handle isolated nodes (entities/services not in any valid edge, but in whitelisted datasets)
-    for row in original_rows {
+    // 3. Identify connected components (new clusters) and handle isolated nodes
+    let GraphClusters { mut clusters, membership: mut node_to_cluster_id } = connected_clusters(&graph);
+
+    // 4. Handle isolated nodes (entities/services not in any valid edge, but in whitelisted datasets).
+    // The ids are streamed and checked against the graph one row at a time, so of the
+    // records only the isolated ones and the dataset of each are kept.
+    let (dataset_filter, filter_params) = create_dataset_filter_clause(
+        "t", "source_system", &team_info.whitelisted_datasets, 1, filter_options
+    );
+
+    // A sample takes the same first ids as the export fetch, so every fetched record has a cluster
+    let all_original_ids_query = format!(
+        r#"SELECT id, source_system FROM {} t WHERE {} {}"#,
+        table_names::hsds_table(all_original_ids_table), dataset_filter, create_sample_limit_clause("t.id", sample)
+    );
+
+    let started = Instant::now();
+    let memory_before = resident_memory();
+    let original_rows = tx.query_raw(&all_original_ids_query, filter_params.iter()).await
+        .context(format!("Failed to fetch all source {} IDs filtered by whitelisted datasets", entity_or_service))?;
+    let mut original_rows = pin!(original_rows);
+    let mut original_count = 0usize;
+    let mut isolated_count = 0usize;
+    // Only edge endpoints need a dataset for the pair tally, so the other ids are not kept
+    let edge_endpoints: HashSet<&str> = endpoint_ids.iter().map(String::as_str).collect();
+    let mut record_datasets = RecordDatasets::default();
+    while let Some(row) = original_rows.try_next().await
+        .context(format!("Failed to read public {} IDs filtered by whitelisted datasets", entity_or_service))?
+    {
+        original_count += 1;
         let original_id: String = row.get("id");
+        let source_system: Option<String> = row.get("source_system");
+        if edge_endpoints.contains(original_id.as_str()) {
+            record_datasets.insert(&original_id, source_system.as_deref());
+        }
         if !node_map.contains_key(&original_id) {
             // This entity/service has no valid edges, give it its own cluster
+            isolated_count += 1;
             let cluster_id = Uuid::new_v4().to_string();
             let mut single_node_cluster = HashSet::new();
             single_node_cluster.insert(original_id.clone());
@@ -206,15 +475,25 @@ pub async fn run_reclustering(
         }
     }
 
+    info!("Streamed {} original {}s in whitelisted datasets for opinion '{}' in {:.1?}; {} isolated ids kept (resident memory {} before, {} after).",
+          original_count, entity_or_service, opinion_name, started.elapsed(), isolated_count, memory_before, resident_memory());
+
+    let dataset_pairs = tally_dataset_pairs(&all_edges, entity_or_service, &record_datasets, overrides);
+
     info!("Created {} clusters from user opinion '{}' (filtered by whitelisted datasets).", clusters.len(), opinion_name);
 
-    // 5. Store re-clustered data in timestamped export tables
-    let tx = client.transaction().await.context("Failed to start transaction for storing re-clustered data")?;
+    // Clusters the review left unchanged keep their pipeline id and name
+    let original_clusters = load_original_clusters(tx, user_prefix, opinion_name, entity_or_service).await?;
+    let lineage = match_original_clusters(&clusters, &original_clusters);
+    apply_reused_ids(&lineage, &mut clusters, &mut node_to_cluster_id);
+    info!("{} of {} {} clusters are unchanged and keep their original id; {} were derived from split or merged clusters.",
+          lineage.reused.len(), clusters.len(), entity_or_service, lineage.derived_from.len());
 
+    // 5. Store re-clustered data in timestamped export tables
     // Clear existing data in export tables
-    tx.execute(&format!("DELETE FROM \"{}\".\"{}\"", EXPORT_SCHEMA, export_cluster_table), &[]).await?;
-    tx.execute(&format!("DELETE FROM \"{}\".\"{}\"", EXPORT_SCHEMA, export_group_table), &[]).await?;
-    tx.execute(&format!("DELETE FROM \"{}\".\"{}\"", EXPORT_SCHEMA, export_edge_table), &[]).await?;
+    execute_export_write(tx, &format!("DELETE FROM \"{}\".\"{}\"", EXPORT_SCHEMA, export_cluster_table), &[]).await?;
+    execute_export_write(tx, &format!("DELETE FROM \"{}\".\"{}\"", EXPORT_SCHEMA, export_group_table), &[]).await?;
+    execute_export_write(tx, &format!("DELETE FROM \"{}\".\"{}\"", EXPORT_SCHEMA, export_edge_table), &[]).await?;
 
     // Insert new cluster records
     let mut cluster_ids_batch: Vec<String> = Vec::new();
@@ -230,10 +509,20 @@ pub async fn run_reclustering(
         "service_group_count"
     };
 
-    for (cluster_id, member_ids) in &clusters {
-        let cluster_name = format!("{}Cluster-{}", entity_or_service.to_uppercase(), &cluster_id[..8]);
-        let description = format!("Re-clustered {} of {} {}s based on user opinion '{}' (whitelisted datasets only).", 
-                                entity_or_service, member_ids.len(), entity_or_service, opinion_name);
+    for (index, (cluster_id, member_ids)) in clusters_in_sequence(&clusters).into_iter().enumerate() {
+        let template_values = ClusterTemplateValues {
+            seq: index + 1,
+            cluster_id,
+            size: member_ids.len(),
+            record_type: entity_or_service,
+            opinion: opinion_name,
+        };
+        let cluster_name = original_clusters.names.get(cluster_id).cloned()
+            .unwrap_or_else(|| naming.name.render(&template_values));
+        let mut description = naming.description.render(&template_values);
+        if let Some(original_ids) = lineage.derived_from.get(cluster_id) {
+            description.push_str(&derived_from_note(original_ids));
+        }
         let entity_count = member_ids.len() as i32;
         let group_count = 0; // Will be updated when creating group records
         let average_coherence_score = 0.8; // Placeholder - could calculate based on edge weights
@@ -246,7 +535,31 @@ pub async fn run_reclustering(
         average_coherence_scores_batch.push(average_coherence_score);
     }
 
-    if !cluster_ids_batch.is_empty() {
+    let insert_method = InsertMethod::for_batch(fast_insert, cluster_ids_batch.len());
+    if !cluster_ids_batch.is_empty() && insert_method == InsertMethod::Copy {
+        let started = Instant::now();
+        let current_timestamp = copy_timestamp(Local::now().naive_utc());
+        let rows: Vec<CopyRow> = (0..cluster_ids_batch.len())
+            .map(|i| vec![
+                Some(cluster_ids_batch[i].clone()),
+                Some(cluster_names_batch[i].clone()),
+                Some(descriptions_batch[i].clone()),
+                Some(current_timestamp.clone()),
+                Some(current_timestamp.clone()),
+                Some(entity_counts_batch[i].to_string()),
+                Some(group_counts_batch[i].to_string()),
+                Some(average_coherence_scores_batch[i].to_string()),
+                Some(copy_bool(true)),
+            ])
+            .collect();
+        let count_column = format!("{}_count", entity_or_service);
+        let columns = ["id", "name", "description", "created_at", "updated_at", &count_column, group_count_column_name, "average_coherence_score", "was_reviewed"];
+        copy_into_export_table(tx, &export_cluster_table, &columns, &rows)
+            .await.context("Failed to copy cluster records")?;
+        info!("Inserted {} new {} clusters for opinion '{}' in {:.1?} ({}).",
+              cluster_ids_batch.len(), entity_or_service, opinion_name, started.elapsed(), insert_method);
+    } else if !cluster_ids_batch.is_empty() {
+        let started = Instant::now();
         let insert_cluster_batch_query = format!(
             r#"
             INSERT INTO "{}"."{}" (id, name, description, created_at, updated_at, {}_count, {}, average_coherence_score, was_reviewed)
@@ -260,7 +573,8 @@ pub async fn run_reclustering(
         let updated_at_batch = vec![current_timestamp; cluster_ids_batch.len()];
         let was_reviewed_batch = vec![true; cluster_ids_batch.len()];
 
-        tx.execute(
+        execute_export_write(
+            tx,
             &insert_cluster_batch_query,
             &[
                 &cluster_ids_batch as &(dyn ToSql + Sync),
@@ -274,7 +588,8 @@ pub async fn run_reclustering(
                 &was_reviewed_batch as &(dyn ToSql + Sync),
             ],
         ).await.context("Failed to batch insert cluster records")?;
-        info!("Inserted {} new {} clusters for opinion '{}'.", cluster_ids_batch.len(), entity_or_service, opinion_name);
+        info!("Inserted {} new {} clusters for opinion '{}' in {:.1?} ({}).",
+              cluster_ids_batch.len(), entity_or_service, opinion_name, started.elapsed(), insert_method);
     }
 
     // Create group records for all entities/services
@@ -309,7 +624,30 @@ pub async fn run_reclustering(
         }
     }
 
-    if !group_ids_batch.is_empty() {
+    let insert_method = InsertMethod::for_batch(fast_insert, group_ids_batch.len());
+    if !group_ids_batch.is_empty() && insert_method == InsertMethod::Copy {
+        let started = Instant::now();
+        let current_timestamp = copy_timestamp(Local::now().naive_utc());
+        let rows: Vec<CopyRow> = (0..group_ids_batch.len())
+            .map(|i| vec![
+                Some(group_ids_batch[i].clone()),
+                Some(group_id1s_batch[i].clone()),
+                Some(group_id2s_batch[i].clone()),
+                Some(group_cluster_ids_batch[i].clone()),
+                Some(group_method_types_batch[i].clone()),
+                Some(current_timestamp.clone()),
+                Some(current_timestamp.clone()),
+                Some("CONFIRMED".to_string()),
+            ])
+            .collect();
+        let (id_1_column, id_2_column) = (format!("{}_id_1", entity_or_service), format!("{}_id_2", entity_or_service));
+        let columns = ["id", &id_1_column, &id_2_column, "group_cluster_id", "method_type", "created_at", "updated_at", "confirmed_status"];
+        copy_into_export_table(tx, &export_group_table, &columns, &rows)
+            .await.context("Failed to copy group records")?;
+        info!("Inserted {} group records for opinion '{}' in {:.1?} ({}).",
+              group_ids_batch.len(), opinion_name, started.elapsed(), insert_method);
+    } else if !group_ids_batch.is_empty() {
+        let started = Instant::now();
         let insert_group_batch_query = format!(
             r#"
             INSERT INTO "{}"."{}" (id, {}_id_1, {}_id_2, group_cluster_id, method_type, created_at, updated_at, confirmed_status)
@@ -323,7 +661,8 @@ pub async fn run_reclustering(
         let updated_at_batch = vec![current_timestamp; group_ids_batch.len()];
         let confirmed_status_batch = vec!["CONFIRMED".to_string(); group_ids_batch.len()];
 
-        tx.execute(
+        execute_export_write(
+            tx,
             &insert_group_batch_query,
             &[
                 &group_ids_batch as &(dyn ToSql + Sync),
@@ -336,7 +675,8 @@ pub async fn run_reclustering(
                 &confirmed_status_batch as &(dyn ToSql + Sync),
             ],
         ).await.context("Failed to batch insert group records")?;
-        info!("Inserted {} group records for opinion '{}'.", group_ids_batch.len(), opinion_name);
+        info!("Inserted {} group records for opinion '{}' in {:.1?} ({}).",
+              group_ids_batch.len(), opinion_name, started.elapsed(), insert_method);
     }
 
     // Insert visualization edges for valid connections
@@ -347,6 +687,9 @@ pub async fn run_reclustering(
     let mut edge_weights_batch: Vec<f64> = Vec::new();
     let mut edge_details_batch: Vec<Value> = Vec::new();
     let mut edge_statuses_batch: Vec<String> = Vec::new();
+    let mut edge_updated_ats_batch: Vec<Option<NaiveDateTime>> = Vec::new();
+    let mut edge_reviewed_ats_batch: Vec<Option<NaiveDateTime>> = Vec::new();
+    let mut edge_reviewed_bys_batch: Vec<Option<String>> = Vec::new();
 
     let cluster_id_column_name = if entity_or_service == "entity" {
         "cluster_id"
@@ -354,27 +697,112 @@ pub async fn run_reclustering(
         "service_group_cluster_id"
     };
 
-    for (id1, id2, weight, details, status) in valid_edges_for_viz {
+    for edge in valid_edges_for_viz {
         let edge_id = Uuid::new_v4().to_string();
-        let cluster_id = node_to_cluster_id.get(&id1).or_else(|| node_to_cluster_id.get(&id2))
-            .ok_or_else(|| anyhow::anyhow!("Edge nodes not found in any cluster after reclustering for edge {} - {} (opinion: {})", id1, id2, opinion_name))?;
-        
+        let cluster_id = match edge_cluster(&node_to_cluster_id, &edge.id_1, &edge.id_2) {
+            EdgeCluster::Same(cluster_id) => cluster_id,
+            EdgeCluster::Split(first, second) => {
+                let message = format!(
+                    "{} edge {} - {} joins clusters {} and {} (opinion: {})",
+                    entity_or_service, edge.id_1, edge.id_2, first, second, opinion_name
+                );
+                if strict {
+                    return Err(anyhow::anyhow!("Re-clustering is inconsistent: {}", message));
+                }
+                warn!("Re-clustering is inconsistent: {}; the edge is assigned to the first cluster.", message);
+                warnings.record(WarningCategory::SplitEdge, message);
+                first
+            }
+            EdgeCluster::Missing => return Err(anyhow::anyhow!(
+                "Edge nodes not found in any cluster after reclustering for edge {} - {} (opinion: {})", edge.id_1, edge.id_2, opinion_name
+            )),
+        };
+
         edge_ids_batch.push(edge_id);
-        edge_cluster_ids_batch.push(cluster_id.clone());
-        edge_id1s_batch.push(id1);
-        edge_id2s_batch.push(id2);
-        edge_weights_batch.push(weight);
-        edge_details_batch.push(details);
-        edge_statuses_batch.push(status);
+        edge_cluster_ids_batch.push(cluster_id.to_string());
+        edge_id1s_batch.push(edge.id_1);
+        edge_id2s_batch.push(edge.id_2);
+        edge_weights_batch.push(edge.weight);
+        edge_details_batch.push(edge.details);
+        edge_statuses_batch.push(edge.status);
+        edge_updated_ats_batch.push(edge.updated_at);
+        edge_reviewed_ats_batch.push(edge.reviewed_at);
+        edge_reviewed_bys_batch.push(edge.reviewed_by);
     }
 
-    if !edge_ids_batch.is_empty() {
+    let insert_method = InsertMethod::for_batch(fast_insert, edge_ids_batch.len());
+    if !edge_ids_batch.is_empty() && insert_method == InsertMethod::Copy {
+        let started = Instant::now();
+        let pipeline_run_id = format!("user_export_pipeline_{}", opinion_name);
+        let current_timestamp = copy_timestamp(Local::now().naive_utc());
+        let rows: Vec<CopyRow> = (0..edge_ids_batch.len())
+            .map(|i| {
+                let mut row = vec![
+                    Some(edge_ids_batch[i].clone()),
+                    Some(edge_cluster_ids_batch[i].clone()),
+                    Some(edge_id1s_batch[i].clone()),
+                    Some(edge_id2s_batch[i].clone()),
+                    Some(edge_weights_batch[i].to_string()),
+                    Some(edge_details_batch[i].to_string()),
+                    Some(pipeline_run_id.clone()),
+                    Some(current_timestamp.clone()),
+                    Some(edge_statuses_batch[i].clone()),
+                    Some(copy_bool(true)),
+                ];
+                // The optional review columns, in the same order as the UNNEST path
+                if review_columns.updated_at {
+                    row.push(edge_updated_ats_batch[i].map(copy_timestamp));
+                }
+                if review_columns.reviewed_at {
+                    row.push(edge_reviewed_ats_batch[i].map(copy_timestamp));
+                }
+                if review_columns.reviewed_by.is_some() {
+                    row.push(edge_reviewed_bys_batch[i].clone());
+                }
+                row
+            })
+            .collect();
+        let (id_1_column, id_2_column) = (format!("{}_id_1", entity_or_service), format!("{}_id_2", entity_or_service));
+        let mut columns = vec![
+            "id", cluster_id_column_name, &id_1_column, &id_2_column, "edge_weight", "details",
+            "pipeline_run_id", "created_at", "confirmed_status", "was_reviewed",
+        ];
+        for (present, column) in [
+            (review_columns.updated_at, "updated_at"),
+            (review_columns.reviewed_at, "reviewed_at"),
+        ] {
+            if present {
+                columns.push(column);
+            }
+        }
+        columns.extend(review_columns.reviewed_by);
+        copy_into_export_table(tx, &export_edge_table, &columns, &rows)
+            .await.context("Failed to copy edge visualization records")?;
+        info!("Inserted {} visualization edges into export table for opinion '{}' in {:.1?} ({}).",
+              edge_ids_batch.len(), opinion_name, started.elapsed(), insert_method);
+    } else if !edge_ids_batch.is_empty() {
+        let started = Instant::now();
+        // The optional review columns follow $10, numbered in the order the table has them
+        let optional_columns = [
+            (review_columns.updated_at, "updated_at", "timestamp[]".to_string()),
+            (review_columns.reviewed_at, "reviewed_at", "timestamp[]".to_string()),
+            (
+                review_columns.reviewed_by.is_some(),
+                review_columns.reviewed_by.unwrap_or("reviewed_by"),
+                format!("text[]::{}[]", reviewer_type.as_deref().unwrap_or("text")),
+            ),
+        ];
+        let (mut extra_columns, mut extra_values) = (String::new(), String::new());
+        for (index, (_, column, sql_type)) in optional_columns.iter().filter(|(present, _, _)| *present).enumerate() {
+            extra_columns.push_str(&format!(", {}", column));
+            extra_values.push_str(&format!(", ${}::{}", 11 + index, sql_type));
+        }
         let insert_edge_viz_batch_query = format!(
             r#"
-            INSERT INTO "{0}"."{1}" (id, {2}, {3}_id_1, {3}_id_2, edge_weight, details, pipeline_run_id, created_at, confirmed_status, was_reviewed)
-            SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::float8[], $6::jsonb[], $7::text[], $8::timestamp[], $9::text[], $10::boolean[])
+            INSERT INTO "{0}"."{1}" (id, {2}, {3}_id_1, {3}_id_2, edge_weight, details, pipeline_run_id, created_at, confirmed_status, was_reviewed{4})
+            SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::float8[], $6::jsonb[], $7::text[], $8::timestamp[], $9::text[], $10::boolean[]{5})
             "#,
-            EXPORT_SCHEMA, export_edge_table, cluster_id_column_name, entity_or_service
+            EXPORT_SCHEMA, export_edge_table, cluster_id_column_name, entity_or_service, extra_columns, extra_values
         );
 
         let pipeline_run_id_batch = vec![format!("user_export_pipeline_{}", opinion_name); edge_ids_batch.len()];
@@ -382,27 +810,493 @@ pub async fn run_reclustering(
         let created_at_batch = vec![current_timestamp; edge_ids_batch.len()];
         let was_reviewed_batch = vec![true; edge_ids_batch.len()];
 
-        tx.execute(
-            &insert_edge_viz_batch_query,
-            &[
-                &edge_ids_batch as &(dyn ToSql + Sync),
-                &edge_cluster_ids_batch as &(dyn ToSql + Sync),
-                &edge_id1s_batch as &(dyn ToSql + Sync),
-                &edge_id2s_batch as &(dyn ToSql + Sync),
-                &edge_weights_batch as &(dyn ToSql + Sync),
-                &edge_details_batch as &(dyn ToSql + Sync),
-                &pipeline_run_id_batch as &(dyn ToSql + Sync),
-                &created_at_batch as &(dyn ToSql + Sync),
-                &edge_statuses_batch as &(dyn ToSql + Sync),
-                &was_reviewed_batch as &(dyn ToSql + Sync),
-            ],
-        ).await.context("Failed to batch insert edge visualization records")?;
-        info!("Inserted {} visualization edges into export table for opinion '{}'.", edge_ids_batch.len(), opinion_name);
-    }
+        let mut edge_params: Vec<&(dyn ToSql + Sync)> = vec![
+            &edge_ids_batch,
+            &edge_cluster_ids_batch,
+            &edge_id1s_batch,
+            &edge_id2s_batch,
+            &edge_weights_batch,
+            &edge_details_batch,
+            &pipeline_run_id_batch,
+            &created_at_batch,
+            &edge_statuses_batch,
+            &was_reviewed_batch,
+        ];
+        if review_columns.updated_at {
+            edge_params.push(&edge_updated_ats_batch);
+        }
+        if review_columns.reviewed_at {
+            edge_params.push(&edge_reviewed_ats_batch);
+        }
+        if review_columns.reviewed_by.is_some() {
+            edge_params.push(&edge_reviewed_bys_batch);
+        }
 
-    tx.commit().await.context("Failed to commit re-clustering transaction")?;
+        execute_export_write(tx, &insert_edge_viz_batch_query, &edge_params)
+            .await.context("Failed to batch insert edge visualization records")?;
+        info!("Inserted {} visualization edges into export table for opinion '{}' in {:.1?} ({}).",
+              edge_ids_batch.len(), opinion_name, started.elapsed(), insert_method);
+    }
 
     info!("Re-clustering for {} for user '{}' with opinion '{}' completed successfully. Created {} clusters (filtered by whitelisted datasets).", 
           entity_or_service, user_prefix, opinion_name, clusters.len());
-    Ok(())
-}
\ No newline at end of file
+    Ok(ReclusterOutput {
+        cluster_count: clusters.len(),
+        node_count: graph.node_count(),
+        edge_count: graph.edge_count(),
+        membership: node_to_cluster_id,
+        orphaned_edges,
+        method_stats,
+        overrides_applied,
+        dataset_pairs,
+        shared_status_changes,
+        reused_cluster_ids: lineage.reused.len(),
+    })
+}
+
+/// An edge kept by re-clustering (CONFIRMED_MATCH or PENDING_REVIEW), as written to
+/// the export edge table.
+#[derive(Debug, Clone)]
+pub struct ValidEdge {
+    pub id_1: String,
+    pub id_2: String,
+    pub weight: f64,
+    pub details: Value,
+    pub status: String,
+    pub updated_at: Option<NaiveDateTime>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub reviewed_by: Option<String>,
+}
+
+/// The result of `classify_edges`.
+#[derive(Debug, Clone, Default)]
+pub struct ClassifiedEdges {
+    pub valid: Vec<ValidEdge>,
+    pub orphaned: Vec<OrphanedEdge>,
+    pub empty_ids: EmptyIdCounts,
+    /// Indexes of the `--overrides` entries that matched an edge
+    pub overrides_applied: BTreeSet<usize>,
+}
+
+/// Edges skipped for an empty endpoint id, by which endpoint was empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmptyIdCounts {
+    pub id_1: usize,
+    pub id_2: usize,
+    pub both: usize,
+}
+
+impl EmptyIdCounts {
+    /// Counts an edge if either endpoint is empty, and says whether it was counted.
+    pub fn add(&mut self, id1: &str, id2: &str) -> bool {
+        match (id1.is_empty(), id2.is_empty()) {
+            (true, true) => self.both += 1,
+            (true, false) => self.id_1 += 1,
+            (false, true) => self.id_2 += 1,
+            (false, false) => return false,
+        }
+        true
+    }
+
+    pub fn total(&self) -> usize {
+        self.id_1 + self.id_2 + self.both
+    }
+
+    /// Each non-zero count with the endpoint it describes.
+    fn reasons(&self) -> impl Iterator<Item = (usize, &'static str)> {
+        [(self.id_1, "an empty id_1"), (self.id_2, "an empty id_2"), (self.both, "both ids empty")]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+    }
+}
+
+/// `--max-skipped-edges`: how many edges of one record type may be skipped for an
+/// empty endpoint before the export is abandoned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkippedEdgeLimit {
+    /// At most this many edges
+    Count(usize),
+    /// At most this percentage of the fetched edges
+    Percent(f64),
+}
+
+impl SkippedEdgeLimit {
+    /// Parses a count such as `500` or a percentage such as `2.5%`.
+    pub fn parse(s: &str) -> Result<SkippedEdgeLimit, String> {
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent: f64 = percent.trim().parse().map_err(|_| format!("invalid percentage '{}'", s))?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err("the skipped edge percentage must be between 0 and 100".to_string());
+            }
+            return Ok(SkippedEdgeLimit::Percent(percent));
+        }
+        s.parse().map(SkippedEdgeLimit::Count).map_err(|_| format!("invalid skipped edge limit '{}'; expected a count or a percentage such as 5%", s))
+    }
+
+    /// Whether `skipped` of `total` edges goes over the limit.
+    pub fn is_exceeded(&self, skipped: usize, total: usize) -> bool {
+        match *self {
+            SkippedEdgeLimit::Count(max) => skipped > max,
+            SkippedEdgeLimit::Percent(max) => total > 0 && skipped as f64 * 100.0 / total as f64 > max,
+        }
+    }
+}
+
+impl std::fmt::Display for SkippedEdgeLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkippedEdgeLimit::Count(max) => write!(f, "{}", max),
+            SkippedEdgeLimit::Percent(max) => write!(f, "{}%", max),
+        }
+    }
+}
+
+/// The process's resident set size from `/proc/self/status`, e.g. "182340 kB", for
+/// the log; "unknown" where that file does not exist.
+fn resident_memory() -> String {
+    std::fs::read_to_string("/proc/self/status").ok()
+        .and_then(|status| status.lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))
+            .map(|value| value.trim().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Fails when more of an opinion's `total` edges were skipped for an empty endpoint
+/// than `limit` allows: clusters built from the rest would look complete but miss
+/// most of the reviewer's decisions.
+pub fn check_skipped_edges(
+    limit: Option<SkippedEdgeLimit>,
+    empty_ids: &EmptyIdCounts,
+    total: usize,
+    entity_or_service: &str,
+) -> Result<()> {
+    match limit {
+        Some(limit) if limit.is_exceeded(empty_ids.total(), total) => Err(anyhow::anyhow!(
+            "{} of {} {} edges have an empty endpoint id (empty id_1: {}, empty id_2: {}, both: {}), over --max-skipped-edges {}; check the edge table",
+            empty_ids.total(), total, entity_or_service, empty_ids.id_1, empty_ids.id_2, empty_ids.both, limit
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Splits an opinion's edges into the valid connections to cluster on and the
+/// orphaned edges whose endpoints are not in `existing_ids`. CONFIRMED_NON_MATCH
+/// edges are dropped. Edges with an empty endpoint or with both endpoints on the
+/// same record are skipped, and edges without
+/// a calculated weight get the default of 1.0; both are recorded in `warnings`,
+/// as is each orphaned edge. Empty endpoints are only counted per edge, then logged
+/// and recorded once per kind in `empty_ids`, since a broken table can have
+/// thousands. Statuses are read through `ConfirmedStatus`, and each
+/// NULL or unknown status is recorded once with the number of edges carrying it.
+/// A decision from `overrides` replaces the stored status of the edge it matches.
+pub fn classify_edges(
+    edges: &[RawEdgeVisualization],
+    entity_or_service: &str, // "entity" or "service"
+    existing_ids: &HashSet<String>,
+    overrides: Option<&StatusOverrides>,
+    warnings: &RunWarnings,
+) -> ClassifiedEdges {
+    let mut classified = ClassifiedEdges::default();
+    let mut statuses = StatusTally::default();
+    let mut first_empty_id_edge = None;
+
+    for edge in edges {
+        let [id1, id2] = edge_endpoint_ids(edge, entity_or_service);
+
+        if classified.empty_ids.add(&id1, &id2) {
+            first_empty_id_edge.get_or_insert(edge.id.as_str());
+            continue;
+        }
+
+        // A record never duplicates itself, and a self-loop would only add a
+        // degenerate edge to the graph and the visualization table
+        if id1.trim() == id2.trim() {
+            warn!("Skipping self-referencing {} edge {} ({:?})", entity_or_service, edge.id, id1);
+            warnings.record(WarningCategory::SkippedEdge, format!(
+                "{} edge {} connects {:?} to itself", entity_or_service, edge.id, id1
+            ));
+            continue;
+        }
+
+        let missing_ids: Vec<String> = [&id1, &id2].into_iter()
+            .filter(|id| !existing_ids.contains(*id))
+            .cloned()
+            .collect();
+        if !missing_ids.is_empty() {
+            debug!("Excluding orphaned {} edge {} referencing missing ids {:?}", entity_or_service, edge.id, missing_ids);
+            warnings.record(WarningCategory::OrphanedEdge, format!(
+                "{} edge {} references missing ids {}", entity_or_service, edge.id, missing_ids.join(", ")
+            ));
+            classified.orphaned.push(OrphanedEdge {
+                record_type: entity_or_service.to_string(),
+                edge_id: edge.id.clone(),
+                id_1: id1,
+                id_2: id2,
+                missing_ids: missing_ids.join(", "),
+                confirmed_status: edge.confirmed_status.clone(),
+            });
+            continue;
+        }
+
+        let status = match overrides.and_then(|o| o.decision_for(&edge.id, &id1, &id2)) {
+            Some((index, decision)) => {
+                classified.overrides_applied.insert(index);
+                decision
+            }
+            None => statuses.add(edge.confirmed_status.as_deref(), 1),
+        };
+
+        // Valid connections: CONFIRMED_MATCH or PENDING_REVIEW
+        // Invalid connections: CONFIRMED_NON_MATCH (breaks the connection)
+        if !status.keeps_connection() {
+            continue;
+        }
+
+        // Extract edge weight and details from the original edge
+        let calculated_weight = edge.details.as_ref()
+            .and_then(|d| d.get("calculated_edge_weight"))
+            .and_then(|w| w.as_f64());
+        if calculated_weight.is_none() {
+            warnings.record(WarningCategory::FallbackWeight, format!(
+                "{} edge {} has no calculated_edge_weight; using 1.0", entity_or_service, edge.id
+            ));
+        }
+        let edge_weight = calculated_weight.unwrap_or(1.0); // Default weight if not available
+
+        let edge_details = edge.details.clone().unwrap_or_else(|| {
+            json!({
+                "contributing_methods": [],
+                "total_confidence": edge_weight,
+                "pre_rl_total_confidence": edge_weight,
+                "calculated_edge_weight": edge_weight
+            })
+        });
+
+        classified.valid.push(ValidEdge {
+            id_1: id1,
+            id_2: id2,
+            weight: edge_weight,
+            details: edge_details,
+            status: status.as_db_str().to_string(),
+            updated_at: edge.updated_at,
+            reviewed_at: edge.reviewed_at,
+            reviewed_by: edge.reviewed_by.clone(),
+        });
+    }
+
+    if let Some(first) = first_empty_id_edge {
+        let empty_ids = &classified.empty_ids;
+        warn!("Skipped {} {} edges with an empty ID (empty id_1: {}, empty id_2: {}, both: {}; first: edge {}).",
+              empty_ids.total(), entity_or_service, empty_ids.id_1, empty_ids.id_2, empty_ids.both, first);
+        for (count, reason) in empty_ids.reasons() {
+            warnings.record(WarningCategory::SkippedEdge, format!(
+                "{} {} edges with {}", count, entity_or_service, reason
+            ));
+        }
+    }
+
+    for unmapped in &statuses.unmapped {
+        warn!("{} {} edges have confirmed_status {}; treating them as PENDING_REVIEW.", unmapped.count, entity_or_service, unmapped);
+        warnings.record(WarningCategory::UnmappedStatus, format!(
+            "{} {} edges with confirmed_status {} treated as PENDING_REVIEW", unmapped.count, entity_or_service, unmapped
+        ));
+    }
+
+    classified
+}
+
+/// Returns the two endpoint ids of an edge for the given record type, with
+/// missing ids as empty strings.
+fn edge_endpoint_ids(edge: &RawEdgeVisualization, entity_or_service: &str) -> [String; 2] {
+    if entity_or_service == "entity" {
+        [edge.entity_id_1.clone().unwrap_or_default(), edge.entity_id_2.clone().unwrap_or_default()]
+    } else {
+        [edge.service_id_1.clone().unwrap_or_default(), edge.service_id_2.clone().unwrap_or_default()]
+    }
+}
+
+/// The result of `tally_method_stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MethodTally {
+    pub stats: Vec<MethodStats>,
+    /// `contributing_methods` entries that are not a `[method_type, confidence]` pair,
+    /// or lists that are not an array; they were skipped
+    pub malformed_entries: usize,
+}
+
+/// Tallies, per method type, every edge of an opinion whose `contributing_methods`
+/// list it, by how the edge was decided, with the mean edge weight. Unlike the
+/// Method Breakdown this includes CONFIRMED_NON_MATCH edges, which re-clustering
+/// drops. Statuses are read through `ConfirmedStatus` with NULL and unknown values
+/// as PENDING_REVIEW, an `--overrides` decision replaces the stored one, and weights
+/// default to 1.0 as in `classify_edges`. A method listed twice on one edge counts
+/// once. Malformed entries are counted and skipped.
+pub fn tally_method_stats(
+    edges: &[RawEdgeVisualization],
+    entity_or_service: &str,
+    overrides: Option<&StatusOverrides>,
+) -> MethodTally {
+    // method_type -> (edges, [match, non-match, pending], weight sum)
+    let mut totals: HashMap<String, (usize, [usize; 3], f64)> = HashMap::new();
+    let mut malformed_entries = 0;
+
+    for edge in edges {
+        let Some(methods) = edge.details.as_ref().and_then(|d| d.get("contributing_methods")) else {
+            continue;
+        };
+        let Some(entries) = methods.as_array() else {
+            malformed_entries += 1;
+            continue;
+        };
+        let mut edge_methods: HashSet<&str> = HashSet::new();
+        for entry in entries {
+            match entry.as_array().map(Vec::as_slice) {
+                Some([Value::String(method_type), confidence]) if confidence.is_number() => {
+                    edge_methods.insert(method_type.as_str());
+                }
+                _ => malformed_entries += 1,
+            }
+        }
+        if edge_methods.is_empty() {
+            continue;
+        }
+
+        let [id1, id2] = edge_endpoint_ids(edge, entity_or_service);
+        let status = match overrides.and_then(|o| o.decision_for(&edge.id, &id1, &id2)) {
+            Some((_, decision)) => decision,
+            None => edge.confirmed_status.as_deref()
+                .and_then(ConfirmedStatus::from_db)
+                .unwrap_or(ConfirmedStatus::PendingReview),
+        };
+        let status_index = match status {
+            ConfirmedStatus::ConfirmedMatch => 0,
+            ConfirmedStatus::ConfirmedNonMatch => 1,
+            ConfirmedStatus::PendingReview => 2,
+        };
+        let weight = edge.details.as_ref()
+            .and_then(|d| d.get("calculated_edge_weight"))
+            .and_then(|w| w.as_f64())
+            .unwrap_or(1.0);
+        for method_type in edge_methods {
+            let entry = totals.entry(method_type.to_string()).or_insert((0, [0; 3], 0.0));
+            entry.0 += 1;
+            entry.1[status_index] += 1;
+            entry.2 += weight;
+        }
+    }
+
+    let mut stats: Vec<MethodStats> = totals.into_iter()
+        .map(|(method_type, (edge_count, [confirmed_match, confirmed_non_match, pending_review], weight_sum))| MethodStats {
+            record_type: entity_or_service.to_string(),
+            method_type,
+            edge_count,
+            confirmed_match,
+            confirmed_non_match,
+            pending_review,
+            mean_edge_weight: weight_sum / edge_count as f64,
+        })
+        .collect();
+    sort_method_stats(&mut stats);
+    MethodTally { stats, malformed_entries }
+}
+
+/// The dataset label of records whose `source_system` is NULL (with `--include-null-source-system`).
+pub const NO_DATASET: &str = "(no source_system)";
+
+/// The dataset (`source_system`) of the edge endpoints in the whitelisted datasets,
+/// filled from the streamed id query of re-clustering. Each dataset name is stored once.
+#[derive(Debug, Clone, Default)]
+pub struct RecordDatasets {
+    names: Vec<String>,
+    index_of: HashMap<String, usize>,
+    by_id: HashMap<String, usize>,
+}
+
+impl RecordDatasets {
+    /// Records `id` as part of `dataset`; `None` is a NULL `source_system`.
+    pub fn insert(&mut self, id: &str, dataset: Option<&str>) {
+        let dataset = dataset.unwrap_or(NO_DATASET);
+        let index = match self.index_of.get(dataset) {
+            Some(&index) => index,
+            None => {
+                self.names.push(dataset.to_string());
+                self.index_of.insert(dataset.to_string(), self.names.len() - 1);
+                self.names.len() - 1
+            }
+        };
+        self.by_id.insert(id.to_string(), index);
+    }
+
+    /// The dataset of a record, or `None` for a record outside the whitelisted datasets.
+    pub fn dataset_of(&self, id: &str) -> Option<&str> {
+        self.by_id.get(id).map(|&index| self.names[index].as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+}
+
+/// Tallies an opinion's edges by the datasets of their two records and how they were
+/// decided, so partners can see how many pairs between two datasets were confirmed.
+/// Like `tally_method_stats` it counts every edge, non-matches included; statuses are
+/// read through `ConfirmedStatus`, and an `--overrides` decision replaces the stored
+/// one as in `classify_edges`. Edges skipped by re-clustering (an empty id or a
+/// self-loop) and edges with a record outside `datasets` are not counted. Sorted by
+/// dataset pair.
+pub fn tally_dataset_pairs(
+    edges: &[RawEdgeVisualization],
+    entity_or_service: &str,
+    datasets: &RecordDatasets,
+    overrides: Option<&StatusOverrides>,
+) -> Vec<DatasetPairCount> {
+    let mut totals: BTreeMap<(&str, &str), [usize; 3]> = BTreeMap::new();
+    for edge in edges {
+        let [id1, id2] = edge_endpoint_ids(edge, entity_or_service);
+        if id1.is_empty() || id2.is_empty() || id1.trim() == id2.trim() {
+            continue;
+        }
+        let (Some(dataset_1), Some(dataset_2)) = (datasets.dataset_of(&id1), datasets.dataset_of(&id2)) else {
+            continue;
+        };
+        let status = match overrides.and_then(|o| o.decision_for(&edge.id, &id1, &id2)) {
+            Some((_, decision)) => decision,
+            None => edge.confirmed_status.as_deref()
+                .and_then(ConfirmedStatus::from_db)
+                .unwrap_or(ConfirmedStatus::PendingReview),
+        };
+        let pair = if dataset_1 <= dataset_2 { (dataset_1, dataset_2) } else { (dataset_2, dataset_1) };
+        let counts = totals.entry(pair).or_default();
+        match status {
+            ConfirmedStatus::ConfirmedMatch => counts[0] += 1,
+            ConfirmedStatus::ConfirmedNonMatch => counts[1] += 1,
+            ConfirmedStatus::PendingReview => counts[2] += 1,
+        }
+    }
+
+    totals.into_iter()
+        .map(|((dataset_1, dataset_2), [confirmed_match, confirmed_non_match, pending_review])| DatasetPairCount {
+            record_type: entity_or_service.to_string(),
+            dataset_1: dataset_1.to_string(),
+            dataset_2: dataset_2.to_string(),
+            confirmed_match,
+            confirmed_non_match,
+            pending_review,
+        })
+        .collect()
+}
+
+/// Orders method stats by edge count (highest first), then record type and method name,
+/// the same order as the Method Breakdown.
+pub fn sort_method_stats(stats: &mut [MethodStats]) {
+    stats.sort_by(|a, b| {
+        b.edge_count.cmp(&a.edge_count)
+            .then_with(|| a.record_type.cmp(&b.record_type))
+            .then_with(|| a.method_type.cmp(&b.method_type))
+    });
+}
@@ -0,0 +1,146 @@
+// src/replication.rs
+// Copies a finished export to a second database, such as an analytics warehouse.
+// The local export tables stay the source of truth: replication runs after they are
+// committed, and its failure is reported as a warning rather than failing the run.
+
+use anyhow::{Context, Result};
+use futures::{SinkExt, TryStreamExt};
+use log::info;
+use std::io::Cursor;
+use std::pin::pin;
+use std::time::Instant;
+use tokio_postgres::{Client, Transaction};
+
+use crate::db_connect::PgPool;
+use crate::export_schema;
+use crate::models::RecordScope;
+use crate::sql_guard::{self, execute_export_write, validate_export_write};
+use crate::table_names::{self, EXPORT_SCHEMA};
+
+/// What one replication copied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplicationSummary {
+    pub tables: usize,
+    pub rows: u64,
+}
+
+/// A column of an export table: its name and its type as printed by `format_type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub data_type: String,
+}
+
+/// `CREATE TABLE` for the target copy of an export table. Only column names and
+/// types are carried over; indexes, defaults and constraints are not.
+pub fn create_table_statement(table_full: &str, columns: &[ColumnDefinition]) -> String {
+    let columns: Vec<String> = columns.iter()
+        .map(|column| format!(r#""{}" {}"#, column.name.replace('"', "\"\""), column.data_type))
+        .collect();
+    format!("CREATE TABLE {} ({});", table_full, columns.join(", "))
+}
+
+/// The columns of `table_full` in column order.
+async fn table_columns(client: &Client, table_full: &str) -> Result<Vec<ColumnDefinition>> {
+    let rows = client.query(
+        r#"
+        SELECT a.attname::text AS name, format_type(a.atttypid, a.atttypmod) AS data_type
+        FROM pg_attribute a
+        WHERE a.attrelid = $1::text::regclass AND a.attnum > 0 AND NOT a.attisdropped
+        ORDER BY a.attnum
+        "#,
+        &[&table_full],
+    ).await
+        .context(format!("Failed to read the columns of {}", table_full))?;
+    Ok(rows.iter()
+        .map(|row| ColumnDefinition { name: row.get("name"), data_type: row.get("data_type") })
+        .collect())
+}
+
+/// One export table read from the source, ready to be loaded into the target.
+struct TableCopy {
+    table_full: String,
+    columns: Vec<ColumnDefinition>,
+    data: Vec<u8>,
+}
+
+/// Reads a table's columns and its rows in COPY text format.
+async fn read_table(client: &Client, table_full: String) -> Result<TableCopy> {
+    let columns = table_columns(client, &table_full).await?;
+    let stream = client.copy_out(&format!("COPY {} TO STDOUT", table_full)).await
+        .context(format!("Failed to start COPY out of {}", table_full))?;
+    let mut stream = pin!(stream);
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.try_next().await.context(format!("Failed to read COPY data from {}", table_full))? {
+        data.extend_from_slice(&chunk);
+    }
+    Ok(TableCopy { table_full, columns, data })
+}
+
+/// Recreates a table on the target and loads the rows read from the source.
+async fn write_table(tx: &Transaction<'_>, table: TableCopy) -> Result<u64> {
+    let TableCopy { table_full, columns, data } = table;
+    execute_export_write(tx, &format!("DROP TABLE IF EXISTS {} CASCADE;", table_full), &[]).await
+        .context(format!("Failed to drop {} on the export target", table_full))?;
+    execute_export_write(tx, &create_table_statement(&table_full, &columns), &[]).await
+        .context(format!("Failed to create {} on the export target", table_full))?;
+
+    let statement = format!("COPY {} FROM STDIN", table_full);
+    validate_export_write(&statement)?;
+    let sink = tx.copy_in(&statement).await
+        .context(format!("Failed to start COPY into {} on the export target", table_full))?;
+    let mut sink = pin!(sink);
+    sink.send(Cursor::new(data)).await
+        .context(format!("Failed to send COPY data for {} to the export target", table_full))?;
+    let rows = sink.as_mut().finish().await
+        .context(format!("Failed to finish COPY into {} on the export target", table_full))?;
+    Ok(rows)
+}
+
+/// Copies the committed export tables for one opinion and suffix, and their
+/// `export_runs` row, from `source` to `target`, creating the export schema and
+/// tables there as needed. Existing target tables with the same names are replaced.
+///
+/// All tables are read before the target is written, so the two pools never hold a
+/// client at the same time. The target writes share one transaction: a failure
+/// leaves the target as it was.
+pub async fn replicate_export(
+    source: &PgPool,
+    target: &PgPool,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    scope: RecordScope,
+) -> Result<ReplicationSummary> {
+    let started = Instant::now();
+    info!("Replicating export tables with suffix '{}' for opinion '{}' to the export target...", timestamp_suffix, opinion_name);
+
+    let source_client = sql_guard::read_only_client(source, "replication source").await?;
+    let mut tables = Vec::new();
+    for table_name in export_schema::scoped_tables(scope) {
+        let table_full = table_names::qualified(EXPORT_SCHEMA, &table_names::export_table(user_prefix, opinion_name, table_name, timestamp_suffix));
+        tables.push(read_table(&source_client, table_full).await?);
+    }
+    drop(source_client);
+
+    let mut target_client = sql_guard::export_write_client(target, "replication target").await?;
+    export_schema::create_export_schema(&target_client).await?;
+    let tx = target_client.transaction().await
+        .context("Failed to start the export target transaction")?;
+    let mut summary = ReplicationSummary::default();
+    for table in tables {
+        let table_full = table.table_full.clone();
+        let rows = write_table(&tx, table).await?;
+        info!("Replicated {} rows to {} on the export target.", rows, table_full);
+        summary.tables += 1;
+        summary.rows += rows;
+    }
+    export_schema::mark_export_complete(&tx, user_prefix, opinion_name, timestamp_suffix, scope).await?;
+    tx.commit().await.context("Failed to commit the export target transaction")?;
+
+    info!(
+        "Replicated {} tables ({} rows) for opinion '{}' to the export target in {:.1?}.",
+        summary.tables, summary.rows, opinion_name, started.elapsed()
+    );
+    Ok(summary)
+}
@@ -0,0 +1,102 @@
+// src/retry.rs
+// Retries a stage of the per-reviewer pipeline after a transient database failure,
+// such as a serialization failure or a dropped connection, instead of failing the run.
+
+use anyhow::Result;
+use log::warn;
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+use tokio_postgres::error::SqlState;
+
+use crate::run_warnings::RunWarnings;
+
+/// How often a stage is attempted in total, and how long to wait before the first retry.
+/// Each further retry waits twice as long as the one before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { attempts: 2, backoff: Duration::from_millis(500) }
+    }
+}
+
+/// SQLSTATEs after which the same statements can succeed when run again.
+const TRANSIENT_STATES: [&SqlState; 4] = [
+    &SqlState::T_R_SERIALIZATION_FAILURE,
+    &SqlState::T_R_DEADLOCK_DETECTED,
+    &SqlState::ADMIN_SHUTDOWN,
+    &SqlState::CANNOT_CONNECT_NOW,
+];
+
+/// Whether anything in `error`'s chain is a failure that a retry may get past: a
+/// transient SQLSTATE, a closed connection, a connection-level I/O error, or a pool
+/// checkout that timed out. Errors in the data or the queries themselves are not.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<tokio_postgres::Error>() {
+            return e.is_closed() || e.code().is_some_and(|code| TRANSIENT_STATES.contains(&code));
+        }
+        if let Some(e) = cause.downcast_ref::<bb8::RunError<tokio_postgres::Error>>() {
+            return matches!(e, bb8::RunError::TimedOut);
+        }
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut | io::ErrorKind::UnexpectedEof
+            );
+        }
+        false
+    })
+}
+
+/// Runs `attempt` until it succeeds, fails with an error that is not transient, or
+/// `policy.attempts` are used up, logging each retry. Every call of `attempt` must
+/// redo the whole stage from the start, including its transaction.
+pub async fn retry_transient<T, F, Fut>(stage: &str, policy: RetryPolicy, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = policy.backoff;
+    let mut attempt_number = 1;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt_number < policy.attempts && is_transient(&e) => {
+                warn!(
+                    "{} failed with a transient error (attempt {} of {}); retrying in {:?}: {:#}",
+                    stage, attempt_number, policy.attempts, backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt_number += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// `retry_transient` for a stage that records run warnings. Each attempt records into
+/// its own `RunWarnings`, and only the warnings of the attempt that succeeds are added
+/// to `warnings`, so a retried stage does not count its warnings twice.
+pub async fn retry_recording_warnings<T, F, Fut>(stage: &str, policy: RetryPolicy, warnings: &RunWarnings, mut attempt: F) -> Result<T>
+where
+    F: FnMut(RunWarnings) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    retry_transient(stage, policy, || {
+        let attempt_warnings = RunWarnings::new();
+        let result = attempt(attempt_warnings.clone());
+        async move {
+            let value = result.await?;
+            warnings.extend_from(&attempt_warnings);
+            Ok(value)
+        }
+    }).await
+}
@@ -0,0 +1,71 @@
+// src/row_count_check.rs
+// Cross-checks the fetched export rows against the records in the team's whitelisted
+// datasets. Every record should appear exactly once, so a join in the fetch query that
+// drops or repeats rows shows up here instead of going unnoticed in the workbook.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// How many missing or duplicated ids the mismatch message lists.
+pub const MISMATCH_EXAMPLES: usize = 5;
+
+/// The difference between the records of one type and the rows fetched for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowCountMismatch {
+    pub record_type: String,
+    /// Records of this type in the whitelisted datasets
+    pub expected: usize,
+    /// Rows the fetch returned
+    pub fetched: usize,
+    /// Ids fetched more than once, in id order
+    pub duplicated: Vec<String>,
+    /// Ids in the datasets that the fetch left out, in id order
+    pub missing: Vec<String>,
+}
+
+impl fmt::Display for RowCountMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} records in the whitelisted datasets but {} export rows", self.expected, self.record_type, self.fetched)?;
+        for (label, ids) in [("duplicated", &self.duplicated), ("missing", &self.missing)] {
+            if ids.is_empty() {
+                continue;
+            }
+            let examples: Vec<&str> = ids.iter().take(MISMATCH_EXAMPLES).map(String::as_str).collect();
+            write!(f, "; {} {}: {}", ids.len(), label, examples.join(", "))?;
+            if ids.len() > examples.len() {
+                f.write_str(", ...")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the ids of the fetched rows with `expected_ids`, the records of
+/// `record_type` in the whitelisted datasets. Returns `None` when every record was
+/// fetched exactly once and nothing else was; otherwise the ids that were repeated or
+/// left out. Fetched ids outside `expected_ids` count toward `fetched` only.
+pub fn reconcile_rows<'a>(
+    record_type: &str,
+    expected_ids: &[String],
+    fetched_ids: impl IntoIterator<Item = &'a str>,
+) -> Option<RowCountMismatch> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut fetched = 0;
+    for id in fetched_ids {
+        *seen.entry(id).or_default() += 1;
+        fetched += 1;
+    }
+
+    let duplicated: BTreeSet<&str> = seen.iter().filter(|(_, count)| **count > 1).map(|(id, _)| *id).collect();
+    let missing: BTreeSet<&str> = expected_ids.iter().map(String::as_str).filter(|id| !seen.contains_key(id)).collect();
+    if fetched == expected_ids.len() && duplicated.is_empty() && missing.is_empty() {
+        return None;
+    }
+    Some(RowCountMismatch {
+        record_type: record_type.to_string(),
+        expected: expected_ids.len(),
+        fetched,
+        duplicated: duplicated.into_iter().map(str::to_string).collect(),
+        missing: missing.into_iter().map(str::to_string).collect(),
+    })
+}
@@ -0,0 +1,140 @@
+// src/run_warnings.rs
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// How many examples each summary keeps.
+pub const SUMMARY_EXAMPLES: usize = 3;
+
+/// The kinds of problems a run can work around without failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WarningCategory {
+    /// A `--sample` run that only covers part of the data; listed first
+    SampleExport,
+    /// An edge with an empty endpoint id, or a self-loop, left out of re-clustering
+    SkippedEdge,
+    /// An edge referencing a record deleted from the public tables
+    OrphanedEdge,
+    /// Fetched export rows that do not match the records in the whitelisted datasets
+    RowCountMismatch,
+    /// A NULL or unknown `confirmed_status`, counted as PENDING_REVIEW
+    UnmappedStatus,
+    /// An `--overrides` entry that matched no edge of the opinion
+    UnmatchedOverride,
+    /// A reviewer of a shared opinion whose decisions `--include-shared-reviewers` could not merge
+    SharedReviewer,
+    /// A kept edge whose endpoints were re-clustered into different clusters
+    SplitEdge,
+    /// An edge without `calculated_edge_weight`, given the default weight of 1.0
+    FallbackWeight,
+    /// A `contributing_methods` entry that is not a `[method_type, confidence]` pair
+    MalformedMethod,
+    /// Records with a NULL `source_system`, excluded by the dataset filter
+    NullSourceSystem,
+    /// A `--cluster-id` / `--entity-id` that matched nothing
+    MissingSpotCheckId,
+    /// The Progress Overview or its trend could not be built
+    ProgressOverview,
+    /// The export could not be copied to `--target-db`; the local export is complete
+    TargetReplication,
+}
+
+impl fmt::Display for WarningCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WarningCategory::SampleExport => "SAMPLE export",
+            WarningCategory::SkippedEdge => "Skipped edges",
+            WarningCategory::OrphanedEdge => "Orphaned edges",
+            WarningCategory::RowCountMismatch => "Row count mismatch",
+            WarningCategory::UnmappedStatus => "Unmapped confirmed_status",
+            WarningCategory::UnmatchedOverride => "Unmatched overrides",
+            WarningCategory::SharedReviewer => "Shared reviewers",
+            WarningCategory::SplitEdge => "Edges across clusters",
+            WarningCategory::FallbackWeight => "Fallback edge weights",
+            WarningCategory::MalformedMethod => "Malformed contributing methods",
+            WarningCategory::NullSourceSystem => "NULL source_system",
+            WarningCategory::MissingSpotCheckId => "Missing spot-check ids",
+            WarningCategory::ProgressOverview => "Progress Overview",
+            WarningCategory::TargetReplication => "Export target (partial)",
+        })
+    }
+}
+
+/// One category's warnings, as shown at the end of the run and on the Warnings sheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarningSummary {
+    pub category: WarningCategory,
+    pub count: usize,
+    pub examples: Vec<String>,
+}
+
+/// Collects the warnings of one run so they can be summarized after the log has
+/// scrolled past them. Clones share the same list, so it can be handed to any
+/// task or module that needs to record into it.
+#[derive(Debug, Clone, Default)]
+pub struct RunWarnings {
+    warnings: Arc<Mutex<Vec<(WarningCategory, String)>>>,
+}
+
+impl RunWarnings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a warning. Callers still log it themselves where the log should show it.
+    pub fn record(&self, category: WarningCategory, message: impl Into<String>) {
+        self.lock().push((category, message.into()));
+    }
+
+    /// Records every warning recorded in `other`, in order.
+    pub fn extend_from(&self, other: &RunWarnings) {
+        let recorded = other.lock().clone();
+        self.lock().extend(recorded);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// One summary per category that was recorded, in category order, each with its
+    /// count and first few messages.
+    pub fn summaries(&self) -> Vec<WarningSummary> {
+        let mut grouped: BTreeMap<WarningCategory, WarningSummary> = BTreeMap::new();
+        for (category, message) in self.lock().iter() {
+            let summary = grouped.entry(*category).or_insert_with(|| WarningSummary {
+                category: *category,
+                count: 0,
+                examples: Vec::new(),
+            });
+            summary.count += 1;
+            if summary.examples.len() < SUMMARY_EXAMPLES {
+                summary.examples.push(message.clone());
+            }
+        }
+        grouped.into_values().collect()
+    }
+
+    /// Prints the grouped summary to stdout; prints nothing when there were no warnings.
+    pub fn print_summary(&self) {
+        let summaries = self.summaries();
+        if summaries.is_empty() {
+            return;
+        }
+        println!("\n⚠️  Warnings during this run:");
+        for summary in summaries {
+            println!("   {} ({})", summary.category, summary.count);
+            for example in &summary.examples {
+                println!("     - {}", example);
+            }
+            if summary.count > summary.examples.len() {
+                println!("     ... and {} more", summary.count - summary.examples.len());
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(WarningCategory, String)>> {
+        // A panic while holding the lock can't leave the list half-updated
+        self.warnings.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
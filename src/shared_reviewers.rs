@@ -0,0 +1,262 @@
+// src/shared_reviewers.rs
+// For `--include-shared-reviewers`: the decisions of the other reviewers of a shared
+// opinion (its owner and `other_users`) are merged into the exported edges before
+// re-clustering. Each reviewer keeps their own `{prefix}_{opinion}_..._edge_visualization`
+// table; those tables are only read.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::debug;
+use std::collections::{BTreeMap, HashMap};
+use tokio_postgres::GenericClient;
+
+use crate::confirmed_status::ConfirmedStatus;
+use crate::models::RawEdgeVisualization;
+use crate::table_names::{self, TEAM_SCHEMA};
+use crate::team_utils::{OpinionInfo, UserInfo};
+
+/// Another reviewer of the opinion whose edge decisions are merged in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedReviewer {
+    pub username: String,
+    /// The reviewer's `user_opinion_prefix`, naming their opinion tables
+    pub prefix: String,
+}
+
+/// The reviewers `resolve_shared_reviewers` found for an opinion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedReviewers {
+    pub reviewers: Vec<SharedReviewer>,
+    /// The reviewer whose prefix the exported tables have, when they are one of the opinion's
+    pub exporting: Option<String>,
+    /// Reviewers left out, with the reason, e.g. no usable opinion prefix
+    pub skipped: Vec<String>,
+}
+
+/// How `reconcile` resolves an edge that one reviewer marked CONFIRMED_MATCH and
+/// another CONFIRMED_NON_MATCH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ConflictPolicy {
+    /// The non-match wins, so the records are split
+    #[default]
+    MostRestrictive,
+    /// The match wins, so the records stay together
+    MostPermissive,
+    /// The opinion owner's decision wins; without one, the most restrictive of the others
+    OwnerWins,
+}
+
+impl ConflictPolicy {
+    /// The name as given to `--conflict-policy`.
+    pub fn label(self) -> &'static str {
+        match self {
+            ConflictPolicy::MostRestrictive => "most-restrictive",
+            ConflictPolicy::MostPermissive => "most-permissive",
+            ConflictPolicy::OwnerWins => "owner-wins",
+        }
+    }
+}
+
+/// The shared reviewers merged into one export, and how their conflicts are resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedReview {
+    pub reviewers: Vec<SharedReviewer>,
+    /// Whose decisions the exported tables hold: a username, or the table prefix when
+    /// it belongs to none of the opinion's reviewers
+    pub exporting_reviewer: String,
+    /// The username of the opinion's owner, for `ConflictPolicy::OwnerWins`
+    pub owner: String,
+    pub policy: ConflictPolicy,
+}
+
+/// A reviewer's decisions by unordered record pair, as returned by `pair_key`.
+pub type ReviewerStatuses = HashMap<(String, String), ConfirmedStatus>;
+
+/// The decisions of one shared reviewer on one record type.
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerDecisions {
+    pub username: String,
+    pub statuses: ReviewerStatuses,
+}
+
+/// The outcome of `reconcile` for one edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reconciled {
+    pub status: ConfirmedStatus,
+    /// Whether one reviewer marked the edge CONFIRMED_MATCH and another CONFIRMED_NON_MATCH
+    pub conflict: bool,
+}
+
+/// Reconciles the decisions of several reviewers, keyed by reviewer, on one edge. A
+/// PENDING_REVIEW never overrides a decision, so the edge stays pending only when nobody
+/// decided it. When the reviewers that decided agree, their decision stands; when they
+/// conflict, `policy` picks the winner, with `owner` naming the opinion's owner.
+pub fn reconcile(decisions: &BTreeMap<&str, ConfirmedStatus>, owner: &str, policy: ConflictPolicy) -> Reconciled {
+    let has = |wanted: ConfirmedStatus| decisions.values().any(|&status| status == wanted);
+    let (matched, split) = (has(ConfirmedStatus::ConfirmedMatch), has(ConfirmedStatus::ConfirmedNonMatch));
+    let status = match (matched, split) {
+        (false, false) => ConfirmedStatus::PendingReview,
+        (true, false) => ConfirmedStatus::ConfirmedMatch,
+        (false, true) => ConfirmedStatus::ConfirmedNonMatch,
+        (true, true) => match policy {
+            ConflictPolicy::MostRestrictive => ConfirmedStatus::ConfirmedNonMatch,
+            ConflictPolicy::MostPermissive => ConfirmedStatus::ConfirmedMatch,
+            ConflictPolicy::OwnerWins => decisions.get(owner).copied()
+                .filter(|status| status.is_reviewed())
+                .unwrap_or(ConfirmedStatus::ConfirmedNonMatch),
+        },
+    };
+    Reconciled { status, conflict: matched && split }
+}
+
+/// What `merge_reviewer_statuses` changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Edges whose status changed
+    pub changed: usize,
+    /// Conflicting edges resolved as CONFIRMED_MATCH
+    pub conflicts_matched: usize,
+    /// Conflicting edges resolved as CONFIRMED_NON_MATCH
+    pub conflicts_split: usize,
+}
+
+impl MergeSummary {
+    pub fn conflicts(&self) -> usize {
+        self.conflicts_matched + self.conflicts_split
+    }
+}
+
+/// The merged status of two decisions on the same edge: a confirmed non-match by anyone
+/// wins, then a confirmed match, and the edge stays pending only when nobody decided it.
+/// Repeated pairs in one reviewer's table are merged this way.
+pub fn merged_status(a: ConfirmedStatus, b: ConfirmedStatus) -> ConfirmedStatus {
+    let rank = |status: ConfirmedStatus| match status {
+        ConfirmedStatus::PendingReview => 0,
+        ConfirmedStatus::ConfirmedMatch => 1,
+        ConfirmedStatus::ConfirmedNonMatch => 2,
+    };
+    if rank(b) > rank(a) { b } else { a }
+}
+
+/// The key of a record pair, independent of the direction of the edge.
+pub fn pair_key(id_1: &str, id_2: &str) -> (String, String) {
+    let (a, b) = (id_1.trim(), id_2.trim());
+    if a <= b { (a.to_string(), b.to_string()) } else { (b.to_string(), a.to_string()) }
+}
+
+/// The reviewers of `opinion` other than the one whose tables are exported under
+/// `user_prefix`: its owner and the users in `other_users`, looked up in `users`. A
+/// reviewer without a prefix that is safe in a table name, or missing from `users`, is
+/// skipped, as is a second reviewer with the same prefix. The reviewer with
+/// `user_prefix` is returned as `exporting`.
+pub fn resolve_shared_reviewers(opinion: &OpinionInfo, users: &[UserInfo], user_prefix: &str) -> ResolvedReviewers {
+    let mut resolved = ResolvedReviewers::default();
+    let reviewer_ids = std::iter::once(&opinion.user_id).chain(&opinion.other_users);
+    let mut seen_prefixes: Vec<String> = Vec::new();
+    for id in reviewer_ids {
+        let Some(user) = users.iter().find(|u| &u.id == id) else {
+            resolved.skipped.push(format!("user {} was not found in auth.users", id));
+            continue;
+        };
+        let Some(prefix) = user.user_opinion_prefix.as_deref().map(str::trim).filter(|p| !p.is_empty()) else {
+            resolved.skipped.push(format!("{} has no opinion prefix", user.username));
+            continue;
+        };
+        if prefix == user_prefix {
+            resolved.exporting.get_or_insert_with(|| user.username.clone());
+            continue;
+        }
+        if seen_prefixes.iter().any(|p| p == prefix) {
+            continue;
+        }
+        if let Err(e) = table_names::validate_table_prefix(prefix) {
+            resolved.skipped.push(format!("{} has an unusable opinion prefix: {}", user.username, e));
+            continue;
+        }
+        seen_prefixes.push(prefix.to_string());
+        resolved.reviewers.push(SharedReviewer { username: user.username.clone(), prefix: prefix.to_string() });
+    }
+    resolved
+}
+
+/// Reads the decisions of `reviewer` on the `entity_or_service` edges of `opinion_name`,
+/// merging repeated pairs with `merged_status`. NULL and unknown statuses count as
+/// PENDING_REVIEW. Returns `None` when the reviewer has no edge table for the opinion.
+pub async fn fetch_reviewer_statuses<C: GenericClient>(
+    client: &C,
+    reviewer: &SharedReviewer,
+    opinion_name: &str,
+    entity_or_service: &str,
+) -> Result<Option<ReviewerStatuses>> {
+    let table = table_names::qualified(TEAM_SCHEMA, &table_names::edge_viz_source(&reviewer.prefix, opinion_name, entity_or_service));
+    let exists: bool = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&table]).await
+        .context(format!("Failed to check whether {} exists", table))?
+        .get(0);
+    if !exists {
+        return Ok(None);
+    }
+
+    let query = format!("SELECT {0}_id_1, {0}_id_2, confirmed_status FROM {1}", entity_or_service, table);
+    let rows = client.query(&query, &[]).await
+        .context(format!("Failed to read the {} edge decisions of reviewer {}", entity_or_service, reviewer.username))?;
+    let mut statuses = ReviewerStatuses::new();
+    for row in rows {
+        let id_1: Option<String> = row.get(0);
+        let id_2: Option<String> = row.get(1);
+        let (Some(id_1), Some(id_2)) = (id_1, id_2) else { continue };
+        let status = row.get::<_, Option<String>>(2).as_deref()
+            .and_then(ConfirmedStatus::from_db)
+            .unwrap_or(ConfirmedStatus::PendingReview);
+        let merged = statuses.get(&pair_key(&id_1, &id_2)).map_or(status, |&earlier| merged_status(earlier, status));
+        statuses.insert(pair_key(&id_1, &id_2), merged);
+    }
+    Ok(Some(statuses))
+}
+
+/// Merges the shared reviewers' decisions into `edges`, the exported opinion's own
+/// edges, which hold the decisions of `shared.exporting_reviewer`. Each edge that another
+/// reviewer also decided gets the status `reconcile` picks under `shared.policy`. Only
+/// edges the opinion already has are changed; a pair that only another reviewer has is
+/// not added.
+pub fn merge_reviewer_statuses(
+    edges: &mut [RawEdgeVisualization],
+    entity_or_service: &str,
+    shared: &SharedReview,
+    others: &[ReviewerDecisions],
+) -> MergeSummary {
+    let mut summary = MergeSummary::default();
+    for edge in edges {
+        let (id_1, id_2) = if entity_or_service == "entity" {
+            (&edge.entity_id_1, &edge.entity_id_2)
+        } else {
+            (&edge.service_id_1, &edge.service_id_2)
+        };
+        let key = pair_key(id_1.as_deref().unwrap_or_default(), id_2.as_deref().unwrap_or_default());
+        let own = edge.confirmed_status.as_deref()
+            .and_then(ConfirmedStatus::from_db)
+            .unwrap_or(ConfirmedStatus::PendingReview);
+        let mut decisions = BTreeMap::from([(shared.exporting_reviewer.as_str(), own)]);
+        for other in others {
+            if let Some(&status) = other.statuses.get(&key) {
+                decisions.insert(other.username.as_str(), status);
+            }
+        }
+        if decisions.len() == 1 {
+            continue;
+        }
+
+        let reconciled = reconcile(&decisions, &shared.owner, shared.policy);
+        if reconciled.conflict {
+            debug!("Conflicting decisions on {} edge {}: {:?}; resolved as {}.", entity_or_service, edge.id, decisions, reconciled.status);
+            match reconciled.status {
+                ConfirmedStatus::ConfirmedMatch => summary.conflicts_matched += 1,
+                _ => summary.conflicts_split += 1,
+            }
+        }
+        if reconciled.status != own {
+            edge.confirmed_status = Some(reconciled.status.as_db_str().to_string());
+            summary.changed += 1;
+        }
+    }
+    summary
+}
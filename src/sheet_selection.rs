@@ -0,0 +1,48 @@
+// src/sheet_selection.rs
+
+use clap::ValueEnum;
+
+use crate::models::RecordScope;
+
+/// A worksheet of the export workbook, as named for `--sheets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SheetKind {
+    /// Progress Overview and User Breakdown
+    Progress,
+    Organizations,
+    Services,
+    MethodBreakdown,
+    MethodPerformance,
+    OrphanedEdges,
+    Warnings,
+    Legend,
+}
+
+/// The sheets chosen with `--sheets`. An empty selection means every sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SheetSelection {
+    sheets: Vec<SheetKind>,
+}
+
+impl SheetSelection {
+    pub fn new(sheets: Vec<SheetKind>) -> SheetSelection {
+        SheetSelection { sheets }
+    }
+
+    pub fn includes(&self, sheet: SheetKind) -> bool {
+        self.sheets.is_empty() || self.sheets.contains(&sheet)
+    }
+
+    /// Narrows `scope` to the record types whose sheet is selected, so an excluded
+    /// sheet is neither re-clustered nor fetched. `None` when no data sheet is left.
+    pub fn narrow(&self, scope: RecordScope) -> Option<RecordScope> {
+        let entities = scope.includes("entity") && self.includes(SheetKind::Organizations);
+        let services = scope.includes("service") && self.includes(SheetKind::Services);
+        match (entities, services) {
+            (true, true) => Some(RecordScope::Both),
+            (true, false) => Some(RecordScope::EntitiesOnly),
+            (false, true) => Some(RecordScope::ServicesOnly),
+            (false, false) => None,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// src/snapshots.rs
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Client;
+
+use crate::dashboard::OpinionDashboard;
+use crate::sql_guard::execute_export_write;
+use crate::table_names::{self, EXPORT_SCHEMA, SNAPSHOT_TABLE};
+
+/// Review completion of one user's opinion at the time a snapshot was recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotPoint {
+    pub recorded_at: DateTime<Utc>,
+    pub entity_percentage: f64,
+    pub service_percentage: f64,
+    pub overall_percentage: f64,
+}
+
+/// Creates the snapshot table in the export schema if it does not already exist.
+pub async fn ensure_snapshot_table(client: &Client) -> Result<()> {
+    let table = table_names::qualified(EXPORT_SCHEMA, SNAPSHOT_TABLE);
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            id BIGSERIAL PRIMARY KEY,
+            recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            username TEXT NOT NULL,
+            user_prefix TEXT NOT NULL,
+            opinion_name TEXT NOT NULL,
+            record_type TEXT NOT NULL,
+            pending_review BIGINT NOT NULL,
+            confirmed_match BIGINT NOT NULL,
+            confirmed_non_match BIGINT NOT NULL,
+            total BIGINT NOT NULL,
+            reviewed_count BIGINT NOT NULL,
+            review_percentage DOUBLE PRECISION NOT NULL
+        )
+        "#,
+        table
+    );
+    execute_export_write(client, &query, &[]).await
+        .context(format!("Failed to create snapshot table {}", table))?;
+    Ok(())
+}
+
+/// Records the entity and service review stats of a dashboard as one snapshot.
+/// Both rows share the same `recorded_at`, which groups them when reading the trend.
+pub async fn record_snapshot(client: &Client, dashboard: &OpinionDashboard) -> Result<()> {
+    ensure_snapshot_table(client).await?;
+
+    let table = table_names::qualified(EXPORT_SCHEMA, SNAPSHOT_TABLE);
+    let query = format!(
+        r#"
+        INSERT INTO {} (username, user_prefix, opinion_name, record_type, pending_review,
+                        confirmed_match, confirmed_non_match, total, reviewed_count, review_percentage)
+        VALUES ($1, $2, $3, 'entity', $4, $5, $6, $7, $8, $9),
+               ($1, $2, $3, 'service', $10, $11, $12, $13, $14, $15)
+        "#,
+        table
+    );
+    let e = &dashboard.entity_stats;
+    let s = &dashboard.service_stats;
+    execute_export_write(
+        client,
+        &query,
+        &[
+            &dashboard.username, &dashboard.user_prefix, &dashboard.opinion_name,
+            &e.pending_review, &e.confirmed_match, &e.confirmed_non_match, &e.total, &e.reviewed_count, &e.review_percentage,
+            &s.pending_review, &s.confirmed_match, &s.confirmed_non_match, &s.total, &s.reviewed_count, &s.review_percentage,
+        ],
+    ).await.context(format!("Failed to record review snapshot in {}", table))?;
+
+    info!("Recorded review snapshot for user '{}' with opinion '{}'.", dashboard.username, dashboard.opinion_name);
+    Ok(())
+}
+
+/// Returns up to `limit` of the most recent snapshots for a user's opinion, oldest first.
+/// Returns an empty list when no snapshot has ever been recorded.
+pub async fn recent_snapshots(
+    client: &Client,
+    user_prefix: &str,
+    opinion_name: &str,
+    limit: i64,
+) -> Result<Vec<SnapshotPoint>> {
+    let table = table_names::qualified(EXPORT_SCHEMA, SNAPSHOT_TABLE);
+    let exists: bool = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&table]).await
+        .context(format!("Failed to check whether {} exists", table))?
+        .get(0);
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let query = format!(
+        r#"
+        SELECT recorded_at, entity_percentage, service_percentage, overall_percentage
+        FROM (
+            SELECT
+                recorded_at,
+                COALESCE(MAX(review_percentage) FILTER (WHERE record_type = 'entity'), 0) AS entity_percentage,
+                COALESCE(MAX(review_percentage) FILTER (WHERE record_type = 'service'), 0) AS service_percentage,
+                CASE WHEN SUM(total) > 0
+                     THEN SUM(reviewed_count)::float8 / SUM(total)::float8 * 100.0
+                     ELSE 0 END AS overall_percentage
+            FROM {}
+            WHERE user_prefix = $1 AND opinion_name = $2
+            GROUP BY recorded_at
+            ORDER BY recorded_at DESC
+            LIMIT $3
+        ) recent
+        ORDER BY recorded_at
+        "#,
+        table
+    );
+    let rows = client.query(&query, &[&user_prefix, &opinion_name, &limit]).await
+        .context(format!("Failed to read review snapshots from {}", table))?;
+
+    Ok(rows.iter()
+        .map(|row| SnapshotPoint {
+            recorded_at: row.get("recorded_at"),
+            entity_percentage: row.get("entity_percentage"),
+            service_percentage: row.get("service_percentage"),
+            overall_percentage: row.get("overall_percentage"),
+        })
+        .collect())
+}
@@ -0,0 +1,191 @@
+// src/sql_guard.rs
+
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::GenericClient;
+
+use crate::db_connect::{self, LimitedClient, PgPool};
+use crate::table_names::EXPORT_SCHEMA;
+
+/// A client checked out of the connection pool under the `PG_MAX_CONCURRENCY` bound.
+pub type PooledClient<'a> = LimitedClient<'a>;
+
+/// Set once by `--read-only`; after that every export write is refused.
+static WRITES_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Refuses all further writes for the rest of the process, including writes to the export schema.
+pub fn disable_writes() {
+    WRITES_DISABLED.store(true, Ordering::SeqCst);
+}
+
+/// Returns true once `disable_writes` has been called.
+pub fn writes_disabled() -> bool {
+    WRITES_DISABLED.load(Ordering::SeqCst)
+}
+
+/// Checks out a client whose session defaults to read-only transactions, so any
+/// accidental write through it is rejected by PostgreSQL itself.
+/// `stage` names the caller in the error raised when the pool times out.
+pub async fn read_only_client<'a>(pool: &'a PgPool, stage: &str) -> Result<PooledClient<'a>> {
+    let client = db_connect::get_client(pool, stage).await?;
+    client.batch_execute("SET default_transaction_read_only = on").await
+        .context(format!("Failed to make DB client for {} read-only", stage))?;
+    Ok(client)
+}
+
+/// Checks out a client for writing to the export schema. Pooled connections are
+/// reused, so the read-only default a previous borrower may have set is turned off.
+/// Statements that write should still go through `execute_export_write`.
+pub async fn export_write_client<'a>(pool: &'a PgPool, stage: &str) -> Result<PooledClient<'a>> {
+    if writes_disabled() {
+        bail!("Refusing to open a write connection for {} in read-only mode", stage);
+    }
+    let client = db_connect::get_client(pool, stage).await?;
+    client.batch_execute("SET default_transaction_read_only = off").await
+        .context(format!("Failed to make DB client for {} writable", stage))?;
+    Ok(client)
+}
+
+/// Executes a write statement after checking that it only targets the export schema.
+pub async fn execute_export_write<C: GenericClient>(
+    client: &C,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<u64> {
+    validate_export_write(sql)?;
+    debug!("Executing export write: {}", sql.trim());
+    Ok(client.execute(sql, params).await?)
+}
+
+/// Rejects any statement that is not a single DDL/DML statement targeting a table
+/// (or the schema itself) in the export schema.
+///
+/// All statements are generated by this tool, so this only needs to understand the
+/// shapes we produce: `CREATE SCHEMA`, `CREATE/DROP/ALTER/TRUNCATE TABLE`,
+/// `INSERT INTO`, `DELETE FROM`, `UPDATE` and `COPY`, each followed by a schema-qualified name,
+/// and the schema-level grants `GRANT ... ON SCHEMA`, `GRANT ... IN SCHEMA` and
+/// `ALTER DEFAULT PRIVILEGES IN SCHEMA`.
+pub fn validate_export_write(sql: &str) -> Result<()> {
+    if writes_disabled() {
+        bail!("Refusing to write in read-only mode: {}", sql.trim());
+    }
+
+    let statement = sql.trim().trim_end_matches(';').trim_end();
+    if statement.contains(';') {
+        bail!("Refusing to execute multiple statements as one export write: {}", statement);
+    }
+
+    let upper = statement.to_ascii_uppercase();
+    let (target, is_schema) = if let Some(rest) = strip_keywords(statement, &upper, &["CREATE", "SCHEMA"]) {
+        (skip_if_exists(rest), true)
+    } else if let Some(rest) = strip_keywords(statement, &upper, &["GRANT", "USAGE", "ON", "SCHEMA"])
+        .or_else(|| strip_keywords(statement, &upper, &["GRANT", "SELECT", "ON", "ALL", "TABLES", "IN", "SCHEMA"]))
+        .or_else(|| strip_keywords(statement, &upper, &["ALTER", "DEFAULT", "PRIVILEGES", "IN", "SCHEMA"]))
+    {
+        (rest, true)
+    } else if let Some(rest) = strip_keywords(statement, &upper, &["CREATE", "TABLE"])
+        .or_else(|| strip_keywords(statement, &upper, &["DROP", "TABLE"]))
+        .or_else(|| strip_keywords(statement, &upper, &["ALTER", "TABLE"]))
+        .or_else(|| strip_keywords(statement, &upper, &["TRUNCATE", "TABLE"]))
+        .or_else(|| strip_keywords(statement, &upper, &["TRUNCATE"]))
+        .or_else(|| strip_keywords(statement, &upper, &["INSERT", "INTO"]))
+        .or_else(|| strip_keywords(statement, &upper, &["DELETE", "FROM"]))
+        .or_else(|| strip_keywords(statement, &upper, &["UPDATE"]))
+        .or_else(|| strip_keywords(statement, &upper, &["COPY"]))
+    {
+        (skip_if_exists(rest), false)
+    } else {
+        bail!("Refusing to execute unrecognized export write: {}", statement);
+    };
+
+    let (first, second) = parse_qualified_name(target)
+        .with_context(|| format!("Could not parse the target of export write: {}", statement))?;
+    let schema = if is_schema {
+        Some(first)
+    } else {
+        second.map(|_| first)
+    };
+
+    match schema {
+        Some(schema) if schema == EXPORT_SCHEMA => Ok(()),
+        Some(schema) => bail!(
+            "Refusing to write outside the export schema '{}' (target schema '{}'): {}",
+            EXPORT_SCHEMA, schema, statement
+        ),
+        None => bail!(
+            "Refusing export write to a table without an explicit '{}' schema: {}",
+            EXPORT_SCHEMA, statement
+        ),
+    }
+}
+
+/// If `upper` starts with the given keywords (separated by whitespace), returns the
+/// remainder of the original statement after them.
+fn strip_keywords<'a>(statement: &'a str, upper: &str, keywords: &[&str]) -> Option<&'a str> {
+    let mut offset = 0;
+    for keyword in keywords {
+        let rest = &upper[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+        if !trimmed.starts_with(keyword) {
+            return None;
+        }
+        offset += keyword.len();
+        // The keyword must end at a word boundary
+        if !upper[offset..].starts_with(char::is_whitespace) {
+            return None;
+        }
+    }
+    Some(statement[offset..].trim_start())
+}
+
+/// Skips an optional `IF EXISTS` / `IF NOT EXISTS` clause.
+fn skip_if_exists(rest: &str) -> &str {
+    let upper = rest.to_ascii_uppercase();
+    strip_keywords(rest, &upper, &["IF", "NOT", "EXISTS"])
+        .or_else(|| strip_keywords(rest, &upper, &["IF", "EXISTS"]))
+        .unwrap_or(rest)
+}
+
+/// Parses `name` or `first.second`, where each part is either a quoted identifier
+/// (kept as written) or an unquoted one (folded to lowercase, as PostgreSQL does).
+fn parse_qualified_name(input: &str) -> Option<(String, Option<String>)> {
+    let (first, rest) = parse_identifier(input)?;
+    match rest.strip_prefix('.') {
+        Some(rest) => {
+            let (second, _) = parse_identifier(rest)?;
+            Some((first, Some(second)))
+        }
+        None => Some((first, None)),
+    }
+}
+
+/// Parses one identifier from the start of `input`, returning it and the remainder.
+fn parse_identifier(input: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = input.strip_prefix('"') {
+        let mut ident = String::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '"' {
+                if let Some((_, '"')) = chars.peek() {
+                    ident.push('"');
+                    chars.next();
+                    continue;
+                }
+                return Some((ident, &quoted[i + 1..]));
+            }
+            ident.push(c);
+        }
+        None
+    } else {
+        let end = input
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(input.len());
+        if end == 0 {
+            return None;
+        }
+        Some((input[..end].to_lowercase(), &input[end..]))
+    }
+}
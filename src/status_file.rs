@@ -0,0 +1,185 @@
+// src/status_file.rs
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::db_connect::PgPool;
+use crate::models::MethodStats;
+use crate::pool_metrics::PoolStats;
+
+/// How often the status file is rewritten while the run is in progress.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Whether the run is still going, and how it ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// The JSON document written to `--status-file`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunStatus {
+    pub outcome: RunOutcome,
+    pub stage: String,
+    /// Export rows fetched so far, across opinions and record types
+    pub rows_processed: u64,
+    /// Primary pool usage at the last heartbeat
+    pub pool: Option<PoolStats>,
+    pub started_at: DateTime<Utc>,
+    pub last_heartbeat: DateTime<Utc>,
+    /// The error of a failed run
+    pub error: Option<String>,
+    /// Per-method decision counts from re-clustering, once it has run
+    pub method_performance: Vec<MethodStats>,
+    /// The tables `--export-to-table` wrote the rows to, schema-qualified
+    pub export_tables: Vec<String>,
+}
+
+impl RunStatus {
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        RunStatus {
+            outcome: RunOutcome::Running,
+            stage: "starting".to_string(),
+            rows_processed: 0,
+            pool: None,
+            started_at,
+            last_heartbeat: started_at,
+            error: None,
+            method_performance: Vec::new(),
+            export_tables: Vec::new(),
+        }
+    }
+}
+
+/// Replaces `path` with `contents` by writing a sibling ".tmp" file and renaming it
+/// over `path`, so a reader never sees a partly written file.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp_name = path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Status file path {} has no file name", path.display()))?
+        .to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    std::fs::write(&tmp_path, contents).context(format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path).context(format!("Failed to move {} to {}", tmp_path.display(), path.display()))?;
+    Ok(())
+}
+
+struct Shared {
+    path: PathBuf,
+    pool: PgPool,
+    status: Mutex<RunStatus>,
+    heartbeat: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Shared {
+    /// Stamps the heartbeat and pool stats and rewrites the file. A failed write only
+    /// logs a warning: monitoring must never break the export itself.
+    fn write(&self) {
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            status.last_heartbeat = Utc::now();
+            status.pool = Some(PoolStats::snapshot(&self.pool));
+            status.clone()
+        };
+        let written = serde_json::to_vec_pretty(&status)
+            .context("Failed to serialize the run status")
+            .and_then(|json| write_atomically(&self.path, &json));
+        if let Err(e) = written {
+            warn!("Could not update the status file: {:#}", e);
+        }
+    }
+}
+
+/// Keeps the `--status-file` up to date for external monitoring. Without a status
+/// file every method is a no-op, so callers report progress unconditionally.
+#[derive(Clone, Default)]
+pub struct StatusReporter {
+    shared: Option<Arc<Shared>>,
+}
+
+impl StatusReporter {
+    /// Writes the initial status and starts rewriting it every 15 seconds until `finish`.
+    pub fn start(path: Option<PathBuf>, pool: PgPool) -> StatusReporter {
+        let Some(path) = path else {
+            return StatusReporter::default();
+        };
+        info!("Writing run status to {} every {:?}.", path.display(), HEARTBEAT_INTERVAL);
+        let shared = Arc::new(Shared {
+            path,
+            pool,
+            status: Mutex::new(RunStatus::new(Utc::now())),
+            heartbeat: Mutex::new(None),
+        });
+        let task_shared = Arc::clone(&shared);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
+            loop {
+                ticker.tick().await;
+                task_shared.write();
+            }
+        });
+        *shared.heartbeat.lock().unwrap() = Some(handle);
+        StatusReporter { shared: Some(shared) }
+    }
+
+    /// Records the stage the run has reached; shown from the next heartbeat on.
+    pub fn stage(&self, stage: &str) {
+        if let Some(shared) = &self.shared {
+            shared.status.lock().unwrap().stage = stage.to_string();
+        }
+    }
+
+    /// Adds fetched rows to the running total.
+    pub fn add_rows(&self, rows: usize) {
+        if let Some(shared) = &self.shared {
+            shared.status.lock().unwrap().rows_processed += rows as u64;
+        }
+    }
+
+    /// Records the method statistics of the run; shown from the next heartbeat on.
+    pub fn method_performance(&self, stats: &[MethodStats]) {
+        if let Some(shared) = &self.shared {
+            shared.status.lock().unwrap().method_performance = stats.to_vec();
+        }
+    }
+
+    /// Records the tables the export rows were written to; shown from the next heartbeat on.
+    pub fn export_tables(&self, tables: &[String]) {
+        if let Some(shared) = &self.shared {
+            shared.status.lock().unwrap().export_tables = tables.to_vec();
+        }
+    }
+
+    /// Stops the heartbeat and writes the final status with the run's outcome.
+    pub fn finish(&self, result: &Result<()>) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        if let Some(handle) = shared.heartbeat.lock().unwrap().take() {
+            handle.abort();
+        }
+        {
+            let mut status = shared.status.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    status.outcome = RunOutcome::Succeeded;
+                    status.stage = "done".to_string();
+                }
+                Err(e) => {
+                    status.outcome = RunOutcome::Failed;
+                    status.error = Some(format!("{:#}", e));
+                }
+            }
+        }
+        shared.write();
+    }
+}
@@ -0,0 +1,214 @@
+// src/table_export.rs
+// Writes the fetched export rows into tables in the export schema for `--export-to-table`,
+// so downstream SQL can read the export directly instead of parsing the workbook.
+
+use anyhow::{Context, Result};
+use log::info;
+use serde::Serialize;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::GenericClient;
+
+use crate::db_connect::PgPool;
+use crate::models::{OrganizationExportRow, ServiceExportRow};
+use crate::sql_guard::{self, execute_export_write};
+use crate::table_names::{self, EXPORT_SCHEMA};
+
+/// Columns of the organization export table and their types, in `OrganizationExportRow`
+/// field order. `services` is flattened into its three columns, as on the Organizations sheet.
+pub const ORGANIZATION_COLUMNS: [(&str, &str); 19] = [
+    ("contributor", "text"),
+    ("contributor_id", "text"),
+    ("entity_id", "text"),
+    ("name", "text"),
+    ("alternate_names", "text"),
+    ("cluster_confirmed_status", "text"),
+    ("cluster", "text"),
+    ("has_duplicates", "boolean"),
+    ("cluster_representative_name", "text"),
+    ("canonical_record", "boolean"),
+    ("last_reviewed", "timestamp"),
+    ("reviewed_by", "text"),
+    ("min_edge_confidence", "float8"),
+    ("low_confidence", "boolean"),
+    ("reviewed_edge_fraction", "float8"),
+    ("mixed_status", "boolean"),
+    ("service_count", "int8"),
+    ("services_with_duplicates", "int8"),
+    ("worst_service_status", "text"),
+];
+
+/// Columns of the service export table and their types, in `ServiceExportRow` field
+/// order. The per-term `taxonomies` are left out; `taxonomy_terms` lists them.
+pub const SERVICE_COLUMNS: [(&str, &str); 22] = [
+    ("contributor", "text"),
+    ("contributor_id", "text"),
+    ("service_id", "text"),
+    ("organization_name", "text"),
+    ("service_name", "text"),
+    ("location_name", "text"),
+    ("full_address", "text"),
+    ("address_missing_parts", "int8"),
+    ("latitude", "float8"),
+    ("longitude", "float8"),
+    ("cluster_confirmed_status", "text"),
+    ("taxonomy_terms", "text"),
+    ("taxonomy_count", "int8"),
+    ("cluster", "text"),
+    ("has_duplicates", "boolean"),
+    ("canonical_record", "boolean"),
+    ("last_reviewed", "timestamp"),
+    ("reviewed_by", "text"),
+    ("min_edge_confidence", "float8"),
+    ("low_confidence", "boolean"),
+    ("reviewed_edge_fraction", "float8"),
+    ("mixed_status", "boolean"),
+];
+
+/// The tables an `--export-to-table` run wrote, qualified with the export schema. A
+/// record type outside the export has no table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExportTables {
+    pub organizations: Option<String>,
+    pub services: Option<String>,
+}
+
+impl ExportTables {
+    pub fn names(&self) -> Vec<String> {
+        self.organizations.iter().chain(&self.services).cloned().collect()
+    }
+}
+
+/// The statement dropping an earlier export table of the same name, as a rerun with a
+/// fixed `--timestamp-suffix` and `--replace` leaves one.
+pub fn drop_table_sql(table: &str) -> String {
+    format!("DROP TABLE IF EXISTS {}", table_names::qualified(EXPORT_SCHEMA, table))
+}
+
+/// The `CREATE TABLE` statement for an export table with `columns`.
+pub fn create_table_sql(table: &str, columns: &[(&str, &str)]) -> String {
+    let definitions: Vec<String> = columns.iter().map(|(name, sql_type)| format!("{} {}", name, sql_type)).collect();
+    format!("CREATE TABLE {} ({})", table_names::qualified(EXPORT_SCHEMA, table), definitions.join(", "))
+}
+
+/// The batched `INSERT ... SELECT * FROM UNNEST(...)` statement for an export table
+/// with `columns`, taking one array parameter per column.
+pub fn insert_sql(table: &str, columns: &[(&str, &str)]) -> String {
+    let names: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+    let arrays: Vec<String> = columns.iter().enumerate()
+        .map(|(i, (_, sql_type))| format!("${}::{}[]", i + 1, sql_type))
+        .collect();
+    format!(
+        "INSERT INTO {} ({}) SELECT * FROM UNNEST({})",
+        table_names::qualified(EXPORT_SCHEMA, table), names.join(", "), arrays.join(", ")
+    )
+}
+
+/// One column of rows as a Postgres array parameter.
+fn array<'r, R, T: ToSql + Sync + 'r>(rows: &'r [R], value: impl Fn(&'r R) -> T) -> Box<dyn ToSql + Sync + 'r> {
+    Box::new(rows.iter().map(value).collect::<Vec<T>>())
+}
+
+/// The array parameters of `insert_sql` for organization rows, in `ORGANIZATION_COLUMNS` order.
+fn organization_arrays(rows: &[OrganizationExportRow]) -> Vec<Box<dyn ToSql + Sync + '_>> {
+    vec![
+        array(rows, |r| r.contributor.as_deref()),
+        array(rows, |r| r.contributor_id.as_deref()),
+        array(rows, |r| r.entity_id.as_str()),
+        array(rows, |r| r.name.as_deref()),
+        array(rows, |r| r.alternate_names.as_deref()),
+        array(rows, |r| r.cluster_confirmed_status.as_str()),
+        array(rows, |r| r.cluster.as_deref()),
+        array(rows, |r| r.has_duplicates),
+        array(rows, |r| r.cluster_representative_name.as_deref()),
+        array(rows, |r| r.canonical_record),
+        array(rows, |r| r.last_reviewed),
+        array(rows, |r| r.reviewed_by.as_deref()),
+        array(rows, |r| r.min_edge_confidence),
+        array(rows, |r| r.low_confidence),
+        array(rows, |r| r.reviewed_edge_fraction),
+        array(rows, |r| r.mixed_status),
+        array(rows, |r| r.services.as_ref().map(|s| s.service_count as i64)),
+        array(rows, |r| r.services.as_ref().map(|s| s.services_with_duplicates as i64)),
+        array(rows, |r| r.services.as_ref().and_then(|s| s.worst_status.as_deref())),
+    ]
+}
+
+/// The array parameters of `insert_sql` for service rows, in `SERVICE_COLUMNS` order.
+fn service_arrays(rows: &[ServiceExportRow]) -> Vec<Box<dyn ToSql + Sync + '_>> {
+    vec![
+        array(rows, |r| r.contributor.as_deref()),
+        array(rows, |r| r.contributor_id.as_deref()),
+        array(rows, |r| r.service_id.as_str()),
+        array(rows, |r| r.organization_name.as_deref()),
+        array(rows, |r| r.service_name.as_deref()),
+        array(rows, |r| r.location_name.as_deref()),
+        array(rows, |r| r.full_address.as_deref()),
+        array(rows, |r| r.address_missing_parts.map(|m| m as i64)),
+        array(rows, |r| r.latitude),
+        array(rows, |r| r.longitude),
+        array(rows, |r| r.cluster_confirmed_status.as_str()),
+        array(rows, |r| r.taxonomy_terms.as_deref()),
+        array(rows, |r| r.taxonomy_count as i64),
+        array(rows, |r| r.cluster.as_deref()),
+        array(rows, |r| r.has_duplicates),
+        array(rows, |r| r.canonical_record),
+        array(rows, |r| r.last_reviewed),
+        array(rows, |r| r.reviewed_by.as_deref()),
+        array(rows, |r| r.min_edge_confidence),
+        array(rows, |r| r.low_confidence),
+        array(rows, |r| r.reviewed_edge_fraction),
+        array(rows, |r| r.mixed_status),
+    ]
+}
+
+/// Replaces `table` with a new one with `columns` and inserts the row arrays in one
+/// UNNEST statement.
+async fn write_table<C: GenericClient>(
+    client: &C,
+    table: &str,
+    columns: &[(&str, &str)],
+    arrays: Vec<Box<dyn ToSql + Sync + '_>>,
+    row_count: usize,
+) -> Result<String> {
+    execute_export_write(client, &drop_table_sql(table), &[]).await
+        .context(format!("Failed to drop the earlier export table {}", table))?;
+    execute_export_write(client, &create_table_sql(table, columns), &[]).await
+        .context(format!("Failed to create export table {}", table))?;
+    if row_count > 0 {
+        let params: Vec<&(dyn ToSql + Sync)> = arrays.iter().map(|a| a.as_ref()).collect();
+        execute_export_write(client, &insert_sql(table, columns), &params).await
+            .context(format!("Failed to insert the export rows into {}", table))?;
+    }
+    info!("Wrote {} export rows to {}.{}.", row_count, EXPORT_SCHEMA, table);
+    Ok(format!("{}.{}", EXPORT_SCHEMA, table))
+}
+
+/// Writes the organization and service rows of an export into
+/// `{prefix}_{opinion}_org_export_{ts}` and `{prefix}_{opinion}_svc_export_{ts}` in the
+/// export schema, replacing tables of an earlier export with the same suffix. Both tables
+/// are written in one transaction, so either both are replaced or neither is. A `None`
+/// record type gets no table.
+pub async fn write_export_tables(
+    pool: &PgPool,
+    user_prefix: &str,
+    opinion_name: &str,
+    timestamp_suffix: &str,
+    org_data: Option<&[OrganizationExportRow]>,
+    svc_data: Option<&[ServiceExportRow]>,
+) -> Result<ExportTables> {
+    let mut client = sql_guard::export_write_client(pool, "export row tables").await?;
+    let tx = client.transaction().await.context("Failed to start the export row table transaction")?;
+
+    let mut tables = ExportTables::default();
+    if let Some(rows) = org_data {
+        let table = table_names::rows_export(user_prefix, opinion_name, "org", timestamp_suffix);
+        tables.organizations = Some(write_table(&tx, &table, &ORGANIZATION_COLUMNS, organization_arrays(rows), rows.len()).await?);
+    }
+    if let Some(rows) = svc_data {
+        let table = table_names::rows_export(user_prefix, opinion_name, "svc", timestamp_suffix);
+        tables.services = Some(write_table(&tx, &table, &SERVICE_COLUMNS, service_arrays(rows), rows.len()).await?);
+    }
+
+    tx.commit().await.context("Failed to commit the export row tables")?;
+    Ok(tables)
+}
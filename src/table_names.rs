@@ -0,0 +1,147 @@
+// table_names.rs
+// Single source of truth for schema and table names. Every module that reads or
+// writes opinion or export tables builds the names here, so the copy, re-clustering,
+// fetch, and dashboard steps can never disagree about which table they mean.
+
+use std::sync::OnceLock;
+
+/// Environment variable naming the schema of the HSDS source tables (entity, service,
+/// organization, location, ...), which differs per environment, e.g. `hsds_prod`.
+pub const SOURCE_SCHEMA_VAR: &str = "SOURCE_SCHEMA";
+/// The source schema when `SOURCE_SCHEMA` is not set.
+pub const DEFAULT_SOURCE_SCHEMA: &str = "public";
+
+static SOURCE_SCHEMA: OnceLock<String> = OnceLock::new();
+
+/// Schema holding the team's opinion-specific tables.
+pub const TEAM_SCHEMA: &str = "wa211_to_wric";
+/// Schema holding the timestamped export tables.
+pub const EXPORT_SCHEMA: &str = "wa211_to_wric_exports";
+
+/// Table in the export schema holding one row per recorded review-stats snapshot.
+pub const SNAPSHOT_TABLE: &str = "dashboard_snapshots";
+
+/// Table in the export schema with one row per export whose tables were all written successfully.
+pub const EXPORT_RUNS_TABLE: &str = "export_runs";
+
+/// The opinion tables copied into the export schema for every export, per record type.
+pub const EXPORTED_TABLES: [&str; 6] = [
+    "entity_group",
+    "entity_group_cluster",
+    "entity_edge_visualization",
+    "service_group",
+    "service_group_cluster",
+    "service_edge_visualization",
+];
+
+/// Longest accepted `--timestamp-suffix`, so the export table names stay within
+/// PostgreSQL's 63-byte identifier limit for typical prefixes and opinion names.
+pub const MAX_SUFFIX_LEN: usize = 32;
+
+/// Checks a user-supplied timestamp suffix: 1 to `MAX_SUFFIX_LEN` ASCII letters, digits,
+/// or underscores, so it is safe inside table and file names.
+pub fn validate_timestamp_suffix(suffix: &str) -> Result<String, String> {
+    if suffix.is_empty() || suffix.len() > MAX_SUFFIX_LEN {
+        return Err(format!("suffix must be 1 to {} characters long", MAX_SUFFIX_LEN));
+    }
+    if !suffix.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("suffix '{}' may only contain ASCII letters, digits, and underscores", suffix));
+    }
+    Ok(suffix.to_string())
+}
+
+/// Longest accepted `--prefix-override`, for the same reason as `MAX_SUFFIX_LEN`.
+pub const MAX_PREFIX_LEN: usize = 32;
+
+/// Checks a `--prefix-override`: 1 to `MAX_PREFIX_LEN` ASCII letters, digits, or
+/// underscores, so it is safe inside the opinion and export table names.
+pub fn validate_table_prefix(prefix: &str) -> Result<String, String> {
+    if prefix.is_empty() || prefix.len() > MAX_PREFIX_LEN {
+        return Err(format!("prefix must be 1 to {} characters long", MAX_PREFIX_LEN));
+    }
+    if !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("prefix '{}' may only contain ASCII letters, digits, and underscores", prefix));
+    }
+    Ok(prefix.to_string())
+}
+
+/// Name of an opinion table in the team schema: `{prefix}_{opinion}_{table}`.
+pub fn source_table(user_prefix: &str, opinion_name: &str, table: &str) -> String {
+    format!("{}_{}_{}", user_prefix, opinion_name, table)
+}
+
+/// Name of a timestamped copy in the export schema: `{prefix}_{opinion}_{table}_export_{ts}`.
+pub fn export_table(user_prefix: &str, opinion_name: &str, table: &str, timestamp_suffix: &str) -> String {
+    format!("{}_{}_{}_export_{}", user_prefix, opinion_name, table, timestamp_suffix)
+}
+
+/// The user's edge visualization table for `kind` ("entity" or "service") in the team schema.
+pub fn edge_viz_source(user_prefix: &str, opinion_name: &str, kind: &str) -> String {
+    source_table(user_prefix, opinion_name, &format!("{}_edge_visualization", kind))
+}
+
+/// The exported edge visualization table for `kind`.
+pub fn edge_viz_export(user_prefix: &str, opinion_name: &str, kind: &str, timestamp_suffix: &str) -> String {
+    export_table(user_prefix, opinion_name, &format!("{}_edge_visualization", kind), timestamp_suffix)
+}
+
+/// The exported group table for `kind`.
+pub fn group_export(user_prefix: &str, opinion_name: &str, kind: &str, timestamp_suffix: &str) -> String {
+    export_table(user_prefix, opinion_name, &format!("{}_group", kind), timestamp_suffix)
+}
+
+/// The exported group cluster table for `kind`.
+pub fn cluster_export(user_prefix: &str, opinion_name: &str, kind: &str, timestamp_suffix: &str) -> String {
+    export_table(user_prefix, opinion_name, &format!("{}_group_cluster", kind), timestamp_suffix)
+}
+
+/// The `--export-to-table` table holding an export's organization ("org") or service
+/// ("svc") rows: `{prefix}_{opinion}_{rows}_export_{ts}`.
+pub fn rows_export(user_prefix: &str, opinion_name: &str, rows: &str, timestamp_suffix: &str) -> String {
+    export_table(user_prefix, opinion_name, rows, timestamp_suffix)
+}
+
+/// Quotes a schema and table into a fully qualified identifier: `"schema"."table"`.
+pub fn qualified(schema: &str, table: &str) -> String {
+    format!(r#""{}"."{}""#, schema, table)
+}
+
+/// Checks a schema name from configuration: 1 to 63 ASCII letters, digits, or
+/// underscores, not starting with a digit, so it can be quoted into SQL as is.
+pub fn validate_schema_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.len() > 63 {
+        return Err(format!("schema name '{}' must be 1 to 63 characters long", name));
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("schema name '{}' may only contain ASCII letters, digits, and underscores, and may not start with a digit", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Sets the source schema for the rest of the process. Setting it again to the same
+/// name is allowed; a different name is an error, so no query can see two schemas.
+pub fn set_source_schema(name: &str) -> Result<(), String> {
+    let name = validate_schema_name(name)?;
+    let current = SOURCE_SCHEMA.get_or_init(|| name.clone());
+    if *current != name {
+        return Err(format!("the source schema is already set to '{}'", current));
+    }
+    Ok(())
+}
+
+/// Sets the source schema from `SOURCE_SCHEMA`, or to "public" when it is unset.
+pub fn init_source_schema() -> Result<&'static str, String> {
+    let name = std::env::var(SOURCE_SCHEMA_VAR).unwrap_or_else(|_| DEFAULT_SOURCE_SCHEMA.to_string());
+    set_source_schema(name.trim()).map_err(|e| format!("{}: {}", SOURCE_SCHEMA_VAR, e))?;
+    Ok(source_schema())
+}
+
+/// The schema of the HSDS source tables; "public" until one is set.
+pub fn source_schema() -> &'static str {
+    SOURCE_SCHEMA.get().map(String::as_str).unwrap_or(DEFAULT_SOURCE_SCHEMA)
+}
+
+/// A source table quoted with its schema, e.g. `"public"."entity"`.
+pub fn hsds_table(table: &str) -> String {
+    qualified(source_schema(), table)
+}
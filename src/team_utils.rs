@@ -1,10 +1,14 @@
 // team_utils.rs
 use anyhow::{Context, Result};
-use log::info;
+use log::{info, warn};
+use std::collections::HashSet;
 use tokio_postgres::Client;
 use serde::{Deserialize, Serialize};
 
 use crate::db_connect::PgPool;
+use crate::export_schema;
+use crate::sql_guard;
+use crate::table_names;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TeamInfo {
@@ -32,6 +36,8 @@ pub struct OpinionInfo {
     pub user_id: String,
     pub owner_username: String,
     pub other_users: Vec<String>,
+    /// Usernames of the users in `other_users`, sorted
+    pub shared_with_usernames: Vec<String>,
     pub disconnect_dependent_services: bool,
 }
 
@@ -39,7 +45,7 @@ pub struct OpinionInfo {
 pub async fn get_all_teams(pool: &PgPool) -> Result<Vec<TeamInfo>> {
     info!("Fetching all teams from auth schema...");
     
-    let client = pool.get().await.context("Failed to get DB client for teams")?;
+    let client = sql_guard::read_only_client(pool, "teams").await?;
     
     let query = r#"
         SELECT id, name, display_name, whitelisted_datasets, is_active
@@ -66,20 +72,38 @@ pub async fn get_all_teams(pool: &PgPool) -> Result<Vec<TeamInfo>> {
     Ok(teams)
 }
 
-/// Fetches all users for a specific team from the auth schema
-pub async fn get_users_for_team(pool: &PgPool, team_id: &str) -> Result<Vec<UserInfo>> {
-    info!("Fetching users for team: {}", team_id);
-    
-    let client = pool.get().await.context("Failed to get DB client for users")?;
-    
-    let query = r#"
+/// The condition on `auth.users` that `filter_exportable_users` applies in Rust: a
+/// non-blank opinion prefix is set.
+const HAS_OPINION_PREFIX: &str = "user_opinion_prefix IS NOT NULL AND btrim(user_opinion_prefix) <> ''";
+
+/// The query behind `get_users_for_team`, taking the team id as `$1`.
+pub fn users_for_team_query(exportable_only: bool) -> String {
+    let prefix_filter = if exportable_only {
+        format!("AND {}", HAS_OPINION_PREFIX)
+    } else {
+        String::new()
+    };
+    format!(
+        r#"
         SELECT id, username, email, user_opinion_prefix, team_id, is_active
         FROM auth.users
-        WHERE team_id = $1 AND is_active = true
+        WHERE team_id = $1 AND is_active = true {}
         ORDER BY username
-    "#;
+        "#,
+        prefix_filter
+    )
+}
+
+/// Fetches all users for a specific team from the auth schema.
+/// With `exportable_only`, users without a non-blank opinion prefix are excluded in SQL,
+/// matching `filter_exportable_users`, for callers that don't need to report them.
+pub async fn get_users_for_team(pool: &PgPool, team_id: &str, exportable_only: bool) -> Result<Vec<UserInfo>> {
+    info!("Fetching users for team: {}", team_id);
+    
+    let client = sql_guard::read_only_client(pool, "users").await?;
+    let query = users_for_team_query(exportable_only);
     
-    let rows = client.query(query, &[&team_id]).await
+    let rows = client.query(&query, &[&team_id]).await
         .context("Failed to query users from auth schema")?;
     
     let mut users = Vec::new();
@@ -98,29 +122,106 @@ pub async fn get_users_for_team(pool: &PgPool, team_id: &str) -> Result<Vec<User
     Ok(users)
 }
 
+/// Counts the active users of a team that `get_users_for_team` leaves out with
+/// `exportable_only`, because they have no opinion prefix.
+pub async fn count_users_without_prefix(pool: &PgPool, team_id: &str) -> Result<i64> {
+    let client = sql_guard::read_only_client(pool, "users").await?;
+    let query = format!(
+        "SELECT count(*) FROM auth.users WHERE team_id = $1 AND is_active = true AND NOT ({})",
+        HAS_OPINION_PREFIX
+    );
+    let row = client.query_one(&query, &[&team_id]).await
+        .context("Failed to count users without an opinion prefix")?;
+    Ok(row.get(0))
+}
+
+/// Fetches the users with the given ids from the auth schema, active or not, such as
+/// the reviewers an opinion is shared with. Ids without a user are left out.
+pub async fn get_users_by_ids(pool: &PgPool, user_ids: &[String]) -> Result<Vec<UserInfo>> {
+    let client = sql_guard::read_only_client(pool, "users").await?;
+
+    let query = r#"
+        SELECT id, username, email, user_opinion_prefix, team_id, is_active
+        FROM auth.users
+        WHERE id = ANY($1)
+        ORDER BY username
+    "#;
+
+    let rows = client.query(query, &[&user_ids]).await
+        .context("Failed to query users by id from auth schema")?;
+
+    Ok(rows.iter().map(|row| UserInfo {
+        id: row.get("id"),
+        username: row.get("username"),
+        email: row.get("email"),
+        user_opinion_prefix: row.get("user_opinion_prefix"),
+        team_id: row.get("team_id"),
+        is_active: row.get("is_active"),
+    }).collect())
+}
+
+/// Splits users into those that can be exported (a non-blank opinion prefix is set)
+/// and returns them along with the number of users that were filtered out.
+/// Users without a prefix have no opinion tables, so selecting them can never succeed.
+pub fn filter_exportable_users(users: Vec<UserInfo>) -> (Vec<UserInfo>, usize) {
+    let total = users.len();
+    let exportable: Vec<UserInfo> = users
+        .into_iter()
+        .filter(|u| u.user_opinion_prefix.as_deref().is_some_and(|p| !p.trim().is_empty()))
+        .collect();
+    let hidden = total - exportable.len();
+    (exportable, hidden)
+}
+
+/// The prefix of the opinion tables to export: `prefix_override` when given
+/// (`--prefix-override`), otherwise the user's `user_opinion_prefix`, which must be set.
+pub fn opinion_table_prefix<'a>(user: &'a UserInfo, prefix_override: Option<&'a str>) -> Result<&'a str> {
+    if let Some(prefix) = prefix_override {
+        return Ok(prefix);
+    }
+    user.user_opinion_prefix.as_deref()
+        .filter(|p| !p.trim().is_empty())
+        .ok_or_else(|| anyhow::anyhow!(
+            "User '{}' has no opinion prefix set (auth.users.user_opinion_prefix) and cannot be exported",
+            user.username
+        ))
+}
+
+/// The names `auth.opinions` uses for the disconnect-dependent-services flag: production
+/// spells it without underscores, staging with them.
+pub const DISCONNECT_COLUMN_NAMES: [&str; 2] = ["disconnectdependentservices", "disconnect_dependent_services"];
+
 /// Fetches all opinions accessible to a specific user from the auth schema
 /// This includes opinions owned by the user and opinions shared with the user
 pub async fn get_opinions_for_user(pool: &PgPool, user_id: &str) -> Result<Vec<OpinionInfo>> {
     info!("Fetching opinions for user: {}", user_id);
     
-    let client = pool.get().await.context("Failed to get DB client for opinions")?;
+    let client = sql_guard::read_only_client(pool, "opinions").await?;
+
+    let disconnect_column = export_schema::find_column(&*client, "auth", "opinions", &DISCONNECT_COLUMN_NAMES).await?
+        .ok_or_else(|| anyhow::anyhow!("auth.opinions has none of the columns {}", DISCONNECT_COLUMN_NAMES.join(", ")))?;
     
-    let query = r#"
+    let query = format!(r#"
         SELECT 
             o.id,
             o.name,
             o.user_id,
             u.username as owner_username,
             o.other_users,
-            o.disconnectdependentservices
+            ARRAY(
+                SELECT su.username FROM auth.users su
+                WHERE o.other_users ? su.id
+                ORDER BY su.username
+            ) AS shared_with_usernames,
+            o.{} AS disconnect_dependent_services
         FROM auth.opinions o
         JOIN auth.users u ON o.user_id = u.id
         WHERE o.user_id = $1 
            OR o.other_users ? $1
         ORDER BY o.name
-    "#;
+    "#, disconnect_column);
     
-    let rows = client.query(query, &[&user_id]).await
+    let rows = client.query(&query, &[&user_id]).await
         .context("Failed to query opinions from auth schema")?;
     
     let mut opinions = Vec::new();
@@ -135,7 +236,8 @@ pub async fn get_opinions_for_user(pool: &PgPool, user_id: &str) -> Result<Vec<O
             user_id: row.get("user_id"),
             owner_username: row.get("owner_username"),
             other_users,
-            disconnect_dependent_services: row.get("disconnectdependentservices"),
+            shared_with_usernames: row.get("shared_with_usernames"),
+            disconnect_dependent_services: row.get("disconnect_dependent_services"),
         });
     }
     
@@ -147,7 +249,7 @@ pub async fn get_opinions_for_user(pool: &PgPool, user_id: &str) -> Result<Vec<O
 pub async fn get_team_by_id(pool: &PgPool, team_id: &str) -> Result<TeamInfo> {
     info!("Fetching team information for team ID: {}", team_id);
 
-    let client = pool.get().await.context("Failed to get DB client for team info")?;
+    let client = sql_guard::read_only_client(pool, "team info").await?;
 
     let query = r#"
         SELECT id, name, display_name, whitelisted_datasets, is_active
@@ -175,12 +277,103 @@ pub async fn get_team_by_id(pool: &PgPool, team_id: &str) -> Result<TeamInfo> {
     Ok(team_info)
 }
 
+/// Options controlling how records are matched against a team's whitelisted datasets.
+#[derive(Debug, Clone, Copy)]
+pub struct DatasetFilterOptions {
+    /// Also match records whose dataset column is NULL. These records cannot be
+    /// attributed to any whitelisted dataset, so they are excluded by default.
+    pub include_nulls: bool,
+    /// Compare case-insensitively and ignore surrounding whitespace on both the
+    /// column and the whitelist values, so "WA211" matches a stored " wa211 ".
+    pub normalize: bool,
+}
+
+impl Default for DatasetFilterOptions {
+    fn default() -> Self {
+        DatasetFilterOptions {
+            include_nulls: false,
+            normalize: true,
+        }
+    }
+}
+
+/// Logs the team's whitelisted datasets and refuses a team without any unless
+/// `allow_all_datasets` is set. An empty whitelist makes the dataset filter match
+/// every record, which would export other teams' datasets too.
+pub fn check_dataset_whitelist(team: &TeamInfo, allow_all_datasets: bool) -> Result<()> {
+    if !team.whitelisted_datasets.is_empty() {
+        info!("Team '{}' whitelisted datasets: {}", team.name, team.whitelisted_datasets.join(", "));
+        return Ok(());
+    }
+    if allow_all_datasets {
+        warn!("Team '{}' has no whitelisted datasets; exporting records from ALL datasets (--allow-all-datasets).", team.name);
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "Team '{}' has no whitelisted datasets, so the export would include every dataset in {} and {}, \
+         including other teams' data. Configure the team's whitelisted_datasets, or pass --allow-all-datasets if this is intended",
+        team.name, table_names::hsds_table("entity"), table_names::hsds_table("service")
+    ))
+}
+
+/// Removes the `--exclude-datasets` from the team's whitelist before the filter clause
+/// is built. Every excluded dataset must be on the whitelist, so a typo fails instead of
+/// silently exporting everything; with `options.normalize` names are compared
+/// case-insensitively and trimmed, like the filter itself. Excluding every dataset is
+/// refused, since an empty whitelist would match all records.
+pub fn exclude_datasets(team: &mut TeamInfo, excluded: &[String], options: DatasetFilterOptions) -> Result<()> {
+    if excluded.is_empty() {
+        return Ok(());
+    }
+    if team.whitelisted_datasets.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Team '{}' has no whitelisted datasets to exclude from (--exclude-datasets {})",
+            team.name, excluded.join(",")
+        ));
+    }
+
+    let key = |dataset: &str| if options.normalize { dataset.trim().to_lowercase() } else { dataset.to_string() };
+    let whitelisted: HashSet<String> = team.whitelisted_datasets.iter().map(|d| key(d)).collect();
+    let unknown: Vec<&str> = excluded.iter()
+        .filter(|d| !whitelisted.contains(&key(d)))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Cannot exclude datasets that are not whitelisted for team '{}': {} (whitelisted: {})",
+            team.name, unknown.join(", "), team.whitelisted_datasets.join(", ")
+        ));
+    }
+
+    let excluded: HashSet<String> = excluded.iter().map(|d| key(d)).collect();
+    let remaining: Vec<String> = team.whitelisted_datasets.iter()
+        .filter(|d| !excluded.contains(&key(d)))
+        .cloned()
+        .collect();
+    if remaining.is_empty() {
+        return Err(anyhow::anyhow!(
+            "--exclude-datasets removes every whitelisted dataset of team '{}'; nothing would be left to export",
+            team.name
+        ));
+    }
+
+    info!("Effective datasets for team '{}' after exclusions: {}", team.name, remaining.join(", "));
+    team.whitelisted_datasets = remaining;
+    Ok(())
+}
+
 /// Helper function to create WHERE clause for filtering by whitelisted datasets
+///
+/// Records whose dataset column is NULL never match `= ANY(...)`, so they are
+/// excluded unless `options.include_nulls` is set. With `options.normalize` the
+/// column is compared as `lower(trim(...))` and the returned parameter values are
+/// lowercased and trimmed to match.
 pub fn create_dataset_filter_clause(
     table_alias: &str,
     column_name: &str,
     whitelisted_datasets: &[String],
     param_start_index: usize,
+    options: DatasetFilterOptions,
 ) -> (String, Vec<String>) {
     if whitelisted_datasets.is_empty() {
         return ("1=1".to_string(), vec![]);
@@ -190,12 +383,110 @@ pub fn create_dataset_filter_clause(
         .map(|i| format!("${}", i))
         .collect();
 
-    let where_clause = format!(
-        "{}.{} = ANY(ARRAY[{}])",
-        table_alias,
-        column_name,
+    let column = format!("{}.{}", table_alias, column_name);
+    let compared_column = if options.normalize {
+        format!("lower(trim({}))", column)
+    } else {
+        column.clone()
+    };
+
+    let mut where_clause = format!(
+        "{} = ANY(ARRAY[{}])",
+        compared_column,
         placeholders.join(", ")
     );
 
-    (where_clause, whitelisted_datasets.to_vec())
-}
\ No newline at end of file
+    if options.include_nulls {
+        where_clause = format!("({} OR {} IS NULL)", where_clause, column);
+    }
+
+    let params = if options.normalize {
+        whitelisted_datasets.iter().map(|d| d.trim().to_lowercase()).collect()
+    } else {
+        whitelisted_datasets.to_vec()
+    };
+
+    (where_clause, params)
+}
+
+/// Counts the records in the source `entity` or `service` table whose `source_system`
+/// is NULL. These are silently excluded by the dataset filter unless requested,
+/// so callers log the count to make the omission visible.
+pub async fn count_null_source_system(client: &Client, record_type: &str) -> Result<i64> {
+    let source_table = match record_type {
+        "entity" => "entity",
+        "service" => "service",
+        _ => return Err(anyhow::anyhow!("Invalid record type: {}", record_type)),
+    };
+
+    let query = format!("SELECT COUNT(*) FROM {} WHERE source_system IS NULL", table_names::hsds_table(source_table));
+    let row = client.query_one(&query, &[]).await
+        .context(format!("Failed to count {} records with NULL source_system", record_type))?;
+    Ok(row.get(0))
+}
+
+/// Counts the records of the given type ("entity" or "service") that fall within
+/// the team's whitelisted datasets. Used for the pre-export confirmation summary.
+pub async fn count_whitelisted_records(
+    client: &Client,
+    record_type: &str,
+    whitelisted_datasets: &[String],
+    filter_options: DatasetFilterOptions,
+) -> Result<i64> {
+    let source_table = match record_type {
+        "entity" => "entity",
+        "service" => "service",
+        _ => return Err(anyhow::anyhow!("Invalid record type: {}", record_type)),
+    };
+
+    let (dataset_filter, filter_params) = create_dataset_filter_clause(
+        "t", "source_system", whitelisted_datasets, 1, filter_options
+    );
+
+    let query = format!(
+        "SELECT COUNT(*) FROM {} t WHERE {}",
+        table_names::hsds_table(source_table), dataset_filter
+    );
+
+    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = filter_params
+        .iter()
+        .map(|s| s as &(dyn tokio_postgres::types::ToSql + Sync))
+        .collect();
+
+    let row = client.query_one(&query, &params).await
+        .context(format!("Failed to count whitelisted {} records", record_type))?;
+    Ok(row.get(0))
+}
+
+/// Ids of the records of a given type in the team's whitelisted datasets, as filtered
+/// for the export. The row-count check compares them with the fetched export rows.
+pub async fn fetch_whitelisted_ids(
+    client: &Client,
+    record_type: &str,
+    whitelisted_datasets: &[String],
+    filter_options: DatasetFilterOptions,
+) -> Result<Vec<String>> {
+    let source_table = match record_type {
+        "entity" => "entity",
+        "service" => "service",
+        _ => return Err(anyhow::anyhow!("Invalid record type: {}", record_type)),
+    };
+
+    let (dataset_filter, filter_params) = create_dataset_filter_clause(
+        "t", "source_system", whitelisted_datasets, 1, filter_options
+    );
+
+    let query = format!(
+        "SELECT t.id FROM {} t WHERE {}",
+        table_names::hsds_table(source_table), dataset_filter
+    );
+
+    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = filter_params
+        .iter()
+        .map(|s| s as &(dyn tokio_postgres::types::ToSql + Sync))
+        .collect();
+
+    let rows = client.query(&query, &params).await
+        .context(format!("Failed to fetch whitelisted {} ids", record_type))?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
@@ -0,0 +1,166 @@
+// src/workbook_verify.rs
+// Reads a saved workbook back with calamine and checks the Organizations and Services
+// sheets against the rows they were written from (`--verify`), so a workbook that does
+// not parse or lost rows fails the run instead of reaching a partner.
+
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use calamine::{open_workbook, Data, Range, Reader, Xlsx};
+use rust_xlsxwriter::ExcelDateTime;
+
+use crate::cluster_labels::LabelMap;
+use crate::excel_writer::{organization_sheet, service_sheet, CellValue, DataSheetOptions, TableRow, TableSheet};
+use crate::models::{OrganizationExportRow, ServiceExportRow};
+
+/// A cell as the workbook should hold it, read back through calamine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpectedCell {
+    Text(String),
+    Flag(bool),
+    /// A number, or the serial number of a date
+    Number(f64),
+    Blank,
+}
+
+impl ExpectedCell {
+    fn from_cell(cell: CellValue<'_>) -> Result<ExpectedCell> {
+        Ok(match cell {
+            CellValue::Text(value) => ExpectedCell::Text(value.to_string()),
+            CellValue::Flag(value) => ExpectedCell::Flag(value),
+            CellValue::Number(Some(value)) | CellValue::NumberOrText(Some(value), _) => ExpectedCell::Number(value),
+            CellValue::NumberOrText(None, fallback) => ExpectedCell::Text(fallback.to_string()),
+            CellValue::ReviewTime(Some(reviewed)) => {
+                ExpectedCell::Number(ExcelDateTime::from_timestamp(reviewed.and_utc().timestamp())?.to_excel())
+            }
+            CellValue::Number(None) | CellValue::ReviewTime(None) | CellValue::Blank => ExpectedCell::Blank,
+        })
+    }
+
+    /// Whether calamine read `data` back as this value. An empty string is stored as
+    /// a blank cell, so either reads as blank text.
+    fn matches(&self, data: Option<&Data>) -> bool {
+        match (self, data.unwrap_or(&Data::Empty)) {
+            (ExpectedCell::Text(expected), Data::String(value)) => expected == value,
+            (ExpectedCell::Text(expected), Data::Empty) => expected.is_empty(),
+            (ExpectedCell::Flag(expected), Data::Bool(value)) => expected == value,
+            (ExpectedCell::Number(expected), Data::Float(value)) => expected == value,
+            (ExpectedCell::Number(expected), Data::Int(value)) => *expected == *value as f64,
+            (ExpectedCell::Number(expected), Data::DateTime(value)) => *expected == value.as_f64(),
+            (ExpectedCell::Blank, Data::Empty) => true,
+            (ExpectedCell::Blank, Data::String(value)) => value.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// What one table sheet of a saved workbook should hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectedSheet {
+    pub name: &'static str,
+    pub headers: Vec<&'static str>,
+    /// Data rows written, not counting the header or the footer
+    pub rows: usize,
+    /// Sampled data rows by their index among the data rows, with every cell
+    pub samples: Vec<(usize, Vec<ExpectedCell>)>,
+}
+
+impl ExpectedSheet {
+    /// Records what `table` writes for `data` under `name`, sampling the first, middle
+    /// and last rows. Call it before the rows are handed to the writer, which takes them.
+    pub fn from_rows<R: TableRow>(name: &'static str, table: &TableSheet<R>, data: &[R], labels: &LabelMap) -> Result<ExpectedSheet> {
+        let mut indexes = vec![0, data.len() / 2, data.len().saturating_sub(1)];
+        indexes.dedup();
+        let samples = indexes.into_iter()
+            .filter(|&index| index < data.len())
+            .map(|index| {
+                let cells = table.cells(&data[index], labels).into_iter()
+                    .map(ExpectedCell::from_cell)
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((index, cells))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ExpectedSheet { name, headers: table.headers(), rows: data.len(), samples })
+    }
+}
+
+/// The table sheets a saved workbook should hold, and whether they end in a footer row.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpectedWorkbook {
+    pub sheets: Vec<ExpectedSheet>,
+    pub footer: bool,
+}
+
+impl ExpectedWorkbook {
+    /// What `write_excel_file` writes to the Organizations and Services sheets for
+    /// these rows; a `None` dataset has no sheet to check.
+    pub fn for_rows(
+        org_data: Option<&[OrganizationExportRow]>,
+        svc_data: Option<&[ServiceExportRow]>,
+        options: DataSheetOptions<'_>,
+    ) -> Result<ExpectedWorkbook> {
+        let mut sheets = Vec::new();
+        if let Some(org_data) = org_data {
+            sheets.push(ExpectedSheet::from_rows("Organizations", &organization_sheet(), org_data, options.cluster_labels)?);
+        }
+        if let Some(svc_data) = svc_data {
+            sheets.push(ExpectedSheet::from_rows("Services", &service_sheet(), svc_data, options.cluster_labels)?);
+        }
+        Ok(ExpectedWorkbook { sheets, footer: options.footer })
+    }
+}
+
+/// Opens the workbook at `path` and checks it with `verify_workbook`.
+pub fn verify_workbook_file(path: &Path, expected: &ExpectedWorkbook) -> Result<()> {
+    let mut workbook: Xlsx<_> = open_workbook(path)
+        .with_context(|| format!("Failed to read {} back", path.display()))?;
+    verify_workbook(&mut workbook, expected)
+        .with_context(|| format!("{} does not hold what was written", path.display()))
+}
+
+/// Checks that each expected sheet exists with the canonical header row, one row per
+/// written record (plus the blank row and footer when enabled), and that the sampled
+/// rows read back cell for cell. Every mismatch is listed in the error.
+pub fn verify_workbook<RS: Read + Seek>(workbook: &mut Xlsx<RS>, expected: &ExpectedWorkbook) -> Result<()> {
+    let mut problems = Vec::new();
+    for sheet in &expected.sheets {
+        match workbook.worksheet_range(sheet.name) {
+            Ok(range) => check_sheet(&range, sheet, expected.footer, &mut problems),
+            Err(e) => problems.push(format!("sheet '{}' cannot be read: {}", sheet.name, e)),
+        }
+    }
+    if !problems.is_empty() {
+        bail!("{} problem(s) reading the workbook back:\n  {}", problems.len(), problems.join("\n  "));
+    }
+    Ok(())
+}
+
+fn check_sheet(range: &Range<Data>, sheet: &ExpectedSheet, footer: bool, problems: &mut Vec<String>) {
+    let headers: Vec<String> = (0..sheet.headers.len())
+        .map(|col| range.get((0, col)).map(Data::to_string).unwrap_or_default())
+        .collect();
+    if headers != sheet.headers {
+        problems.push(format!("'{}' headers are {:?}, expected {:?}", sheet.name, headers, sheet.headers));
+        return;
+    }
+
+    let trailing = if footer { 2 } else { 0 };
+    let rows = range.height().saturating_sub(1 + trailing);
+    if rows != sheet.rows {
+        problems.push(format!("'{}' has {} data rows, expected {}", sheet.name, rows, sheet.rows));
+        return;
+    }
+
+    for (index, cells) in &sheet.samples {
+        for (col, expected) in cells.iter().enumerate() {
+            let data = range.get((index + 1, col));
+            if !expected.matches(data) {
+                problems.push(format!(
+                    "'{}' row {} column '{}' reads {:?}, expected {:?}",
+                    sheet.name, index + 2, sheet.headers[col], data.unwrap_or(&Data::Empty), expected
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use export_opinion::address::AddressParts;
+
+fn complete() -> AddressParts {
+    AddressParts {
+        address_1: Some("12 Main St".to_string()),
+        address_2: Some("Suite 4".to_string()),
+        city: Some("Springfield".to_string()),
+        state_province: Some("IL".to_string()),
+        postal_code: Some("62701".to_string()),
+        country: Some("US".to_string()),
+    }
+}
+
+#[test]
+fn complete_address_keeps_the_usual_layout() {
+    assert_eq!(complete().full_address().as_deref(), Some("12 Main St, Suite 4, Springfield, IL 62701, US"));
+    assert_eq!(complete().missing_parts(), 0);
+}
+
+#[test]
+fn each_missing_component_is_skipped() {
+    let cases = [
+        (AddressParts { address_1: None, ..complete() }, "Suite 4, Springfield, IL 62701, US", 1),
+        (AddressParts { address_2: None, ..complete() }, "12 Main St, Springfield, IL 62701, US", 0),
+        (AddressParts { city: None, ..complete() }, "12 Main St, Suite 4, IL 62701, US", 1),
+        (AddressParts { state_province: None, ..complete() }, "12 Main St, Suite 4, Springfield, 62701, US", 1),
+        (AddressParts { postal_code: None, ..complete() }, "12 Main St, Suite 4, Springfield, IL, US", 1),
+        (AddressParts { country: Some("  ".to_string()), ..complete() }, "12 Main St, Suite 4, Springfield, IL 62701", 1),
+    ];
+    for (address, expected, missing) in cases {
+        assert_eq!(address.full_address().as_deref(), Some(expected));
+        assert_eq!(address.missing_parts(), missing, "{}", expected);
+    }
+}
+
+#[test]
+fn empty_address_has_no_text_and_every_part_missing() {
+    let empty = AddressParts::default();
+    assert_eq!(empty.full_address(), None);
+    assert_eq!(empty.missing_parts(), 5);
+}
@@ -0,0 +1,28 @@
+use export_opinion::data_fetch::AliasSources;
+
+#[test]
+fn no_alias_source_means_null() {
+    assert_eq!(AliasSources::default().expression("e"), "NULL::text");
+}
+
+#[test]
+fn entity_array_names_are_trimmed_distinct_and_sorted() {
+    let sources = AliasSources { entity_alternate_names: true, organization_alternate_name: false };
+    let expression = sources.expression("e");
+    assert!(expression.contains("unnest(e.alternate_names)"), "{}", expression);
+    assert!(expression.contains("string_agg(DISTINCT a.alternate_name, ', ' ORDER BY a.alternate_name)"), "{}", expression);
+    // Blank names and names repeating the primary name are left out
+    assert!(expression.contains("a.alternate_name <> ''"), "{}", expression);
+    assert!(expression.contains("lower(COALESCE(btrim(e.name), ''))"), "{}", expression);
+    assert!(!expression.contains(r#""organization""#), "{}", expression);
+}
+
+#[test]
+fn both_sources_are_combined() {
+    let sources = AliasSources { entity_alternate_names: true, organization_alternate_name: true };
+    let expression = sources.expression("e");
+    assert!(expression.contains("unnest(e.alternate_names) n UNION ALL SELECT btrim(ao.alternate_name)"), "{}", expression);
+    assert!(expression.contains("ao.id = e.organization_id"), "{}", expression);
+    // Without SOURCE_SCHEMA the source tables are in "public"
+    assert!(expression.contains(r#"FROM "public"."organization" ao"#), "{}", expression);
+}
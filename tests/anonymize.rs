@@ -0,0 +1,94 @@
+use export_opinion::anonymize::{AnonymizationKey, Anonymizer};
+use export_opinion::models::{OrganizationExportRow, ServiceExportRow};
+
+fn key(secret: &str) -> AnonymizationKey {
+    AnonymizationKey::new(secret.as_bytes()).unwrap()
+}
+
+#[test]
+fn pseudonyms_are_deterministic_per_key() {
+    let first = key("alpha");
+    let second = key("beta");
+    let name = Anonymizer::new(&first).pseudonym("Org", "Food Bank of King County").unwrap();
+    assert!(name.starts_with("Org-") && name.len() == 16, "{}", name);
+    // A new anonymizer, as in the next export, maps the value the same way
+    assert_eq!(Anonymizer::new(&first).pseudonym("Org", " Food Bank of King County ").unwrap(), name);
+    assert_ne!(Anonymizer::new(&second).pseudonym("Org", "Food Bank of King County").unwrap(), name);
+    assert_ne!(first.key_id(), second.key_id());
+    assert!(AnonymizationKey::new(b"").is_err());
+}
+
+#[test]
+fn pseudonyms_do_not_depend_on_insertion_order() {
+    let key = key("alpha");
+    let values: Vec<String> = (0..2_000).map(|i| format!("Organization {}", i)).collect();
+    let mut forward = Anonymizer::new(&key);
+    let mut backward = Anonymizer::new(&key);
+    let ahead: Vec<String> = values.iter().map(|v| forward.pseudonym("Org", v).unwrap()).collect();
+    let behind: Vec<String> = values.iter().rev().map(|v| backward.pseudonym("Org", v).unwrap()).collect();
+    assert!(behind.into_iter().rev().eq(ahead.iter().cloned()));
+    assert!(ahead.iter().all(|p| p.len() == "Org-".len() + 12), "{}", ahead[0]);
+}
+
+fn org(name: &str) -> OrganizationExportRow {
+    OrganizationExportRow {
+        contributor: Some("King County".to_string()),
+        contributor_id: Some("kc-17".to_string()),
+        entity_id: "e1".to_string(),
+        name: Some(name.to_string()),
+        alternate_names: Some("FB, The Pantry".to_string()),
+        cluster_confirmed_status: "CONFIRMED".to_string(),
+        cluster: Some("c1".to_string()),
+        has_duplicates: true,
+        cluster_representative_name: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+fn svc(organization_name: &str) -> ServiceExportRow {
+    ServiceExportRow {
+        contributor: Some("King County".to_string()),
+        contributor_id: Some("kc-svc-3".to_string()),
+        service_id: "s1".to_string(),
+        organization_name: Some(organization_name.to_string()),
+        service_name: Some("Hot Meals".to_string()),
+        location_name: Some("Main Hall".to_string()),
+        full_address: Some("1 Main St, Seattle, WA 98101".to_string()),
+        address_missing_parts: Some(0),
+        latitude: Some(47.6),
+        longitude: Some(-122.3),
+        cluster_confirmed_status: "CONFIRMED".to_string(),
+        taxonomy_terms: Some("Food".to_string()),
+        taxonomy_count: 1,
+        cluster: Some("c2".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn rows_lose_names_addresses_and_contributor_ids() {
+    let key = key("alpha");
+    let mut anonymizer = Anonymizer::new(&key);
+    let mut orgs = vec![org("Food Bank")];
+    let mut svcs = vec![svc("Food Bank")];
+    anonymizer.anonymize_organizations(&mut orgs).unwrap();
+    anonymizer.anonymize_services(&mut svcs).unwrap();
+
+    let org_name = orgs[0].name.clone().unwrap();
+    assert!(org_name.starts_with("Org-"));
+    assert_eq!(orgs[0].cluster_representative_name.as_deref(), Some(org_name.as_str()));
+    assert_eq!(orgs[0].contributor_id, None);
+    let aliases = orgs[0].alternate_names.clone().unwrap();
+    assert_eq!(aliases.split(", ").filter(|a| a.starts_with("Org-")).count(), 2, "{}", aliases);
+    // Cluster structure and the dataset stay visible
+    assert_eq!(orgs[0].cluster.as_deref(), Some("c1"));
+    assert_eq!(orgs[0].contributor.as_deref(), Some("King County"));
+
+    // The same organization has the same pseudonym on the Services sheet
+    assert_eq!(svcs[0].organization_name.as_deref(), Some(org_name.as_str()));
+    assert!(svcs[0].service_name.as_deref().unwrap().starts_with("Svc-"));
+    assert!(svcs[0].location_name.as_deref().unwrap().starts_with("Loc-"));
+    assert!(svcs[0].full_address.as_deref().unwrap().starts_with("Addr-"));
+    assert_eq!((svcs[0].latitude, svcs[0].longitude, svcs[0].contributor_id.as_deref()), (None, None, None));
+    assert_eq!(svcs[0].taxonomy_terms.as_deref(), Some("Food"));
+}
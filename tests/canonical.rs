@@ -0,0 +1,105 @@
+use export_opinion::canonical::{mark_canonical_records, CanonicalSelection, CanonicalStrategy};
+use export_opinion::models::{OrganizationExportRow, ServiceExportRow};
+
+fn org(id: &str, contributor: Option<&str>, name: Option<&str>, cluster: Option<&str>) -> OrganizationExportRow {
+    OrganizationExportRow {
+        contributor: contributor.map(str::to_string),
+        entity_id: id.to_string(),
+        name: name.map(str::to_string),
+        cluster_confirmed_status: "CONFIRMED_MATCH".to_string(),
+        cluster: cluster.map(str::to_string),
+        has_duplicates: cluster.is_some(),
+        ..Default::default()
+    }
+}
+
+fn svc(id: &str, contributor: Option<&str>, address: Option<&str>, cluster: Option<&str>) -> ServiceExportRow {
+    ServiceExportRow {
+        contributor: contributor.map(str::to_string),
+        service_id: id.to_string(),
+        service_name: Some("Pantry".to_string()),
+        full_address: address.map(str::to_string),
+        address_missing_parts: address.map(|_| 0),
+        cluster_confirmed_status: "PENDING_REVIEW".to_string(),
+        cluster: cluster.map(str::to_string),
+        has_duplicates: cluster.is_some(),
+        ..Default::default()
+    }
+}
+
+fn selection(strategy: CanonicalStrategy, priority: &[&str]) -> CanonicalSelection {
+    CanonicalSelection { strategy, source_priority: priority.iter().map(|s| s.to_string()).collect() }
+}
+
+fn canonical_ids(rows: &[OrganizationExportRow]) -> Vec<&str> {
+    let mut ids: Vec<&str> = rows.iter().filter(|r| r.canonical_record).map(|r| r.entity_id.as_str()).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn completeness_prefers_the_fullest_record() {
+    let mut rows = vec![
+        org("e1", Some("a"), None, Some("c1")),
+        org("e2", Some("b"), Some("Food Bank"), Some("c1")),
+        org("e3", Some("a"), Some("   "), Some("c1")),
+    ];
+    mark_canonical_records(&mut rows, &selection(CanonicalStrategy::Completeness, &[]));
+    assert_eq!(canonical_ids(&rows), vec!["e2"]);
+
+    let mut services = vec![
+        svc("s1", Some("a"), None, Some("c1")),
+        svc("s2", Some("a"), Some("1 Main St"), Some("c1")),
+    ];
+    mark_canonical_records(&mut services, &selection(CanonicalStrategy::Completeness, &[]));
+    assert!(!services[0].canonical_record);
+    assert!(services[1].canonical_record);
+}
+
+#[test]
+fn source_priority_prefers_the_listed_source() {
+    let mut rows = vec![
+        org("e1", Some("alpha"), Some("Food Bank"), Some("c1")),
+        org("e2", Some("Beta"), None, Some("c1")),
+        org("e3", Some("gamma"), Some("Food Bank"), Some("c1")),
+    ];
+    mark_canonical_records(&mut rows, &selection(CanonicalStrategy::SourcePriority, &["beta", "alpha"]));
+    assert_eq!(canonical_ids(&rows), vec!["e2"]);
+
+    // Unlisted sources fall back to completeness, then lowest id.
+    mark_canonical_records(&mut rows, &selection(CanonicalStrategy::SourcePriority, &["delta"]));
+    assert_eq!(canonical_ids(&rows), vec!["e1"]);
+}
+
+#[test]
+fn lowest_id_and_ties_resolve_deterministically() {
+    let fixture = vec![
+        org("e3", Some("a"), Some("Food Bank"), Some("c1")),
+        org("e2", Some("a"), Some("Food Bank"), Some("c1")),
+        org("e9", Some("a"), None, Some("c1")),
+    ];
+    for strategy in [CanonicalStrategy::Completeness, CanonicalStrategy::SourcePriority, CanonicalStrategy::LowestId] {
+        let mut rows = fixture.clone();
+        mark_canonical_records(&mut rows, &selection(strategy, &[]));
+        assert_eq!(canonical_ids(&rows), vec!["e2"], "{strategy:?}");
+
+        let mut reversed: Vec<_> = fixture.iter().rev().cloned().collect();
+        mark_canonical_records(&mut reversed, &selection(strategy, &[]));
+        assert_eq!(canonical_ids(&reversed), vec!["e2"], "{strategy:?} reversed");
+    }
+}
+
+#[test]
+fn one_canonical_per_multi_member_cluster_only() {
+    let mut rows = vec![
+        org("e1", Some("a"), Some("A"), Some("c1")),
+        org("e2", Some("a"), Some("A"), Some("c1")),
+        org("e3", Some("a"), Some("B"), Some("c2")),
+        org("e4", Some("a"), Some("C"), None),
+        org("e5", Some("a"), Some("D"), Some("c3")),
+        org("e6", Some("a"), Some("D"), Some("c3")),
+    ];
+    rows[2].canonical_record = true;
+    mark_canonical_records(&mut rows, &selection(CanonicalStrategy::LowestId, &[]));
+    assert_eq!(canonical_ids(&rows), vec!["e1", "e5"]);
+}
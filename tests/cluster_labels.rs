@@ -0,0 +1,43 @@
+use export_opinion::cluster_labels::{ClusterLabelStyle, LabelMap};
+
+#[test]
+fn entity_and_service_sheets_share_labels() {
+    let entity_clusters = ["c-b", "c-a", "c-b", "shared"];
+    let service_clusters = ["shared", "s-z", "c-a"];
+
+    for style in [ClusterLabelStyle::Sequential, ClusterLabelStyle::Short] {
+        let forward = LabelMap::build(style, entity_clusters.iter().chain(service_clusters.iter()).copied());
+        let backward = LabelMap::build(style, service_clusters.iter().rev().chain(entity_clusters.iter().rev()).copied());
+        for id in entity_clusters.iter().chain(service_clusters.iter()) {
+            assert_eq!(forward.label(id), backward.label(id), "{style:?} {id}");
+        }
+    }
+}
+
+#[test]
+fn sequential_labels_follow_sorted_cluster_ids() {
+    let labels = LabelMap::build(ClusterLabelStyle::Sequential, ["b", "a", "c", "a"]);
+    assert_eq!(labels.label("a"), "C-000001");
+    assert_eq!(labels.label("b"), "C-000002");
+    assert_eq!(labels.label("c"), "C-000003");
+    assert_eq!(labels.label("unknown"), "unknown");
+    assert!(!labels.is_full());
+}
+
+#[test]
+fn short_labels_fall_back_to_full_ids_on_collision() {
+    let a = "1234abcd-0000-4000-8000-000000000001";
+    let b = "1234abcd-0000-4000-8000-000000000002";
+    let c = "9876fedc-0000-4000-8000-000000000003";
+    let labels = LabelMap::build(ClusterLabelStyle::Short, [a, b, c]);
+    assert_eq!(labels.label(a), a);
+    assert_eq!(labels.label(b), b);
+    assert_eq!(labels.label(c), "9876fedc");
+}
+
+#[test]
+fn full_style_keeps_ids() {
+    let labels = LabelMap::build(ClusterLabelStyle::Full, ["abc"]);
+    assert!(labels.is_full());
+    assert_eq!(labels.label("abc"), "abc");
+}
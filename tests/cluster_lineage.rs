@@ -0,0 +1,86 @@
+use export_opinion::cluster_lineage::{apply_reused_ids, derived_from_note, match_original_clusters, OriginalClusters};
+use std::collections::{HashMap, HashSet};
+
+fn clusters(groups: &[(&str, &[&str])]) -> HashMap<String, HashSet<String>> {
+    groups.iter()
+        .map(|(id, members)| (id.to_string(), members.iter().map(|m| m.to_string()).collect()))
+        .collect()
+}
+
+/// Pipeline clusters p1 = {a, b, c} and p2 = {d, e}, as the group tables list them.
+fn original() -> OriginalClusters {
+    let mut original = OriginalClusters::default();
+    original.insert("p1", "a", "b");
+    original.insert("p1", "b", "c");
+    original.insert("p2", "d", "e");
+    original.names.insert("p1".to_string(), "EntityCluster-p1".to_string());
+    original
+}
+
+#[test]
+fn an_unchanged_cluster_reuses_its_original_id() {
+    let mut new = clusters(&[("n1", &["a", "b", "c"]), ("n2", &["d", "e"]), ("n3", &["z"])]);
+    let lineage = match_original_clusters(&new, &original());
+    assert_eq!(lineage.reused, HashMap::from([("n1".to_string(), "p1".to_string()), ("n2".to_string(), "p2".to_string())]));
+    // z was in no pipeline cluster, so it has no lineage
+    assert!(lineage.derived_from.is_empty());
+
+    let mut membership: HashMap<String, String> = new.iter()
+        .flat_map(|(id, members)| members.iter().map(move |m| (m.clone(), id.clone())))
+        .collect();
+    apply_reused_ids(&lineage, &mut new, &mut membership);
+    let mut ids: Vec<&String> = new.keys().collect();
+    ids.sort();
+    assert_eq!(ids, vec!["n3", "p1", "p2"]);
+    assert_eq!((membership["a"].as_str(), membership["e"].as_str(), membership["z"].as_str()), ("p1", "p2", "n3"));
+}
+
+#[test]
+fn split_and_merged_clusters_get_new_ids_derived_from_the_originals() {
+    // p1 was split into {a} and {b, c}; c then joined p2's records
+    let new = clusters(&[("n1", &["a"]), ("n2", &["b", "c", "d", "e"])]);
+    let lineage = match_original_clusters(&new, &original());
+    assert!(lineage.reused.is_empty());
+    assert_eq!(lineage.derived_from["n1"], vec!["p1"]);
+    assert_eq!(lineage.derived_from["n2"], vec!["p1", "p2"]);
+    assert_eq!(derived_from_note(&lineage.derived_from["n2"]), " Derived from p1, p2.");
+
+    // A record that was in no original cluster joining p2 also changes it
+    let grown = clusters(&[("n1", &["a", "b", "c"]), ("n2", &["d", "e", "z"])]);
+    let lineage = match_original_clusters(&grown, &original());
+    assert_eq!(lineage.reused.len(), 1);
+    assert_eq!(lineage.derived_from["n2"], vec!["p2"]);
+}
+
+#[test]
+fn records_outside_the_export_do_not_count_as_changes() {
+    // c is in another team's dataset and was never exported
+    let new = clusters(&[("n1", &["a", "b"]), ("n2", &["d", "e"])]);
+    let lineage = match_original_clusters(&new, &original());
+    assert_eq!(lineage.reused["n1"], "p1");
+    assert_eq!(lineage.reused["n2"], "p2");
+}
+
+#[test]
+fn a_cluster_that_grew_and_split_keeps_its_new_id() {
+    // p1 lost c to its own cluster and gained z, which was in no pipeline cluster; the
+    // size still matches p1's
+    let new = clusters(&[("n1", &["a", "b", "z"]), ("n2", &["c"]), ("n3", &["d", "e"])]);
+    let lineage = match_original_clusters(&new, &original());
+    assert_eq!(lineage.reused, HashMap::from([("n3".to_string(), "p2".to_string())]));
+    assert_eq!(lineage.derived_from["n1"], vec!["p1"]);
+    assert_eq!(lineage.derived_from["n2"], vec!["p1"]);
+}
+
+#[test]
+fn an_original_id_is_reused_by_one_cluster_only() {
+    let mut new = clusters(&[("n1", &["d", "e"]), ("n2", &["d", "e"])]);
+    let lineage = match_original_clusters(&new, &original());
+    assert_eq!(lineage.reused, HashMap::from([("n1".to_string(), "p2".to_string())]));
+    assert_eq!(lineage.derived_from["n2"], vec!["p2"]);
+
+    let mut membership = HashMap::new();
+    apply_reused_ids(&lineage, &mut new, &mut membership);
+    assert_eq!(new.len(), 2);
+    assert!(new.contains_key("p2") && new.contains_key("n2"));
+}
@@ -0,0 +1,59 @@
+use std::collections::{HashMap, HashSet};
+
+use export_opinion::reclustering::{clusters_in_sequence, ClusterNaming, ClusterTemplate, ClusterTemplateValues};
+
+fn values(seq: usize, size: usize) -> ClusterTemplateValues<'static> {
+    ClusterTemplateValues {
+        seq,
+        cluster_id: "1a2b3c4d-5e6f-7a8b-9c0d-ef1234567890",
+        size,
+        record_type: "entity",
+        opinion: "default",
+    }
+}
+
+#[test]
+fn placeholders_are_substituted() {
+    let template: ClusterTemplate = "DUP-{seq} {TYPE}/{type} {short_id} x{size} ({opinion})".parse().unwrap();
+    assert_eq!(template.render(&values(7, 3)), "DUP-7 ENTITY/entity 1a2b3c4d x3 (default)");
+}
+
+#[test]
+fn defaults_reproduce_the_previous_names() {
+    let naming = ClusterNaming::default();
+    assert_eq!(naming.name.render(&values(1, 2)), "ENTITYCluster-1a2b3c4d");
+    assert_eq!(
+        naming.description.render(&values(1, 2)),
+        "Re-clustered entity of 2 entitys based on user opinion 'default' (whitelisted datasets only)."
+    );
+}
+
+#[test]
+fn unknown_placeholders_and_unclosed_braces_are_rejected() {
+    let unknown = "DUP-{sequence}".parse::<ClusterTemplate>().unwrap_err();
+    assert!(unknown.contains("unknown placeholder '{sequence}'"), "{}", unknown);
+    assert!("DUP-{seq".parse::<ClusterTemplate>().unwrap_err().contains("unclosed"));
+    assert!("no placeholders".parse::<ClusterTemplate>().is_ok());
+}
+
+#[test]
+fn sequence_follows_representative_ids_not_cluster_ids() {
+    let cluster = |members: &[&str]| members.iter().map(|m| m.to_string()).collect::<HashSet<String>>();
+    let first_run = HashMap::from([
+        ("ffff".to_string(), cluster(&["b", "a"])),
+        ("0000".to_string(), cluster(&["z"])),
+        ("8888".to_string(), cluster(&["m", "c"])),
+    ]);
+    // The same clusters with fresh random ids
+    let second_run = HashMap::from([
+        ("1234".to_string(), cluster(&["a", "b"])),
+        ("abcd".to_string(), cluster(&["z"])),
+        ("0001".to_string(), cluster(&["c", "m"])),
+    ]);
+
+    let order = |clusters: &HashMap<String, HashSet<String>>| -> Vec<String> {
+        clusters_in_sequence(clusters).into_iter().map(|(_, members)| members.iter().min().unwrap().clone()).collect()
+    };
+    assert_eq!(order(&first_run), ["a", "c", "z"]);
+    assert_eq!(order(&first_run), order(&second_run));
+}
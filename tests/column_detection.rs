@@ -0,0 +1,48 @@
+use export_opinion::export_schema::{first_present_column, ReviewColumns};
+use export_opinion::team_utils::DISCONNECT_COLUMN_NAMES;
+
+fn columns(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+// auth.opinions as production and staging shape it
+const PRODUCTION_OPINIONS: [&str; 5] = ["id", "name", "user_id", "other_users", "disconnectdependentservices"];
+const STAGING_OPINIONS: [&str; 5] = ["id", "name", "user_id", "other_users", "disconnect_dependent_services"];
+
+#[test]
+fn finds_the_disconnect_column_under_either_name() {
+    assert_eq!(
+        first_present_column(&columns(&PRODUCTION_OPINIONS), &DISCONNECT_COLUMN_NAMES),
+        Some("disconnectdependentservices")
+    );
+    assert_eq!(
+        first_present_column(&columns(&STAGING_OPINIONS), &DISCONNECT_COLUMN_NAMES),
+        Some("disconnect_dependent_services")
+    );
+    assert_eq!(first_present_column(&columns(&["id", "name"]), &DISCONNECT_COLUMN_NAMES), None);
+}
+
+#[test]
+fn earlier_candidates_win_when_both_exist() {
+    let both = columns(&["disconnect_dependent_services", "disconnectdependentservices"]);
+    assert_eq!(first_present_column(&both, &DISCONNECT_COLUMN_NAMES), Some("disconnectdependentservices"));
+}
+
+#[test]
+fn review_columns_follow_the_edge_table_shape() {
+    let current = columns(&["id", "confirmed_status", "updated_at", "reviewed_at", "reviewed_by"]);
+    assert_eq!(
+        ReviewColumns::from_columns(&current),
+        ReviewColumns { updated_at: true, reviewed_at: true, reviewed_by: Some("reviewed_by") }
+    );
+    let legacy = columns(&["id", "confirmed_status", "updated_at"]);
+    assert_eq!(ReviewColumns::from_columns(&legacy), ReviewColumns { updated_at: true, ..ReviewColumns::default() });
+}
+
+#[test]
+fn a_reviewer_id_column_names_the_reviewer() {
+    let tenant = columns(&["id", "confirmed_status", "reviewer_id"]);
+    let review_columns = ReviewColumns::from_columns(&tenant);
+    assert_eq!(review_columns.reviewed_by, Some("reviewer_id"));
+    assert_eq!(review_columns.reviewer_expression("ev"), "ev.reviewer_id::text");
+}
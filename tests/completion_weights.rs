@@ -0,0 +1,46 @@
+use export_opinion::dashboard::{weighted_completion_percentage, CompletionWeights, ReviewStats};
+
+#[test]
+fn default_weights_match_raw_record_percentage() {
+    // 30 of 100 entity edges and 10 of 50 service edges reviewed: 40 / 150 overall
+    let entity = ReviewStats::new(70, 20, 10);
+    let service = ReviewStats::new(40, 5, 5);
+    let pct = weighted_completion_percentage(&entity, &service, CompletionWeights::default());
+    assert!((pct - 40.0 / 150.0 * 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn service_weight_counts_services_more() {
+    let entity = ReviewStats::new(70, 20, 10);
+    let service = ReviewStats::new(40, 5, 5);
+    let weights: CompletionWeights = "entity=1,service=2".parse().unwrap();
+    let pct = weighted_completion_percentage(&entity, &service, weights);
+    // (30 + 2 * 10) / (100 + 2 * 50)
+    assert!((pct - 50.0 / 200.0 * 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn empty_stats_give_zero() {
+    let empty = ReviewStats::new(0, 0, 0);
+    assert_eq!(weighted_completion_percentage(&empty, &empty, CompletionWeights::default()), 0.0);
+}
+
+#[test]
+fn parses_partial_and_rejects_invalid_weights() {
+    assert_eq!("service=3".parse::<CompletionWeights>(), Ok(CompletionWeights { entity: 1.0, service: 3.0 }));
+    assert_eq!(" entity = 0.5 , service=1 ".parse::<CompletionWeights>(), Ok(CompletionWeights { entity: 0.5, service: 1.0 }));
+    for invalid in ["org=1", "entity", "entity=abc", "entity=-1", "entity=0,service=0"] {
+        assert!(invalid.parse::<CompletionWeights>().is_err(), "accepted: {}", invalid);
+    }
+}
+
+#[test]
+fn completion_is_a_fraction_and_not_applicable_without_records() {
+    let stats = ReviewStats::new(23, 15, 2);
+    assert_eq!(stats.completion_fraction(), Some(17.0 / 40.0));
+    assert_eq!(stats.display_percentage(), "42.5%");
+
+    let empty = ReviewStats::new(0, 0, 0);
+    assert_eq!(empty.completion_fraction(), None);
+    assert_eq!(empty.display_percentage(), "n/a");
+}
@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use export_opinion::confirmed_status::{ConfirmedStatus, StatusTally, UnmappedStatus};
+use export_opinion::models::RawEdgeVisualization;
+use export_opinion::reclustering::classify_edges;
+use export_opinion::run_warnings::{RunWarnings, WarningCategory};
+use serde_json::json;
+
+#[test]
+fn legacy_synonyms_map_to_their_status() {
+    assert_eq!(ConfirmedStatus::from_db("CONFIRMED_MATCH"), Some(ConfirmedStatus::ConfirmedMatch));
+    assert_eq!(ConfirmedStatus::from_db("MATCH"), Some(ConfirmedStatus::ConfirmedMatch));
+    assert_eq!(ConfirmedStatus::from_db(" non_match "), Some(ConfirmedStatus::ConfirmedNonMatch));
+    assert_eq!(ConfirmedStatus::from_db("PENDING_REVIEW"), Some(ConfirmedStatus::PendingReview));
+    assert_eq!(ConfirmedStatus::from_db("MAYBE"), None);
+
+    let case = ConfirmedStatus::sql_case("ev.confirmed_status");
+    assert!(case.contains("IN ('CONFIRMED_MATCH', 'MATCH') THEN 'CONFIRMED_MATCH'"), "{}", case);
+    assert!(case.ends_with("ELSE 'PENDING_REVIEW' END)"), "{}", case);
+}
+
+#[test]
+fn unmapped_statuses_still_add_up_to_the_edge_count() {
+    // Grouped counts as the dashboard query returns them
+    let rows = [
+        (Some("CONFIRMED_MATCH"), 10),
+        (Some("MATCH"), 4),
+        (Some("NON_MATCH"), 3),
+        (Some("PENDING_REVIEW"), 20),
+        (None, 2),
+        (Some("SKIPPED"), 1),
+    ];
+    let mut tally = StatusTally::default();
+    for (status, count) in rows {
+        tally.add(status, count);
+    }
+
+    assert_eq!((tally.confirmed_match, tally.confirmed_non_match, tally.pending_review), (14, 3, 23));
+    assert_eq!(tally.total(), rows.iter().map(|(_, count)| count).sum::<i64>());
+    assert_eq!(tally.unmapped, vec![
+        UnmappedStatus { value: None, count: 2 },
+        UnmappedStatus { value: Some("SKIPPED".to_string()), count: 1 },
+    ]);
+}
+
+#[test]
+fn reclustering_reads_legacy_statuses_and_records_unknown_ones() {
+    let edge = |id: &str, id_2: &str, status: Option<&str>| RawEdgeVisualization {
+        id: id.to_string(),
+        entity_id_1: Some("a".to_string()),
+        entity_id_2: Some(id_2.to_string()),
+        service_id_1: None,
+        service_id_2: None,
+        confirmed_status: status.map(str::to_string),
+        details: Some(json!({ "calculated_edge_weight": 1.0 })),
+        updated_at: None,
+        reviewed_at: None,
+        reviewed_by: None,
+    };
+    let existing: HashSet<String> = ["a", "b", "c", "d", "e"].iter().map(|s| s.to_string()).collect();
+    let edges = vec![
+        edge("legacy_match", "b", Some("MATCH")),
+        edge("legacy_non_match", "c", Some("NON_MATCH")),
+        edge("null", "d", None),
+        edge("unknown", "e", Some("SKIPPED")),
+    ];
+    let warnings = RunWarnings::new();
+
+    let classified = classify_edges(&edges, "entity", &existing, None, &warnings);
+
+    let statuses: Vec<&str> = classified.valid.iter().map(|e| e.status.as_str()).collect();
+    assert_eq!(statuses, ["CONFIRMED_MATCH", "PENDING_REVIEW", "PENDING_REVIEW"]);
+    let summaries = warnings.summaries();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!((summaries[0].category, summaries[0].count), (WarningCategory::UnmappedStatus, 2));
+}
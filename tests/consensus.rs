@@ -0,0 +1,55 @@
+use export_opinion::consensus::ConsensusBuilder;
+use export_opinion::models::OrganizationExportRow;
+
+fn org(id: &str, cluster: &str, status: &str) -> OrganizationExportRow {
+    OrganizationExportRow {
+        contributor: Some("wa211".to_string()),
+        entity_id: id.to_string(),
+        name: Some(format!("Org {}", id)),
+        cluster_confirmed_status: status.to_string(),
+        cluster: Some(cluster.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reviewers_agree_only_with_the_same_partners() {
+    let mut builder = ConsensusBuilder::new();
+    // Cluster ids differ between reviewers; only the members are compared
+    builder.add_reviewer("ann", vec![
+        org("e1", "a1", "CONFIRMED"), org("e2", "a1", "CONFIRMED"), org("e3", "a2", "NO_MATCH"),
+    ]);
+    builder.add_reviewer("bo", vec![
+        org("e1", "b1", "CONFIRMED"), org("e2", "b1", "CONFIRMED"), org("e3", "b1", "PENDING_REVIEW"),
+    ]);
+    let (reviewers, rows) = builder.build();
+
+    assert_eq!(reviewers, vec!["ann", "bo"]);
+    let ids: Vec<&str> = rows.iter().map(|r| r.entity_id.as_str()).collect();
+    assert_eq!(ids, vec!["e1", "e2", "e3"]);
+    assert!(rows.iter().all(|r| !r.agreement));
+    assert_eq!(rows[0].placements, vec![
+        Some("C-000001 (CONFIRMED)".to_string()),
+        Some("C-000001 (CONFIRMED)".to_string()),
+    ]);
+    assert_eq!(rows[2].placements[0].as_deref(), Some("NO_MATCH"));
+}
+
+#[test]
+fn missing_reviewer_data_is_empty_and_not_a_disagreement() {
+    let mut builder = ConsensusBuilder::new();
+    builder.add_reviewer("ann", vec![org("e1", "a1", "CONFIRMED"), org("e2", "a1", "CONFIRMED")]);
+    builder.add_reviewer("bo", vec![org("e1", "b1", "CONFIRMED"), org("e2", "b1", "CONFIRMED"), org("e9", "b2", "NO_MATCH")]);
+    builder.add_reviewer("cy", vec![]);
+    let (_, rows) = builder.build();
+
+    assert_eq!(rows.len(), 3);
+    for row in &rows {
+        assert!(row.agreement, "{}", row.entity_id);
+        assert_eq!(row.placements[2], None);
+    }
+    assert_eq!(rows[0].reviewers_with_data, 2);
+    let e9 = rows.iter().find(|r| r.entity_id == "e9").unwrap();
+    assert_eq!(e9.placements[0], None);
+    assert_eq!(e9.reviewers_with_data, 1);
+}
@@ -0,0 +1,220 @@
+use chrono::NaiveDate;
+use export_opinion::copy_load::{
+    copy_bool, copy_into_export_table, copy_text_line, copy_timestamp, escape_copy_text, CopyRow, InsertMethod, COPY_ROW_THRESHOLD,
+};
+use export_opinion::db_connect::{self, PgPool};
+use export_opinion::export_schema;
+use export_opinion::models::RecordScope;
+use export_opinion::reclustering::{run_reclustering, ClusterNaming, ReclusterOptions};
+use export_opinion::run_warnings::RunWarnings;
+use export_opinion::sql_guard::{self, execute_export_write};
+use export_opinion::table_names::{export_table, hsds_table, qualified, source_table, EXPORT_SCHEMA, TEAM_SCHEMA};
+use export_opinion::team_utils::{DatasetFilterOptions, TeamInfo};
+use serde_json::json;
+use tokio_postgres::Client;
+
+#[test]
+fn special_characters_are_escaped_and_nulls_written_as_backslash_n() {
+    assert_eq!(escape_copy_text("a\tb\nc\\d\re"), "a\\tb\\nc\\\\d\\re");
+
+    let details = json!({ "note": "line\nbreak", "path": "C:\\data" }).to_string();
+    let line = copy_text_line(&[Some("id-1".to_string()), None, Some(details), Some(copy_bool(true))]);
+
+    assert_eq!(line, "id-1\t\\N\t{\"note\":\"line\\\\nbreak\",\"path\":\"C:\\\\\\\\data\"}\tt\n");
+    assert_eq!(line.matches('\t').count(), 3);
+}
+
+#[test]
+fn timestamps_keep_microseconds() {
+    let timestamp = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap().and_hms_micro_opt(5, 6, 7, 89).unwrap();
+    assert_eq!(copy_timestamp(timestamp), "2025-03-04 05:06:07.000089");
+    assert_eq!(copy_bool(false), "f");
+}
+
+#[test]
+fn large_batches_use_copy_without_the_flag() {
+    assert_eq!(InsertMethod::for_batch(false, 10), InsertMethod::Unnest);
+    assert_eq!(InsertMethod::for_batch(false, COPY_ROW_THRESHOLD - 1), InsertMethod::Unnest);
+    assert_eq!(InsertMethod::for_batch(false, COPY_ROW_THRESHOLD), InsertMethod::Copy);
+    assert_eq!(InsertMethod::for_batch(true, 10), InsertMethod::Copy);
+    assert_eq!(InsertMethod::Copy.to_string(), "COPY");
+}
+
+const PREFIX: &str = "copy_load_test";
+const OPINION: &str = "main";
+
+/// Creates the opinion's entity edge table and the entity source rows it points at.
+/// The edge details and reviewer hold characters the COPY text format has to escape.
+async fn create_opinion(client: &Client) {
+    let edges = qualified(TEAM_SCHEMA, &source_table(PREFIX, OPINION, "entity_edge_visualization"));
+    client.batch_execute(&format!(
+        r#"
+        CREATE SCHEMA IF NOT EXISTS {team};
+        DROP TABLE IF EXISTS {edges};
+        CREATE TABLE {edges} (
+            id text PRIMARY KEY, cluster_id text, entity_id_1 text NOT NULL, entity_id_2 text NOT NULL,
+            edge_weight double precision, details jsonb, pipeline_run_id text, created_at timestamp,
+            confirmed_status text, was_reviewed boolean, updated_at timestamp, reviewed_at timestamp, reviewed_by text
+        );
+        INSERT INTO {edges} (id, entity_id_1, entity_id_2, edge_weight, details, confirmed_status, updated_at, reviewed_at, reviewed_by) VALUES
+            ('x1', '{p}a', '{p}b', 0.91, '{{"note": "line\nbreak\ttab", "path": "C:\\data", "total_confidence": 0.9}}', 'CONFIRMED_MATCH',
+             '2025-01-02 03:04:05.123456', '2025-01-02 03:04:05.5', E'rev\tiewer'),
+            ('x2', '{p}b', '{p}c', 0.5, '{{"name": "Café \"Ümlaut\""}}', 'PENDING_REVIEW', NULL, NULL, NULL),
+            ('x3', '{p}d', '{p}e', 0.2, '{{}}', 'CONFIRMED_NON_MATCH', '2025-01-01', NULL, 'reviewer');
+        CREATE TABLE IF NOT EXISTS {entity} (id text PRIMARY KEY, source_system text);
+        DELETE FROM {entity} WHERE id LIKE '{p}%';
+        INSERT INTO {entity} VALUES ('{p}a', 'copy_ds1'), ('{p}b', 'copy_ds1'), ('{p}c', 'copy_ds1'), ('{p}d', 'copy_ds1'), ('{p}e', 'copy_ds1'), ('{p}f', 'copy_ds2');
+        "#,
+        team = TEAM_SCHEMA,
+        edges = edges,
+        entity = hsds_table("entity"),
+        p = "copy_load_",
+    )).await.unwrap();
+}
+
+/// Re-clusters the opinion into the export tables of `suffix`, with COPY or UNNEST inserts.
+async fn recluster(pool: &PgPool, suffix: &str, fast_insert: bool) {
+    let mut client = sql_guard::export_write_client(pool, "test export").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    let tx = client.transaction().await.unwrap();
+    export_schema::create_timestamped_tables(&tx, PREFIX, OPINION, suffix, RecordScope::EntitiesOnly).await.unwrap();
+    let team_info = TeamInfo {
+        id: "t1".to_string(),
+        name: "test".to_string(),
+        display_name: "Test".to_string(),
+        whitelisted_datasets: vec!["copy_ds1".to_string()],
+        is_active: true,
+    };
+    let warnings = RunWarnings::new();
+    let naming = ClusterNaming::default();
+    let options = ReclusterOptions {
+        team_info: &team_info,
+        filter_options: DatasetFilterOptions::default(),
+        warnings: &warnings,
+        sample: None,
+        fast_insert,
+        naming: &naming,
+        strict: true,
+        max_skipped_edges: None,
+        overrides: None,
+        shared_review: None,
+    };
+    run_reclustering(&tx, PREFIX, OPINION, suffix, "entity", &options).await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+/// The export tables of `suffix` as text, without the generated ids and creation times
+/// that differ between any two runs. Cluster ids are replaced by the cluster's members.
+async fn export_contents(pool: &PgPool, suffix: &str) -> Vec<Vec<Option<String>>> {
+    let table = |name: &str| qualified(EXPORT_SCHEMA, &export_table(PREFIX, OPINION, name, suffix));
+    let members = format!(
+        "(SELECT string_agg(m, ',' ORDER BY m) FROM (SELECT entity_id_1 AS m FROM {0} WHERE group_cluster_id = {{}} \
+         UNION SELECT entity_id_2 FROM {0} WHERE group_cluster_id = {{}}) members)",
+        table("entity_group")
+    );
+    let cluster_members = |id: &str| members.replace("{}", id);
+    let client = pool.get().await.unwrap();
+    let mut contents = Vec::new();
+    for query in [
+        format!(
+            "SELECT {}, description, entity_count::text, group_count::text, average_coherence_score::text, was_reviewed::text \
+             FROM {} c ORDER BY 1",
+            cluster_members("c.id"), table("entity_group_cluster")
+        ),
+        format!(
+            "SELECT least(entity_id_1, entity_id_2), greatest(entity_id_1, entity_id_2), {}, method_type, confirmed_status \
+             FROM {} g ORDER BY 1, 2",
+            cluster_members("g.group_cluster_id"), table("entity_group")
+        ),
+        format!(
+            "SELECT entity_id_1, entity_id_2, {}, edge_weight::text, details::text, pipeline_run_id, confirmed_status, \
+             was_reviewed::text, updated_at::text, reviewed_at::text, reviewed_by FROM {} e ORDER BY 1, 2",
+            cluster_members("e.cluster_id"), table("entity_edge_visualization")
+        ),
+    ] {
+        for row in client.query(&query, &[]).await.unwrap() {
+            contents.push((0..row.len()).map(|i| row.get::<_, Option<String>>(i)).collect());
+        }
+        contents.push(Vec::new());
+    }
+    contents
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL"]
+async fn copy_and_unnest_inserts_write_the_same_rows() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    let client = pool.get().await.unwrap();
+    create_opinion(&client).await;
+    drop(client);
+
+    recluster(&pool, "unnest", false).await;
+    recluster(&pool, "copy", true).await;
+    let unnest = export_contents(&pool, "unnest").await;
+    assert_eq!(export_contents(&pool, "copy").await, unnest);
+    // Clusters {a, b, c}, {d}, {e}; f is outside the whitelisted dataset
+    assert_eq!(unnest.iter().take_while(|row| !row.is_empty()).count(), 3);
+    assert!(unnest.iter().flatten().any(|value| value.as_deref() == Some("rev\tiewer")));
+}
+
+/// Loads `rows` group-table rows into a fresh export table with `method`, returning the time taken.
+async fn timed_load(pool: &PgPool, rows: usize, method: InsertMethod) -> std::time::Duration {
+    let table = format!("copy_load_benchmark_{}", method.to_string().to_lowercase());
+    let table_full = qualified(EXPORT_SCHEMA, &table);
+    let columns = ["id", "entity_id_1", "entity_id_2", "group_cluster_id", "method_type", "created_at", "confirmed_status"];
+    let ids: Vec<String> = (0..rows).map(|i| format!("00000000-0000-4000-8000-{:012}", i)).collect();
+    let created_at = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap().and_hms_opt(5, 6, 7).unwrap();
+
+    let mut client = sql_guard::export_write_client(pool, "benchmark").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    client.batch_execute(&format!(
+        "DROP TABLE IF EXISTS {0}; CREATE TABLE {0} (id text PRIMARY KEY, entity_id_1 text, entity_id_2 text, \
+         group_cluster_id text, method_type text, created_at timestamp, confirmed_status text);",
+        table_full
+    )).await.unwrap();
+    let tx = client.transaction().await.unwrap();
+    let started = std::time::Instant::now();
+    match method {
+        InsertMethod::Copy => {
+            let copy_rows: Vec<CopyRow> = ids.iter()
+                .map(|id| vec![
+                    Some(id.clone()), Some(format!("e{}", id)), Some(format!("f{}", id)), Some("c1".to_string()),
+                    Some("USER_OPINION_MAIN_CONNECTED".to_string()), Some(copy_timestamp(created_at)), Some("CONFIRMED".to_string()),
+                ])
+                .collect();
+            copy_into_export_table(&tx, &table, &columns, &copy_rows).await.unwrap();
+        }
+        InsertMethod::Unnest => {
+            let id_1s: Vec<String> = ids.iter().map(|id| format!("e{}", id)).collect();
+            let id_2s: Vec<String> = ids.iter().map(|id| format!("f{}", id)).collect();
+            let clusters = vec!["c1".to_string(); rows];
+            let methods = vec!["USER_OPINION_MAIN_CONNECTED".to_string(); rows];
+            let created = vec![created_at; rows];
+            let statuses = vec!["CONFIRMED".to_string(); rows];
+            let sql = format!(
+                "INSERT INTO {} ({}) SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::text[], $6::timestamp[], $7::text[])",
+                table_full, columns.join(", ")
+            );
+            execute_export_write(&tx, &sql, &[&ids, &id_1s, &id_2s, &clusters, &methods, &created, &statuses]).await.unwrap();
+        }
+    }
+    tx.commit().await.unwrap();
+    let elapsed = started.elapsed();
+    client.batch_execute(&format!("DROP TABLE {}", table_full)).await.unwrap();
+    elapsed
+}
+
+/// Measures the COPY and UNNEST loads behind `COPY_ROW_THRESHOLD`; run with
+/// `--ignored --nocapture` to see the timings.
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL; loads 1M rows"]
+async fn benchmark_copy_against_unnest() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    for rows in [10_000, COPY_ROW_THRESHOLD, 1_000_000] {
+        let unnest = timed_load(&pool, rows, InsertMethod::Unnest).await;
+        let copy = timed_load(&pool, rows, InsertMethod::Copy).await;
+        println!("{:>9} rows: UNNEST {:>8.1?}, COPY {:>8.1?}", rows, unnest, copy);
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use export_opinion::data_fetch::fetch_organization_export_data;
+use export_opinion::db_connect;
+use export_opinion::export_schema;
+use export_opinion::models::{ExportSelection, RecordScope};
+use export_opinion::reclustering::{run_reclustering, ClusterNaming, ReclusterOptions};
+use export_opinion::run_warnings::RunWarnings;
+use export_opinion::sql_guard;
+use export_opinion::table_names::{hsds_table, qualified, source_table, TEAM_SCHEMA};
+use export_opinion::team_utils::{DatasetFilterOptions, TeamInfo};
+
+const PREFIX: &str = "cross_dataset_test";
+const OPINION: &str = "main";
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL"]
+async fn a_match_in_a_non_whitelisted_dataset_is_not_a_duplicate() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    let mut client = sql_guard::export_write_client(&pool, "test setup").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    // a's only match is g, in a dataset the team may not see; b and c are both visible
+    client.batch_execute(&format!(
+        r#"
+        CREATE SCHEMA IF NOT EXISTS {team};
+        DROP TABLE IF EXISTS {edges};
+        CREATE TABLE {edges} (
+            id text PRIMARY KEY, cluster_id text, entity_id_1 text NOT NULL, entity_id_2 text NOT NULL,
+            edge_weight double precision, details jsonb, pipeline_run_id text, created_at timestamp,
+            confirmed_status text, was_reviewed boolean
+        );
+        INSERT INTO {edges} (id, entity_id_1, entity_id_2, edge_weight, details, confirmed_status) VALUES
+            ('x1', 'cross_a', 'cross_g', 0.9, '{{}}', 'CONFIRMED_MATCH'),
+            ('x2', 'cross_b', 'cross_c', 0.9, '{{}}', 'CONFIRMED_MATCH');
+        CREATE TABLE IF NOT EXISTS {entity} (id text PRIMARY KEY, source_system text);
+        ALTER TABLE {entity} ADD COLUMN IF NOT EXISTS source_id text, ADD COLUMN IF NOT EXISTS name text;
+        DELETE FROM {entity} WHERE id LIKE 'cross_%';
+        INSERT INTO {entity} (id, source_system) VALUES
+            ('cross_a', 'cross_ds1'), ('cross_b', 'cross_ds1'), ('cross_c', 'cross_ds1'), ('cross_g', 'cross_ds2');
+        "#,
+        team = TEAM_SCHEMA,
+        edges = qualified(TEAM_SCHEMA, &source_table(PREFIX, OPINION, "entity_edge_visualization")),
+        entity = hsds_table("entity"),
+    )).await.unwrap();
+
+    let team_info = TeamInfo {
+        id: "t1".to_string(),
+        name: "test".to_string(),
+        display_name: "Test".to_string(),
+        whitelisted_datasets: vec!["cross_ds1".to_string()],
+        is_active: true,
+    };
+    let tx = client.transaction().await.unwrap();
+    export_schema::create_timestamped_tables(&tx, PREFIX, OPINION, "cross", RecordScope::EntitiesOnly).await.unwrap();
+    let warnings = RunWarnings::new();
+    let naming = ClusterNaming::default();
+    let options = ReclusterOptions {
+        team_info: &team_info,
+        filter_options: DatasetFilterOptions::default(),
+        warnings: &warnings,
+        sample: None,
+        fast_insert: false,
+        naming: &naming,
+        strict: true,
+        max_skipped_edges: None,
+        overrides: None,
+        shared_review: None,
+    };
+    run_reclustering(&tx, PREFIX, OPINION, "cross", "entity", &options).await.unwrap();
+    tx.commit().await.unwrap();
+    drop(client);
+
+    let rows = fetch_organization_export_data(
+        &pool, PREFIX, OPINION, "cross", &team_info, DatasetFilterOptions::default(), &ExportSelection::default(),
+    ).await.unwrap();
+    let duplicates: HashMap<&str, bool> = rows.iter().map(|row| (row.entity_id.as_str(), row.has_duplicates)).collect();
+    assert_eq!(duplicates, HashMap::from([("cross_a", false), ("cross_b", true), ("cross_c", true)]));
+    // a is still clustered with g, it just has no visible duplicate
+    assert!(rows.iter().find(|row| row.entity_id == "cross_a").unwrap().cluster.is_some());
+}
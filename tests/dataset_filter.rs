@@ -0,0 +1,86 @@
+use export_opinion::team_utils::{
+    check_dataset_whitelist, create_dataset_filter_clause, exclude_datasets, DatasetFilterOptions, TeamInfo,
+};
+
+fn datasets(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn normalized_filter_lowercases_and_trims_column_and_params() {
+    let (clause, params) = create_dataset_filter_clause(
+        "e", "source_system", &datasets(&[" WA211 ", "Whatcom"]), 1, DatasetFilterOptions::default(),
+    );
+
+    assert_eq!(clause, "lower(trim(e.source_system)) = ANY(ARRAY[$1, $2])");
+    assert_eq!(params, datasets(&["wa211", "whatcom"]));
+}
+
+#[test]
+fn exact_filter_keeps_column_and_params_untouched() {
+    let options = DatasetFilterOptions { normalize: false, ..Default::default() };
+    let (clause, params) = create_dataset_filter_clause(
+        "s", "source_system", &datasets(&[" WA211 "]), 3, options,
+    );
+
+    assert_eq!(clause, "s.source_system = ANY(ARRAY[$3])");
+    assert_eq!(params, datasets(&[" WA211 "]));
+}
+
+#[test]
+fn include_nulls_checks_the_raw_column() {
+    let options = DatasetFilterOptions { include_nulls: true, ..Default::default() };
+    let (clause, _) = create_dataset_filter_clause("t", "source_system", &datasets(&["wa211"]), 1, options);
+
+    assert_eq!(clause, "(lower(trim(t.source_system)) = ANY(ARRAY[$1]) OR t.source_system IS NULL)");
+}
+
+#[test]
+fn empty_whitelist_matches_everything() {
+    let (clause, params) = create_dataset_filter_clause("e", "source_system", &[], 1, DatasetFilterOptions::default());
+
+    assert_eq!(clause, "1=1");
+    assert!(params.is_empty());
+}
+
+#[test]
+fn empty_whitelist_is_refused_unless_all_datasets_are_allowed() {
+    let mut team = TeamInfo {
+        id: "t1".to_string(),
+        name: "wa211".to_string(),
+        display_name: "WA 211".to_string(),
+        whitelisted_datasets: Vec::new(),
+        is_active: true,
+    };
+    let error = check_dataset_whitelist(&team, false).unwrap_err().to_string();
+    assert!(error.contains("--allow-all-datasets"), "{}", error);
+    assert!(check_dataset_whitelist(&team, true).is_ok());
+
+    team.whitelisted_datasets = datasets(&["wa211"]);
+    assert!(check_dataset_whitelist(&team, false).is_ok());
+}
+
+#[test]
+fn excluded_datasets_are_removed_from_the_whitelist() {
+    let mut team = TeamInfo {
+        id: "t1".to_string(),
+        name: "wa211".to_string(),
+        display_name: "WA 211".to_string(),
+        whitelisted_datasets: datasets(&["WA211", "whatcom", "King"]),
+        is_active: true,
+    };
+    let options = DatasetFilterOptions::default();
+
+    let error = exclude_datasets(&mut team.clone(), &datasets(&["whatcomm"]), options).unwrap_err().to_string();
+    assert!(error.contains("whatcomm"), "{}", error);
+    let error = exclude_datasets(&mut team.clone(), &datasets(&["wa211", "whatcom", "king"]), options).unwrap_err().to_string();
+    assert!(error.contains("every whitelisted dataset"), "{}", error);
+    let exact = DatasetFilterOptions { normalize: false, ..Default::default() };
+    assert!(exclude_datasets(&mut team.clone(), &datasets(&["king"]), exact).is_err());
+
+    exclude_datasets(&mut team, &datasets(&[" wa211 ", "King"]), options).unwrap();
+    assert_eq!(team.whitelisted_datasets, datasets(&["whatcom"]));
+
+    exclude_datasets(&mut team, &[], options).unwrap();
+    assert_eq!(team.whitelisted_datasets, datasets(&["whatcom"]));
+}
@@ -0,0 +1,60 @@
+use export_opinion::models::RawEdgeVisualization;
+use export_opinion::overrides::StatusOverrides;
+use export_opinion::reclustering::{tally_dataset_pairs, RecordDatasets, NO_DATASET};
+
+fn edge(id: &str, id_1: &str, id_2: &str, status: Option<&str>) -> RawEdgeVisualization {
+    RawEdgeVisualization {
+        id: id.to_string(),
+        entity_id_1: None,
+        entity_id_2: None,
+        service_id_1: Some(id_1.to_string()),
+        service_id_2: Some(id_2.to_string()),
+        confirmed_status: status.map(str::to_string),
+        details: None,
+        updated_at: None,
+        reviewed_at: None,
+        reviewed_by: None,
+    }
+}
+
+fn datasets() -> RecordDatasets {
+    let mut datasets = RecordDatasets::default();
+    for (id, dataset) in [("a1", Some("alpha")), ("a2", Some("alpha")), ("b1", Some("beta")), ("n1", None)] {
+        datasets.insert(id, dataset);
+    }
+    datasets
+}
+
+#[test]
+fn edges_are_tallied_per_unordered_dataset_pair_and_status() {
+    let edges = vec![
+        edge("e1", "a1", "b1", Some("CONFIRMED_MATCH")),
+        edge("e2", "b1", "a2", Some("NON_MATCH")),
+        edge("e3", "a1", "a2", None),
+        edge("e4", "a2", "a1", Some("CONFIRMED_MATCH")),
+        edge("e5", "n1", "b1", Some("PENDING_REVIEW")),
+        // Outside the whitelisted datasets, a self-loop and an empty id are not counted
+        edge("e6", "a1", "x9", Some("CONFIRMED_MATCH")),
+        edge("e7", "b1", "b1", Some("CONFIRMED_MATCH")),
+        edge("e8", "", "a1", Some("CONFIRMED_MATCH")),
+    ];
+    let pairs = tally_dataset_pairs(&edges, "service", &datasets(), None);
+
+    let rows: Vec<(&str, &str, usize, usize, usize)> = pairs.iter()
+        .map(|p| (p.dataset_1.as_str(), p.dataset_2.as_str(), p.confirmed_match, p.confirmed_non_match, p.pending_review))
+        .collect();
+    assert_eq!(rows, vec![
+        (NO_DATASET, "beta", 0, 0, 1),
+        ("alpha", "alpha", 1, 0, 1),
+        ("alpha", "beta", 1, 1, 0),
+    ]);
+    assert!(pairs.iter().all(|p| p.record_type == "service"));
+    assert_eq!(pairs.iter().map(|p| p.edge_count()).sum::<usize>(), 5);
+}
+
+#[test]
+fn overrides_change_the_tallied_status() {
+    let overrides = StatusOverrides::parse_csv("edge_id,decision\ne1,CONFIRMED_NON_MATCH\n".as_bytes()).unwrap();
+    let pairs = tally_dataset_pairs(&[edge("e1", "a1", "b1", Some("CONFIRMED_MATCH"))], "service", &datasets(), Some(&overrides));
+    assert_eq!((pairs[0].confirmed_match, pairs[0].confirmed_non_match), (0, 1));
+}
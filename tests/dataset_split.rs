@@ -0,0 +1,107 @@
+use export_opinion::dataset_split::{file_name_part, partition_by_dataset, unique_file_name_parts, ForeignRows};
+use export_opinion::models::{OrganizationExportRow, ServiceExportRow};
+
+fn org(id: &str, dataset: &str, name: &str, cluster: Option<&str>) -> OrganizationExportRow {
+    OrganizationExportRow {
+        contributor: Some(dataset.to_string()),
+        contributor_id: Some(format!("src-{}", id)),
+        entity_id: id.to_string(),
+        name: Some(name.to_string()),
+        alternate_names: Some("aka".to_string()),
+        cluster_confirmed_status: "CONFIRMED_MATCH".to_string(),
+        cluster: cluster.map(str::to_string),
+        has_duplicates: cluster.is_some(),
+        cluster_representative_name: Some(name.to_string()),
+        reviewed_by: Some("alice".to_string()),
+        ..Default::default()
+    }
+}
+
+fn svc(id: &str, dataset: &str, cluster: Option<&str>) -> ServiceExportRow {
+    ServiceExportRow {
+        contributor: Some("contributor-7".to_string()),
+        // The service query returns the source system as contributor_id
+        contributor_id: Some(dataset.to_string()),
+        service_id: id.to_string(),
+        organization_name: Some("Food Bank".to_string()),
+        service_name: Some("Pantry".to_string()),
+        location_name: Some("Main St".to_string()),
+        full_address: Some("1 Main St".to_string()),
+        address_missing_parts: Some(0),
+        latitude: Some(47.6),
+        longitude: Some(-122.3),
+        cluster_confirmed_status: "PENDING_REVIEW".to_string(),
+        taxonomy_terms: Some("Food".to_string()),
+        taxonomy_count: 1,
+        cluster: cluster.map(str::to_string),
+        has_duplicates: cluster.is_some(),
+        ..Default::default()
+    }
+}
+
+fn orgs() -> Vec<OrganizationExportRow> {
+    vec![
+        org("a1", "alpha", "Alpha Pantry", Some("c1")),
+        org("b1", "beta", "Beta Pantry", Some("c1")),
+        org("b2", "beta", "Beta Shelter", Some("c2")),
+        org("a2", "alpha", "Alpha Clinic", None),
+    ]
+}
+
+#[test]
+fn each_dataset_gets_its_rows_and_minimal_matched_rows() {
+    let orgs = orgs();
+    let partitions = partition_by_dataset(Some(&orgs), None, ForeignRows::Minimal);
+    let datasets: Vec<&str> = partitions.iter().map(|p| p.dataset.as_str()).collect();
+    assert_eq!(datasets, vec!["alpha", "beta"]);
+
+    let alpha = &partitions[0];
+    assert!(alpha.svc_data.is_none());
+    let rows = alpha.org_data.as_ref().unwrap();
+    let ids: Vec<&str> = rows.iter().map(|r| r.entity_id.as_str()).collect();
+    // beta's b2 shares no cluster with alpha and is left out
+    assert_eq!(ids, vec!["a1", "b1", "a2"]);
+    assert_eq!(rows[0], orgs[0]);
+
+    let matched = &rows[1];
+    assert_eq!(matched.name.as_deref(), Some("Beta Pantry"));
+    assert_eq!(matched.contributor.as_deref(), Some("beta"));
+    assert_eq!(matched.cluster.as_deref(), Some("c1"));
+    assert_eq!((matched.contributor_id.as_ref(), matched.alternate_names.as_ref()), (None, None));
+    assert!(matched.cluster_representative_name.is_none());
+    assert_eq!((alpha.own_rows(), alpha.foreign_rows()), (2, 1));
+}
+
+#[test]
+fn id_only_and_omit_reduce_matched_rows_further() {
+    let orgs = orgs();
+    let svcs = vec![svc("s1", "alpha", Some("k1")), svc("s2", "beta", Some("k1"))];
+
+    let partitions = partition_by_dataset(Some(&orgs), Some(&svcs), ForeignRows::IdOnly);
+    let beta = &partitions[1];
+    let matched_org = &beta.org_data.as_ref().unwrap()[0];
+    assert_eq!((matched_org.entity_id.as_str(), matched_org.name.as_ref()), ("a1", None));
+    let matched_svc = &beta.svc_data.as_ref().unwrap()[0];
+    assert_eq!(matched_svc.service_id, "s1");
+    assert_eq!(matched_svc.contributor_id.as_deref(), Some("alpha"));
+    assert!(matched_svc.service_name.is_none() && matched_svc.full_address.is_none() && matched_svc.organization_name.is_none());
+    assert!(matched_svc.latitude.is_none() && matched_svc.taxonomy_terms.is_none());
+    assert_eq!(matched_svc.taxonomy_count, 0);
+
+    let partitions = partition_by_dataset(Some(&orgs), Some(&svcs), ForeignRows::Omit);
+    assert_eq!(partitions[1].foreign_rows(), 0);
+    assert_eq!(partitions[1].own_rows(), 3);
+}
+
+#[test]
+fn dataset_names_are_made_safe_for_file_names() {
+    assert_eq!(file_name_part("king_county-211"), "king_county-211");
+    assert_eq!(file_name_part("WA 211/Food"), "WA_211_Food");
+    assert_eq!(file_name_part("  "), "dataset");
+}
+
+#[test]
+fn datasets_with_the_same_file_name_get_distinct_ones() {
+    let parts = unique_file_name_parts(["a b", "a_b", "x/y", "x_y", "X_Y", "a_b_2", "king"]);
+    assert_eq!(parts, vec!["a_b", "a_b_2", "x_y", "x_y_2", "X_Y_3", "a_b_2_2", "king"]);
+}
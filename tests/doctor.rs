@@ -0,0 +1,60 @@
+use clap::Parser;
+use export_opinion::cli::{Cli, Command};
+use export_opinion::doctor::{
+    auth_tables_result, failed_checks, source_tables_result, team_schema_result, users_with_prefix_result, CheckOutcome,
+    CheckResult,
+};
+
+#[test]
+fn results_print_one_line_with_a_hint_when_not_passed() {
+    let passed = CheckResult::pass("connect", "connected to review as export");
+    assert_eq!(passed.to_string(), "✅ PASS  connect: connected to review as export");
+
+    let failed = source_tables_result("hsds", &[r#""hsds"."location""#.to_string()]);
+    assert_eq!(failed.outcome, CheckOutcome::Fail);
+    let printed = failed.to_string();
+    assert!(printed.starts_with(r#"❌ FAIL  source tables: missing "hsds"."location""#));
+    assert!(printed.contains("\n         hint: Set SOURCE_SCHEMA"));
+    assert!(printed.contains("(now 'hsds')"));
+}
+
+#[test]
+fn each_check_result_reflects_its_findings() {
+    assert_eq!(auth_tables_result(&[]).outcome, CheckOutcome::Pass);
+    let denied = auth_tables_result(&["auth.users: permission denied for table users".to_string()]);
+    assert_eq!(denied.outcome, CheckOutcome::Fail);
+    assert!(denied.detail.contains("permission denied"));
+
+    assert_eq!(team_schema_result(true, true).outcome, CheckOutcome::Pass);
+    assert!(team_schema_result(true, false).hint.unwrap().starts_with("GRANT USAGE"));
+    assert!(team_schema_result(false, false).detail.ends_with("does not exist"));
+
+    assert_eq!(source_tables_result("public", &[]).outcome, CheckOutcome::Pass);
+}
+
+#[test]
+fn only_failed_required_checks_fail_the_run() {
+    // No user with a prefix is worth a warning, but the environment itself works
+    let no_users = users_with_prefix_result(0);
+    assert_eq!(no_users.outcome, CheckOutcome::Warn);
+    assert_eq!(users_with_prefix_result(3).outcome, CheckOutcome::Pass);
+
+    let results = vec![
+        CheckResult::pass("connect", "ok"),
+        no_users,
+        team_schema_result(false, false),
+        auth_tables_result(&["auth.teams: relation does not exist".to_string()]),
+    ];
+    assert_eq!(failed_checks(&results), 2);
+    assert_eq!(failed_checks(&results[..2]), 0);
+}
+
+#[test]
+fn doctor_takes_its_own_read_only_flag() {
+    let read_only = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+        Some(Command::Doctor(doctor)) => doctor.read_only,
+        other => panic!("parsed as {:?}", other),
+    };
+    assert!(read_only(&["export", "doctor", "--read-only"]));
+    assert!(!read_only(&["export", "doctor"]));
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use export_opinion::reclustering::{connected_clusters, edge_cluster, EdgeCluster};
+use petgraph::graph::{NodeIndex, UnGraph};
+
+fn graph(edges: &[(&str, &str)]) -> UnGraph<String, ()> {
+    let mut graph = UnGraph::new_undirected();
+    let mut nodes: HashMap<&str, NodeIndex> = HashMap::new();
+    for (a, b) in edges {
+        let a_idx = *nodes.entry(a).or_insert_with(|| graph.add_node(a.to_string()));
+        let b_idx = *nodes.entry(b).or_insert_with(|| graph.add_node(b.to_string()));
+        graph.add_edge(a_idx, b_idx, ());
+    }
+    graph
+}
+
+#[test]
+fn every_edge_of_a_correct_clustering_stays_in_one_cluster() {
+    let edges = [("a", "b"), ("b", "c"), ("d", "e"), ("c", "a"), ("f", "g"), ("g", "h"), ("e", "i")];
+    let found = connected_clusters(&graph(&edges));
+
+    assert_eq!(found.clusters.len(), 3);
+    for (id_1, id_2) in edges {
+        assert!(
+            matches!(edge_cluster(&found.membership, id_1, id_2), EdgeCluster::Same(_)),
+            "edge {} - {} was split", id_1, id_2
+        );
+    }
+}
+
+#[test]
+fn endpoints_in_different_clusters_are_reported() {
+    let membership = HashMap::from([
+        ("a".to_string(), "c1".to_string()),
+        ("b".to_string(), "c1".to_string()),
+        ("x".to_string(), "c2".to_string()),
+    ]);
+
+    assert_eq!(edge_cluster(&membership, "a", "b"), EdgeCluster::Same("c1"));
+    assert_eq!(edge_cluster(&membership, "a", "x"), EdgeCluster::Split("c1", "c2"));
+    assert_eq!(edge_cluster(&membership, "unknown", "x"), EdgeCluster::Same("c2"));
+    assert_eq!(edge_cluster(&membership, "unknown", "other"), EdgeCluster::Missing);
+}
@@ -0,0 +1,57 @@
+use export_opinion::anonymize::AnonymizationKey;
+use export_opinion::dashboard::{ProgressOptions, ReviewStats, OpinionDashboard};
+use export_opinion::excel_writer::export_info_rows;
+use export_opinion::models::RecordScope;
+
+fn dashboard(opinion_owner: Option<&str>, shared_with: &[&str]) -> OpinionDashboard {
+    OpinionDashboard {
+        username: "sam".to_string(),
+        user_prefix: "sam".to_string(),
+        opinion_name: "default".to_string(),
+        opinion_owner: opinion_owner.map(str::to_string),
+        shared_with: shared_with.iter().map(|u| u.to_string()).collect(),
+        entity_stats: ReviewStats::new(1, 2, 3),
+        service_stats: ReviewStats::new(0, 0, 0),
+        trend: Vec::new(),
+        dataset_pairs: Vec::new(),
+    }
+}
+
+fn labels(rows: &[(&'static str, String)]) -> Vec<&'static str> {
+    rows.iter().map(|(label, _)| *label).collect()
+}
+
+#[test]
+fn own_unshared_opinion_has_no_ownership_rows() {
+    let rows = export_info_rows(&dashboard(None, &[]), RecordScope::Both, ProgressOptions::default());
+    assert_eq!(labels(&rows), ["User", "User Prefix", "Opinion Name", "Record Types"]);
+}
+
+#[test]
+fn shared_opinion_records_owner_exporter_and_shared_users() {
+    let rows = export_info_rows(&dashboard(Some("hannah"), &["ana", "sam"]), RecordScope::Both, ProgressOptions::default());
+    assert_eq!(
+        labels(&rows),
+        ["User", "User Prefix", "Opinion Name", "Opinion Owner", "Exported By", "Shared With", "Record Types"]
+    );
+    assert!(rows.contains(&("Opinion Owner", "hannah".to_string())));
+    assert!(rows.contains(&("Exported By", "sam".to_string())));
+    assert!(rows.contains(&("Shared With", "ana, sam".to_string())));
+}
+
+#[test]
+fn sample_export_is_flagged_on_the_first_row() {
+    let rows = export_info_rows(&dashboard(None, &[]), RecordScope::Both, ProgressOptions { sample: Some(500), ..ProgressOptions::default() });
+    assert_eq!(labels(&rows)[0], "SAMPLE");
+    assert!(rows[0].1.contains("500"));
+}
+
+#[test]
+fn anonymized_export_names_the_key_id_but_not_the_key() {
+    let key = AnonymizationKey::new(b"vendor-review-2026").unwrap();
+    let options = ProgressOptions { anonymized: Some(key.key_id()), ..ProgressOptions::default() };
+    let rows = export_info_rows(&dashboard(None, &[]), RecordScope::Both, options);
+    let (_, value) = rows.iter().find(|(label, _)| *label == "Anonymized").unwrap();
+    assert!(value.contains(&key.key_id().to_string()), "{}", value);
+    assert!(!value.contains("vendor-review-2026"), "{}", value);
+}
@@ -0,0 +1,70 @@
+use export_opinion::graph_export::{write_dot, write_graphml, ClusterGraph, GraphEdge, GraphNode, GraphSelection};
+
+fn fixture() -> ClusterGraph {
+    ClusterGraph {
+        record_type: "entity".to_string(),
+        nodes: vec![
+            GraphNode {
+                record_id: "e1".to_string(),
+                name: Some("Bread & Butter <Pantry>".to_string()),
+                cluster_id: "c1".to_string(),
+                dataset: Some("dataset_a".to_string()),
+            },
+            GraphNode { record_id: "e2".to_string(), name: None, cluster_id: "c1".to_string(), dataset: None },
+        ],
+        edges: vec![GraphEdge {
+            source: "e1".to_string(),
+            target: "e2".to_string(),
+            weight: 0.875,
+            confirmed_status: Some("CONFIRMED_MATCH".to_string()),
+            reviewed_by: Some("alice".to_string()),
+        }],
+    }
+}
+
+#[test]
+fn graphml_matches_golden_file() {
+    let mut output = Vec::new();
+    write_graphml(&mut output, &fixture()).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), include_str!("golden/cluster_graph.graphml"));
+}
+
+#[test]
+fn dot_quotes_and_escapes_values() {
+    let mut graph = fixture();
+    graph.nodes[1].name = Some("Say \"hi\"".to_string());
+    let mut output = Vec::new();
+    write_dot(&mut output, &graph).unwrap();
+    let dot = String::from_utf8(output).unwrap();
+    assert!(dot.starts_with("graph \"entity\" {\n"));
+    assert!(dot.contains("  \"e2\" [cluster_id=\"c1\", label=\"Say \\\"hi\\\"\"];\n"));
+    assert!(dot.contains("  \"e1\" -- \"e2\" [weight=0.875, confirmed_status=\"CONFIRMED_MATCH\", reviewed_by=\"alice\"];\n"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn unreviewed_edges_have_no_reviewer() {
+    let mut graph = fixture();
+    graph.edges[0].confirmed_status = Some("PENDING_REVIEW".to_string());
+    graph.edges[0].reviewed_by = None;
+    let mut output = Vec::new();
+    write_graphml(&mut output, &graph).unwrap();
+    let graphml = String::from_utf8(output).unwrap();
+    assert!(graphml.contains(r#"<key id="reviewed_by" for="edge""#));
+    assert!(!graphml.contains(r#"<data key="reviewed_by">"#));
+
+    let mut output = Vec::new();
+    write_dot(&mut output, &graph).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains("[weight=0.875, confirmed_status=\"PENDING_REVIEW\"];\n"));
+}
+
+#[test]
+fn graph_selection_parses_all_and_cluster_lists() {
+    assert_eq!("all".parse::<GraphSelection>(), Ok(GraphSelection::All));
+    assert_eq!(
+        "clusters=c1, c2,".parse::<GraphSelection>(),
+        Ok(GraphSelection::Clusters(vec!["c1".to_string(), "c2".to_string()]))
+    );
+    assert!("clusters=".parse::<GraphSelection>().is_err());
+    assert!("c1,c2".parse::<GraphSelection>().is_err());
+}
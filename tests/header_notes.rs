@@ -0,0 +1,12 @@
+use export_opinion::excel_writer::header_note;
+
+#[test]
+fn computed_columns_are_explained_and_source_columns_are_not() {
+    for header in ["cluster_confirmed_status", "cluster", "has_duplicates", "canonical_record", "low_confidence"] {
+        assert!(header_note(header).is_some(), "{} has no note", header);
+    }
+    for header in ["contributor", "entity_id", "service_name", "latitude"] {
+        assert_eq!(header_note(header), None, "{}", header);
+    }
+    assert!(header_note("cluster_confirmed_status").unwrap().contains("CONFIRMED = at least one confirmed match"));
+}
@@ -0,0 +1,88 @@
+use std::collections::{BTreeSet, HashMap};
+
+use export_opinion::db_connect;
+use export_opinion::export_schema;
+use export_opinion::models::RecordScope;
+use export_opinion::reclustering::{run_reclustering, ClusterNaming, ReclusterOptions};
+use export_opinion::run_warnings::RunWarnings;
+use export_opinion::sql_guard;
+use export_opinion::table_names::{hsds_table, qualified, source_table, TEAM_SCHEMA};
+use export_opinion::team_utils::{DatasetFilterOptions, TeamInfo};
+
+const PREFIX: &str = "isolated_test";
+const OPINION: &str = "main";
+
+/// The clusters of `membership` as sets of record ids, independent of the generated cluster ids.
+fn cluster_set(membership: &HashMap<String, String>) -> BTreeSet<BTreeSet<String>> {
+    let mut clusters: HashMap<&str, BTreeSet<String>> = HashMap::new();
+    for (record, cluster) in membership {
+        clusters.entry(cluster.as_str()).or_default().insert(record.clone());
+    }
+    clusters.into_values().collect()
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL"]
+async fn streamed_ids_give_every_unconnected_record_its_own_cluster() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    let mut client = sql_guard::export_write_client(&pool, "test setup").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    // a-b-c are connected; the non-match leaves d and e alone; f has no edge; g is
+    // in a dataset outside the whitelist
+    client.batch_execute(&format!(
+        r#"
+        CREATE SCHEMA IF NOT EXISTS {team};
+        DROP TABLE IF EXISTS {edges};
+        CREATE TABLE {edges} (
+            id text PRIMARY KEY, cluster_id text, entity_id_1 text NOT NULL, entity_id_2 text NOT NULL,
+            edge_weight double precision, details jsonb, pipeline_run_id text, created_at timestamp,
+            confirmed_status text, was_reviewed boolean
+        );
+        INSERT INTO {edges} (id, entity_id_1, entity_id_2, edge_weight, details, confirmed_status) VALUES
+            ('x1', 'iso_a', 'iso_b', 0.9, '{{}}', 'CONFIRMED_MATCH'),
+            ('x2', 'iso_b', 'iso_c', 0.6, '{{}}', 'PENDING_REVIEW'),
+            ('x3', 'iso_d', 'iso_e', 0.2, '{{}}', 'CONFIRMED_NON_MATCH');
+        CREATE TABLE IF NOT EXISTS {entity} (id text PRIMARY KEY, source_system text);
+        DELETE FROM {entity} WHERE id LIKE 'iso_%';
+        INSERT INTO {entity} VALUES ('iso_a', 'iso_ds1'), ('iso_b', 'iso_ds1'), ('iso_c', 'iso_ds1'), ('iso_d', 'iso_ds1'),
+            ('iso_e', 'iso_ds1'), ('iso_f', 'iso_ds1'), ('iso_g', 'iso_ds2');
+        "#,
+        team = TEAM_SCHEMA,
+        edges = qualified(TEAM_SCHEMA, &source_table(PREFIX, OPINION, "entity_edge_visualization")),
+        entity = hsds_table("entity"),
+    )).await.unwrap();
+
+    let tx = client.transaction().await.unwrap();
+    export_schema::create_timestamped_tables(&tx, PREFIX, OPINION, "isolated", RecordScope::EntitiesOnly).await.unwrap();
+    let team_info = TeamInfo {
+        id: "t1".to_string(),
+        name: "test".to_string(),
+        display_name: "Test".to_string(),
+        whitelisted_datasets: vec!["iso_ds1".to_string()],
+        is_active: true,
+    };
+    let warnings = RunWarnings::new();
+    let naming = ClusterNaming::default();
+    let options = ReclusterOptions {
+        team_info: &team_info,
+        filter_options: DatasetFilterOptions::default(),
+        warnings: &warnings,
+        sample: None,
+        fast_insert: false,
+        naming: &naming,
+        strict: true,
+        max_skipped_edges: None,
+        overrides: None,
+        shared_review: None,
+    };
+    let output = run_reclustering(&tx, PREFIX, OPINION, "isolated", "entity", &options).await.unwrap();
+    tx.rollback().await.unwrap();
+
+    let expected: BTreeSet<BTreeSet<String>> = [&["iso_a", "iso_b", "iso_c"][..], &["iso_d"], &["iso_e"], &["iso_f"]]
+        .iter()
+        .map(|members| members.iter().map(|m| m.to_string()).collect())
+        .collect();
+    assert_eq!(cluster_set(&output.membership), expected);
+    assert_eq!(output.cluster_count, 4);
+}
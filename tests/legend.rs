@@ -0,0 +1,24 @@
+use export_opinion::excel_writer::{legend_entries, organization_sheet, service_sheet};
+
+#[test]
+fn every_organization_and_service_column_is_described() {
+    let entries = legend_entries();
+    for (sheet, headers) in [("Organizations", organization_sheet().headers()), ("Services", service_sheet().headers())] {
+        for header in &headers {
+            let described = entries.iter().any(|e| {
+                e.sheet == sheet && e.column.split(" / ").any(|c| c == *header) && !e.description.is_empty()
+            });
+            assert!(described, "{} column {} is missing from the legend", sheet, header);
+        }
+    }
+}
+
+#[test]
+fn status_columns_list_their_values() {
+    let entries = legend_entries();
+    let status = entries.iter()
+        .find(|e| e.sheet == "Organizations" && e.column == "cluster_confirmed_status")
+        .unwrap();
+    assert_eq!(status.values, "PENDING_REVIEW, CONFIRMED, NO_MATCH");
+    assert!(entries.iter().all(|e| !e.description.is_empty()), "every legend row needs a description");
+}
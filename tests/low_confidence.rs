@@ -0,0 +1,10 @@
+use export_opinion::data_fetch::is_low_confidence;
+
+#[test]
+fn flags_only_below_an_explicit_threshold() {
+    assert!(!is_low_confidence(Some(0.2), None), "no threshold means no flagging");
+    assert!(!is_low_confidence(None, Some(0.5)), "records without cluster edges are not flagged");
+    assert!(is_low_confidence(Some(0.49), Some(0.5)));
+    assert!(!is_low_confidence(Some(0.5), Some(0.5)));
+    assert!(!is_low_confidence(Some(0.9), Some(0.5)));
+}
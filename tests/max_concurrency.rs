@@ -0,0 +1,16 @@
+use export_opinion::db_connect::{parse_max_concurrency, DEFAULT_MAX_CONCURRENCY};
+
+#[test]
+fn unset_or_invalid_values_fall_back_to_the_default() {
+    assert_eq!(parse_max_concurrency(None), DEFAULT_MAX_CONCURRENCY);
+    assert_eq!(parse_max_concurrency(Some("")), DEFAULT_MAX_CONCURRENCY);
+    assert_eq!(parse_max_concurrency(Some("many")), DEFAULT_MAX_CONCURRENCY);
+    assert_eq!(parse_max_concurrency(Some("-3")), DEFAULT_MAX_CONCURRENCY);
+    assert_eq!(parse_max_concurrency(Some("0")), DEFAULT_MAX_CONCURRENCY);
+}
+
+#[test]
+fn positive_values_are_used_as_is() {
+    assert_eq!(parse_max_concurrency(Some("1")), 1);
+    assert_eq!(parse_max_concurrency(Some(" 20 ")), 20);
+}
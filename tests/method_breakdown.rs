@@ -0,0 +1,28 @@
+use export_opinion::data_fetch::summarize_contributing_methods;
+use serde_json::json;
+
+#[test]
+fn methods_are_counted_per_edge_and_sorted_by_count() {
+    let methods = vec![
+        json!([["email", 0.9], ["name", 0.6]]),
+        json!([["name", 0.8], ["name", 0.4]]),
+        json!([["phone", 1.0]]),
+        json!([["email", 0.7], ["phone", 0.5]]),
+        json!([["name", 1.0]]),
+        json!("not a list"),
+    ];
+    let summaries = summarize_contributing_methods("entity", &methods);
+    let rows: Vec<(&str, usize)> = summaries.iter().map(|s| (s.method_type.as_str(), s.edge_count)).collect();
+    assert_eq!(rows, vec![("name", 3), ("email", 2), ("phone", 2)]);
+
+    // A method listed twice on one edge counts once, at its highest confidence
+    let name = &summaries[0];
+    assert!((name.average_confidence - (0.6 + 0.8 + 1.0) / 3.0).abs() < 1e-9);
+    assert!(summaries.iter().all(|s| s.record_type == "entity"));
+}
+
+#[test]
+fn no_methods_give_no_rows() {
+    assert!(summarize_contributing_methods("service", &[]).is_empty());
+    assert!(summarize_contributing_methods("service", &[json!([])]).is_empty());
+}
@@ -0,0 +1,52 @@
+use export_opinion::models::RawEdgeVisualization;
+use export_opinion::reclustering::tally_method_stats;
+use serde_json::{json, Value};
+
+fn edge(status: Option<&str>, details: Option<Value>) -> RawEdgeVisualization {
+    RawEdgeVisualization {
+        id: "edge".to_string(),
+        entity_id_1: Some("a".to_string()),
+        entity_id_2: Some("b".to_string()),
+        service_id_1: None,
+        service_id_2: None,
+        confirmed_status: status.map(str::to_string),
+        details,
+        updated_at: None,
+        reviewed_at: None,
+        reviewed_by: None,
+    }
+}
+
+#[test]
+fn methods_are_tallied_by_decision_including_non_matches() {
+    let edges = vec![
+        edge(Some("CONFIRMED_MATCH"), Some(json!({ "contributing_methods": [["email", 0.9], ["name", 0.6]], "calculated_edge_weight": 0.8 }))),
+        edge(Some("NON_MATCH"), Some(json!({ "contributing_methods": [["name", 0.5], ["name", 0.4]], "calculated_edge_weight": 0.4 }))),
+        edge(None, Some(json!({ "contributing_methods": [["name", 0.7]] }))),
+        edge(Some("PENDING_REVIEW"), None),
+    ];
+    let tally = tally_method_stats(&edges, "entity", None);
+    assert_eq!(tally.malformed_entries, 0);
+
+    let rows: Vec<(&str, usize, usize, usize, usize)> = tally.stats.iter()
+        .map(|s| (s.method_type.as_str(), s.edge_count, s.confirmed_match, s.confirmed_non_match, s.pending_review))
+        .collect();
+    assert_eq!(rows, vec![("name", 3, 1, 1, 1), ("email", 1, 1, 0, 0)]);
+
+    // An edge without calculated_edge_weight weighs 1.0, as in re-clustering
+    assert!((tally.stats[0].mean_edge_weight - (0.8 + 0.4 + 1.0) / 3.0).abs() < 1e-9);
+    assert!(tally.stats.iter().all(|s| s.record_type == "entity"));
+}
+
+#[test]
+fn malformed_entries_are_counted_and_skipped() {
+    let edges = vec![
+        edge(Some("CONFIRMED_MATCH"), Some(json!({ "contributing_methods": [["email", 0.9], ["phone"], [3, 0.5], "name"] }))),
+        edge(Some("CONFIRMED_MATCH"), Some(json!({ "contributing_methods": "email" }))),
+        edge(Some("CONFIRMED_MATCH"), Some(json!({ "contributing_methods": [["name", "high"]] }))),
+    ];
+    let tally = tally_method_stats(&edges, "service", None);
+    assert_eq!(tally.malformed_entries, 5);
+    assert_eq!(tally.stats.len(), 1);
+    assert_eq!((tally.stats[0].method_type.as_str(), tally.stats[0].edge_count), ("email", 1));
+}
@@ -0,0 +1,48 @@
+use export_opinion::export_schema::{fallback_table_columns, plan_table_sources, TableSource};
+use export_opinion::models::RecordScope;
+
+const EDGE_TABLES: [&str; 2] = ["entity_edge_visualization", "service_edge_visualization"];
+
+#[test]
+fn only_edge_tables_present_synthesizes_group_and_cluster_tables() {
+    let plan = plan_table_sources(RecordScope::Both, |table| EDGE_TABLES.contains(&table)).unwrap();
+    assert_eq!(plan.len(), 6);
+    for (table, source) in plan {
+        let expected = if EDGE_TABLES.contains(&table) { TableSource::Copy } else { TableSource::Synthesized };
+        assert_eq!(source, expected, "{}", table);
+    }
+}
+
+#[test]
+fn all_tables_present_copies_everything() {
+    let plan = plan_table_sources(RecordScope::Both, |_| true).unwrap();
+    assert!(plan.iter().all(|(_, source)| *source == TableSource::Copy));
+}
+
+#[test]
+fn missing_edge_table_fails() {
+    let err = plan_table_sources(RecordScope::EntitiesOnly, |table| table != "entity_edge_visualization").unwrap_err();
+    assert!(err.to_string().contains("entity_edge_visualization"), "{}", err);
+    // Services are out of scope, so their missing edge table does not matter
+    assert!(plan_table_sources(RecordScope::EntitiesOnly, |table| table != "service_edge_visualization").is_ok());
+}
+
+#[test]
+fn fallbacks_cover_the_columns_reclustering_writes() {
+    assert!(EDGE_TABLES.iter().all(|table| fallback_table_columns(table).is_none()));
+    for record_type in ["entity", "service"] {
+        let group = fallback_table_columns(&format!("{}_group", record_type)).unwrap();
+        for column in [format!("{}_id_1", record_type), format!("{}_id_2", record_type)] {
+            assert!(group.contains(&column), "{} lacks {}", group, column);
+        }
+        for column in ["group_cluster_id", "method_type", "confirmed_status"] {
+            assert!(group.contains(column), "{} lacks {}", group, column);
+        }
+        let cluster = fallback_table_columns(&format!("{}_group_cluster", record_type)).unwrap();
+        for column in ["average_coherence_score", "was_reviewed"] {
+            assert!(cluster.contains(column), "{} lacks {}", cluster, column);
+        }
+    }
+    assert!(fallback_table_columns("entity_group_cluster").unwrap().contains("entity_count"));
+    assert!(fallback_table_columns("service_group_cluster").unwrap().contains("service_group_count"));
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use export_opinion::confirmed_status::ConfirmedStatus;
+use export_opinion::data_fetch::{fetch_organization_export_data, mixed_status_expression};
+use export_opinion::db_connect;
+use export_opinion::export_schema;
+use export_opinion::models::{ExportSelection, RecordScope};
+use export_opinion::reclustering::{run_reclustering, ClusterNaming, ReclusterOptions};
+use export_opinion::run_warnings::RunWarnings;
+use export_opinion::sql_guard;
+use export_opinion::table_names::{hsds_table, qualified, source_table, TEAM_SCHEMA};
+use export_opinion::team_utils::{DatasetFilterOptions, TeamInfo};
+
+#[test]
+fn flags_clusters_with_a_confirmed_match_and_any_other_status() {
+    assert_eq!(
+        mixed_status_expression("s"),
+        "(COUNT(DISTINCT s) > 1 AND bool_or(s = 'CONFIRMED_MATCH'))"
+    );
+}
+
+#[test]
+fn compares_canonical_statuses() {
+    // Raw spellings like 'confirmed' must not count as a status of their own
+    let status = ConfirmedStatus::sql_case("ev.confirmed_status");
+    let expression = mixed_status_expression(&status);
+    assert!(expression.starts_with(&format!("(COUNT(DISTINCT {}) > 1", status)), "{}", expression);
+    assert!(expression.contains(&format!("bool_or({} = 'CONFIRMED_MATCH')", status)), "{}", expression);
+}
+
+const PREFIX: &str = "mixed_test";
+const OPINION: &str = "main";
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL"]
+async fn clusters_with_a_pending_edge_beside_a_match_are_flagged() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    let mut client = sql_guard::export_write_client(&pool, "test setup").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    // a-b-c holds a match and a pending edge; d-e-f holds only matches
+    client.batch_execute(&format!(
+        r#"
+        CREATE SCHEMA IF NOT EXISTS {team};
+        DROP TABLE IF EXISTS {edges};
+        CREATE TABLE {edges} (
+            id text PRIMARY KEY, cluster_id text, entity_id_1 text NOT NULL, entity_id_2 text NOT NULL,
+            edge_weight double precision, details jsonb, pipeline_run_id text, created_at timestamp,
+            confirmed_status text, was_reviewed boolean
+        );
+        INSERT INTO {edges} (id, entity_id_1, entity_id_2, edge_weight, details, confirmed_status) VALUES
+            ('x1', 'mix_a', 'mix_b', 0.9, '{{}}', 'CONFIRMED_MATCH'),
+            ('x2', 'mix_b', 'mix_c', 0.6, '{{}}', 'PENDING_REVIEW'),
+            ('x3', 'mix_d', 'mix_e', 0.9, '{{}}', 'CONFIRMED_MATCH'),
+            ('x4', 'mix_e', 'mix_f', 0.8, '{{}}', 'CONFIRMED_MATCH');
+        CREATE TABLE IF NOT EXISTS {entity} (id text PRIMARY KEY, source_system text);
+        ALTER TABLE {entity} ADD COLUMN IF NOT EXISTS source_id text, ADD COLUMN IF NOT EXISTS name text;
+        DELETE FROM {entity} WHERE id LIKE 'mix_%';
+        INSERT INTO {entity} (id, source_system) SELECT 'mix_' || c, 'mix_ds' FROM unnest(ARRAY['a', 'b', 'c', 'd', 'e', 'f']) c;
+        "#,
+        team = TEAM_SCHEMA,
+        edges = qualified(TEAM_SCHEMA, &source_table(PREFIX, OPINION, "entity_edge_visualization")),
+        entity = hsds_table("entity"),
+    )).await.unwrap();
+
+    let team_info = TeamInfo {
+        id: "t1".to_string(),
+        name: "test".to_string(),
+        display_name: "Test".to_string(),
+        whitelisted_datasets: vec!["mix_ds".to_string()],
+        is_active: true,
+    };
+    let tx = client.transaction().await.unwrap();
+    export_schema::create_timestamped_tables(&tx, PREFIX, OPINION, "mixed", RecordScope::EntitiesOnly).await.unwrap();
+    let warnings = RunWarnings::new();
+    let naming = ClusterNaming::default();
+    let options = ReclusterOptions {
+        team_info: &team_info,
+        filter_options: DatasetFilterOptions::default(),
+        warnings: &warnings,
+        sample: None,
+        fast_insert: false,
+        naming: &naming,
+        strict: true,
+        max_skipped_edges: None,
+        overrides: None,
+        shared_review: None,
+    };
+    run_reclustering(&tx, PREFIX, OPINION, "mixed", "entity", &options).await.unwrap();
+    tx.commit().await.unwrap();
+    drop(client);
+
+    let rows = fetch_organization_export_data(
+        &pool, PREFIX, OPINION, "mixed", &team_info, DatasetFilterOptions::default(), &ExportSelection::default(),
+    ).await.unwrap();
+    let mixed: HashMap<&str, bool> = rows.iter().map(|row| (row.entity_id.as_str(), row.mixed_status)).collect();
+    assert_eq!(mixed.len(), 6);
+    for id in ["mix_a", "mix_b", "mix_c"] {
+        assert!(mixed[id], "{} is in a cluster with a pending edge", id);
+    }
+    for id in ["mix_d", "mix_e", "mix_f"] {
+        assert!(!mixed[id], "{} is in an all-match cluster", id);
+    }
+}
@@ -0,0 +1,38 @@
+use export_opinion::models::TaxonomyExportRow;
+use export_opinion::ndjson_export::{ndjson_file_name, write_ndjson};
+use serde_json::Value;
+
+fn term(service_id: &str, term: &str) -> TaxonomyExportRow {
+    TaxonomyExportRow {
+        service_id: service_id.to_string(),
+        taxonomy_term: term.to_string(),
+        taxonomy_category: None,
+        taxonomy_id: format!("t-{}", term),
+    }
+}
+
+#[test]
+fn each_row_is_one_parseable_line() {
+    let mut out = Vec::new();
+    // Rows come from a lazy iterator, as a streaming fetch would produce them
+    let written = write_ndjson(&mut out, (1..=3).map(|i| term(&format!("s{}", i), "Food\nPantry"))).unwrap();
+    assert_eq!(written, 3);
+
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(text.ends_with('\n'));
+    let first: Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(first["service_id"], "s1");
+    // Embedded newlines are escaped and never split a row
+    assert_eq!(first["taxonomy_term"], "Food\nPantry");
+    assert!(first["taxonomy_category"].is_null());
+}
+
+#[test]
+fn no_rows_write_an_empty_file() {
+    let mut out = Vec::new();
+    assert_eq!(write_ndjson(&mut out, Vec::<TaxonomyExportRow>::new()).unwrap(), 0);
+    assert!(out.is_empty());
+    assert_eq!(ndjson_file_name("sam", "default", "services", "20250101"), "sam_default_services_20250101.ndjson");
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use export_opinion::data_fetch::{apply_service_summaries, worst_cluster_status};
+use export_opinion::models::{OrganizationExportRow, OrganizationServices};
+
+fn org(id: &str) -> OrganizationExportRow {
+    OrganizationExportRow {
+        entity_id: id.to_string(),
+        cluster_confirmed_status: "NO_MATCH".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pending_review_outranks_confirmed_and_no_match() {
+    assert_eq!(worst_cluster_status(["NO_MATCH", "PENDING_REVIEW", "CONFIRMED"]), Some("PENDING_REVIEW"));
+    assert_eq!(worst_cluster_status(["NO_MATCH", "CONFIRMED"]), Some("CONFIRMED"));
+    assert_eq!(worst_cluster_status(["NO_MATCH"]), Some("NO_MATCH"));
+    assert_eq!(worst_cluster_status([]), None);
+}
+
+#[test]
+fn entities_without_services_get_zero_counts() {
+    let with_services = OrganizationServices {
+        service_count: 3,
+        services_with_duplicates: 1,
+        worst_status: Some("PENDING_REVIEW".to_string()),
+    };
+    let summaries = HashMap::from([("a".to_string(), with_services.clone())]);
+    let mut rows = vec![org("a"), org("b")];
+
+    apply_service_summaries(&mut rows, &summaries);
+
+    assert_eq!(rows[0].services, Some(with_services));
+    assert_eq!(rows[1].services, Some(OrganizationServices::default()));
+    assert_eq!(rows[1].services.as_ref().unwrap().service_count, 0);
+}
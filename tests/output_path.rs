@@ -0,0 +1,29 @@
+use std::fs;
+
+use export_opinion::excel_writer::{check_output_path, parse_output_path};
+
+#[test]
+fn existing_output_is_kept_unless_overwrite_is_set() {
+    let dir = std::env::temp_dir().join(format!("export_opinion_output_path_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("hannah_default_export_2024w05.xlsx");
+
+    assert!(check_output_path(&path, false).is_ok());
+
+    fs::write(&path, b"previous export").unwrap();
+    let error = check_output_path(&path, false).unwrap_err().to_string();
+    assert!(error.contains("--overwrite"), "{}", error);
+    assert!(check_output_path(&path, true).is_ok());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn output_path_must_be_an_xlsx_file() {
+    assert_eq!(parse_output_path("reports/latest.xlsx").unwrap(), std::path::PathBuf::from("reports/latest.xlsx"));
+    assert!(parse_output_path("latest.XLSX").is_ok());
+
+    let error = parse_output_path("latest.csv").unwrap_err();
+    assert!(error.contains(".csv"), "{}", error);
+    assert!(parse_output_path("latest").is_err());
+}
@@ -0,0 +1,140 @@
+use std::collections::{BTreeSet, HashSet};
+
+use export_opinion::confirmed_status::ConfirmedStatus;
+use export_opinion::models::RawEdgeVisualization;
+use export_opinion::overrides::{OverrideKey, StatusOverrides};
+use export_opinion::reclustering::{classify_edges, tally_method_stats};
+use export_opinion::run_warnings::RunWarnings;
+use rust_xlsxwriter::Workbook;
+use serde_json::json;
+
+fn parse(csv: &str) -> anyhow::Result<StatusOverrides> {
+    StatusOverrides::parse_csv(csv.as_bytes())
+}
+
+fn edge(id: &str, id_1: &str, id_2: &str, status: &str) -> RawEdgeVisualization {
+    RawEdgeVisualization {
+        id: id.to_string(),
+        entity_id_1: Some(id_1.to_string()),
+        entity_id_2: Some(id_2.to_string()),
+        service_id_1: None,
+        service_id_2: None,
+        confirmed_status: Some(status.to_string()),
+        details: Some(json!({ "calculated_edge_weight": 0.9 })),
+        updated_at: None,
+        reviewed_at: None,
+        reviewed_by: None,
+    }
+}
+
+#[test]
+fn edge_ids_and_unordered_pairs_are_looked_up() {
+    let overrides = parse("Decision,edge_id,id_1,id_2,note\n\
+        CONFIRMED_NON_MATCH,e1,,,split these\n\
+        match,,b,a,\n\
+        ,,,,\n\
+        PENDING_REVIEW,e9,x,y,\n").unwrap();
+    assert_eq!(overrides.len(), 3);
+    assert_eq!(overrides.iter().nth(1).unwrap().key, OverrideKey::Pair("a".to_string(), "b".to_string()));
+
+    assert_eq!(overrides.decision_for("e1", "p", "q"), Some((0, ConfirmedStatus::ConfirmedNonMatch)));
+    assert_eq!(overrides.decision_for("other", "a", "b"), Some((1, ConfirmedStatus::ConfirmedMatch)));
+    // The edge id wins over the pair, which e9's row does not define anyway
+    assert_eq!(overrides.decision_for("e9", "a", "b"), Some((2, ConfirmedStatus::PendingReview)));
+    assert_eq!(overrides.decision_for("other", "a", "c"), None);
+}
+
+#[test]
+fn malformed_rows_are_all_reported_with_their_lines() {
+    let error = parse("edge_id,id_1,id_2,decision\n\
+        e1,,,MAYBE\n\
+        ,a,,CONFIRMED_MATCH\n\
+        ,,,CONFIRMED_MATCH\n\
+        ,c,c,CONFIRMED_MATCH\n\
+        e2,,,CONFIRMED_MATCH\n").unwrap_err();
+    let message = error.to_string();
+    assert!(message.starts_with("4 problem(s) in the overrides"), "{}", message);
+    assert!(message.contains("line 2: unknown decision 'MAYBE'"), "{}", message);
+    assert!(message.contains("line 3: a record pair needs both id_1 and id_2"), "{}", message);
+    assert!(message.contains("line 4: no edge_id or id_1/id_2"), "{}", message);
+    assert!(message.contains("line 5: id_1 and id_2 are the same record 'c'"), "{}", message);
+
+    assert!(parse("edge_id,status\ne1,MATCH\n").unwrap_err().to_string().contains("no 'decision' column"));
+    assert!(parse("id_1,decision\na,MATCH\n").unwrap_err().to_string().contains("both 'id_1' and 'id_2'"));
+    assert!(parse("").is_err());
+}
+
+#[test]
+fn duplicate_keys_count_once_and_conflicting_decisions_fail() {
+    let overrides = parse("id_1,id_2,decision\na,b,CONFIRMED_MATCH\nb,a,MATCH\na,b,confirmed_match\n").unwrap();
+    assert_eq!((overrides.len(), overrides.duplicate_rows), (1, 2));
+
+    let error = parse("edge_id,id_1,id_2,decision\ne1,,,CONFIRMED_MATCH\n,a,b,CONFIRMED_MATCH\ne1,,,CONFIRMED_NON_MATCH\n").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "1 problem(s) in the overrides:\n  line 4: edge e1 is CONFIRMED_NON_MATCH here but CONFIRMED_MATCH on line 2"
+    );
+}
+
+#[test]
+fn overrides_replace_the_stored_status_and_report_what_matched() {
+    let overrides = parse("edge_id,id_1,id_2,decision\n\
+        rejected,,,CONFIRMED_MATCH\n\
+        ,c,b,CONFIRMED_NON_MATCH\n\
+        typo,,,CONFIRMED_MATCH\n").unwrap();
+    let existing: HashSet<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+    let edges = vec![
+        edge("rejected", "a", "b", "CONFIRMED_NON_MATCH"),
+        edge("pending", "b", "c", "PENDING_REVIEW"),
+        edge("kept", "c", "d", "CONFIRMED_MATCH"),
+    ];
+
+    let classified = classify_edges(&edges, "entity", &existing, Some(&overrides), &RunWarnings::new());
+
+    let kept: Vec<(&str, &str, &str)> = classified.valid.iter()
+        .map(|e| (e.id_1.as_str(), e.id_2.as_str(), e.status.as_str()))
+        .collect();
+    assert_eq!(kept, vec![("a", "b", "CONFIRMED_MATCH"), ("c", "d", "CONFIRMED_MATCH")]);
+    assert_eq!(classified.overrides_applied, BTreeSet::from([0, 1]));
+    let unmatched: Vec<(usize, String)> = overrides.unmatched(&classified.overrides_applied)
+        .map(|o| (o.line, o.key.to_string()))
+        .collect();
+    assert_eq!(unmatched, vec![(4, "edge typo".to_string())]);
+}
+
+#[test]
+fn overrides_replace_the_stored_status_in_the_method_stats() {
+    let overrides = parse("edge_id,id_1,id_2,decision\n\
+        rejected,,,CONFIRMED_MATCH\n\
+        ,c,b,CONFIRMED_NON_MATCH\n").unwrap();
+    let mut edges = vec![
+        edge("rejected", "a", "b", "CONFIRMED_NON_MATCH"),
+        edge("pending", "b", "c", "PENDING_REVIEW"),
+        edge("kept", "c", "d", "CONFIRMED_MATCH"),
+    ];
+    for e in &mut edges {
+        e.details = Some(json!({ "calculated_edge_weight": 0.9, "contributing_methods": [["name", 0.9]] }));
+    }
+
+    let stats = &tally_method_stats(&edges, "entity", Some(&overrides)).stats[0];
+    assert_eq!((stats.confirmed_match, stats.confirmed_non_match, stats.pending_review), (2, 1, 0));
+    let stored = &tally_method_stats(&edges, "entity", None).stats[0];
+    assert_eq!((stored.confirmed_match, stored.confirmed_non_match, stored.pending_review), (1, 1, 1));
+}
+
+#[test]
+fn xlsx_files_are_read_from_the_first_sheet() {
+    let path = std::env::temp_dir().join(format!("export_opinion_overrides_{}.xlsx", std::process::id()));
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    for (col, header) in ["edge_id", "decision"].iter().enumerate() {
+        sheet.write_string(0, col as u16, *header).unwrap();
+    }
+    sheet.write_string(1, 0, "e1").unwrap();
+    sheet.write_string(1, 1, "CONFIRMED_NON_MATCH").unwrap();
+    workbook.save(&path).unwrap();
+
+    let overrides = StatusOverrides::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(overrides.decision_for("e1", "a", "b"), Some((0, ConfirmedStatus::ConfirmedNonMatch)));
+}
@@ -0,0 +1,59 @@
+use export_opinion::preflight::{missing_schema_objects, required_columns, ALTERNATIVE_COLUMNS};
+
+/// Every required column, with the alternative columns under the name at `alternative`.
+fn schema_with(alternative: usize) -> Vec<(String, String, String)> {
+    required_columns("public").into_iter()
+        .map(|(schema, table, columns)| (schema, table, columns.to_vec()))
+        .chain(ALTERNATIVE_COLUMNS.iter().map(|(schema, table, names)| (*schema, *table, vec![names[alternative]])))
+        .flat_map(|(schema, table, columns)| {
+            columns.into_iter().map(move |column| (schema.to_string(), table.to_string(), column.to_string()))
+        })
+        .collect()
+}
+
+fn all_required() -> Vec<(String, String, String)> {
+    schema_with(0)
+}
+
+#[test]
+fn complete_schema_has_nothing_missing() {
+    assert!(missing_schema_objects(&all_required(), "public").is_empty());
+}
+
+#[test]
+fn either_name_of_an_alternative_column_passes() {
+    // Staging spells auth.opinions.disconnect_dependent_services with underscores
+    assert!(missing_schema_objects(&schema_with(1), "public").is_empty());
+}
+
+#[test]
+fn missing_tables_and_columns_are_listed_together() {
+    let dropped = [
+        ("auth", "teams", Some("whitelisted_datasets")),
+        ("auth", "opinions", Some("disconnectdependentservices")),
+        ("public", "service_taxonomy", None),
+    ];
+    let present: Vec<_> = all_required().into_iter()
+        .filter(|(schema, table, column)| {
+            !dropped.iter().any(|(s, t, c)| schema == s && table == t && c.is_none_or(|c| column == c))
+        })
+        .collect();
+
+    assert_eq!(missing_schema_objects(&present, "public"), vec![
+        "column auth.teams.whitelisted_datasets",
+        "table public.service_taxonomy",
+        "column auth.opinions.disconnectdependentservices or disconnect_dependent_services",
+    ]);
+}
+
+#[test]
+fn source_tables_are_looked_up_in_the_configured_schema() {
+    let missing = missing_schema_objects(&all_required(), "hsds_prod");
+    assert_eq!(missing.len(), 8);
+    assert!(missing.contains(&"table hsds_prod.entity".to_string()), "{:?}", missing);
+
+    let moved: Vec<_> = all_required().into_iter()
+        .map(|(schema, table, column)| (if schema == "public" { "hsds_prod".to_string() } else { schema }, table, column))
+        .collect();
+    assert!(missing_schema_objects(&moved, "hsds_prod").is_empty());
+}
@@ -0,0 +1,101 @@
+use std::io::Cursor;
+
+use calamine::{Data, Reader, Xlsx};
+use export_opinion::cluster_labels::LabelMap;
+use export_opinion::dashboard::{group_by_user, overall_totals, DashboardSection, OpinionDashboard, ProgressOptions, ReviewStats};
+use export_opinion::excel_writer::{group_info_rows, write_excel_to_bytes, DataSheetOptions, TrailingSheets};
+use export_opinion::models::RecordScope;
+
+fn opinion(user: &str, name: &str, entity: (i64, i64, i64), service: (i64, i64, i64)) -> OpinionDashboard {
+    OpinionDashboard {
+        username: user.to_string(),
+        user_prefix: user.to_string(),
+        opinion_name: name.to_string(),
+        opinion_owner: None,
+        shared_with: Vec::new(),
+        entity_stats: ReviewStats::new(entity.0, entity.1, entity.2),
+        service_stats: ReviewStats::new(service.0, service.1, service.2),
+        trend: Vec::new(),
+        dataset_pairs: Vec::new(),
+    }
+}
+
+fn team() -> Vec<OpinionDashboard> {
+    vec![
+        opinion("sam", "default", (2, 1, 1), (0, 3, 0)),
+        opinion("ana", "default", (5, 0, 0), (1, 1, 0)),
+        opinion("sam", "strict", (0, 4, 2), (2, 0, 0)),
+    ]
+}
+
+#[test]
+fn opinions_are_grouped_under_users_in_first_seen_order() {
+    let data = team();
+    let users = group_by_user(&data);
+    let grouped: Vec<(&str, Vec<&str>)> = users.iter()
+        .map(|u| (u.username, u.opinions.iter().map(|o| o.opinion_name.as_str()).collect()))
+        .collect();
+    assert_eq!(grouped, vec![("sam", vec!["default", "strict"]), ("ana", vec!["default"])]);
+
+    let sam = users[0].entity_totals();
+    assert_eq!((sam.pending_review, sam.confirmed_match, sam.confirmed_non_match, sam.total), (2, 5, 3, 10));
+    assert!((sam.review_percentage - 80.0).abs() < 1e-9);
+}
+
+#[test]
+fn totals_agree_across_groupings() {
+    let data = team();
+    let (entity, service) = overall_totals(&data);
+    let users = group_by_user(&data);
+    let by_user_entity = ReviewStats::sum(users.iter().map(|u| u.entity_totals()).collect::<Vec<_>>().iter());
+    let by_user_service = ReviewStats::sum(users.iter().map(|u| u.service_totals()).collect::<Vec<_>>().iter());
+
+    for (overall, by_user) in [(&entity, &by_user_entity), (&service, &by_user_service)] {
+        assert_eq!(
+            (overall.pending_review, overall.reviewed_count, overall.total),
+            (by_user.pending_review, by_user.reviewed_count, by_user.total),
+        );
+    }
+    assert_eq!((entity.total, entity.reviewed_count), (15, 8));
+    assert_eq!((service.total, service.reviewed_count), (7, 4));
+    // The percentage comes from the summed counts, not an average of percentages
+    assert!((entity.review_percentage - 800.0 / 15.0).abs() < 1e-9);
+    assert_eq!(ReviewStats::sum([]).completion_fraction(), None);
+}
+
+#[test]
+fn several_opinions_get_a_breakdown_with_subtotals() {
+    let labels = LabelMap::default();
+    let bytes = write_excel_to_bytes(
+        Some(Vec::new()),
+        Some(Vec::new()),
+        DashboardSection::Included(team()),
+        ProgressOptions::default(),
+        TrailingSheets { method_breakdown: Vec::new(), method_performance: Vec::new(), orphaned_edges: Vec::new(), warnings: Vec::new(), legend: false, extra_sheets: None },
+        DataSheetOptions { cluster_labels: &labels, footer: false, taxonomy_sheet: false },
+    ).unwrap();
+    let mut workbook = Xlsx::new(Cursor::new(bytes)).unwrap();
+    let overview = workbook.worksheet_range("Progress Overview").unwrap();
+
+    let rows: Vec<Vec<String>> = overview.rows().map(|row| row.iter().map(Data::to_string).collect()).collect();
+    let start = rows.iter().position(|row| row[0] == "BY OPINION").unwrap();
+    let table: Vec<(&str, &str, &str)> = rows[start + 2..start + 7].iter()
+        .map(|row| (row[0].as_str(), row[1].as_str(), row[4].as_str()))
+        .collect();
+    // Columns: user, opinion, entity pending, entity reviewed, service pending, ...
+    assert_eq!(table, vec![
+        ("sam", "default", "0"),
+        ("sam", "strict", "2"),
+        ("sam", "Subtotal", "2"),
+        ("ana", "default", "1"),
+        ("All Users", "Total", "3"),
+    ]);
+    assert_eq!(rows[start + 6][6], "22");
+
+    let info = group_info_rows(&team(), RecordScope::Both, ProgressOptions::default());
+    assert_eq!(info, vec![
+        ("Users", "2".to_string()),
+        ("Opinions", "3".to_string()),
+        ("Record Types", RecordScope::Both.label().to_string()),
+    ]);
+}
@@ -0,0 +1,14 @@
+// Kept in its own test binary because read-only mode cannot be turned off once enabled.
+
+use export_opinion::sql_guard::{disable_writes, validate_export_write, writes_disabled};
+use export_opinion::table_names::{qualified, EXPORT_SCHEMA};
+
+#[test]
+fn read_only_mode_rejects_export_writes() {
+    let sql = format!("DELETE FROM {}", qualified(EXPORT_SCHEMA, "t"));
+    assert!(validate_export_write(&sql).is_ok());
+
+    disable_writes();
+    assert!(writes_disabled());
+    assert!(validate_export_write(&sql).is_err());
+}
@@ -0,0 +1,112 @@
+use export_opinion::db_connect::{self, PgPool};
+use export_opinion::export_schema;
+use export_opinion::models::RecordScope;
+use export_opinion::replication::{create_table_statement, replicate_export, ColumnDefinition, ReplicationSummary};
+use export_opinion::sql_guard;
+use export_opinion::table_names::{export_table, qualified, EXPORT_SCHEMA};
+
+fn column(name: &str, data_type: &str) -> ColumnDefinition {
+    ColumnDefinition { name: name.to_string(), data_type: data_type.to_string() }
+}
+
+#[test]
+fn target_table_keeps_column_order_and_types() {
+    let columns = [
+        column("id", "text"),
+        column("confidence_score", "double precision"),
+        column("created_at", "timestamp without time zone"),
+        column("dataset_tags", "character varying(64)[]"),
+    ];
+    assert_eq!(
+        create_table_statement(r#""wa211_to_wric_exports"."u_default_entity_group_export_1""#, &columns),
+        r#"CREATE TABLE "wa211_to_wric_exports"."u_default_entity_group_export_1" ("id" text, "confidence_score" double precision, "created_at" timestamp without time zone, "dataset_tags" character varying(64)[]);"#
+    );
+}
+
+#[test]
+fn column_names_are_quoted() {
+    let statement = create_table_statement(r#""s"."t""#, &[column(r#"odd"name"#, "integer")]);
+    assert_eq!(statement, r#"CREATE TABLE "s"."t" ("odd""name" integer);"#);
+}
+
+const PREFIX: &str = "replication_test";
+const OPINION: &str = "main";
+
+/// The source and target pools from TEST_DATABASE_URL and TEST_TARGET_DATABASE_URL,
+/// which must name two different databases.
+async fn source_and_target() -> (PgPool, PgPool) {
+    let connect = |var: &'static str| async move {
+        let url = std::env::var(var).unwrap_or_else(|_| panic!("{} is not set", var));
+        db_connect::connect_to(url.parse().unwrap()).await.unwrap()
+    };
+    (connect("TEST_DATABASE_URL").await, connect("TEST_TARGET_DATABASE_URL").await)
+}
+
+fn entity_table(table: &str, suffix: &str) -> String {
+    qualified(EXPORT_SCHEMA, &export_table(PREFIX, OPINION, table, suffix))
+}
+
+/// Creates the entity export tables for `suffix` on `pool`, each with `rows` rows.
+async fn create_source_export(pool: &PgPool, suffix: &str, rows: i64) {
+    let client = sql_guard::export_write_client(pool, "test setup").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    for table in export_schema::scoped_tables(RecordScope::EntitiesOnly) {
+        let table_full = entity_table(table, suffix);
+        client.batch_execute(&format!(
+            "DROP TABLE IF EXISTS {0}; CREATE TABLE {0} (id text, confidence_score double precision); \
+             INSERT INTO {0} SELECT 'r' || n, n / 10.0 FROM generate_series(1, {1}) n;",
+            table_full, rows
+        )).await.unwrap();
+    }
+}
+
+/// The number of rows in `table_full`, or `None` when it does not exist.
+async fn row_count(pool: &PgPool, table_full: &str) -> Option<i64> {
+    let client = pool.get().await.unwrap();
+    let exists: bool = client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&table_full]).await.unwrap().get(0);
+    if !exists {
+        return None;
+    }
+    Some(client.query_one(&format!("SELECT count(*) FROM {}", table_full), &[]).await.unwrap().get(0))
+}
+
+async fn completed_suffixes(pool: &PgPool) -> Vec<String> {
+    let client = pool.get().await.unwrap();
+    export_schema::completed_exports(&client, PREFIX, OPINION).await.unwrap()
+        .into_iter()
+        .map(|export| export.timestamp_suffix)
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "needs two PostgreSQL databases in TEST_DATABASE_URL and TEST_TARGET_DATABASE_URL"]
+async fn export_is_copied_and_a_failed_copy_leaves_the_target_unchanged() {
+    let (source, target) = source_and_target().await;
+    let tables: Vec<&str> = export_schema::scoped_tables(RecordScope::EntitiesOnly).collect();
+
+    create_source_export(&source, "20240101000000", 5).await;
+    let summary = replicate_export(&source, &target, PREFIX, OPINION, "20240101000000", RecordScope::EntitiesOnly).await.unwrap();
+    assert_eq!(summary, ReplicationSummary { tables: 3, rows: 15 });
+    for table in &tables {
+        assert_eq!(row_count(&target, &entity_table(table, "20240101000000")).await, Some(5));
+    }
+    assert_eq!(completed_suffixes(&target).await, vec!["20240101000000"]);
+
+    // A view where the last table goes makes its DROP TABLE fail after the other
+    // tables were already copied in the same transaction
+    create_source_export(&source, "20240202000000", 7).await;
+    let blocked = entity_table(tables[tables.len() - 1], "20240202000000");
+    let client = target.get().await.unwrap();
+    client.batch_execute(&format!("DROP VIEW IF EXISTS {0}; CREATE VIEW {0} AS SELECT 1 AS id;", blocked)).await.unwrap();
+    drop(client);
+    assert!(replicate_export(&source, &target, PREFIX, OPINION, "20240202000000", RecordScope::EntitiesOnly).await.is_err());
+
+    for table in &tables[..tables.len() - 1] {
+        assert_eq!(row_count(&target, &entity_table(table, "20240202000000")).await, None);
+    }
+    for table in &tables {
+        assert_eq!(row_count(&target, &entity_table(table, "20240101000000")).await, Some(5));
+        assert_eq!(row_count(&source, &entity_table(table, "20240202000000")).await, Some(7));
+    }
+    assert_eq!(completed_suffixes(&target).await, vec!["20240101000000"]);
+}
@@ -0,0 +1,124 @@
+use std::cell::Cell;
+use std::io;
+use std::time::Duration;
+
+use anyhow::Context;
+use bb8::PooledConnection;
+use bb8_postgres::PostgresConnectionManager;
+use export_opinion::db_connect::{self, PgPool};
+use export_opinion::export_schema;
+use export_opinion::retry::{is_transient, retry_recording_warnings, retry_transient, RetryPolicy};
+use export_opinion::run_warnings::{RunWarnings, WarningCategory};
+use tokio_postgres::NoTls;
+
+const QUICK: RetryPolicy = RetryPolicy { attempts: 2, backoff: Duration::from_millis(1) };
+
+fn connection_reset() -> anyhow::Error {
+    anyhow::Error::new(io::Error::from(io::ErrorKind::ConnectionReset)).context("Failed to fetch service export data")
+}
+
+#[test]
+fn classifies_connection_failures_as_transient() {
+    assert!(is_transient(&connection_reset()));
+    assert!(is_transient(&anyhow::Error::new(io::Error::from(io::ErrorKind::BrokenPipe))));
+    assert!(!is_transient(&anyhow::Error::new(io::Error::from(io::ErrorKind::PermissionDenied))));
+    assert!(!is_transient(&anyhow::anyhow!("The opinion has no entity_edge_visualization table")));
+}
+
+#[test]
+fn context_does_not_hide_the_cause() {
+    let wrapped: anyhow::Result<()> = Err(connection_reset()).context("Consensus export of reviewer ana");
+    assert!(is_transient(&wrapped.unwrap_err()));
+}
+
+#[tokio::test]
+async fn transient_failure_on_the_first_attempt_is_retried() {
+    let calls = Cell::new(0);
+    let result = retry_transient("service fetch", QUICK, || {
+        calls.set(calls.get() + 1);
+        let call = calls.get();
+        async move { if call == 1 { Err(connection_reset()) } else { Ok(call) } }
+    }).await;
+    assert_eq!(result.unwrap(), 2);
+    assert_eq!(calls.get(), 2);
+}
+
+#[tokio::test]
+async fn gives_up_once_attempts_are_exhausted() {
+    let calls = Cell::new(0);
+    let result: anyhow::Result<()> = retry_transient("service fetch", QUICK, || {
+        calls.set(calls.get() + 1);
+        async { Err(connection_reset()) }
+    }).await;
+    assert!(is_transient(&result.unwrap_err()));
+    assert_eq!(calls.get(), 2);
+}
+
+#[tokio::test]
+async fn permanent_failures_are_not_retried() {
+    let calls = Cell::new(0);
+    let result: anyhow::Result<()> = retry_transient("service fetch", QUICK, || {
+        calls.set(calls.get() + 1);
+        async { Err(anyhow::anyhow!("column \"disconnectdependentservices\" does not exist")) }
+    }).await;
+    assert!(result.is_err());
+    assert_eq!(calls.get(), 1);
+}
+
+#[tokio::test]
+async fn only_the_successful_attempt_records_warnings() {
+    let warnings = RunWarnings::new();
+    let calls = Cell::new(0);
+    retry_recording_warnings("consensus export", QUICK, &warnings, |attempt_warnings| {
+        calls.set(calls.get() + 1);
+        let call = calls.get();
+        async move {
+            attempt_warnings.record(WarningCategory::SkippedEdge, "edge e1 has an empty record id");
+            if call == 1 { Err(connection_reset()) } else { Ok(()) }
+        }
+    }).await.unwrap();
+    assert_eq!(calls.get(), 2);
+    let summaries = warnings.summaries();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].count, 1);
+}
+
+/// Hands out clients from `pool`, terminating the server side of the first `faults`
+/// of them before returning them, as a restarted or failed-over server would.
+struct FaultInjectingPool {
+    pool: PgPool,
+    faults: Cell<u32>,
+}
+
+impl FaultInjectingPool {
+    async fn get(&self) -> anyhow::Result<PooledConnection<'_, PostgresConnectionManager<NoTls>>> {
+        let client = self.pool.get().await?;
+        if self.faults.get() > 0 {
+            self.faults.set(self.faults.get() - 1);
+            let pid: i32 = client.query_one("SELECT pg_backend_pid()", &[]).await?.get(0);
+            self.pool.get().await?.execute("SELECT pg_terminate_backend($1)", &[&pid]).await?;
+        }
+        Ok(client)
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL"]
+async fn stage_is_rerun_after_its_connection_is_terminated() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    let flaky = FaultInjectingPool { pool, faults: Cell::new(1) };
+    let warnings = RunWarnings::new();
+    let calls = Cell::new(0);
+    retry_recording_warnings("export schema creation", QUICK, &warnings, |attempt_warnings| {
+        calls.set(calls.get() + 1);
+        let flaky = &flaky;
+        async move {
+            let client = flaky.get().await?;
+            attempt_warnings.record(WarningCategory::SkippedEdge, "recorded before the stage's query");
+            export_schema::create_export_schema(&client).await
+        }
+    }).await.unwrap();
+    assert_eq!(calls.get(), 2);
+    assert_eq!(warnings.summaries()[0].count, 1);
+}
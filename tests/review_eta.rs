@@ -0,0 +1,13 @@
+use export_opinion::dashboard::format_review_eta;
+
+#[test]
+fn formats_days_hours_and_minutes() {
+    assert_eq!(format_review_eta(16_800, 200.0), "~3.5 days at 200/hr");
+    assert_eq!(format_review_eta(500, 200.0), "~2.5 hours at 200/hr");
+    assert_eq!(format_review_eta(50, 200.0), "~15 min at 200/hr");
+}
+
+#[test]
+fn nothing_remaining_is_done() {
+    assert_eq!(format_review_eta(0, 200.0), "done");
+}
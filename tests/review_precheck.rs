@@ -0,0 +1,35 @@
+use export_opinion::dashboard::ReviewStats;
+use export_opinion::precheck::ReviewPrecheck;
+
+#[test]
+fn reviewed_edges_need_no_confirmation() {
+    let entity = ReviewStats::new(40, 3, 0);
+    let service = ReviewStats::new(10, 0, 2);
+    let precheck = ReviewPrecheck::evaluate([&entity, &service]);
+    assert_eq!(precheck, ReviewPrecheck::Reviewed { reviewed: 5, pending: 50 });
+    assert!(!precheck.needs_confirmation());
+}
+
+#[test]
+fn all_pending_needs_confirmation_and_reports_counts() {
+    let entity = ReviewStats::new(120, 0, 0);
+    let service = ReviewStats::new(30, 0, 0);
+    let precheck = ReviewPrecheck::evaluate([&entity, &service]);
+    assert_eq!(precheck, ReviewPrecheck::AllPending { pending: 150 });
+    assert!(precheck.needs_confirmation());
+    assert!(precheck.describe("default").contains("150 pending"));
+
+    // Reviews outside the exported record types don't count
+    let reviewed_services = ReviewStats::new(5, 4, 1);
+    assert_eq!(ReviewPrecheck::evaluate([&entity]), ReviewPrecheck::AllPending { pending: 120 });
+    assert!(!ReviewPrecheck::evaluate([&entity, &reviewed_services]).needs_confirmation());
+}
+
+#[test]
+fn no_edges_needs_confirmation() {
+    let empty = ReviewStats::new(0, 0, 0);
+    let precheck = ReviewPrecheck::evaluate([&empty, &empty]);
+    assert_eq!(precheck, ReviewPrecheck::NoEdges);
+    assert!(precheck.needs_confirmation());
+    assert_eq!(ReviewPrecheck::evaluate([]), ReviewPrecheck::NoEdges);
+}
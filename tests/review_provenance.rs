@@ -0,0 +1,14 @@
+use export_opinion::export_schema::ReviewColumns;
+
+#[test]
+fn review_time_prefers_reviewed_at_and_degrades_to_null() {
+    let all = ReviewColumns { updated_at: true, reviewed_at: true, reviewed_by: Some("reviewed_by") };
+    assert_eq!(all.review_time_expression("ev"), "COALESCE(ev.reviewed_at::timestamp, ev.updated_at::timestamp)");
+    assert_eq!(all.reviewer_expression("ev"), "ev.reviewed_by::text");
+
+    let legacy = ReviewColumns { updated_at: true, ..ReviewColumns::default() };
+    assert_eq!(legacy.review_time_expression("ev"), "ev.updated_at::timestamp");
+    assert_eq!(legacy.reviewer_expression("ev"), "NULL::text");
+
+    assert_eq!(ReviewColumns::default().review_time_expression("ev"), "NULL::timestamp");
+}
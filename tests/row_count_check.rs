@@ -0,0 +1,35 @@
+use export_opinion::row_count_check::reconcile_rows;
+
+fn ids(n: usize) -> Vec<String> {
+    (1..=n).map(|i| format!("e{:02}", i)).collect()
+}
+
+#[test]
+fn every_record_fetched_once_passes() {
+    let expected = ids(12);
+    // The fetch sorts by cluster, so the order differs from the source table
+    let fetched: Vec<&str> = expected.iter().rev().map(String::as_str).collect();
+    assert_eq!(reconcile_rows("entity", &expected, fetched), None);
+    assert_eq!(reconcile_rows("entity", &[], []), None);
+}
+
+#[test]
+fn duplicated_and_missing_records_are_listed() {
+    let expected = ids(12);
+    // e03 joined into two clusters and e07 dropped: the counts match, the rows don't
+    let mut fetched: Vec<&str> = expected.iter().map(String::as_str).filter(|id| *id != "e07").collect();
+    fetched.push("e03");
+    let mismatch = reconcile_rows("entity", &expected, fetched).unwrap();
+    assert_eq!((mismatch.expected, mismatch.fetched), (12, 12));
+    assert_eq!(mismatch.duplicated, vec!["e03"]);
+    assert_eq!(mismatch.missing, vec!["e07"]);
+    assert_eq!(
+        mismatch.to_string(),
+        "12 entity records in the whitelisted datasets but 12 export rows; 1 duplicated: e03; 1 missing: e07"
+    );
+
+    let fetched: Vec<&str> = expected.iter().take(4).map(String::as_str).collect();
+    let mismatch = reconcile_rows("service", &expected, fetched).unwrap();
+    assert_eq!((mismatch.fetched, mismatch.missing.len()), (4, 8));
+    assert!(mismatch.to_string().ends_with("8 missing: e05, e06, e07, e08, e09, ..."));
+}
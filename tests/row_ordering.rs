@@ -0,0 +1,93 @@
+use export_opinion::data_fetch::{assemble_service_rows, sort_organization_rows};
+use export_opinion::models::{OrganizationExportRow, ServiceExportRow, TaxonomyExportRow};
+
+fn org(id: &str, name: Option<&str>, cluster: Option<&str>) -> OrganizationExportRow {
+    OrganizationExportRow {
+        contributor: Some("dataset_a".to_string()),
+        entity_id: id.to_string(),
+        name: name.map(str::to_string),
+        cluster_confirmed_status: "CONFIRMED_MATCH".to_string(),
+        cluster: cluster.map(str::to_string),
+        has_duplicates: cluster.is_some(),
+        ..Default::default()
+    }
+}
+
+fn svc(id: &str, name: Option<&str>, cluster: Option<&str>, term: Option<&str>) -> (ServiceExportRow, Option<TaxonomyExportRow>) {
+    let row = ServiceExportRow {
+        contributor: Some("dataset_a".to_string()),
+        service_id: id.to_string(),
+        service_name: name.map(str::to_string),
+        cluster_confirmed_status: "PENDING_REVIEW".to_string(),
+        cluster: cluster.map(str::to_string),
+        ..Default::default()
+    };
+    let taxonomy = term.map(|term| TaxonomyExportRow {
+        service_id: id.to_string(),
+        taxonomy_term: term.to_string(),
+        taxonomy_category: Some("Basic Needs".to_string()),
+        taxonomy_id: format!("t-{}", term.to_lowercase()),
+    });
+    (row, taxonomy)
+}
+
+/// Deterministic reorderings of a fixture, standing in for row order changes between runs.
+fn shuffles<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    let mut reversed = items.to_vec();
+    reversed.reverse();
+    let mut rotated = items.to_vec();
+    rotated.rotate_left(items.len() / 2);
+    let mut interleaved: Vec<T> = items.iter().step_by(2).cloned().collect();
+    interleaved.extend(items.iter().skip(1).step_by(2).cloned());
+    vec![items.to_vec(), reversed, rotated, interleaved]
+}
+
+#[test]
+fn organization_order_is_total_and_stable() {
+    let fixture = vec![
+        org("e3", Some("Food Bank"), Some("c1")),
+        org("e1", Some("Food Bank"), Some("c1")),
+        org("e2", None, Some("c1")),
+        org("e5", Some("Alpha"), None),
+        org("e4", Some("Zeta"), Some("c0")),
+        org("e6", None, None),
+    ];
+
+    let sorted: Vec<Vec<String>> = shuffles(&fixture).into_iter()
+        .map(|mut rows| {
+            sort_organization_rows(&mut rows);
+            rows.into_iter().map(|r| r.entity_id).collect()
+        })
+        .collect();
+
+    assert_eq!(sorted[0], ["e4", "e1", "e3", "e2", "e5", "e6"]);
+    assert!(sorted.iter().all(|ids| ids == &sorted[0]));
+}
+
+#[test]
+fn service_assembly_is_independent_of_row_order() {
+    let fixture = vec![
+        svc("s2", Some("Meals"), Some("c1"), Some("Food")),
+        svc("s1", Some("Meals"), Some("c1"), Some("Nutrition")),
+        svc("s1", Some("Meals"), Some("c1"), Some("Food")),
+        svc("s3", None, Some("c1"), None),
+        svc("s4", Some("Shelter"), None, Some("Housing")),
+        svc("s2", Some("Meals"), Some("c1"), Some("Basic Needs")),
+    ];
+
+    let assembled: Vec<Vec<ServiceExportRow>> = shuffles(&fixture).into_iter()
+        .map(|parts| assemble_service_rows(parts, None))
+        .collect();
+
+    let ids: Vec<&str> = assembled[0].iter().map(|r| r.service_id.as_str()).collect();
+    assert_eq!(ids, ["s1", "s2", "s3", "s4"]);
+    assert_eq!(assembled[0][0].taxonomy_terms.as_deref(), Some("Food, Nutrition"));
+    assert_eq!(assembled[0][1].taxonomy_terms.as_deref(), Some("Basic Needs, Food"));
+    assert_eq!(assembled[0][2].taxonomy_terms, None);
+    // The per-term rows behind the Service Taxonomies sheet follow the joined terms
+    let terms: Vec<&str> = assembled[0][1].taxonomies.iter().map(|t| t.taxonomy_term.as_str()).collect();
+    assert_eq!(terms, ["Basic Needs", "Food"]);
+    assert!(assembled[0][1].taxonomies.iter().all(|t| t.service_id == "s2"));
+    assert!(assembled[0][2].taxonomies.is_empty());
+    assert!(assembled.iter().all(|rows| rows == &assembled[0]));
+}
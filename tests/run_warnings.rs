@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use export_opinion::models::RawEdgeVisualization;
+use export_opinion::reclustering::{check_skipped_edges, classify_edges, EmptyIdCounts, SkippedEdgeLimit};
+use export_opinion::run_warnings::{RunWarnings, WarningCategory, SUMMARY_EXAMPLES};
+use serde_json::json;
+
+fn edge(id: &str, id_1: &str, id_2: &str, status: &str, weight: Option<f64>) -> RawEdgeVisualization {
+    RawEdgeVisualization {
+        id: id.to_string(),
+        entity_id_1: Some(id_1.to_string()),
+        entity_id_2: Some(id_2.to_string()),
+        service_id_1: None,
+        service_id_2: None,
+        confirmed_status: Some(status.to_string()),
+        details: weight.map(|w| json!({ "calculated_edge_weight": w })),
+        updated_at: None,
+        reviewed_at: None,
+        reviewed_by: None,
+    }
+}
+
+#[test]
+fn bad_edges_are_recorded_by_category() {
+    let existing: HashSet<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+    let edges = vec![
+        edge("good", "a", "b", "CONFIRMED_MATCH", Some(0.9)),
+        edge("empty", "a", "", "CONFIRMED_MATCH", Some(0.9)),
+        edge("orphan", "a", "deleted", "PENDING_REVIEW", Some(0.9)),
+        edge("unweighted", "b", "c", "PENDING_REVIEW", None),
+        edge("rejected", "a", "c", "CONFIRMED_NON_MATCH", None),
+    ];
+    let warnings = RunWarnings::new();
+
+    let classified = classify_edges(&edges, "entity", &existing, None, &warnings);
+
+    assert_eq!(classified.valid.len(), 2);
+    assert_eq!(classified.valid[1].weight, 1.0);
+    assert_eq!(classified.orphaned.len(), 1);
+    assert_eq!(classified.orphaned[0].missing_ids, "deleted");
+
+    let counts: Vec<(WarningCategory, usize)> = warnings.summaries().iter().map(|s| (s.category, s.count)).collect();
+    assert_eq!(counts, vec![
+        (WarningCategory::SkippedEdge, 1),
+        (WarningCategory::OrphanedEdge, 1),
+        (WarningCategory::FallbackWeight, 1),
+    ]);
+}
+
+#[test]
+fn self_loops_are_skipped_without_touching_the_other_edges() {
+    let existing: HashSet<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+    let edges = vec![
+        edge("ab", "a", "b", "CONFIRMED_MATCH", Some(0.9)),
+        edge("loop", "c", "c", "CONFIRMED_MATCH", Some(0.9)),
+        edge("padded-loop", "b", " b ", "PENDING_REVIEW", Some(0.5)),
+    ];
+    let warnings = RunWarnings::new();
+
+    let classified = classify_edges(&edges, "entity", &existing, None, &warnings);
+
+    let kept: Vec<(&str, &str)> = classified.valid.iter().map(|e| (e.id_1.as_str(), e.id_2.as_str())).collect();
+    assert_eq!(kept, vec![("a", "b")]);
+    assert!(classified.orphaned.is_empty());
+    let summaries = warnings.summaries();
+    assert_eq!((summaries[0].category, summaries[0].count), (WarningCategory::SkippedEdge, 2));
+    assert!(summaries[0].examples[0].contains("loop"));
+}
+
+#[test]
+fn empty_ids_are_counted_by_endpoint_and_recorded_once_per_kind() {
+    let existing: HashSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    let mut edges = vec![edge("ab", "a", "b", "CONFIRMED_MATCH", Some(0.9))];
+    edges.extend((0..50).map(|i| edge(&format!("no-2-{}", i), "a", "", "CONFIRMED_MATCH", Some(0.9))));
+    edges.push(edge("no-1", "", "b", "PENDING_REVIEW", Some(0.9)));
+    edges.push(edge("none", "", "", "PENDING_REVIEW", Some(0.9)));
+    let warnings = RunWarnings::new();
+
+    let classified = classify_edges(&edges, "entity", &existing, None, &warnings);
+
+    assert_eq!(classified.valid.len(), 1);
+    assert_eq!(classified.empty_ids, EmptyIdCounts { id_1: 1, id_2: 50, both: 1 });
+    let summaries = warnings.summaries();
+    assert_eq!((summaries[0].category, summaries[0].count), (WarningCategory::SkippedEdge, 3));
+    assert_eq!(summaries[0].examples, vec![
+        "1 entity edges with an empty id_1",
+        "50 entity edges with an empty id_2",
+        "1 entity edges with both ids empty",
+    ]);
+}
+
+#[test]
+fn skipped_edge_limit_takes_a_count_or_a_percentage() {
+    assert_eq!(SkippedEdgeLimit::parse("500"), Ok(SkippedEdgeLimit::Count(500)));
+    assert_eq!(SkippedEdgeLimit::parse(" 2.5% "), Ok(SkippedEdgeLimit::Percent(2.5)));
+    assert!(SkippedEdgeLimit::parse("150%").is_err());
+    assert!(SkippedEdgeLimit::parse("many").is_err());
+
+    let empty_ids = EmptyIdCounts { id_1: 3, id_2: 2, both: 0 };
+    assert!(check_skipped_edges(None, &empty_ids, 10, "entity").is_ok());
+    assert!(check_skipped_edges(Some(SkippedEdgeLimit::Count(5)), &empty_ids, 10, "entity").is_ok());
+    assert!(check_skipped_edges(Some(SkippedEdgeLimit::Percent(50.0)), &empty_ids, 10, "entity").is_ok());
+
+    let error = check_skipped_edges(Some(SkippedEdgeLimit::Count(4)), &empty_ids, 10, "entity").unwrap_err();
+    assert!(error.to_string().starts_with("5 of 10 entity edges have an empty endpoint id (empty id_1: 3, empty id_2: 2, both: 0)"));
+    assert!(check_skipped_edges(Some(SkippedEdgeLimit::Percent(10.0)), &empty_ids, 100, "service").is_ok());
+    assert!(check_skipped_edges(Some(SkippedEdgeLimit::Percent(4.9)), &empty_ids, 100, "service").is_err());
+    // An opinion without edges has nothing to skip
+    assert!(check_skipped_edges(Some(SkippedEdgeLimit::Percent(0.0)), &EmptyIdCounts::default(), 0, "entity").is_ok());
+}
+
+#[test]
+fn summaries_keep_counts_but_only_the_first_examples() {
+    let warnings = RunWarnings::new();
+    assert!(warnings.is_empty());
+
+    let shared = warnings.clone();
+    for i in 0..SUMMARY_EXAMPLES + 2 {
+        shared.record(WarningCategory::OrphanedEdge, format!("edge {}", i));
+    }
+
+    let summaries = warnings.summaries();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].count, SUMMARY_EXAMPLES + 2);
+    assert_eq!(summaries[0].examples, (0..SUMMARY_EXAMPLES).map(|i| format!("edge {}", i)).collect::<Vec<_>>());
+}
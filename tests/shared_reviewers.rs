@@ -0,0 +1,150 @@
+use export_opinion::confirmed_status::ConfirmedStatus;
+use export_opinion::models::RawEdgeVisualization;
+use export_opinion::shared_reviewers::{
+    merge_reviewer_statuses, merged_status, pair_key, reconcile, resolve_shared_reviewers, ConflictPolicy, MergeSummary,
+    Reconciled, ReviewerDecisions, SharedReview, SharedReviewer,
+};
+use export_opinion::team_utils::{OpinionInfo, UserInfo};
+use std::collections::BTreeMap;
+
+use ConfirmedStatus::{ConfirmedMatch, ConfirmedNonMatch, PendingReview};
+
+fn edge(id: &str, id_1: &str, id_2: &str, status: Option<&str>) -> RawEdgeVisualization {
+    RawEdgeVisualization {
+        id: id.to_string(),
+        entity_id_1: Some(id_1.to_string()),
+        entity_id_2: Some(id_2.to_string()),
+        service_id_1: None,
+        service_id_2: None,
+        confirmed_status: status.map(str::to_string),
+        details: None,
+        updated_at: None,
+        reviewed_at: None,
+        reviewed_by: None,
+    }
+}
+
+fn user(username: &str, prefix: Option<&str>) -> UserInfo {
+    UserInfo {
+        id: format!("id-{}", username),
+        username: username.to_string(),
+        email: None,
+        user_opinion_prefix: prefix.map(str::to_string),
+        team_id: Some("team-1".to_string()),
+        is_active: true,
+    }
+}
+
+#[test]
+fn non_match_wins_then_match_then_pending() {
+    for a in ConfirmedStatus::ALL {
+        assert_eq!(merged_status(a, ConfirmedNonMatch), ConfirmedNonMatch);
+        assert_eq!(merged_status(ConfirmedNonMatch, a), ConfirmedNonMatch);
+        assert_eq!(merged_status(a, PendingReview), a);
+        assert_eq!(merged_status(PendingReview, a), a);
+    }
+    assert_eq!(merged_status(ConfirmedMatch, ConfirmedMatch), ConfirmedMatch);
+}
+
+fn shared(policy: ConflictPolicy) -> SharedReview {
+    SharedReview { reviewers: Vec::new(), exporting_reviewer: "alice".to_string(), owner: "owner".to_string(), policy }
+}
+
+fn decisions(username: &str, pairs: &[(&str, &str, ConfirmedStatus)]) -> ReviewerDecisions {
+    ReviewerDecisions {
+        username: username.to_string(),
+        statuses: pairs.iter().map(|(a, b, status)| (pair_key(a, b), *status)).collect(),
+    }
+}
+
+#[test]
+fn conflicts_are_resolved_by_the_policy() {
+    let conflicting = BTreeMap::from([("owner", ConfirmedMatch), ("alice", ConfirmedNonMatch), ("bob", PendingReview)]);
+    let resolved = |policy| reconcile(&conflicting, "owner", policy);
+    assert_eq!(resolved(ConflictPolicy::MostRestrictive).status, ConfirmedNonMatch);
+    assert_eq!(resolved(ConflictPolicy::MostPermissive).status, ConfirmedMatch);
+    assert_eq!(resolved(ConflictPolicy::OwnerWins).status, ConfirmedMatch);
+    assert!(resolved(ConflictPolicy::OwnerWins).conflict);
+
+    // Without an owner decision, owner-wins falls back to the most restrictive
+    let owner_pending = BTreeMap::from([("owner", PendingReview), ("alice", ConfirmedMatch), ("bob", ConfirmedNonMatch)]);
+    assert_eq!(reconcile(&owner_pending, "owner", ConflictPolicy::OwnerWins).status, ConfirmedNonMatch);
+}
+
+#[test]
+fn agreeing_decisions_win_over_pending_under_every_policy() {
+    for policy in [ConflictPolicy::MostRestrictive, ConflictPolicy::MostPermissive, ConflictPolicy::OwnerWins] {
+        let agreed = BTreeMap::from([("owner", PendingReview), ("alice", ConfirmedMatch), ("bob", ConfirmedMatch)]);
+        assert_eq!(reconcile(&agreed, "owner", policy), Reconciled { status: ConfirmedMatch, conflict: false });
+        let undecided = BTreeMap::from([("owner", PendingReview), ("alice", PendingReview)]);
+        assert_eq!(reconcile(&undecided, "owner", policy).status, PendingReview);
+        let split = BTreeMap::from([("alice", ConfirmedNonMatch)]);
+        assert_eq!(reconcile(&split, "owner", policy).status, ConfirmedNonMatch);
+    }
+}
+
+#[test]
+fn reviewer_decisions_merge_into_existing_edges_in_either_direction() {
+    let edges = || vec![
+        edge("e1", "a", "b", Some("CONFIRMED_MATCH")),
+        edge("e2", "b", "c", None),
+        edge("e3", "c", "d", Some("NON_MATCH")),
+        edge("e4", "d", "e", Some("PENDING_REVIEW")),
+    ];
+    let others = vec![
+        decisions("owner", &[
+            ("b", "a", ConfirmedNonMatch),
+            ("c", "b", ConfirmedMatch),
+            ("c", "d", ConfirmedMatch),
+            // Only the owner has this pair; it is not added
+            ("x", "y", ConfirmedMatch),
+        ]),
+    ];
+
+    let mut restrictive = edges();
+    let summary = merge_reviewer_statuses(&mut restrictive, "entity", &shared(ConflictPolicy::MostRestrictive), &others);
+    assert_eq!(summary, MergeSummary { changed: 2, conflicts_matched: 0, conflicts_split: 2 });
+    let merged: Vec<Option<&str>> = restrictive.iter().map(|e| e.confirmed_status.as_deref()).collect();
+    // The legacy NON_MATCH already wins and is left as stored
+    assert_eq!(merged, vec![Some("CONFIRMED_NON_MATCH"), Some("CONFIRMED_MATCH"), Some("NON_MATCH"), Some("PENDING_REVIEW")]);
+    assert_eq!(restrictive.len(), 4);
+
+    let mut owner_wins = edges();
+    let summary = merge_reviewer_statuses(&mut owner_wins, "entity", &shared(ConflictPolicy::OwnerWins), &others);
+    assert_eq!(summary, MergeSummary { changed: 3, conflicts_matched: 1, conflicts_split: 1 });
+    let merged: Vec<Option<&str>> = owner_wins.iter().map(|e| e.confirmed_status.as_deref()).collect();
+    assert_eq!(merged, vec![Some("CONFIRMED_NON_MATCH"), Some("CONFIRMED_MATCH"), Some("CONFIRMED_MATCH"), Some("PENDING_REVIEW")]);
+}
+
+#[test]
+fn reviewers_are_the_owner_and_other_users_except_the_exported_prefix() {
+    let opinion = OpinionInfo {
+        id: "op-1".to_string(),
+        name: "main".to_string(),
+        user_id: "id-owner".to_string(),
+        owner_username: "owner".to_string(),
+        other_users: ["id-alice", "id-bob", "id-carol", "id-gone", "id-twin"].iter().map(|s| s.to_string()).collect(),
+        shared_with_usernames: Vec::new(),
+        disconnect_dependent_services: false,
+    };
+    let users = vec![
+        user("owner", Some("own")),
+        user("alice", Some("ali")),
+        user("bob", None),
+        user("carol", Some("bad prefix")),
+        user("twin", Some("ali")),
+    ];
+
+    // Alice exports her own tables; the owner is merged in
+    let resolved = resolve_shared_reviewers(&opinion, &users, "ali");
+    assert_eq!(resolved.exporting.as_deref(), Some("alice"));
+    assert_eq!(resolved.reviewers, vec![SharedReviewer { username: "owner".to_string(), prefix: "own".to_string() }]);
+    assert_eq!(resolved.skipped.len(), 3);
+    assert!(resolved.skipped[0].contains("bob has no opinion prefix"));
+    assert!(resolved.skipped[1].contains("carol has an unusable opinion prefix"));
+    assert!(resolved.skipped[2].contains("id-gone"));
+
+    let prefixes: Vec<String> = resolve_shared_reviewers(&opinion, &users, "own").reviewers.into_iter().map(|r| r.prefix).collect();
+    assert_eq!(prefixes, vec!["ali"]);
+    assert!(resolve_shared_reviewers(&opinion, &users, "other").exporting.is_none());
+}
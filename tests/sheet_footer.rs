@@ -0,0 +1,16 @@
+use export_opinion::excel_writer::FooterCounts;
+
+#[test]
+fn footer_counts_records_and_duplicates_below_a_blank_row() {
+    let counts = FooterCounts::count([true, false, true, false, false].into_iter());
+
+    assert_eq!(counts, FooterCounts { records: 5, with_duplicates: 2 });
+    // Header in row 0, data in rows 1-5, blank row 6
+    assert_eq!(counts.row(), 7);
+}
+
+#[test]
+fn empty_sheet_footer_follows_the_header() {
+    let counts = FooterCounts::count(std::iter::empty());
+    assert_eq!((counts.records, counts.with_duplicates, counts.row()), (0, 0, 2));
+}
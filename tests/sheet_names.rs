@@ -0,0 +1,32 @@
+use export_opinion::excel_writer::{sanitize_sheet_name, SheetNameAllocator};
+
+#[test]
+fn illegal_characters_are_replaced() {
+    assert_eq!(sanitize_sheet_name("Services – 2024/25: [draft]?*"), "Services – 2024_25_ _draft___");
+    assert_eq!(sanitize_sheet_name(r"C:\exports"), "C__exports");
+    assert_eq!(sanitize_sheet_name("'quoted'"), "quoted");
+    assert_eq!(sanitize_sheet_name("  "), "Sheet");
+    assert_eq!(sanitize_sheet_name("Organizations"), "Organizations");
+}
+
+#[test]
+fn names_that_collide_after_truncation_get_a_suffix() {
+    let mut names = SheetNameAllocator::new();
+    let first = names.allocate("Organizations – king_county_food_access_2024");
+    let second = names.allocate("Organizations – King_County_Food_Access_2025");
+    let third = names.allocate("Organizations – king_county_food_access_2026");
+
+    assert_eq!(first, "Organizations – king_county_foo");
+    assert_eq!(second, "Organizations – King_County (2)");
+    assert_eq!(third, "Organizations – king_county (3)");
+    for name in [&first, &second, &third] {
+        assert!(name.chars().count() <= 31, "{}", name);
+    }
+}
+
+#[test]
+fn sanitized_names_are_also_deduplicated() {
+    let mut names = SheetNameAllocator::new();
+    assert_eq!(names.allocate("Opinion a/b"), "Opinion a_b");
+    assert_eq!(names.allocate("Opinion a:b"), "Opinion a_b (2)");
+}
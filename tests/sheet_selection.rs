@@ -0,0 +1,20 @@
+use export_opinion::models::RecordScope;
+use export_opinion::sheet_selection::{SheetKind, SheetSelection};
+
+#[test]
+fn empty_selection_keeps_every_sheet_and_the_scope() {
+    let all = SheetSelection::default();
+    assert!(all.includes(SheetKind::Legend) && all.includes(SheetKind::Progress));
+    assert_eq!(all.narrow(RecordScope::Both), Some(RecordScope::Both));
+    assert_eq!(all.narrow(RecordScope::EntitiesOnly), Some(RecordScope::EntitiesOnly));
+}
+
+#[test]
+fn leaving_out_a_data_sheet_skips_its_record_type() {
+    let services = SheetSelection::new(vec![SheetKind::Services, SheetKind::Legend]);
+    assert!(!services.includes(SheetKind::Organizations));
+    assert!(!services.includes(SheetKind::Progress));
+    assert_eq!(services.narrow(RecordScope::Both), Some(RecordScope::ServicesOnly));
+    // --entities-only with only the Services sheet leaves nothing to export
+    assert_eq!(services.narrow(RecordScope::EntitiesOnly), None);
+}
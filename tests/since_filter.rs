@@ -0,0 +1,28 @@
+use chrono::NaiveDate;
+use export_opinion::data_fetch::{check_since_columns, create_since_clause};
+use export_opinion::export_schema::ReviewColumns;
+
+#[test]
+fn without_a_date_every_cluster_matches() {
+    assert_eq!(create_since_clause("cr.last_reviewed", None), "1=1");
+}
+
+#[test]
+fn clusters_need_a_review_on_or_after_the_date() {
+    let since = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    // A NULL last_reviewed (no cluster, or only pending edges) never compares true
+    assert_eq!(create_since_clause("cr.last_reviewed", Some(since)), "cr.last_reviewed >= DATE '2025-03-01'");
+}
+
+#[test]
+fn since_fails_without_a_review_time_column() {
+    let since = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    let none = ReviewColumns::from_columns(&["id".to_string(), "created_at".to_string()]);
+    let error = check_since_columns(none, Some(since), "a_b_entity_edge_visualization_1").unwrap_err().to_string();
+    assert!(error.contains("reviewed_at or updated_at") && error.contains("a_b_entity_edge_visualization_1"), "{}", error);
+    // Without --since the table exports as before
+    assert!(check_since_columns(none, None, "t").is_ok());
+
+    let updated = ReviewColumns::from_columns(&["updated_at".to_string()]);
+    assert!(check_since_columns(updated, Some(since), "t").is_ok());
+}
@@ -0,0 +1,44 @@
+use export_opinion::data_fetch::AliasSources;
+use export_opinion::table_names::{hsds_table, set_source_schema, source_schema, validate_schema_name};
+
+/// Every test in this binary uses the same source schema, which can only be set once.
+fn use_hsds_prod() {
+    set_source_schema("hsds_prod").unwrap();
+}
+
+#[test]
+fn schema_names_must_be_plain_identifiers() {
+    assert_eq!(validate_schema_name("hsds_prod"), Ok("hsds_prod".to_string()));
+    assert_eq!(validate_schema_name("public"), Ok("public".to_string()));
+    assert!(validate_schema_name("").is_err());
+    assert!(validate_schema_name("1hsds").is_err());
+    assert!(validate_schema_name(r#"hsds"; DROP SCHEMA auth; --"#).is_err());
+    assert!(validate_schema_name("hsds.prod").is_err());
+    assert!(validate_schema_name(&"s".repeat(64)).is_err());
+}
+
+#[test]
+fn source_tables_are_quoted_with_the_configured_schema() {
+    use_hsds_prod();
+    assert_eq!(source_schema(), "hsds_prod");
+    assert_eq!(hsds_table("entity"), r#""hsds_prod"."entity""#);
+    assert_eq!(hsds_table("service_at_location"), r#""hsds_prod"."service_at_location""#);
+}
+
+#[test]
+fn the_schema_cannot_change_once_set() {
+    use_hsds_prod();
+    assert!(set_source_schema("hsds_prod").is_ok());
+    assert!(set_source_schema("public").is_err());
+    assert!(set_source_schema("bad name").is_err());
+    assert_eq!(source_schema(), "hsds_prod");
+}
+
+#[test]
+fn alternate_names_read_the_organization_from_the_source_schema() {
+    use_hsds_prod();
+    let sources = AliasSources { entity_alternate_names: false, organization_alternate_name: true };
+    let sql = sources.expression("e");
+    assert!(sql.contains(r#"FROM "hsds_prod"."organization" ao"#), "{}", sql);
+    assert!(!sql.contains("public."), "{}", sql);
+}
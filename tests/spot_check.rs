@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+
+use export_opinion::data_fetch::create_spot_check_clause;
+use export_opinion::models::SpotCheck;
+
+fn ids(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn empty_spot_check_matches_everything() {
+    let (clause, params) = create_spot_check_clause("e", "cs.cluster_id", "g_table", "entity", &SpotCheck::default(), 3);
+    assert_eq!(clause, "1=1");
+    assert!(params.is_empty());
+}
+
+#[test]
+fn record_ids_pull_in_their_whole_cluster() {
+    let spot_check = SpotCheck { cluster_ids: ids(&["c1"]), record_ids: ids(&["e1", "e2"]) };
+    let (clause, params) = create_spot_check_clause("e", "cs.cluster_id", "g_table", "entity", &spot_check, 3);
+
+    assert_eq!(
+        clause,
+        "(cs.cluster_id = ANY(ARRAY[$3]) OR e.id = ANY(ARRAY[$4, $5]) OR cs.cluster_id IN \
+         (SELECT g.group_cluster_id FROM g_table g WHERE g.entity_id_1 = ANY(ARRAY[$4, $5]) OR g.entity_id_2 = ANY(ARRAY[$4, $5])))"
+    );
+    assert_eq!(params, ids(&["c1", "e1", "e2"]));
+}
+
+#[test]
+fn cluster_ids_alone_filter_on_the_cluster_column() {
+    let spot_check = SpotCheck { cluster_ids: ids(&["c1", "c2"]), record_ids: vec![] };
+    let (clause, params) = create_spot_check_clause("s", "cs.cluster_id", "g_table", "service", &spot_check, 1);
+
+    assert_eq!(clause, "(cs.cluster_id = ANY(ARRAY[$1, $2]))");
+    assert_eq!(params, ids(&["c1", "c2"]));
+}
+
+#[test]
+fn missing_ids_lists_ids_absent_from_the_export() {
+    let spot_check = SpotCheck { cluster_ids: ids(&["c1", "c9"]), record_ids: ids(&["e1", "e9"]) };
+    let exported: HashSet<&str> = ["c1", "e1", "e2"].into_iter().collect();
+    assert_eq!(spot_check.missing_ids(&exported), vec!["c9", "e9"]);
+}
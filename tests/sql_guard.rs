@@ -0,0 +1,76 @@
+use export_opinion::sql_guard::validate_export_write;
+use export_opinion::table_names::{qualified, EXPORT_RUNS_TABLE, EXPORT_SCHEMA, SNAPSHOT_TABLE, TEAM_SCHEMA};
+
+#[test]
+fn writes_to_export_schema_are_allowed() {
+    let target = qualified(EXPORT_SCHEMA, "hannah_default_entity_group_export_20250101120000");
+    let source = qualified(TEAM_SCHEMA, "hannah_default_entity_group");
+    for sql in [
+        format!("CREATE SCHEMA IF NOT EXISTS {};", EXPORT_SCHEMA),
+        format!("DROP TABLE IF EXISTS {} CASCADE;", target),
+        format!("CREATE TABLE {} (LIKE {} INCLUDING ALL);", target, source),
+        format!("ALTER TABLE {} DROP CONSTRAINT IF EXISTS order_check;", target),
+        format!("INSERT INTO {} SELECT * FROM {};", target, source),
+        format!("\n            INSERT INTO {} (id) SELECT * FROM UNNEST($1::text[])\n            ", target),
+        format!("DELETE FROM {}", target),
+        format!("COPY {} (id, name) FROM STDIN", target),
+        format!("CREATE TABLE IF NOT EXISTS {} (id BIGSERIAL PRIMARY KEY)", qualified(EXPORT_SCHEMA, SNAPSHOT_TABLE)),
+        format!("ALTER TABLE {} DROP CONSTRAINT IF EXISTS \"Order_Check\";", target),
+        format!(
+            "INSERT INTO {} (user_prefix) VALUES ($1) ON CONFLICT (user_prefix) DO UPDATE SET completed_at = now()",
+            qualified(EXPORT_SCHEMA, EXPORT_RUNS_TABLE)
+        ),
+        format!("GRANT USAGE ON SCHEMA {} TO \"reporting\";", EXPORT_SCHEMA),
+        format!("GRANT SELECT ON ALL TABLES IN SCHEMA {} TO \"reporting\";", EXPORT_SCHEMA),
+        format!("ALTER DEFAULT PRIVILEGES IN SCHEMA {} GRANT SELECT ON TABLES TO \"reporting\";", EXPORT_SCHEMA),
+    ] {
+        assert!(validate_export_write(&sql).is_ok(), "rejected: {}", sql);
+    }
+}
+
+#[test]
+fn writes_outside_export_schema_are_rejected() {
+    let team_table = qualified(TEAM_SCHEMA, "hannah_default_entity_group");
+    for sql in [
+        format!("DELETE FROM {}", team_table),
+        format!("INSERT INTO {} SELECT 1", team_table),
+        format!("UPDATE {} SET confirmed_status = 'CONFIRMED_MATCH'", team_table),
+        format!("DROP TABLE IF EXISTS {} CASCADE", team_table),
+        format!("TRUNCATE TABLE {}", team_table),
+        format!("COPY {} (id) FROM STDIN", team_table),
+        format!("ALTER TABLE {} DROP CONSTRAINT some_check", team_table),
+        "CREATE SCHEMA IF NOT EXISTS wa211_to_wric".to_string(),
+        "DELETE FROM public.entity".to_string(),
+        format!("GRANT USAGE ON SCHEMA {} TO reporting", TEAM_SCHEMA),
+        format!("GRANT SELECT ON ALL TABLES IN SCHEMA {} TO reporting", TEAM_SCHEMA),
+        "ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT SELECT ON TABLES TO reporting".to_string(),
+    ] {
+        assert!(validate_export_write(&sql).is_err(), "allowed: {}", sql);
+    }
+}
+
+#[test]
+fn unqualified_or_unrecognized_writes_are_rejected() {
+    for sql in [
+        "DELETE FROM entity",
+        "SELECT * FROM public.entity",
+        "GRANT ALL ON SCHEMA public TO reporter",
+        "COPY public.entity FROM '/tmp/x'",
+    ] {
+        assert!(validate_export_write(sql).is_err(), "allowed: {}", sql);
+    }
+}
+
+#[test]
+fn schema_lookalikes_and_multiple_statements_are_rejected() {
+    let export_table = qualified(EXPORT_SCHEMA, "t");
+    for sql in [
+        format!("DELETE FROM \"{}_x\".\"t\"", EXPORT_SCHEMA),
+        format!("DELETE FROM \"{}\".\"t\"", EXPORT_SCHEMA.to_uppercase()),
+        format!("DELETE FROM {}; DELETE FROM public.entity", export_table),
+    ] {
+        assert!(validate_export_write(&sql).is_err(), "allowed: {}", sql);
+    }
+    // Unquoted identifiers fold to lowercase, as in PostgreSQL
+    assert!(validate_export_write(&format!("DELETE FROM {}.t", EXPORT_SCHEMA.to_uppercase())).is_ok());
+}
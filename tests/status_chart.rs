@@ -0,0 +1,19 @@
+use export_opinion::dashboard::{status_split, ReviewStats};
+
+#[test]
+fn split_sums_the_exported_record_types() {
+    let entity = ReviewStats::new(5, 3, 1);
+    let service = ReviewStats::new(2, 0, 4);
+
+    assert_eq!(status_split(&[&entity, &service]), Some([
+        ("Pending Review", 7),
+        ("Confirmed Match", 3),
+        ("Confirmed Non-Match", 5),
+    ]));
+}
+
+#[test]
+fn no_edges_means_no_chart() {
+    assert_eq!(status_split(&[&ReviewStats::new(0, 0, 0)]), None);
+    assert_eq!(status_split(&[]), None);
+}
@@ -0,0 +1,38 @@
+use chrono::{TimeZone, Utc};
+use export_opinion::status_file::{write_atomically, RunOutcome, RunStatus};
+
+#[test]
+fn status_json_has_the_documented_fields() {
+    let started_at = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+    let mut status = RunStatus::new(started_at);
+    status.stage = "fetching services (default)".to_string();
+    status.rows_processed = 1200;
+
+    let json = serde_json::to_value(&status).unwrap();
+
+    assert_eq!(json["outcome"], "running");
+    assert_eq!(json["stage"], "fetching services (default)");
+    assert_eq!(json["rows_processed"], 1200);
+    assert_eq!(json["started_at"], "2026-01-02T03:04:05Z");
+    assert_eq!(json["last_heartbeat"], json["started_at"]);
+    assert!(json["pool"].is_null() && json["error"].is_null());
+    assert_eq!(json["method_performance"], serde_json::json!([]));
+    assert_eq!(json["export_tables"], serde_json::json!([]));
+
+    status.outcome = RunOutcome::Failed;
+    assert_eq!(serde_json::to_value(&status).unwrap()["outcome"], "failed");
+}
+
+#[test]
+fn atomic_write_replaces_the_file_and_leaves_no_temp_file() {
+    let dir = std::env::temp_dir().join(format!("export_opinion_status_file_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("status.json");
+
+    write_atomically(&path, b"{\"stage\":\"one\"}").unwrap();
+    write_atomically(&path, b"{\"stage\":\"two\"}").unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"stage\":\"two\"}");
+    assert!(!dir.join("status.json.tmp").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
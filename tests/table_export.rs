@@ -0,0 +1,79 @@
+use export_opinion::db_connect;
+use export_opinion::excel_writer::{organization_sheet, service_sheet};
+use export_opinion::export_schema::{self, scoped_row_tables};
+use export_opinion::models::{OrganizationExportRow, RecordScope};
+use export_opinion::sql_guard::{self, validate_export_write};
+use export_opinion::table_export::{
+    create_table_sql, drop_table_sql, insert_sql, write_export_tables, ExportTables, ORGANIZATION_COLUMNS, SERVICE_COLUMNS,
+};
+use export_opinion::table_names;
+
+#[test]
+fn table_columns_follow_the_data_sheet_columns() {
+    let org_columns: Vec<&str> = ORGANIZATION_COLUMNS.iter().map(|(name, _)| *name).collect();
+    assert_eq!(org_columns, organization_sheet().headers());
+    let svc_columns: Vec<&str> = SERVICE_COLUMNS.iter().map(|(name, _)| *name).collect();
+    assert_eq!(svc_columns, service_sheet().headers());
+}
+
+#[test]
+fn statements_target_the_export_schema_with_one_array_per_column() {
+    let table = table_names::rows_export("alice", "default", "org", "20250101_120000");
+    assert_eq!(table, "alice_default_org_export_20250101_120000");
+
+    let columns = [("entity_id", "text"), ("has_duplicates", "boolean"), ("service_count", "int8")];
+    let create = create_table_sql(&table, &columns);
+    assert_eq!(
+        create,
+        r#"CREATE TABLE "wa211_to_wric_exports"."alice_default_org_export_20250101_120000" (entity_id text, has_duplicates boolean, service_count int8)"#
+    );
+    let insert = insert_sql(&table, &columns);
+    assert!(insert.ends_with("(entity_id, has_duplicates, service_count) SELECT * FROM UNNEST($1::text[], $2::boolean[], $3::int8[])"));
+
+    let drop = drop_table_sql(&table);
+    assert_eq!(drop, r#"DROP TABLE IF EXISTS "wa211_to_wric_exports"."alice_default_org_export_20250101_120000""#);
+
+    for sql in [drop, create, insert, create_table_sql(&table, &SERVICE_COLUMNS), insert_sql(&table, &ORGANIZATION_COLUMNS)] {
+        validate_export_write(&sql).unwrap();
+    }
+}
+
+#[test]
+fn only_written_tables_are_named() {
+    let tables = ExportTables { organizations: None, services: Some("wa211_to_wric_exports.a_b_svc_export_1".to_string()) };
+    assert_eq!(tables.names(), vec!["wa211_to_wric_exports.a_b_svc_export_1"]);
+}
+
+#[test]
+fn row_tables_follow_the_record_scope() {
+    assert_eq!(scoped_row_tables(RecordScope::Both).collect::<Vec<_>>(), vec!["org", "svc"]);
+    assert_eq!(scoped_row_tables(RecordScope::ServicesOnly).collect::<Vec<_>>(), vec!["svc"]);
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL database in TEST_DATABASE_URL"]
+async fn a_replace_rerun_overwrites_the_row_tables() {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+    let pool = db_connect::connect_to(url.parse().unwrap()).await.unwrap();
+    let client = sql_guard::export_write_client(&pool, "test setup").await.unwrap();
+    export_schema::create_export_schema(&client).await.unwrap();
+    drop(client);
+
+    let rows = |count: usize| -> Vec<OrganizationExportRow> {
+        (0..count).map(|i| OrganizationExportRow { entity_id: format!("e{}", i), ..Default::default() }).collect()
+    };
+    let (prefix, opinion, suffix) = ("table_export_test", "main", "replace_rerun");
+    write_export_tables(&pool, prefix, opinion, suffix, Some(&rows(3)), None).await.unwrap();
+
+    let client = pool.get().await.unwrap();
+    let existing = export_schema::existing_export_tables(&client, prefix, opinion, suffix, RecordScope::EntitiesOnly, true).await.unwrap();
+    let table = table_names::qualified(table_names::EXPORT_SCHEMA, &table_names::rows_export(prefix, opinion, "org", suffix));
+    assert!(existing.contains(&table), "{:?}", existing);
+    drop(client);
+
+    // The same suffix again, as with --timestamp-suffix and --replace
+    write_export_tables(&pool, prefix, opinion, suffix, Some(&rows(5)), None).await.unwrap();
+    let client = pool.get().await.unwrap();
+    let count: i64 = client.query_one(&format!("SELECT count(*) FROM {}", table), &[]).await.unwrap().get(0);
+    assert_eq!(count, 5);
+}
@@ -0,0 +1,85 @@
+use clap::Parser;
+use export_opinion::cli::Cli;
+use export_opinion::table_names::{
+    cluster_export, edge_viz_export, edge_viz_source, export_table, group_export, qualified, source_table,
+    validate_table_prefix, validate_timestamp_suffix, EXPORTED_TABLES, MAX_PREFIX_LEN, MAX_SUFFIX_LEN,
+};
+
+#[test]
+fn source_names_include_prefix_and_opinion() {
+    assert_eq!(source_table("hannah", "default", "entity_group"), "hannah_default_entity_group");
+    assert_eq!(edge_viz_source("hannah", "default", "entity"), "hannah_default_entity_edge_visualization");
+    assert_eq!(edge_viz_source("hannah", "default", "service"), "hannah_default_service_edge_visualization");
+}
+
+#[test]
+fn export_names_include_prefix_opinion_and_timestamp() {
+    let ts = "20250101120000";
+    assert_eq!(export_table("hannah", "default", "entity_group", ts), "hannah_default_entity_group_export_20250101120000");
+    assert_eq!(edge_viz_export("hannah", "default", "entity", ts), "hannah_default_entity_edge_visualization_export_20250101120000");
+    assert_eq!(group_export("hannah", "default", "service", ts), "hannah_default_service_group_export_20250101120000");
+    assert_eq!(cluster_export("hannah", "default", "service", ts), "hannah_default_service_group_cluster_export_20250101120000");
+}
+
+#[test]
+fn kind_specific_names_match_the_copied_table_list() {
+    // The tables read by re-clustering and fetch must be the ones the copy step creates
+    let ts = "20250101120000";
+    for kind in ["entity", "service"] {
+        for name in [
+            edge_viz_export("p", "o", kind, ts),
+            group_export("p", "o", kind, ts),
+            cluster_export("p", "o", kind, ts),
+        ] {
+            assert!(
+                EXPORTED_TABLES.iter().any(|t| export_table("p", "o", t, ts) == name),
+                "{} is not created by the copy step", name
+            );
+        }
+    }
+}
+
+#[test]
+fn qualified_quotes_schema_and_table() {
+    assert_eq!(qualified("wa211_to_wric_exports", "t"), r#""wa211_to_wric_exports"."t""#);
+}
+
+#[test]
+fn timestamp_suffixes_must_be_safe_identifiers() {
+    for ok in ["2024w05", "20250101120000", "Q1_2025"] {
+        assert_eq!(validate_timestamp_suffix(ok).as_deref(), Ok(ok));
+    }
+    let too_long = "x".repeat(MAX_SUFFIX_LEN + 1);
+    for bad in ["", "2024-w05", "a b", "x\"; DROP", "ünicode", too_long.as_str()] {
+        assert!(validate_timestamp_suffix(bad).is_err(), "accepted: {:?}", bad);
+    }
+}
+
+#[test]
+fn prefix_overrides_are_limited_to_identifier_characters() {
+    for ok in ["hannah", "hannah_restored_2024", "X1"] {
+        assert_eq!(validate_table_prefix(ok).as_deref(), Ok(ok));
+    }
+    let too_long = "p".repeat(MAX_PREFIX_LEN + 1);
+    for bad in ["", "hannah-old", "a b", "x\"; DROP", too_long.as_str()] {
+        assert!(validate_table_prefix(bad).is_err(), "accepted: {:?}", bad);
+    }
+}
+
+#[test]
+fn diff_rejects_prefixes_and_suffixes_unsafe_in_names() {
+    let diff = |prefix: &str, old: &str| {
+        Cli::try_parse_from(["export", "diff", "--team-id", "t1", "--user-prefix", prefix, "--opinion", "main", old, "20240202000000"])
+    };
+    assert!(diff("hannah", "20240101000000").is_ok());
+    assert!(diff("../x", "20240101000000").is_err());
+    assert!(diff("hannah", "x\"; DROP").is_err());
+}
+
+#[test]
+fn from_timestamp_rejects_suffixes_unsafe_in_names() {
+    let export = |suffix: &str| Cli::try_parse_from(["export", "--from-timestamp", suffix]);
+    assert!(export("20240101000000").is_ok());
+    assert!(export("x\"; DROP").is_err());
+    assert!(export("../x").is_err());
+}
@@ -0,0 +1,122 @@
+use chrono::NaiveDate;
+use export_opinion::cluster_labels::{ClusterLabelStyle, LabelMap};
+use export_opinion::excel_writer::{organization_sheet, service_sheet, CellValue};
+use export_opinion::models::{OrganizationExportRow, OrganizationServices, ServiceExportRow};
+
+// The sheet layouts as shipped; a change here changes every workbook
+const ORGANIZATION_HEADERS: [&str; 19] = [
+    "contributor", "contributor_id", "entity_id", "name", "alternate_names", "cluster_confirmed_status", "cluster",
+    "has_duplicates", "cluster_representative_name", "canonical_record", "last_reviewed", "reviewed_by",
+    "min_edge_confidence", "low_confidence", "reviewed_edge_fraction", "mixed_status",
+    "service_count", "services_with_duplicates", "worst_service_status",
+];
+
+const SERVICE_HEADERS: [&str; 22] = [
+    "contributor", "contributor_id", "service_id", "organization_name", "service_name", "location_name",
+    "full_address", "address_missing_parts", "latitude", "longitude", "cluster_confirmed_status", "taxonomy_terms",
+    "taxonomy_count", "cluster", "has_duplicates", "canonical_record", "last_reviewed", "reviewed_by", "min_edge_confidence", "low_confidence",
+    "reviewed_edge_fraction", "mixed_status",
+];
+
+#[test]
+fn headers_keep_their_order() {
+    assert_eq!(organization_sheet().headers(), ORGANIZATION_HEADERS);
+    assert_eq!(service_sheet().headers(), SERVICE_HEADERS);
+    assert_eq!(organization_sheet().id_header, "entity_id");
+    assert_eq!(service_sheet().id_header, "service_id");
+}
+
+#[test]
+fn organization_cells_line_up_with_their_headers() {
+    let reviewed = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap().and_hms_opt(5, 6, 7).unwrap();
+    let labels = LabelMap::build(ClusterLabelStyle::Sequential, ["cluster-a"]);
+    let row = OrganizationExportRow {
+        contributor: Some("King County".to_string()),
+        contributor_id: None,
+        entity_id: "e1".to_string(),
+        name: Some("Food Bank".to_string()),
+        alternate_names: Some("FB, Food Bank of King County".to_string()),
+        cluster_confirmed_status: "CONFIRMED".to_string(),
+        cluster: Some("cluster-a".to_string()),
+        has_duplicates: true,
+        cluster_representative_name: None,
+        canonical_record: false,
+        last_reviewed: Some(reviewed),
+        reviewed_by: Some("ana".to_string()),
+        min_edge_confidence: Some(0.5),
+        low_confidence: true,
+        reviewed_edge_fraction: Some(0.25),
+        mixed_status: true,
+        services: Some(OrganizationServices { service_count: 2, services_with_duplicates: 1, worst_status: None }),
+    };
+
+    assert_eq!(organization_sheet().cells(&row, &labels), vec![
+        CellValue::Text("King County"),
+        CellValue::Text(""),
+        CellValue::Text("e1"),
+        CellValue::Text("Food Bank"),
+        CellValue::Text("FB, Food Bank of King County"),
+        CellValue::Text("CONFIRMED"),
+        CellValue::Text("C-000001"),
+        CellValue::Flag(true),
+        CellValue::Text(""),
+        CellValue::Flag(false),
+        CellValue::ReviewTime(Some(reviewed)),
+        CellValue::Text("ana"),
+        CellValue::Number(Some(0.5)),
+        CellValue::Flag(true),
+        CellValue::NumberOrText(Some(0.25), "n/a"),
+        CellValue::Flag(true),
+        CellValue::Number(Some(2.0)),
+        CellValue::Number(Some(1.0)),
+        CellValue::Text(""),
+    ]);
+
+    // Without exported services the service columns stay blank
+    let without_services = OrganizationExportRow { services: None, ..row };
+    let cells = organization_sheet().cells(&without_services, &labels);
+    assert_eq!(cells[16..], [CellValue::Number(None), CellValue::Number(None), CellValue::Blank]);
+}
+
+#[test]
+fn service_cells_line_up_with_their_headers() {
+    let labels = LabelMap::default();
+    let row = ServiceExportRow {
+        contributor: None,
+        contributor_id: None,
+        service_id: "s1".to_string(),
+        organization_name: Some("Food Bank".to_string()),
+        service_name: Some("Pantry".to_string()),
+        location_name: None,
+        full_address: Some("1 Main St, Seattle".to_string()),
+        address_missing_parts: Some(2),
+        latitude: Some(47.6),
+        longitude: None,
+        cluster_confirmed_status: "NO_MATCH".to_string(),
+        taxonomy_terms: Some("Food".to_string()),
+        taxonomy_count: 0,
+        taxonomies: Vec::new(),
+        cluster: None,
+        has_duplicates: false,
+        canonical_record: true,
+        last_reviewed: None,
+        reviewed_by: None,
+        min_edge_confidence: None,
+        low_confidence: false,
+        reviewed_edge_fraction: None,
+        mixed_status: false,
+    };
+
+    let cells = service_sheet().cells(&row, &labels);
+    assert_eq!(cells.len(), SERVICE_HEADERS.len());
+    let by_header = |header: &str| cells[SERVICE_HEADERS.iter().position(|h| *h == header).unwrap()].clone();
+    assert_eq!(by_header("service_id"), CellValue::Text("s1"));
+    assert_eq!(by_header("address_missing_parts"), CellValue::Number(Some(2.0)));
+    assert_eq!(by_header("latitude"), CellValue::Number(Some(47.6)));
+    assert_eq!(by_header("longitude"), CellValue::Number(None));
+    assert_eq!(by_header("cluster"), CellValue::Text(""));
+    assert_eq!(by_header("canonical_record"), CellValue::Flag(true));
+    assert_eq!(by_header("last_reviewed"), CellValue::ReviewTime(None));
+    // A record without edges has no review coverage to show
+    assert_eq!(by_header("reviewed_edge_fraction"), CellValue::NumberOrText(None, "n/a"));
+}
@@ -0,0 +1,72 @@
+use export_opinion::data_fetch::{assemble_service_rows, join_taxonomy_terms};
+use export_opinion::models::{ServiceExportRow, TaxonomyExportRow};
+
+#[test]
+fn unlimited_joins_every_term() {
+    assert_eq!(join_taxonomy_terms(&[], None), None);
+    assert_eq!(join_taxonomy_terms(&["Food", "Meals"], None).as_deref(), Some("Food, Meals"));
+    assert_eq!(join_taxonomy_terms(&["Food", "Meals"], Some(2)).as_deref(), Some("Food, Meals"));
+    assert_eq!(join_taxonomy_terms(&["Food", "Meals"], Some(5)).as_deref(), Some("Food, Meals"));
+}
+
+#[test]
+fn limit_keeps_the_first_terms_and_counts_the_rest() {
+    let terms = ["Clothing", "Food", "Housing", "Meals"];
+    assert_eq!(join_taxonomy_terms(&terms, Some(2)).as_deref(), Some("Clothing, Food (+2 more)"));
+    assert_eq!(join_taxonomy_terms(&terms, Some(1)).as_deref(), Some("Clothing (+3 more)"));
+}
+
+fn part(term: &str, id: &str) -> (ServiceExportRow, Option<TaxonomyExportRow>) {
+    let row = ServiceExportRow {
+        contributor: None,
+        contributor_id: None,
+        service_id: "s1".to_string(),
+        organization_name: None,
+        service_name: None,
+        location_name: None,
+        full_address: None,
+        address_missing_parts: None,
+        latitude: None,
+        longitude: None,
+        cluster_confirmed_status: "NO_MATCH".to_string(),
+        taxonomy_terms: None,
+        taxonomy_count: 0,
+        taxonomies: Vec::new(),
+        cluster: None,
+        has_duplicates: false,
+        canonical_record: false,
+        last_reviewed: None,
+        reviewed_by: None,
+        min_edge_confidence: None,
+        low_confidence: false,
+        reviewed_edge_fraction: None,
+        mixed_status: false,
+    };
+    let taxonomy = TaxonomyExportRow {
+        service_id: "s1".to_string(),
+        taxonomy_term: term.to_string(),
+        taxonomy_category: None,
+        taxonomy_id: id.to_string(),
+    };
+    (row, Some(taxonomy))
+}
+
+#[test]
+fn assembly_dedupes_before_limiting_and_counts_every_term() {
+    let parts = vec![
+        part("Meals", "t3"),
+        part("Food", "t1"),
+        part("Food", "t1"),
+        part("Housing", "t2"),
+        // The same term under another taxonomy id shows and counts once
+        part("Food", "t9"),
+    ];
+    let rows = assemble_service_rows(parts.clone(), Some(2));
+    assert_eq!(rows[0].taxonomy_terms.as_deref(), Some("Food, Housing (+1 more)"));
+    assert_eq!(rows[0].taxonomy_count, 3);
+    assert_eq!(rows[0].taxonomies.len(), 4);
+
+    let rows = assemble_service_rows(parts, None);
+    assert_eq!(rows[0].taxonomy_terms.as_deref(), Some("Food, Housing, Meals"));
+    assert_eq!(rows[0].taxonomy_count, 3);
+}
@@ -0,0 +1,72 @@
+use export_opinion::cli::no_terminal_message;
+use export_opinion::team_utils::{filter_exportable_users, opinion_table_prefix, users_for_team_query, TeamInfo, UserInfo};
+
+fn user(username: &str, prefix: Option<&str>) -> UserInfo {
+    UserInfo {
+        id: format!("id-{}", username),
+        username: username.to_string(),
+        email: None,
+        user_opinion_prefix: prefix.map(str::to_string),
+        team_id: Some("team-1".to_string()),
+        is_active: true,
+    }
+}
+
+#[test]
+fn filter_exportable_users_hides_missing_and_blank_prefixes() {
+    let users = vec![
+        user("hannah", Some("hannah")),
+        user("admin", None),
+        user("blank", Some("  ")),
+        user("dreww", Some("dreww")),
+    ];
+
+    let (exportable, hidden) = filter_exportable_users(users);
+
+    let names: Vec<&str> = exportable.iter().map(|u| u.username.as_str()).collect();
+    assert_eq!(names, vec!["hannah", "dreww"]);
+    assert_eq!(hidden, 2);
+}
+
+#[test]
+fn filter_exportable_users_keeps_everyone_with_a_prefix() {
+    let (exportable, hidden) = filter_exportable_users(vec![user("hannah", Some("hannah"))]);
+    assert_eq!(exportable.len(), 1);
+    assert_eq!(hidden, 0);
+}
+
+#[test]
+fn exportable_only_filters_prefixes_in_sql() {
+    let exportable = users_for_team_query(true);
+    assert!(exportable.contains("WHERE team_id = $1 AND is_active = true AND user_opinion_prefix IS NOT NULL AND btrim(user_opinion_prefix) <> ''"));
+    assert!(!users_for_team_query(false).contains("user_opinion_prefix IS NOT NULL"));
+}
+
+#[test]
+fn prefix_override_replaces_the_users_prefix() {
+    let hannah = user("hannah", Some("hannah"));
+    assert_eq!(opinion_table_prefix(&hannah, None).unwrap(), "hannah");
+    assert_eq!(opinion_table_prefix(&hannah, Some("hannah_restored")).unwrap(), "hannah_restored");
+
+    // With an override, a user without a prefix still supplies the team and opinions
+    let admin = user("admin", Some(" "));
+    assert!(opinion_table_prefix(&admin, None).unwrap_err().to_string().contains("no opinion prefix set"));
+    assert_eq!(opinion_table_prefix(&admin, Some("backup")).unwrap(), "backup");
+}
+
+#[test]
+fn no_terminal_message_names_the_unattended_options_and_the_teams() {
+    let team = TeamInfo {
+        id: "team-1".to_string(),
+        name: "king_county".to_string(),
+        display_name: "King County".to_string(),
+        whitelisted_datasets: Vec::new(),
+        is_active: true,
+    };
+    let message = no_terminal_message(&[team]);
+    assert!(message.starts_with("Cannot prompt for a team and user because stdin is not a terminal."));
+    assert!(message.contains("--opinion NAME") && message.contains("export consensus --team-id ID"));
+    assert!(message.ends_with("Available teams (--team-id):\n  team-1  King County (king_county)"));
+
+    assert!(no_terminal_message(&[]).ends_with("No teams were found."));
+}
@@ -0,0 +1,256 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use calamine::{open_workbook, Data, Range, Reader, Xlsx};
+use export_opinion::cluster_labels::LabelMap;
+use export_opinion::dashboard::{DashboardSection, ProgressOptions, ReviewStats, OpinionDashboard};
+use export_opinion::excel_writer::{organization_sheet, service_sheet, write_excel_file, write_excel_to_bytes, DataSheetOptions, TrailingSheets, TAXONOMY_HEADERS};
+use export_opinion::models::{DatasetPairCount, OrganizationExportRow, ServiceExportRow, TaxonomyExportRow};
+use export_opinion::workbook_verify::{verify_workbook, ExpectedWorkbook};
+
+fn org(id: &str, has_duplicates: bool) -> OrganizationExportRow {
+    OrganizationExportRow {
+        contributor: Some("King County".to_string()),
+        entity_id: id.to_string(),
+        name: Some(format!("Organization {}", id)),
+        cluster_confirmed_status: "CONFIRMED".to_string(),
+        cluster: Some("cluster-a".to_string()),
+        has_duplicates,
+        min_edge_confidence: Some(0.75),
+        ..Default::default()
+    }
+}
+
+fn svc(id: &str) -> ServiceExportRow {
+    ServiceExportRow {
+        service_id: id.to_string(),
+        service_name: Some("Pantry".to_string()),
+        latitude: Some(47.6),
+        cluster_confirmed_status: "NO_MATCH".to_string(),
+        canonical_record: true,
+        ..Default::default()
+    }
+}
+
+fn dashboard() -> OpinionDashboard {
+    OpinionDashboard {
+        username: "sam".to_string(),
+        user_prefix: "sam".to_string(),
+        opinion_name: "default".to_string(),
+        opinion_owner: None,
+        shared_with: Vec::new(),
+        entity_stats: ReviewStats::new(2, 1, 1),
+        service_stats: ReviewStats::new(0, 3, 0),
+        trend: Vec::new(),
+        dataset_pairs: vec![DatasetPairCount {
+            record_type: "entity".to_string(),
+            dataset_1: "alpha".to_string(),
+            dataset_2: "beta".to_string(),
+            confirmed_match: 3,
+            confirmed_non_match: 1,
+            pending_review: 2,
+        }],
+    }
+}
+
+/// Writes a workbook to a fresh temporary path and opens it again.
+async fn write_and_open(
+    name: &str, orgs: Vec<OrganizationExportRow>, svcs: Vec<ServiceExportRow>, footer: bool, taxonomy_sheet: bool,
+) -> Xlsx<std::io::BufReader<std::fs::File>> {
+    let path: PathBuf = std::env::temp_dir().join(format!("readback_{}_{}.xlsx", name, std::process::id()));
+    let labels = LabelMap::default();
+    write_excel_file(
+        &path,
+        Some(orgs),
+        Some(svcs),
+        DashboardSection::Included(vec![dashboard()]),
+        ProgressOptions::default(),
+        TrailingSheets { method_breakdown: Vec::new(), method_performance: Vec::new(), orphaned_edges: Vec::new(), warnings: Vec::new(), legend: true, extra_sheets: None },
+        DataSheetOptions { cluster_labels: &labels, footer, taxonomy_sheet },
+    ).await.unwrap();
+    let workbook = open_workbook(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    workbook
+}
+
+fn header_row(range: &Range<Data>) -> Vec<String> {
+    (0..range.width()).map(|col| range.get((0, col)).unwrap().to_string()).collect()
+}
+
+/// The cell of `row` in the column headed `header`, so adding a column doesn't move
+/// the cells an assertion looks at.
+fn cell<'a>(range: &'a Range<Data>, row: usize, header: &str) -> Option<&'a Data> {
+    let col = header_row(range).iter().position(|h| h == header).unwrap_or_else(|| panic!("no column '{}'", header));
+    range.get((row, col))
+}
+
+#[tokio::test]
+async fn saved_workbook_reads_back_with_the_expected_layout() {
+    let mut orgs = vec![org("e1", true), org("e2", false), org("e3", true)];
+    orgs[1].reviewed_edge_fraction = Some(0.5);
+    orgs[1].last_reviewed = chrono::NaiveDate::from_ymd_opt(2025, 3, 4).unwrap().and_hms_opt(9, 30, 0);
+    let svcs = vec![svc("s1"), svc("s2")];
+    let labels = LabelMap::default();
+    let options = DataSheetOptions { cluster_labels: &labels, footer: false, taxonomy_sheet: false };
+    let expected = ExpectedWorkbook::for_rows(Some(&orgs), Some(&svcs), options).unwrap();
+    let mut workbook = write_and_open("layout", orgs, svcs, false, false).await;
+    verify_workbook(&mut workbook, &expected).unwrap();
+
+    assert_eq!(workbook.sheet_names(), ["Progress Overview", "User Breakdown", "Organizations", "Services", "Legend"]);
+
+    let organizations = workbook.worksheet_range("Organizations").unwrap();
+    assert_eq!(header_row(&organizations), organization_sheet().headers());
+    assert_eq!(organizations.height() - 1, 3);
+    assert_eq!(cell(&organizations, 1, "entity_id"), Some(&Data::String("e1".to_string())));
+    assert_eq!(cell(&organizations, 1, "has_duplicates"), Some(&Data::Bool(true)));
+    assert_eq!(cell(&organizations, 2, "has_duplicates"), Some(&Data::Bool(false)));
+    assert_eq!(cell(&organizations, 3, "min_edge_confidence"), Some(&Data::Float(0.75)));
+    assert_eq!(cell(&organizations, 3, "reviewed_edge_fraction"), Some(&Data::String("n/a".to_string())));
+
+    let services = workbook.worksheet_range("Services").unwrap();
+    assert_eq!(header_row(&services), service_sheet().headers());
+    assert_eq!(services.height() - 1, 2);
+    assert_eq!(cell(&services, 2, "service_id"), Some(&Data::String("s2".to_string())));
+    assert_eq!(cell(&services, 1, "latitude"), Some(&Data::Float(47.6)));
+    assert_eq!(cell(&services, 1, "longitude"), Some(&Data::Empty));
+
+    // Completion % is stored as a fraction and shown through the percentage format
+    let breakdown = workbook.worksheet_range("User Breakdown").unwrap();
+    assert_eq!(cell(&breakdown, 1, "Completion %"), Some(&Data::Float(0.5)));
+    assert_eq!(cell(&breakdown, 2, "Completion %"), Some(&Data::Float(1.0)));
+
+    let progress = workbook.worksheet_range("Progress Overview").unwrap();
+    let pairs_row = (0..progress.height() as u32)
+        .find(|&row| progress.get_value((row, 0)) == Some(&Data::String("DATASET PAIRS".to_string())))
+        .expect("no DATASET PAIRS table");
+    let pair: Vec<String> = (0..7).map(|col| progress.get_value((pairs_row + 2, col)).unwrap().to_string()).collect();
+    assert_eq!(pair, ["entity", "alpha", "beta", "3", "1", "2", "6"]);
+}
+
+#[tokio::test]
+async fn footer_follows_the_data_after_a_blank_row() {
+    let orgs = vec![org("e1", true), org("e2", false)];
+    let svcs = vec![svc("s1")];
+    let labels = LabelMap::default();
+    let options = DataSheetOptions { cluster_labels: &labels, footer: true, taxonomy_sheet: false };
+    let expected = ExpectedWorkbook::for_rows(Some(&orgs), Some(&svcs), options).unwrap();
+    let mut workbook = write_and_open("footer", orgs, svcs, true, false).await;
+    verify_workbook(&mut workbook, &expected).unwrap();
+
+    let organizations = workbook.worksheet_range("Organizations").unwrap();
+    // Header, two data rows, a blank row, the footer
+    assert_eq!(organizations.height(), 5);
+    assert_eq!(organizations.get((3, 0)), Some(&Data::Empty));
+    assert_eq!(organizations.get((4, 0)), Some(&Data::String("Total".to_string())));
+    assert_eq!(cell(&organizations, 4, "entity_id"), Some(&Data::Float(2.0)));
+    assert_eq!(cell(&organizations, 4, "has_duplicates"), Some(&Data::Float(1.0)));
+}
+
+fn taxonomy(service_id: &str, term: &str, category: Option<&str>) -> TaxonomyExportRow {
+    TaxonomyExportRow {
+        service_id: service_id.to_string(),
+        taxonomy_term: term.to_string(),
+        taxonomy_category: category.map(str::to_string),
+        taxonomy_id: format!("t-{}", term.to_lowercase()),
+    }
+}
+
+#[tokio::test]
+async fn taxonomy_sheet_has_one_row_per_term() {
+    let mut s1 = svc("s1");
+    s1.taxonomy_terms = Some("Food, Meals".to_string());
+    s1.taxonomies = vec![taxonomy("s1", "Food", Some("Basic Needs")), taxonomy("s1", "Meals", None)];
+    let mut s2 = svc("s2");
+    s2.taxonomy_terms = Some("Shelter".to_string());
+    s2.taxonomies = vec![taxonomy("s2", "Shelter", Some("Housing"))];
+    let mut workbook = write_and_open("taxonomies", vec![org("e1", false)], vec![s1, s2, svc("s3")], false, true).await;
+
+    assert_eq!(workbook.sheet_names(), ["Progress Overview", "User Breakdown", "Organizations", "Services", "Service Taxonomies", "Legend"]);
+    let taxonomies = workbook.worksheet_range("Service Taxonomies").unwrap();
+    assert_eq!(header_row(&taxonomies), TAXONOMY_HEADERS);
+    assert_eq!(taxonomies.height() - 1, 3);
+    assert_eq!(cell(&taxonomies, 2, "service_id"), Some(&Data::String("s1".to_string())));
+    assert_eq!(cell(&taxonomies, 2, "taxonomy_term"), Some(&Data::String("Meals".to_string())));
+    assert_eq!(cell(&taxonomies, 3, "taxonomy_category"), Some(&Data::String("Housing".to_string())));
+    assert_eq!(cell(&taxonomies, 3, "taxonomy_id"), Some(&Data::String("t-shelter".to_string())));
+
+    // The aggregated column on the Services sheet is unchanged
+    let services = workbook.worksheet_range("Services").unwrap();
+    assert_eq!(cell(&services, 1, "taxonomy_terms"), Some(&Data::String("Food, Meals".to_string())));
+}
+
+#[test]
+fn workbook_serializes_to_bytes_without_a_file() {
+    let labels = LabelMap::default();
+    let bytes = write_excel_to_bytes(
+        Some(vec![org("e1", true), org("e2", false)]),
+        None,
+        DashboardSection::Skipped,
+        ProgressOptions::default(),
+        TrailingSheets { method_breakdown: Vec::new(), method_performance: Vec::new(), orphaned_edges: Vec::new(), warnings: Vec::new(), legend: false, extra_sheets: None },
+        DataSheetOptions { cluster_labels: &labels, footer: false, taxonomy_sheet: false },
+    ).unwrap();
+
+    let mut workbook = Xlsx::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(workbook.sheet_names(), ["Organizations"]);
+    let organizations = workbook.worksheet_range("Organizations").unwrap();
+    assert_eq!(header_row(&organizations), organization_sheet().headers());
+    assert_eq!(organizations.height() - 1, 2);
+}
+
+#[test]
+fn verify_lists_every_sheet_that_does_not_read_back() {
+    let labels = LabelMap::default();
+    let options = DataSheetOptions { cluster_labels: &labels, footer: false, taxonomy_sheet: false };
+    let bytes = write_excel_to_bytes(
+        Some(vec![org("e1", true), org("e2", false), org("e3", true)]),
+        Some(vec![svc("s1"), svc("s2")]),
+        DashboardSection::Skipped,
+        ProgressOptions::default(),
+        TrailingSheets { method_breakdown: Vec::new(), method_performance: Vec::new(), orphaned_edges: Vec::new(), warnings: Vec::new(), legend: false, extra_sheets: None },
+        options,
+    ).unwrap();
+    let mut workbook = Xlsx::new(Cursor::new(bytes)).unwrap();
+
+    let expected = ExpectedWorkbook::for_rows(
+        Some(&[org("e1", true), org("e2", true), org("e3", true)]),
+        Some(&[svc("s1"), svc("s2"), svc("s3")]),
+        options,
+    ).unwrap();
+    let message = verify_workbook(&mut workbook, &expected).unwrap_err().to_string();
+    assert!(message.starts_with("2 problem(s) reading the workbook back"), "{}", message);
+    assert!(message.contains("'Organizations' row 3 column 'has_duplicates' reads Bool(false), expected Flag(true)"), "{}", message);
+    assert!(message.contains("'Services' has 2 data rows, expected 3"), "{}", message);
+}
+
+#[test]
+fn extra_sheets_follow_the_standard_sheets_and_may_not_reuse_their_names() {
+    let labels = LabelMap::default();
+    let with_extra = |name: &'static str| TrailingSheets {
+        method_breakdown: Vec::new(),
+        method_performance: Vec::new(),
+        orphaned_edges: Vec::new(),
+        warnings: Vec::new(),
+        legend: true,
+        extra_sheets: Some(Box::new(move |workbook| {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(name)?;
+            sheet.write_string(0, 0, "contract")?;
+            Ok(())
+        })),
+    };
+    let options = DataSheetOptions { cluster_labels: &labels, footer: false, taxonomy_sheet: false };
+
+    let bytes = write_excel_to_bytes(
+        Some(vec![org("e1", true)]), None, DashboardSection::Skipped, ProgressOptions::default(), with_extra("Contract Summary"), options,
+    ).unwrap();
+    let mut workbook = Xlsx::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(workbook.sheet_names(), ["Organizations", "Legend", "Contract Summary"]);
+    let summary = workbook.worksheet_range("Contract Summary").unwrap();
+    assert_eq!(summary.get((0, 0)), Some(&Data::String("contract".to_string())));
+
+    let error = write_excel_to_bytes(
+        Some(vec![org("e1", true)]), None, DashboardSection::Skipped, ProgressOptions::default(), with_extra("legend"), options,
+    ).unwrap_err();
+    assert!(format!("{:#}", error).contains("already has a sheet named 'legend'"));
+}